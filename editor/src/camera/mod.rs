@@ -939,6 +939,7 @@ fn precise_ray_test(
         graph,
         render_pass_name: &Default::default(),
         dynamic_surface_cache: &mut cache,
+        instance_filter: None,
     });
     let mut closest_distance = f32::MAX;
     let mut closest_point = None;
@@ -181,12 +181,12 @@ impl CommandTrait for CommandGroup {
 
     fn name(&mut self, context: &dyn CommandContext) -> String {
         if self.custom_name.is_empty() {
-            let mut name = String::from("Command group: ");
-            for cmd in self.commands.iter_mut() {
-                name.push_str(&cmd.name(context));
-                name.push_str(", ");
-            }
-            name
+            let names = self
+                .commands
+                .iter_mut()
+                .map(|cmd| cmd.name(context))
+                .collect::<Vec<_>>();
+            format!("Command group: {}", names.join(", "))
         } else {
             self.custom_name.clone()
         }
@@ -677,3 +677,89 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{CommandContext, CommandGroup, CommandTrait};
+    use crate::fyrox::core::type_traits::prelude::*;
+
+    #[derive(Default, ComponentProvider)]
+    struct LogContext {
+        #[component(include)]
+        log: Vec<String>,
+    }
+
+    impl CommandContext for LogContext {}
+
+    #[derive(Debug)]
+    struct LogCommand(&'static str);
+
+    impl CommandTrait for LogCommand {
+        fn name(&mut self, _context: &dyn CommandContext) -> String {
+            self.0.to_owned()
+        }
+
+        fn execute(&mut self, context: &mut dyn CommandContext) {
+            context
+                .get_mut::<Vec<String>>()
+                .push(format!("execute {}", self.0));
+        }
+
+        fn revert(&mut self, context: &mut dyn CommandContext) {
+            context
+                .get_mut::<Vec<String>>()
+                .push(format!("revert {}", self.0));
+        }
+
+        fn finalize(&mut self, context: &mut dyn CommandContext) {
+            context
+                .get_mut::<Vec<String>>()
+                .push(format!("finalize {}", self.0));
+        }
+    }
+
+    #[test]
+    fn test_command_group_order() {
+        let mut context = LogContext::default();
+        let mut group = CommandGroup::default();
+        group.push(LogCommand("a"));
+        group.push(LogCommand("b"));
+        group.push(LogCommand("c"));
+
+        assert_eq!(group.name(&context), "Command group: a, b, c");
+
+        group.execute(&mut context);
+        group.revert(&mut context);
+        group.finalize(&mut context);
+
+        assert_eq!(
+            context.log,
+            [
+                "execute a",
+                "execute b",
+                "execute c",
+                "revert c",
+                "revert b",
+                "revert a",
+                "finalize a",
+                "finalize b",
+                "finalize c",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_command_group() {
+        let mut context = LogContext::default();
+        let mut group = CommandGroup::default();
+
+        assert!(group.is_empty());
+        assert!(!group.is_significant());
+
+        group.execute(&mut context);
+        group.revert(&mut context);
+        group.finalize(&mut context);
+
+        assert!(context.log.is_empty());
+    }
+}
@@ -139,6 +139,7 @@ impl SceneRenderPass for HighlightRenderPass {
                 graph: &ctx.scene.graph,
                 render_pass_name: &render_pass_name,
                 dynamic_surface_cache: ctx.dynamic_surface_cache,
+                instance_filter: None,
             };
 
             for &root_node_handle in self.nodes_to_highlight.iter() {
@@ -175,6 +176,7 @@ impl SceneRenderPass for HighlightRenderPass {
                     scene_depth: Some(ctx.depth_texture),
                     viewport: ctx.observer.viewport,
                     uniform_memory_allocator: ctx.uniform_memory_allocator,
+                    disable_depth_test: false,
                 },
             )?;
         }
@@ -86,7 +86,8 @@ use crate::{
         scene::{
             self,
             base::{
-                Base, LevelOfDetail, LodGroup, Mobility, Property, PropertyValue, ScriptRecord,
+                Base, ImpostorLevel, LevelOfDetail, LodGroup, Mobility, Property, PropertyValue,
+                ScriptRecord,
             },
             camera::{
                 ColorGradingLut, Exposure, OrthographicProjection, PerspectiveProjection,
@@ -320,6 +321,8 @@ pub fn make_property_editors_container(
 
     container.insert(EnumPropertyEditorDefinition::<LodGroup>::new_optional());
     container.insert(InheritablePropertyEditorDefinition::<Option<LodGroup>>::new());
+    container.insert(InspectablePropertyEditorDefinition::<ImpostorLevel>::new());
+    container.insert(EnumPropertyEditorDefinition::<ImpostorLevel>::new_optional());
 
     {
         use crate::fyrox::scene::animation::spritesheet::prelude::*;
@@ -43,7 +43,7 @@ pub struct DeepCloneResult {
     pub root_nodes: Vec<Handle<UiNode>>,
 }
 
-fn deep_clone_nodes(
+pub fn deep_clone_nodes(
     root_nodes: &[Handle<UiNode>],
     source_graph: &UserInterface,
     dest_ui: &mut UserInterface,
@@ -21,17 +21,45 @@
 //! Ui graph manipulation commands.
 
 use crate::command::{CommandContext, CommandTrait};
-use crate::fyrox::graph::{BaseSceneGraph, LinkScheme, SceneGraphNode};
+use crate::fyrox::graph::{BaseSceneGraph, LinkScheme, SceneGraph, SceneGraphNode};
 use crate::fyrox::{
-    core::pool::Handle,
-    gui::{SubGraph, UiNode, UserInterface},
+    asset::{
+        collect_used_resources, manager::ResourceManager, state::ResourceState,
+        untyped::ResourceKind, Resource,
+    },
+    core::{
+        algebra::Vector2, color::Color, futures::executor::block_on, log::Log, pool::Handle,
+        uuid::Uuid,
+    },
+    fxhash::{FxHashMap, FxHashSet},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::ButtonBuilder,
+        canvas::Canvas,
+        check_box::CheckBoxBuilder,
+        dock::{TileBuilder, TileContent, DEFAULT_SPLITTER_SIZE},
+        event_handler::WidgetEvent,
+        expander::ExpanderBuilder,
+        grid::{Column, Grid, GridBuilder, GridDimension, Row},
+        numeric::{NumericType, NumericUpDownBuilder},
+        scroll_viewer::{ScrollViewer, ScrollViewerBuilder},
+        stack_panel::{StackPanel, StackPanelBuilder},
+        text::TextBuilder,
+        text_box::TextBoxBuilder,
+        widget::{Widget, WidgetBuilder},
+        wrap_panel::WrapPanel,
+        BuildContext, HorizontalAlignment, Orientation, SubGraph, Thickness, UiNode, UserInterface,
+        UserInterfaceResourceExtension, VerticalAlignment,
+    },
 };
-use crate::ui_scene::clipboard::DeepCloneResult;
+use crate::ui_scene::clipboard::{deep_clone_nodes, DeepCloneResult};
 use crate::{
     scene::Selection,
     ui_scene::{commands::UiSceneContext, UiSelection},
     Message,
 };
+use std::{cell::RefCell, path::PathBuf};
 
 #[derive(Debug)]
 pub struct AddWidgetCommand {
@@ -407,3 +435,2313 @@ impl CommandTrait for SetWidgetChildPosition {
         self.swap(context)
     }
 }
+
+/// Placement of a widget within its parent.
+#[derive(Debug, Clone)]
+struct WidgetPlacement {
+    margin: Thickness,
+    desired_position: Vector2<f32>,
+    row: usize,
+    column: usize,
+}
+
+impl WidgetPlacement {
+    fn of(widget: &UiNode) -> Self {
+        Self {
+            margin: *widget.margin,
+            desired_position: *widget.desired_local_position,
+            row: *widget.row,
+            column: *widget.column,
+        }
+    }
+
+    fn apply(self, widget: &mut UiNode) {
+        widget.margin.set_value_and_mark_modified(self.margin);
+        widget
+            .desired_local_position
+            .set_value_and_mark_modified(self.desired_position);
+        widget.row.set_value_and_mark_modified(self.row);
+        widget.column.set_value_and_mark_modified(self.column);
+        widget.invalidate_layout();
+    }
+}
+
+/// Creates a scroll viewer at the place of a widget and puts the widget inside it. The scroll viewer
+/// takes the placement (margin, position, grid cell) and the current size of the widget.
+#[derive(Debug)]
+pub struct WrapInScrollViewerCommand {
+    target: Handle<UiNode>,
+    allow_nested: bool,
+    scroll_viewer: Handle<UiNode>,
+    sub_graph: Option<SubGraph>,
+    parent: Handle<UiNode>,
+    position: usize,
+    placement: Option<WidgetPlacement>,
+    prev_selection: Selection,
+}
+
+impl WrapInScrollViewerCommand {
+    /// Creates a new command. If `allow_nested` is `false` and the target is already inside a
+    /// scroll viewer, then the command does nothing.
+    pub fn new(target: Handle<UiNode>, allow_nested: bool) -> Self {
+        Self {
+            target,
+            allow_nested,
+            scroll_viewer: Handle::NONE,
+            sub_graph: None,
+            parent: Handle::NONE,
+            position: 0,
+            placement: None,
+            prev_selection: Selection::new_empty(),
+        }
+    }
+
+    fn is_inside_scroll_viewer(&self, ui: &UserInterface) -> bool {
+        let mut parent = ui.node(self.target).parent();
+        while let Some(parent_ref) = ui.try_get(parent) {
+            if parent_ref.cast::<ScrollViewer>().is_some() {
+                return true;
+            }
+            parent = parent_ref.parent();
+        }
+        false
+    }
+
+    fn create_scroll_viewer(&mut self, ui: &mut UserInterface) -> bool {
+        let Some(target_ref) = ui.try_get(self.target) else {
+            Log::err(format!(
+                "Unable to wrap {} in a scroll viewer, because it is not a valid widget!",
+                self.target
+            ));
+            return false;
+        };
+
+        self.parent = target_ref.parent();
+        if self.parent.is_none() {
+            Log::err("Unable to wrap the root widget in a scroll viewer!");
+            return false;
+        }
+
+        if !self.allow_nested && self.is_inside_scroll_viewer(ui) {
+            Log::warn(format!(
+                "{} is already inside a scroll viewer, nothing to do.",
+                target_ref.name()
+            ));
+            return false;
+        }
+
+        let target_ref = ui.node(self.target);
+        let size = target_ref.actual_local_size();
+        let placement = WidgetPlacement::of(target_ref);
+        let horizontal_alignment = *target_ref.horizontal_alignment;
+        let vertical_alignment = *target_ref.vertical_alignment;
+        self.position = ui
+            .node(self.parent)
+            .child_position(self.target)
+            .unwrap_or_default();
+
+        self.scroll_viewer = ScrollViewerBuilder::new(
+            WidgetBuilder::new()
+                .with_name("Scroll Viewer")
+                .with_width(size.x)
+                .with_height(size.y)
+                .with_margin(placement.margin)
+                .with_desired_position(placement.desired_position)
+                .on_row(placement.row)
+                .on_column(placement.column)
+                .with_horizontal_alignment(horizontal_alignment)
+                .with_vertical_alignment(vertical_alignment),
+        )
+        .with_content(self.target)
+        .build(&mut ui.build_ctx());
+
+        self.placement = Some(placement);
+
+        true
+    }
+}
+
+impl CommandTrait for WrapInScrollViewerCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Wrap In Scroll Viewer".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            self.scroll_viewer = context.ui.put_sub_graph_back(sub_graph);
+            let scroll_panel = context
+                .ui
+                .node(self.scroll_viewer)
+                .cast::<ScrollViewer>()
+                .expect("Must be a scroll viewer!")
+                .scroll_panel;
+            context.ui.link_nodes(self.target, scroll_panel, false);
+        } else if !self.create_scroll_viewer(context.ui) {
+            return;
+        }
+
+        context
+            .ui
+            .link_nodes(self.scroll_viewer, self.parent, false);
+        context
+            .ui
+            .node_mut(self.parent)
+            .set_child_position(self.scroll_viewer, self.position);
+
+        // The scroll viewer now holds the placement of the target.
+        WidgetPlacement {
+            margin: Thickness::zero(),
+            desired_position: Vector2::default(),
+            row: 0,
+            column: 0,
+        }
+        .apply(context.ui.node_mut(self.target));
+
+        self.prev_selection = context.set_selection(Selection::new(UiSelection::single_or_empty(
+            self.scroll_viewer,
+        )));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.scroll_viewer.is_none() {
+            return;
+        }
+
+        context.ui.link_nodes(self.target, self.parent, false);
+        context
+            .ui
+            .node_mut(self.parent)
+            .set_child_position(self.target, self.position);
+        if let Some(placement) = self.placement.clone() {
+            placement.apply(context.ui.node_mut(self.target));
+        }
+
+        self.sub_graph = Some(context.ui.take_reserve_sub_graph(self.scroll_viewer));
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+
+    fn finalize(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+/// Creates an expander (a collapsible section with a header) at the place of the given widgets and
+/// puts the widgets in a vertical stack panel, that is used as the content of the expander. The
+/// expander takes the placement (margin, position, grid cell) and the alignment of the first of
+/// the widgets among the children of their parent and it is inserted at its position.
+///
+/// Descendants of other widgets in the list are moved along with their ancestors. The widgets must
+/// have the same parent, otherwise the command does nothing and reports an error: grouping under a
+/// common ancestor would silently change the layout of the widgets.
+#[derive(Debug)]
+pub struct WrapInExpanderCommand {
+    widgets: Vec<Handle<UiNode>>,
+    header: String,
+    expander: Handle<UiNode>,
+    content: Handle<UiNode>,
+    sub_graph: Option<SubGraph>,
+    parent: Handle<UiNode>,
+    // Wrapped widgets with their original positions among the children of the parent, sorted by
+    // the position.
+    targets: Vec<(Handle<UiNode>, usize)>,
+    prev_selection: Selection,
+}
+
+impl WrapInExpanderCommand {
+    pub fn new(selection: &UiSelection, header: String) -> Self {
+        Self {
+            widgets: selection.widgets.clone(),
+            header,
+            expander: Handle::NONE,
+            content: Handle::NONE,
+            sub_graph: None,
+            parent: Handle::NONE,
+            targets: Default::default(),
+            prev_selection: Selection::new_empty(),
+        }
+    }
+
+    fn create_expander(&mut self, ui: &mut UserInterface) -> Result<(), String> {
+        let widgets = UiSelection {
+            widgets: self
+                .widgets
+                .iter()
+                .copied()
+                .filter(|handle| ui.is_valid_handle(*handle))
+                .collect(),
+        }
+        .root_widgets(ui);
+
+        let Some(&first) = widgets.first() else {
+            return Err("There are no widgets selected!".to_owned());
+        };
+
+        let parent = ui.node(first).parent();
+        if parent.is_none() {
+            return Err("The root widget cannot be wrapped!".to_owned());
+        }
+
+        if let Some(&other) = widgets
+            .iter()
+            .find(|handle| ui.node(**handle).parent() != parent)
+        {
+            return Err(format!(
+                "{} and {} have different parents! Only the widgets with the same parent could \
+                be wrapped.",
+                ui.node(first).name(),
+                ui.node(other).name()
+            ));
+        }
+
+        let parent_ref = ui.node(parent);
+        let mut targets = widgets
+            .iter()
+            .map(|handle| {
+                (
+                    *handle,
+                    parent_ref.child_position(*handle).unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+        targets.sort_by_key(|(_, position)| *position);
+
+        let first = ui.node(targets[0].0);
+        let placement = WidgetPlacement::of(first);
+        let horizontal_alignment = *first.horizontal_alignment;
+        let vertical_alignment = *first.vertical_alignment;
+
+        let ctx = &mut ui.build_ctx();
+        self.content = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_name("Content")
+                .with_children(targets.iter().map(|(handle, _)| *handle)),
+        )
+        .build(ctx);
+        let header = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_name("Header")
+                .with_vertical_alignment(VerticalAlignment::Center),
+        )
+        .with_text(self.header.clone())
+        .build(ctx);
+        self.expander = ExpanderBuilder::new(
+            WidgetBuilder::new()
+                .with_name("Expander")
+                .with_margin(placement.margin)
+                .with_desired_position(placement.desired_position)
+                .on_row(placement.row)
+                .on_column(placement.column)
+                .with_horizontal_alignment(horizontal_alignment)
+                .with_vertical_alignment(vertical_alignment),
+        )
+        .with_header(header)
+        .with_content(self.content)
+        .with_expanded(true)
+        .build(ctx);
+
+        self.parent = parent;
+        self.targets = targets;
+
+        Ok(())
+    }
+}
+
+impl CommandTrait for WrapInExpanderCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Wrap In Expander".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            self.expander = context.ui.put_sub_graph_back(sub_graph);
+            for &(target, _) in self.targets.iter() {
+                context.ui.link_nodes(target, self.content, false);
+            }
+        } else if let Err(err) = self.create_expander(context.ui) {
+            Log::err(format!(
+                "Unable to wrap widgets in an expander. Reason: {err}"
+            ));
+            return;
+        }
+
+        context.ui.link_nodes(self.expander, self.parent, false);
+        context
+            .ui
+            .node_mut(self.parent)
+            .set_child_position(self.expander, self.targets[0].1);
+
+        self.prev_selection =
+            context.set_selection(Selection::new(UiSelection::single_or_empty(self.expander)));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.expander.is_none() {
+            return;
+        }
+
+        for &(target, _) in self.targets.iter() {
+            context.ui.link_nodes(target, self.parent, false);
+        }
+
+        // The expander must be removed before the widgets are moved to their positions, otherwise
+        // it will shift them.
+        self.sub_graph = Some(context.ui.take_reserve_sub_graph(self.expander));
+
+        // The positions are sorted, so each widget is put after every widget that preceded it.
+        let parent = context.ui.node_mut(self.parent);
+        for &(target, position) in self.targets.iter() {
+            parent.set_child_position(target, position);
+        }
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+
+    fn finalize(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+/// Initial position of the divider of a splitter created by [`MakeSplitterCommand`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitRatio {
+    /// A fraction of the size of the splitter, that is taken by the first pane. It is clamped to
+    /// `[0.0; 1.0]` range.
+    Fixed(f32),
+    /// The fraction is calculated from the current sizes of the widgets along the split orientation.
+    /// If the widgets have no size yet, then the splitter is divided in half.
+    FromSizes,
+}
+
+impl Default for SplitRatio {
+    fn default() -> Self {
+        Self::Fixed(0.5)
+    }
+}
+
+/// Creates a splitter (a tile with a draggable divider) at the place of two widgets and puts the
+/// widgets in its panes. The widget that comes first among the children of the parent is put in the
+/// left (or top) pane. The splitter takes the placement (margin, position, grid cell) and the
+/// alignment of the first widget, its size is the combined size of both widgets.
+///
+/// The selection must contain exactly two widgets with the same parent, neither of which is a
+/// descendant of the other. Otherwise the command does nothing and reports an error.
+#[derive(Debug)]
+pub struct MakeSplitterCommand {
+    widgets: Vec<Handle<UiNode>>,
+    orientation: Orientation,
+    ratio: SplitRatio,
+    splitter: Handle<UiNode>,
+    sub_graph: Option<SubGraph>,
+    parent: Handle<UiNode>,
+    // Both widgets with their original positions among the children of the parent and their
+    // original placements, sorted by the position.
+    targets: Vec<(Handle<UiNode>, usize, WidgetPlacement)>,
+    prev_selection: Selection,
+}
+
+impl MakeSplitterCommand {
+    pub fn new(selection: &UiSelection, orientation: Orientation, ratio: SplitRatio) -> Self {
+        Self {
+            widgets: selection.widgets.clone(),
+            orientation,
+            ratio,
+            splitter: Handle::NONE,
+            sub_graph: None,
+            parent: Handle::NONE,
+            targets: Default::default(),
+            prev_selection: Selection::new_empty(),
+        }
+    }
+
+    fn create_splitter(&mut self, ui: &mut UserInterface) -> Result<(), String> {
+        let selection = UiSelection {
+            widgets: self
+                .widgets
+                .iter()
+                .copied()
+                .filter(|handle| ui.is_valid_handle(*handle))
+                .collect(),
+        };
+
+        if selection.widgets.len() != 2 {
+            return Err(format!(
+                "A splitter requires exactly two widgets, but {} are selected!",
+                selection.widgets.len()
+            ));
+        }
+
+        let widgets = selection.root_widgets(ui);
+        if widgets.len() != 2 {
+            return Err(
+                "One of the widgets is a descendant of the other, they cannot be split!".to_owned(),
+            );
+        }
+
+        let parent = ui.node(widgets[0]).parent();
+        if parent.is_none() {
+            return Err("The root widget cannot be split!".to_owned());
+        }
+
+        if ui.node(widgets[1]).parent() != parent {
+            return Err(format!(
+                "{} and {} have different parents! Only the widgets with the same parent could \
+                be split.",
+                ui.node(widgets[0]).name(),
+                ui.node(widgets[1]).name()
+            ));
+        }
+
+        let parent_ref = ui.node(parent);
+        let mut targets = widgets
+            .iter()
+            .map(|handle| {
+                (
+                    *handle,
+                    parent_ref.child_position(*handle).unwrap_or_default(),
+                    WidgetPlacement::of(ui.node(*handle)),
+                )
+            })
+            .collect::<Vec<_>>();
+        targets.sort_by_key(|(_, position, _)| *position);
+
+        let first_size = ui.node(targets[0].0).actual_local_size();
+        let second_size = ui.node(targets[1].0).actual_local_size();
+        let (axis, cross_axis) = match self.orientation {
+            Orientation::Horizontal => (0, 1),
+            Orientation::Vertical => (1, 0),
+        };
+        let mut size = Vector2::default();
+        size[axis] = first_size[axis] + second_size[axis] + DEFAULT_SPLITTER_SIZE;
+        size[cross_axis] = first_size[cross_axis].max(second_size[cross_axis]);
+
+        let splitter = match self.ratio {
+            SplitRatio::Fixed(ratio) => ratio.clamp(0.0, 1.0),
+            SplitRatio::FromSizes => {
+                let total = first_size[axis] + second_size[axis];
+                if total > f32::EPSILON {
+                    first_size[axis] / total
+                } else {
+                    0.5
+                }
+            }
+        };
+
+        let tiles = [targets[0].0, targets[1].0];
+        let content = match self.orientation {
+            Orientation::Horizontal => TileContent::HorizontalTiles { splitter, tiles },
+            Orientation::Vertical => TileContent::VerticalTiles { splitter, tiles },
+        };
+
+        let first = ui.node(targets[0].0);
+        let placement = targets[0].2.clone();
+        let horizontal_alignment = *first.horizontal_alignment;
+        let vertical_alignment = *first.vertical_alignment;
+
+        self.splitter = TileBuilder::new(
+            WidgetBuilder::new()
+                .with_name("Splitter")
+                .with_width(size.x)
+                .with_height(size.y)
+                .with_margin(placement.margin)
+                .with_desired_position(placement.desired_position)
+                .on_row(placement.row)
+                .on_column(placement.column)
+                .with_horizontal_alignment(horizontal_alignment)
+                .with_vertical_alignment(vertical_alignment),
+        )
+        .with_content(content)
+        .build(&mut ui.build_ctx());
+
+        self.parent = parent;
+        self.targets = targets;
+
+        Ok(())
+    }
+}
+
+impl CommandTrait for MakeSplitterCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Make Splitter".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            self.splitter = context.ui.put_sub_graph_back(sub_graph);
+            for (target, _, _) in self.targets.iter() {
+                context.ui.link_nodes(*target, self.splitter, false);
+            }
+        } else if let Err(err) = self.create_splitter(context.ui) {
+            Log::err(format!("Unable to make a splitter. Reason: {err}"));
+            return;
+        }
+
+        context.ui.link_nodes(self.splitter, self.parent, false);
+        context
+            .ui
+            .node_mut(self.parent)
+            .set_child_position(self.splitter, self.targets[0].1);
+
+        // The splitter now holds the placement of the widgets.
+        for (target, _, _) in self.targets.iter() {
+            WidgetPlacement {
+                margin: Thickness::zero(),
+                desired_position: Vector2::default(),
+                row: 0,
+                column: 0,
+            }
+            .apply(context.ui.node_mut(*target));
+        }
+
+        self.prev_selection =
+            context.set_selection(Selection::new(UiSelection::single_or_empty(self.splitter)));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.splitter.is_none() {
+            return;
+        }
+
+        for (target, _, _) in self.targets.iter() {
+            context.ui.link_nodes(*target, self.parent, false);
+        }
+
+        // The splitter must be removed before the widgets are moved to their positions, otherwise
+        // it will shift them.
+        self.sub_graph = Some(context.ui.take_reserve_sub_graph(self.splitter));
+
+        // The positions are sorted, so the first widget is put before the second one.
+        for (target, position, placement) in self.targets.iter() {
+            context
+                .ui
+                .node_mut(self.parent)
+                .set_child_position(*target, *position);
+            placement.clone().apply(context.ui.node_mut(*target));
+        }
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+
+    fn finalize(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+/// Size of a spacer created by [`InsertSpacerCommand`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpacerSize {
+    /// Fixed size in pixels.
+    Fixed(f32),
+    /// A fraction of the current size of the parent widget. The size is calculated once, when the
+    /// spacer is created.
+    Proportional(f32),
+}
+
+/// Creates an invisible, non-interactive spacer widget and inserts it at the given position among
+/// the children of the parent widget. If the parent is a stack panel, then the spacer has the
+/// given size along the orientation of the panel only, otherwise the size is used for both
+/// dimensions.
+#[derive(Debug)]
+pub struct InsertSpacerCommand {
+    parent: Handle<UiNode>,
+    index: usize,
+    size: SpacerSize,
+    spacer: Handle<UiNode>,
+    sub_graph: Option<SubGraph>,
+    prev_selection: Selection,
+}
+
+impl InsertSpacerCommand {
+    pub fn new(parent: Handle<UiNode>, index: usize, size: SpacerSize) -> Self {
+        Self {
+            parent,
+            index,
+            size,
+            spacer: Handle::NONE,
+            sub_graph: None,
+            prev_selection: Selection::new_empty(),
+        }
+    }
+
+    fn create_spacer(&mut self, ui: &mut UserInterface) -> bool {
+        let Some(parent_ref) = ui.try_get(self.parent) else {
+            Log::err(format!(
+                "Unable to insert a spacer, because {} is not a valid widget!",
+                self.parent
+            ));
+            return false;
+        };
+
+        let child_count = parent_ref.children().len();
+        if self.index > child_count {
+            Log::err(format!(
+                "Unable to insert a spacer at {} position, {} has only {} children!",
+                self.index,
+                parent_ref.name(),
+                child_count
+            ));
+            return false;
+        }
+
+        let parent_size = parent_ref.actual_local_size();
+        let (width, height) = match self.size {
+            SpacerSize::Fixed(size) => (size, size),
+            SpacerSize::Proportional(fraction) => {
+                (parent_size.x * fraction, parent_size.y * fraction)
+            }
+        };
+
+        let mut widget_builder = WidgetBuilder::new()
+            .with_name("Spacer")
+            .with_hit_test_visibility(false)
+            .with_background(Brush::Solid(Color::TRANSPARENT).into());
+        widget_builder = match parent_ref
+            .cast::<StackPanel>()
+            .map(|stack_panel| *stack_panel.orientation)
+        {
+            Some(Orientation::Vertical) => widget_builder.with_height(height),
+            Some(Orientation::Horizontal) => widget_builder.with_width(width),
+            None => widget_builder.with_width(width).with_height(height),
+        };
+
+        self.spacer = BorderBuilder::new(widget_builder)
+            .with_stroke_thickness(Thickness::zero().into())
+            .build(&mut ui.build_ctx());
+
+        true
+    }
+}
+
+impl CommandTrait for InsertSpacerCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Insert Spacer".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            self.spacer = context.ui.put_sub_graph_back(sub_graph);
+        } else if !self.create_spacer(context.ui) {
+            return;
+        }
+
+        context.ui.link_nodes(self.spacer, self.parent, false);
+        context
+            .ui
+            .node_mut(self.parent)
+            .set_child_position(self.spacer, self.index);
+
+        self.prev_selection =
+            context.set_selection(Selection::new(UiSelection::single_or_empty(self.spacer)));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.spacer.is_none() {
+            return;
+        }
+
+        // Sibling order is restored automatically, because the spacer is just removed from the
+        // list of children.
+        self.sub_graph = Some(context.ui.take_reserve_sub_graph(self.spacer));
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+
+    fn finalize(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+// Layout panels, that are converted to canvases by [`BakeLayoutCommand`].
+fn is_layout_panel(node: &UiNode) -> bool {
+    node.cast::<StackPanel>().is_some()
+        || node.cast::<WrapPanel>().is_some()
+        || node.cast::<Grid>().is_some()
+}
+
+// Replaces a widget with the given one, keeping its handle, the base widget state and the position
+// among the children of its parent. Returns the old widget.
+fn replace_widget(ui: &mut UserInterface, handle: Handle<UiNode>, mut node: UiNode) -> UiNode {
+    let parent = ui.node(handle).parent();
+    let position = ui
+        .try_get(parent)
+        .and_then(|parent| parent.children().iter().position(|child| *child == handle));
+
+    let (ticket, old_node) = ui.take_reserve(handle);
+    let widget: &mut Widget = &mut node;
+    *widget = Widget::clone(&old_node);
+    ui.put_back(ticket, node);
+
+    if parent.is_some() {
+        ui.link_nodes(handle, parent, false);
+        if let Some(position) = position {
+            ui.node_mut(parent).set_child_position(handle, position);
+        }
+    }
+    ui.node(handle).invalidate_layout();
+
+    old_node
+}
+
+#[derive(Debug, Clone)]
+struct WidgetLayout {
+    position: Vector2<f32>,
+    width: f32,
+    height: f32,
+    margin: Thickness,
+    horizontal_alignment: HorizontalAlignment,
+    vertical_alignment: VerticalAlignment,
+}
+
+impl WidgetLayout {
+    // Absolute layout, that places the widget exactly at its current position with its current size.
+    fn absolute(widget: &Widget) -> Self {
+        let size = widget.actual_local_size();
+        Self {
+            position: widget.actual_local_position(),
+            width: size.x,
+            height: size.y,
+            margin: Thickness::zero(),
+            horizontal_alignment: HorizontalAlignment::Left,
+            vertical_alignment: VerticalAlignment::Top,
+        }
+    }
+
+    // Keeps the current layout of the widget, but fixes its size.
+    fn fixed_size(widget: &Widget) -> Self {
+        let size = widget.actual_local_size();
+        Self {
+            position: widget.desired_local_position(),
+            width: size.x,
+            height: size.y,
+            margin: *widget.margin,
+            horizontal_alignment: *widget.horizontal_alignment,
+            vertical_alignment: *widget.vertical_alignment,
+        }
+    }
+
+    fn swap(&mut self, widget: &mut Widget) {
+        let old = Self {
+            position: widget.desired_local_position(),
+            width: *widget.width,
+            height: *widget.height,
+            margin: *widget.margin,
+            horizontal_alignment: *widget.horizontal_alignment,
+            vertical_alignment: *widget.vertical_alignment,
+        };
+        widget.set_desired_local_position(self.position);
+        widget.width.set_value_and_mark_modified(self.width);
+        widget.height.set_value_and_mark_modified(self.height);
+        widget.margin.set_value_and_mark_modified(self.margin);
+        widget
+            .horizontal_alignment
+            .set_value_and_mark_modified(self.horizontal_alignment);
+        widget
+            .vertical_alignment
+            .set_value_and_mark_modified(self.vertical_alignment);
+        widget.invalidate_layout();
+        *self = old;
+    }
+}
+
+/// Converts layout-driven positions of the descendants of a widget into absolute positions, that
+/// reproduce the current visual layout at the current size. Layout panels (stack panels, wrap
+/// panels and grids) in the subtree are replaced with canvases, and their children get absolute
+/// positions and fixed sizes. Sizes of the converted panels are fixed as well, because a canvas
+/// does not depend on the size of its children. Children of other widgets (borders, buttons,
+/// etc.) are positioned by their parents and they're left as is.
+///
+/// Excluded widgets (and their descendants) stay responsive: they're not baked, and a panel that
+/// has an excluded child is not converted.
+#[derive(Debug)]
+pub struct BakeLayoutCommand {
+    root: Handle<UiNode>,
+    excluded: Vec<Handle<UiNode>>,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    layouts: Option<Vec<(Handle<UiNode>, WidgetLayout)>>,
+    // Widgets that will replace the existing ones on next execute/revert.
+    replacements: Vec<(Handle<UiNode>, UiNode)>,
+}
+
+impl BakeLayoutCommand {
+    pub fn new(root: Handle<UiNode>, excluded: Vec<Handle<UiNode>>) -> Self {
+        Self {
+            root,
+            excluded,
+            layouts: None,
+            replacements: Default::default(),
+        }
+    }
+
+    fn bake(&mut self, ui: &UserInterface) {
+        let mut layouts = FxHashMap::default();
+        let mut panels = Vec::new();
+
+        let mut stack = vec![self.root];
+        while let Some(handle) = stack.pop() {
+            let node = ui.node(handle);
+            let children = node
+                .children()
+                .iter()
+                .copied()
+                .filter(|child| !self.excluded.contains(child))
+                .collect::<Vec<_>>();
+
+            let convert = is_layout_panel(node) && children.len() == node.children().len();
+            if convert {
+                panels.push(handle);
+                layouts
+                    .entry(handle)
+                    .or_insert_with(|| WidgetLayout::fixed_size(node));
+            }
+
+            if convert || node.cast::<Canvas>().is_some() {
+                for &child in children.iter() {
+                    layouts.insert(child, WidgetLayout::absolute(ui.node(child)));
+                }
+            }
+
+            stack.extend(children);
+        }
+
+        self.layouts = Some(layouts.into_iter().collect());
+        self.replacements = panels
+            .into_iter()
+            .map(|handle| {
+                (
+                    handle,
+                    UiNode::new(Canvas {
+                        widget: Default::default(),
+                    }),
+                )
+            })
+            .collect();
+    }
+
+    fn swap_widgets(&mut self, ui: &mut UserInterface) {
+        self.replacements = std::mem::take(&mut self.replacements)
+            .into_iter()
+            .map(|(handle, node)| (handle, replace_widget(ui, handle, node)))
+            .collect();
+    }
+
+    fn swap_layouts(&mut self, ui: &mut UserInterface) {
+        for (handle, layout) in self.layouts.iter_mut().flatten() {
+            layout.swap(ui.node_mut(*handle));
+        }
+    }
+}
+
+impl CommandTrait for BakeLayoutCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Bake Layout".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.layouts.is_none() {
+            if context.ui.try_get(self.root).is_none() {
+                Log::err(format!(
+                    "Unable to bake layout, because {} is not a valid widget!",
+                    self.root
+                ));
+                return;
+            }
+
+            self.bake(context.ui);
+        }
+
+        self.swap_widgets(context.ui);
+        self.swap_layouts(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap_layouts(context.ui);
+        self.swap_widgets(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+/// Axis along which [`MakeEqualColumnsCommand`] distributes widgets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqualCellsAxis {
+    /// Widgets are placed in equal columns, from left to right.
+    Columns,
+    /// Widgets are placed in equal rows, from top to bottom.
+    Rows,
+}
+
+/// Defines what [`MakeEqualColumnsCommand`] does when all the widgets are children of the same
+/// grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExistingGridMode {
+    /// Replaces the columns (or rows) of the existing grid with equal ones. Other dimension of the
+    /// grid is left untouched.
+    Reconfigure,
+    /// Creates a new grid inside the existing one, just like for any other parent.
+    CreateNew,
+}
+
+#[derive(Debug, Clone)]
+struct EqualCellEntry {
+    widget: Handle<UiNode>,
+    parent: Handle<UiNode>,
+    position: usize,
+    placement: WidgetPlacement,
+}
+
+/// Distributes widgets into equal-width columns (or equal-height rows) of a grid, in the order of
+/// the given widgets. By default, a new grid is created at the place of the first widget and the
+/// widgets are moved into it, see [`ExistingGridMode`] for the case when the widgets are already
+/// in the same grid.
+#[derive(Debug)]
+pub struct MakeEqualColumnsCommand {
+    widgets: Vec<Handle<UiNode>>,
+    axis: EqualCellsAxis,
+    count: Option<usize>,
+    existing_grid: ExistingGridMode,
+    entries: Option<Vec<EqualCellEntry>>,
+    grid: Handle<UiNode>,
+    created: bool,
+    sub_graph: Option<SubGraph>,
+    parent: Handle<UiNode>,
+    position: usize,
+    definitions: Vec<GridDimension>,
+    prev_selection: Selection,
+}
+
+impl MakeEqualColumnsCommand {
+    /// Creates a new command, that places the widgets in `count` equal columns. If `count` is
+    /// `None`, then the number of columns is equal to the number of widgets.
+    pub fn new(
+        widgets: Vec<Handle<UiNode>>,
+        count: Option<usize>,
+        existing_grid: ExistingGridMode,
+    ) -> Self {
+        Self {
+            widgets,
+            axis: EqualCellsAxis::Columns,
+            count,
+            existing_grid,
+            entries: None,
+            grid: Handle::NONE,
+            created: false,
+            sub_graph: None,
+            parent: Handle::NONE,
+            position: 0,
+            definitions: Default::default(),
+            prev_selection: Selection::new_empty(),
+        }
+    }
+
+    /// Same as [`Self::new`], but places the widgets in equal rows.
+    pub fn new_rows(
+        widgets: Vec<Handle<UiNode>>,
+        count: Option<usize>,
+        existing_grid: ExistingGridMode,
+    ) -> Self {
+        Self {
+            axis: EqualCellsAxis::Rows,
+            ..Self::new(widgets, count, existing_grid)
+        }
+    }
+
+    fn prepare(&mut self, ui: &mut UserInterface) -> bool {
+        let mut entries = Vec::<EqualCellEntry>::new();
+        for &widget in self.widgets.iter() {
+            if entries.iter().any(|entry| entry.widget == widget) {
+                continue;
+            }
+
+            let Some(widget_ref) = ui.try_get(widget) else {
+                Log::err(format!(
+                    "Unable to put {widget} in a grid, because it is not a valid widget!"
+                ));
+                return false;
+            };
+
+            let parent = widget_ref.parent();
+            if parent.is_none() {
+                Log::err("Unable to put the root widget in a grid!");
+                return false;
+            }
+
+            entries.push(EqualCellEntry {
+                widget,
+                parent,
+                position: ui.node(parent).child_position(widget).unwrap_or_default(),
+                placement: WidgetPlacement::of(widget_ref),
+            });
+        }
+
+        let Some(first) = entries.first().cloned() else {
+            Log::warn("There are no widgets to put in a grid.");
+            return false;
+        };
+
+        let mut count = self.count.unwrap_or(entries.len());
+        if count < entries.len() {
+            Log::warn(format!(
+                "{} widgets cannot fit in {count} cells, the grid will have {} cells instead.",
+                entries.len(),
+                entries.len()
+            ));
+            count = entries.len();
+        }
+        self.definitions = vec![GridDimension::stretch(); count];
+
+        let same_parent = entries.iter().all(|entry| entry.parent == first.parent);
+        if same_parent
+            && self.existing_grid == ExistingGridMode::Reconfigure
+            && ui.node(first.parent).cast::<Grid>().is_some()
+        {
+            self.grid = first.parent;
+            self.created = false;
+        } else {
+            self.parent = first.parent;
+            // Widgets of the parent will be moved into the grid, so the grid takes the place of
+            // the first of them.
+            self.position = entries
+                .iter()
+                .filter(|entry| entry.parent == self.parent)
+                .map(|entry| entry.position)
+                .min()
+                .unwrap_or_default();
+
+            let mut builder = GridBuilder::new(
+                WidgetBuilder::new()
+                    .with_name("Grid")
+                    .with_desired_position(first.placement.desired_position)
+                    .on_row(first.placement.row)
+                    .on_column(first.placement.column),
+            );
+            builder = match self.axis {
+                EqualCellsAxis::Columns => builder
+                    .add_columns(std::mem::take(&mut self.definitions))
+                    .add_row(Row::stretch()),
+                EqualCellsAxis::Rows => builder
+                    .add_rows(std::mem::take(&mut self.definitions))
+                    .add_column(Column::stretch()),
+            };
+            self.grid = builder.build(&mut ui.build_ctx());
+            self.created = true;
+        }
+
+        self.entries = Some(entries);
+
+        true
+    }
+
+    fn swap_definitions(&mut self, ui: &mut UserInterface) {
+        let grid = ui
+            .node_mut(self.grid)
+            .cast_mut::<Grid>()
+            .expect("Must be a grid!");
+        let definitions = match self.axis {
+            EqualCellsAxis::Columns => &mut grid.columns,
+            EqualCellsAxis::Rows => &mut grid.rows,
+        };
+        self.definitions = definitions
+            .set_value_and_mark_modified(RefCell::new(std::mem::take(&mut self.definitions)))
+            .into_inner();
+        grid.invalidate_layout();
+    }
+}
+
+impl CommandTrait for MakeEqualColumnsCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        match self.axis {
+            EqualCellsAxis::Columns => "Make Equal Columns",
+            EqualCellsAxis::Rows => "Make Equal Rows",
+        }
+        .to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            self.grid = context.ui.put_sub_graph_back(sub_graph);
+        } else {
+            if self.entries.is_none() && !self.prepare(context.ui) {
+                return;
+            }
+            if !self.created {
+                self.swap_definitions(context.ui);
+            }
+        }
+
+        if self.created {
+            context.ui.link_nodes(self.grid, self.parent, false);
+        }
+
+        for (index, entry) in self.entries.iter().flatten().enumerate() {
+            if self.created {
+                context.ui.link_nodes(entry.widget, self.grid, false);
+            }
+
+            // Cells of a new grid go along the axis only, an existing grid keeps the other axis.
+            let (row, column) = match (self.axis, self.created) {
+                (EqualCellsAxis::Columns, true) => (0, index),
+                (EqualCellsAxis::Columns, false) => (entry.placement.row, index),
+                (EqualCellsAxis::Rows, true) => (index, 0),
+                (EqualCellsAxis::Rows, false) => (index, entry.placement.column),
+            };
+            WidgetPlacement {
+                margin: entry.placement.margin,
+                desired_position: Vector2::default(),
+                row,
+                column,
+            }
+            .apply(context.ui.node_mut(entry.widget));
+        }
+
+        if self.created {
+            context
+                .ui
+                .node_mut(self.parent)
+                .set_child_position(self.grid, self.position);
+        }
+
+        self.prev_selection =
+            context.set_selection(Selection::new(UiSelection::single_or_empty(self.grid)));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        let Some(entries) = self.entries.as_ref() else {
+            return;
+        };
+
+        if self.created {
+            for entry in entries.iter() {
+                context.ui.link_nodes(entry.widget, entry.parent, false);
+            }
+
+            self.sub_graph = Some(context.ui.take_reserve_sub_graph(self.grid));
+
+            // Restore the order in ascending order of positions, so every widget lands exactly at
+            // its previous position.
+            let mut ordered = entries.iter().collect::<Vec<_>>();
+            ordered.sort_by_key(|entry| entry.position);
+            for entry in ordered {
+                context
+                    .ui
+                    .node_mut(entry.parent)
+                    .set_child_position(entry.widget, entry.position);
+            }
+        }
+
+        for entry in entries.iter() {
+            entry
+                .placement
+                .clone()
+                .apply(context.ui.node_mut(entry.widget));
+        }
+
+        if !self.created {
+            self.swap_definitions(context.ui);
+        }
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+
+    fn finalize(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+/// Name prefix of placeholder widgets, that are used as parameter slots of templates created by
+/// [`MakeTemplateCommand`]. For example, a widget named `$value` defines `value` parameter.
+pub const TEMPLATE_PARAMETER_PREFIX: &str = "$";
+
+/// A parameter slot of a template instance, see [`MakeTemplateCommand`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateParameter {
+    /// Name of the parameter (the name of the placeholder widget without the prefix).
+    pub name: String,
+    /// A handle of the placeholder widget in the template instance.
+    pub placeholder: Handle<UiNode>,
+}
+
+/// Saves a widget subtree as a template (a user interface resource) and replaces the subtree with
+/// an instance of the template. Widgets of the subtree whose names start with
+/// [`TEMPLATE_PARAMETER_PREFIX`] are parameter slots of the template, every such widget must be a
+/// leaf (have no children) and must have a unique name.
+///
+/// An instance fills a parameter slot by replacing the placeholder widget with any other widget:
+/// the new widget is linked to the parent of the placeholder at the position of the placeholder
+/// and takes its placement (margin, grid cell, etc.), then the placeholder is deleted. Since
+/// placeholders are leaves, nothing else of the template is lost by the replacement.
+///
+/// Reverting the command restores the inline subtree, the template resource is not deleted.
+#[derive(Debug)]
+pub struct MakeTemplateCommand {
+    root: Handle<UiNode>,
+    path: PathBuf,
+    resource_manager: ResourceManager,
+    instance: Handle<UiNode>,
+    parent: Handle<UiNode>,
+    position: usize,
+    parameters: Vec<TemplateParameter>,
+    instanced: bool,
+    // A sub graph, that is not in the user interface at the moment: either the inline subtree or
+    // the template instance.
+    sub_graph: Option<SubGraph>,
+}
+
+impl MakeTemplateCommand {
+    /// Creates a new command, the template will be saved to the given path.
+    pub fn new(root: Handle<UiNode>, path: PathBuf, resource_manager: ResourceManager) -> Self {
+        Self {
+            root,
+            path,
+            resource_manager,
+            instance: Handle::NONE,
+            parent: Handle::NONE,
+            position: 0,
+            parameters: Default::default(),
+            instanced: false,
+            sub_graph: None,
+        }
+    }
+
+    /// Returns parameter slots of the template instance. It is empty if the command was not
+    /// executed.
+    pub fn parameters(&self) -> &[TemplateParameter] {
+        &self.parameters
+    }
+
+    fn parameter_name(widget: &UiNode) -> Option<&str> {
+        widget.name().strip_prefix(TEMPLATE_PARAMETER_PREFIX)
+    }
+
+    fn validate(&self, ui: &UserInterface) -> Result<(), String> {
+        let Some(root_ref) = ui.try_get(self.root) else {
+            return Err(format!("{} is not a valid widget!", self.root));
+        };
+
+        if root_ref.parent().is_none() {
+            return Err("The root widget cannot be a template!".to_owned());
+        }
+
+        if Self::parameter_name(root_ref).is_some() {
+            return Err("The root of a template cannot be a parameter slot!".to_owned());
+        }
+
+        let mut names = Vec::new();
+        for handle in ui.traverse_handle_iter(self.root) {
+            let widget = ui.node(handle);
+            let Some(name) = Self::parameter_name(widget) else {
+                continue;
+            };
+
+            if name.is_empty() {
+                return Err(format!(
+                    "Parameter slot {handle} has no name after {TEMPLATE_PARAMETER_PREFIX} prefix!"
+                ));
+            }
+
+            if !widget.children().is_empty() {
+                return Err(format!(
+                    "Parameter slot {name} must be a leaf widget, so it could be replaced in \
+                    template instances!"
+                ));
+            }
+
+            if names.contains(&name) {
+                return Err(format!("Parameter slot {name} is defined more than once!"));
+            }
+
+            names.push(name);
+        }
+
+        if names.is_empty() {
+            Log::warn(format!(
+                "There are no parameter slots (widgets with {TEMPLATE_PARAMETER_PREFIX} name \
+                prefix) in the template."
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn make_template(&mut self, ui: &mut UserInterface) -> Result<(), String> {
+        self.validate(ui)?;
+
+        let mut template = UserInterface::new(ui.screen_size());
+        let (template_root, _) = ui.copy_node_to(self.root, &mut template, &mut |_, _, _| {});
+        // The instance root takes the placement of the subtree.
+        WidgetPlacement {
+            margin: Thickness::zero(),
+            desired_position: Vector2::default(),
+            row: 0,
+            column: 0,
+        }
+        .apply(template.node_mut(template_root));
+
+        template.save(&self.path).map_err(|err| err.to_string())?;
+
+        let resource = Resource::new_ok(Uuid::new_v4(), ResourceKind::External, template);
+        self.resource_manager
+            .register(resource.clone().into_untyped(), &self.path)
+            .map_err(|err| err.to_string())?;
+
+        let root_ref = ui.node(self.root);
+        let placement = WidgetPlacement::of(root_ref);
+        self.parent = root_ref.parent();
+        self.position = ui
+            .node(self.parent)
+            .child_position(self.root)
+            .unwrap_or_default();
+
+        let (instance, _) = resource.instantiate(ui);
+        self.instance = instance;
+        placement.apply(ui.node_mut(instance));
+
+        self.parameters = ui
+            .traverse_handle_iter(instance)
+            .filter_map(|handle| {
+                Self::parameter_name(ui.node(handle)).map(|name| TemplateParameter {
+                    name: name.to_owned(),
+                    placeholder: handle,
+                })
+            })
+            .collect();
+
+        // The instance is linked to the root canvas after instantiation, put it in a sub graph
+        // and then move it in place of the subtree as usual.
+        self.sub_graph = Some(ui.take_reserve_sub_graph(instance));
+
+        Ok(())
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        let Some(sub_graph) = self.sub_graph.take() else {
+            return;
+        };
+
+        let (current, next) = if self.instanced {
+            (self.instance, self.root)
+        } else {
+            (self.root, self.instance)
+        };
+        self.instanced = !self.instanced;
+
+        ui.put_sub_graph_back(sub_graph);
+        ui.link_nodes(next, self.parent, false);
+        self.sub_graph = Some(ui.take_reserve_sub_graph(current));
+        ui.node_mut(self.parent)
+            .set_child_position(next, self.position);
+    }
+}
+
+impl CommandTrait for MakeTemplateCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Make Template".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.instance.is_none() {
+            if let Err(err) = self.make_template(context.ui) {
+                Log::err(format!(
+                    "Unable to make a template of {}. Reason: {err}",
+                    self.root
+                ));
+                return;
+            }
+        }
+
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn finalize(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+/// Imports widget subtrees from another user interface document (`.ui` file) into the current one.
+/// The subtrees are deep-cloned the same way as the clipboard does on paste, so the imported widgets
+/// share resources (textures, fonts, etc.) with the rest of the document. The imported roots are
+/// linked to the given parent and selected.
+///
+/// The source document is loaded on the first execution. If it cannot be loaded (for example, it was
+/// saved by an incompatible version of the engine), the command does nothing and reports a warning.
+/// Resources of the imported widgets, that failed to load, are reported as warnings as well, the
+/// widgets keep the references to them and use default data instead.
+#[derive(Debug)]
+pub struct ImportSubtreeCommand {
+    path: PathBuf,
+    source_roots: Vec<Handle<UiNode>>,
+    parent: Handle<UiNode>,
+    resource_manager: ResourceManager,
+    state: PasteWidgetCommandState,
+}
+
+impl ImportSubtreeCommand {
+    /// Creates a new command. `source_roots` are the handles of the roots of the subtrees in the
+    /// source document, if it is empty, then every child widget of the root canvas of the source
+    /// document is imported.
+    pub fn new(
+        path: PathBuf,
+        source_roots: Vec<Handle<UiNode>>,
+        parent: Handle<UiNode>,
+        resource_manager: ResourceManager,
+    ) -> Self {
+        Self {
+            path,
+            source_roots,
+            parent,
+            resource_manager,
+            state: PasteWidgetCommandState::NonExecuted,
+        }
+    }
+
+    fn import(&self, ui: &mut UserInterface) -> Result<DeepCloneResult, String> {
+        if !ui.is_valid_handle(self.parent) {
+            return Err(format!("{} is not a valid widget!", self.parent));
+        }
+
+        let source = block_on(UserInterface::load_from_file(
+            &self.path,
+            self.resource_manager.clone(),
+        ))
+        .map_err(|err| format!("Unable to load the document. Reason: {err}"))?;
+
+        let source_roots = if self.source_roots.is_empty() {
+            source.node(source.root()).children().to_vec()
+        } else {
+            for &root in self.source_roots.iter() {
+                if !source.is_valid_handle(root) {
+                    return Err(format!("{root} is not a valid widget of the document!"));
+                }
+
+                if root == source.root() {
+                    return Err("The root widget of the document cannot be imported!".to_owned());
+                }
+            }
+
+            // Subtrees of other subtrees are imported along with them.
+            UiSelection {
+                widgets: self.source_roots.clone(),
+            }
+            .root_widgets(&source)
+        };
+
+        if source_roots.is_empty() {
+            return Err("There is nothing to import!".to_owned());
+        }
+
+        let mut resources = FxHashSet::default();
+        for &root in source_roots.iter() {
+            for handle in source.traverse_handle_iter(root) {
+                collect_used_resources(source.node(handle), &mut resources);
+            }
+        }
+        for resource in resources {
+            if let ResourceState::LoadError {
+                ref path,
+                ref error,
+            } = resource.0.lock().state
+            {
+                Log::warn(format!(
+                    "Resource {} used by the imported widgets failed to load and was substituted \
+                    with default data. Reason: {error:?}",
+                    path.display()
+                ));
+            }
+        }
+
+        Ok(deep_clone_nodes(&source_roots, &source, ui))
+    }
+}
+
+impl CommandTrait for ImportSubtreeCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Import Subtree".to_owned()
+    }
+
+    fn execute(&mut self, ctx: &mut dyn CommandContext) {
+        let ctx = ctx.get_mut::<UiSceneContext>();
+
+        let (import_result, mut selection) =
+            match std::mem::replace(&mut self.state, PasteWidgetCommandState::Undefined) {
+                PasteWidgetCommandState::NonExecuted => match self.import(ctx.ui) {
+                    Ok(import_result) => {
+                        let selection = Selection::new(UiSelection {
+                            widgets: import_result.root_nodes.clone(),
+                        });
+                        (import_result, selection)
+                    }
+                    Err(err) => {
+                        Log::warn(format!(
+                            "Unable to import widgets from {}. Reason: {err}",
+                            self.path.display()
+                        ));
+                        self.state = PasteWidgetCommandState::NonExecuted;
+                        return;
+                    }
+                },
+                PasteWidgetCommandState::Reverted {
+                    subgraphs,
+                    selection,
+                } => {
+                    let import_result = DeepCloneResult {
+                        root_nodes: subgraphs
+                            .into_iter()
+                            .map(|subgraph| ctx.ui.put_sub_graph_back(subgraph))
+                            .collect(),
+                    };
+                    (import_result, selection)
+                }
+                _ => unreachable!(),
+            };
+
+        for &handle in import_result.root_nodes.iter() {
+            ctx.ui.link_nodes(handle, self.parent, false);
+        }
+
+        std::mem::swap(ctx.selection, &mut selection);
+        self.state = PasteWidgetCommandState::Executed {
+            paste_result: import_result,
+            last_selection: selection,
+        };
+
+        ctx.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, ctx: &mut dyn CommandContext) {
+        let ctx = ctx.get_mut::<UiSceneContext>();
+
+        if let PasteWidgetCommandState::Executed {
+            paste_result,
+            mut last_selection,
+        } = std::mem::replace(&mut self.state, PasteWidgetCommandState::Undefined)
+        {
+            let subgraphs = paste_result
+                .root_nodes
+                .into_iter()
+                .map(|root_node| ctx.ui.take_reserve_sub_graph(root_node))
+                .collect();
+
+            std::mem::swap(ctx.selection, &mut last_selection);
+
+            self.state = PasteWidgetCommandState::Reverted {
+                subgraphs,
+                selection: last_selection,
+            };
+
+            ctx.message_sender.send(Message::ForceSync);
+        }
+    }
+
+    fn finalize(&mut self, ctx: &mut dyn CommandContext) {
+        let ctx = ctx.get_mut::<UiSceneContext>();
+
+        if let PasteWidgetCommandState::Reverted { subgraphs, .. } =
+            std::mem::replace(&mut self.state, PasteWidgetCommandState::Undefined)
+        {
+            for subgraph in subgraphs {
+                ctx.ui.forget_sub_graph(subgraph);
+            }
+        }
+    }
+}
+
+/// Type of a field of a form generated by [`GenerateFormCommand`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormFieldType {
+    String,
+    Bool,
+    I32,
+    I64,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+impl FormFieldType {
+    fn is_numeric(self) -> bool {
+        !matches!(self, FormFieldType::String | FormFieldType::Bool)
+    }
+
+    fn default_editor(self) -> FormEditorKind {
+        match self {
+            FormFieldType::String => FormEditorKind::TextBox,
+            FormFieldType::Bool => FormEditorKind::CheckBox,
+            _ => FormEditorKind::NumericUpDown,
+        }
+    }
+}
+
+/// Kind of an editor widget of a field of a form generated by [`GenerateFormCommand`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormEditorKind {
+    /// A text box, that could be used for a field of any type.
+    TextBox,
+    /// A check box, that could be used for [`FormFieldType::Bool`] fields only.
+    CheckBox,
+    /// A numeric up-down, that could be used for numeric fields only. The type of the value of
+    /// the widget matches the type of the field.
+    NumericUpDown,
+}
+
+/// A description of a field of a form generated by [`GenerateFormCommand`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormField {
+    /// Name of the field. It is used as the text of the label and in the names of the generated
+    /// widgets, so it must be unique within a form.
+    pub name: String,
+    /// Type of the field.
+    pub ty: FormFieldType,
+    /// Kind of the editor of the field. [`None`] means that the editor is picked by the type of
+    /// the field: a text box for strings, a check box for booleans, a numeric up-down for numbers.
+    pub editor: Option<FormEditorKind>,
+}
+
+impl FormField {
+    fn editor(&self) -> Result<FormEditorKind, String> {
+        let editor = self.editor.unwrap_or_else(|| self.ty.default_editor());
+        let compatible = match editor {
+            FormEditorKind::TextBox => true,
+            FormEditorKind::CheckBox => self.ty == FormFieldType::Bool,
+            FormEditorKind::NumericUpDown => self.ty.is_numeric(),
+        };
+        if compatible {
+            Ok(editor)
+        } else {
+            Err(format!(
+                "{editor:?} editor cannot be used for {} field of {:?} type!",
+                self.name, self.ty
+            ))
+        }
+    }
+}
+
+fn build_numeric_editor<T: NumericType>(
+    widget_builder: WidgetBuilder,
+    precision: usize,
+    ctx: &mut BuildContext,
+) -> Handle<UiNode> {
+    NumericUpDownBuilder::<T>::new(widget_builder)
+        .with_precision(precision)
+        .build(ctx)
+}
+
+/// Generates a form for the given list of fields and adds it to the parent widget. The form is a
+/// grid with two columns, where each row has a label with the name of a field and an editor of the
+/// field. The generated widgets have predictable names, so they could be found later to bind them
+/// to the data: the grid is named `Form`, a label is named `<field>Label` and an editor is named
+/// `<field>Editor`. On execution, the selection is changed to the grid.
+#[derive(Debug)]
+pub struct GenerateFormCommand {
+    parent: Handle<UiNode>,
+    fields: Vec<FormField>,
+    form: Handle<UiNode>,
+    sub_graph: Option<SubGraph>,
+    prev_selection: Selection,
+}
+
+impl GenerateFormCommand {
+    pub fn new(parent: Handle<UiNode>, fields: Vec<FormField>) -> Self {
+        Self {
+            parent,
+            fields,
+            form: Handle::NONE,
+            sub_graph: None,
+            prev_selection: Selection::new_empty(),
+        }
+    }
+
+    fn create_form(&mut self, ui: &mut UserInterface) -> Result<(), String> {
+        if !ui.is_valid_handle(self.parent) {
+            return Err(format!("{} is not a valid widget!", self.parent));
+        }
+
+        if self.fields.is_empty() {
+            return Err("There are no fields!".to_owned());
+        }
+
+        let mut names = FxHashSet::default();
+        let mut editors = Vec::with_capacity(self.fields.len());
+        for field in self.fields.iter() {
+            if field.name.is_empty() {
+                return Err("Field name cannot be empty!".to_owned());
+            }
+            if !names.insert(field.name.as_str()) {
+                return Err(format!("There are multiple fields named {}!", field.name));
+            }
+            editors.push(field.editor()?);
+        }
+
+        let ctx = &mut ui.build_ctx();
+        let mut children = Vec::with_capacity(self.fields.len() * 2);
+        for (row, (field, editor)) in self.fields.iter().zip(editors).enumerate() {
+            children.push(
+                TextBuilder::new(
+                    WidgetBuilder::new()
+                        .with_name(&format!("{}Label", field.name))
+                        .with_margin(Thickness::uniform(1.0))
+                        .with_vertical_alignment(VerticalAlignment::Center)
+                        .on_row(row)
+                        .on_column(0),
+                )
+                .with_text(field.name.clone())
+                .build(ctx),
+            );
+
+            let widget_builder = WidgetBuilder::new()
+                .with_name(&format!("{}Editor", field.name))
+                .with_margin(Thickness::uniform(1.0))
+                .on_row(row)
+                .on_column(1);
+            children.push(match editor {
+                FormEditorKind::TextBox => TextBoxBuilder::new(widget_builder).build(ctx),
+                FormEditorKind::CheckBox => CheckBoxBuilder::new(
+                    widget_builder.with_horizontal_alignment(HorizontalAlignment::Left),
+                )
+                .checked(Some(false))
+                .build(ctx),
+                FormEditorKind::NumericUpDown => match field.ty {
+                    FormFieldType::I32 => build_numeric_editor::<i32>(widget_builder, 0, ctx),
+                    FormFieldType::I64 => build_numeric_editor::<i64>(widget_builder, 0, ctx),
+                    FormFieldType::U32 => build_numeric_editor::<u32>(widget_builder, 0, ctx),
+                    FormFieldType::U64 => build_numeric_editor::<u64>(widget_builder, 0, ctx),
+                    FormFieldType::F64 => build_numeric_editor::<f64>(widget_builder, 3, ctx),
+                    _ => build_numeric_editor::<f32>(widget_builder, 3, ctx),
+                },
+            });
+        }
+
+        self.form = GridBuilder::new(
+            WidgetBuilder::new()
+                .with_name("Form")
+                .with_children(children),
+        )
+        .add_column(Column::auto())
+        .add_column(Column::stretch())
+        .add_rows(self.fields.iter().map(|_| Row::auto()).collect())
+        .build(ctx);
+
+        Ok(())
+    }
+}
+
+impl CommandTrait for GenerateFormCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Generate Form".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            self.form = context.ui.put_sub_graph_back(sub_graph);
+        } else if let Err(err) = self.create_form(context.ui) {
+            Log::err(format!("Unable to generate a form. Reason: {err}"));
+            return;
+        }
+
+        context.ui.link_nodes(self.form, self.parent, false);
+
+        self.prev_selection =
+            context.set_selection(Selection::new(UiSelection::single_or_empty(self.form)));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.form.is_none() {
+            return;
+        }
+
+        self.sub_graph = Some(context.ui.take_reserve_sub_graph(self.form));
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+
+    fn finalize(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+/// A button of a dialog created by [`MakeModalDialogCommand`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogButton {
+    Ok,
+    Cancel,
+}
+
+impl DialogButton {
+    fn text(self) -> &'static str {
+        match self {
+            DialogButton::Ok => "OK",
+            DialogButton::Cancel => "Cancel",
+        }
+    }
+}
+
+/// Creates a modal dialog scaffold and puts the given widgets in it. The scaffold consists of a
+/// semi-transparent backdrop (sized to the screen size of the UI at the moment of creation) and a
+/// panel centered on it, the panel contains the widgets
+/// (stacked vertically) and a row of the given buttons (in the given order). The scaffold is linked
+/// to the root of the UI as its last child, so it is drawn over everything else and the backdrop
+/// (which is hit-test visible) intercepts the input that is meant for the widgets under it.
+///
+/// Descendants of other widgets in the list are moved along with their ancestors. The widgets keep
+/// their own placement properties, revert puts them back to their parents at their original
+/// positions.
+#[derive(Debug)]
+pub struct MakeModalDialogCommand {
+    widgets: Vec<Handle<UiNode>>,
+    buttons: Vec<DialogButton>,
+    backdrop: Handle<UiNode>,
+    content: Handle<UiNode>,
+    sub_graph: Option<SubGraph>,
+    // Moved widgets with their original parents and positions among the children of the parents,
+    // sorted by the position.
+    targets: Vec<(Handle<UiNode>, Handle<UiNode>, usize)>,
+    prev_selection: Selection,
+}
+
+impl MakeModalDialogCommand {
+    pub fn new(selection: &UiSelection, buttons: Vec<DialogButton>) -> Self {
+        Self {
+            widgets: selection.widgets.clone(),
+            buttons,
+            backdrop: Handle::NONE,
+            content: Handle::NONE,
+            sub_graph: None,
+            targets: Default::default(),
+            prev_selection: Selection::new_empty(),
+        }
+    }
+
+    fn create_dialog(&mut self, ui: &mut UserInterface) -> Result<(), String> {
+        let widgets = UiSelection {
+            widgets: self
+                .widgets
+                .iter()
+                .copied()
+                .filter(|handle| ui.is_valid_handle(*handle))
+                .collect(),
+        }
+        .root_widgets(ui);
+
+        if widgets.is_empty() {
+            return Err("There are no widgets selected!".to_owned());
+        }
+
+        let mut targets = Vec::with_capacity(widgets.len());
+        for handle in widgets {
+            let widget = ui.node(handle);
+            let parent = widget.parent();
+            let Some(parent_ref) = ui.try_get(parent) else {
+                return Err(format!(
+                    "{} is the root widget, it cannot be moved to a dialog!",
+                    widget.name()
+                ));
+            };
+            let position = parent_ref.child_position(handle).unwrap_or_default();
+            targets.push((handle, parent, position));
+        }
+        targets.sort_by_key(|(_, _, position)| *position);
+
+        // The root is a canvas, which does not stretch its children, so the backdrop must be
+        // explicitly sized to cover the whole screen.
+        let screen_size = ui.screen_size();
+        let ctx = &mut ui.build_ctx();
+        self.content = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_name("Content")
+                .with_children(targets.iter().map(|(handle, _, _)| *handle)),
+        )
+        .build(ctx);
+        let buttons = self
+            .buttons
+            .iter()
+            .map(|button| {
+                ButtonBuilder::new(
+                    WidgetBuilder::new()
+                        .with_name(button.text())
+                        .with_width(80.0)
+                        .with_margin(Thickness::uniform(2.0)),
+                )
+                .with_text(button.text())
+                .build(ctx)
+            })
+            .collect::<Vec<_>>();
+        let mut panel_children = vec![self.content];
+        if !buttons.is_empty() {
+            panel_children.push(
+                StackPanelBuilder::new(
+                    WidgetBuilder::new()
+                        .with_name("Buttons")
+                        .with_horizontal_alignment(HorizontalAlignment::Right)
+                        .with_children(buttons),
+                )
+                .with_orientation(Orientation::Horizontal)
+                .build(ctx),
+            );
+        }
+        let panel = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_name("DialogPanel")
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Center)
+                .with_child(
+                    StackPanelBuilder::new(
+                        WidgetBuilder::new()
+                            .with_margin(Thickness::uniform(4.0))
+                            .with_children(panel_children),
+                    )
+                    .build(ctx),
+                ),
+        )
+        .build(ctx);
+        self.backdrop = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_name("ModalDialog")
+                .with_width(screen_size.x)
+                .with_height(screen_size.y)
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 150)).into())
+                .with_child(panel),
+        )
+        .with_stroke_thickness(Thickness::zero().into())
+        .build(ctx);
+
+        self.targets = targets;
+
+        Ok(())
+    }
+}
+
+impl CommandTrait for MakeModalDialogCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Make Modal Dialog".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            self.backdrop = context.ui.put_sub_graph_back(sub_graph);
+            for &(target, _, _) in self.targets.iter() {
+                context.ui.link_nodes(target, self.content, false);
+            }
+        } else if let Err(err) = self.create_dialog(context.ui) {
+            Log::err(format!("Unable to make a modal dialog. Reason: {err}"));
+            return;
+        }
+
+        // The last child of the root is drawn on top of everything else.
+        let root = context.ui.root();
+        context.ui.link_nodes(self.backdrop, root, false);
+
+        self.prev_selection =
+            context.set_selection(Selection::new(UiSelection::single_or_empty(self.backdrop)));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.backdrop.is_none() {
+            return;
+        }
+
+        for &(target, parent, _) in self.targets.iter() {
+            context.ui.link_nodes(target, parent, false);
+        }
+
+        self.sub_graph = Some(context.ui.take_reserve_sub_graph(self.backdrop));
+
+        // The positions are sorted, so each widget is put after every widget that preceded it.
+        for &(target, parent, position) in self.targets.iter() {
+            context
+                .ui
+                .node_mut(parent)
+                .set_child_position(target, position);
+        }
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+
+    fn finalize(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+/// Creates a breadcrumb bar for the given widget and adds it to the parent widget. The bar is a
+/// horizontal stack panel with a button per widget on the path from the topmost ancestor (the
+/// root of the UI is excluded) to the widget itself, the buttons are separated by text separators.
+/// A click of each button is bound to a handler named `select_widget:<id>`, where `<id>` is the
+/// id of the corresponding widget, so a handler could find and select the widget.
+///
+/// If the maximum number of segments is specified and the path is longer, the bar is truncated:
+/// it keeps the first segment, then an ellipsis segment, then the last segments. On execution, the
+/// selection is changed to the bar.
+#[derive(Debug)]
+pub struct MakeBreadcrumbsCommand {
+    target: Handle<UiNode>,
+    parent: Handle<UiNode>,
+    max_segments: Option<usize>,
+    bar: Handle<UiNode>,
+    sub_graph: Option<SubGraph>,
+    prev_selection: Selection,
+}
+
+impl MakeBreadcrumbsCommand {
+    pub fn new(
+        target: Handle<UiNode>,
+        parent: Handle<UiNode>,
+        max_segments: Option<usize>,
+    ) -> Self {
+        Self {
+            target,
+            parent,
+            max_segments,
+            bar: Handle::NONE,
+            sub_graph: None,
+            prev_selection: Selection::new_empty(),
+        }
+    }
+
+    fn create_bar(&mut self, ui: &mut UserInterface) -> Result<(), String> {
+        if !ui.is_valid_handle(self.target) {
+            return Err(format!("{} is not a valid widget!", self.target));
+        }
+
+        if !ui.is_valid_handle(self.parent) {
+            return Err(format!("{} is not a valid widget!", self.parent));
+        }
+
+        // The first and the last segments are always kept, so there must be room for both.
+        if self
+            .max_segments
+            .is_some_and(|max_segments| max_segments < 2)
+        {
+            return Err("Maximum number of segments must be at least 2!".to_owned());
+        }
+
+        let root = ui.root();
+        let mut path = Vec::new();
+        let mut handle = self.target;
+        while handle.is_some() && handle != root {
+            let widget = ui.node(handle);
+            path.push((widget.name().to_owned(), widget.id));
+            handle = widget.parent();
+        }
+        path.reverse();
+
+        if path.is_empty() {
+            return Err("The root widget cannot have breadcrumbs!".to_owned());
+        }
+
+        let truncated = match self.max_segments {
+            Some(max_segments) if path.len() > max_segments => {
+                path.drain(1..path.len() - (max_segments - 1));
+                true
+            }
+            _ => false,
+        };
+
+        let ctx = &mut ui.build_ctx();
+        let make_separator = |ctx: &mut BuildContext| {
+            TextBuilder::new(
+                WidgetBuilder::new()
+                    .with_name("Separator")
+                    .with_margin(Thickness::left_right(2.0))
+                    .with_vertical_alignment(VerticalAlignment::Center),
+            )
+            .with_text(">")
+            .build(ctx)
+        };
+        let mut children = Vec::with_capacity(path.len() * 2);
+        for (i, (name, id)) in path.into_iter().enumerate() {
+            if i > 0 {
+                children.push(make_separator(ctx));
+            }
+            children.push(
+                ButtonBuilder::new(
+                    WidgetBuilder::new()
+                        .with_name(&format!("{name}Segment"))
+                        .with_event_handler(WidgetEvent::Click, format!("select_widget:{id}")),
+                )
+                .with_text(&name)
+                .build(ctx),
+            );
+            if i == 0 && truncated {
+                children.push(make_separator(ctx));
+                children.push(
+                    TextBuilder::new(
+                        WidgetBuilder::new()
+                            .with_name("Ellipsis")
+                            .with_vertical_alignment(VerticalAlignment::Center),
+                    )
+                    .with_text("...")
+                    .build(ctx),
+                );
+            }
+        }
+
+        self.bar = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_name("Breadcrumbs")
+                .with_children(children),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+
+        Ok(())
+    }
+}
+
+impl CommandTrait for MakeBreadcrumbsCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Make Breadcrumbs".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            self.bar = context.ui.put_sub_graph_back(sub_graph);
+        } else if let Err(err) = self.create_bar(context.ui) {
+            Log::err(format!("Unable to make breadcrumbs. Reason: {err}"));
+            return;
+        }
+
+        context.ui.link_nodes(self.bar, self.parent, false);
+
+        self.prev_selection =
+            context.set_selection(Selection::new(UiSelection::single_or_empty(self.bar)));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.bar.is_none() {
+            return;
+        }
+
+        self.sub_graph = Some(context.ui.take_reserve_sub_graph(self.bar));
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+
+    fn finalize(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fyrox::{
+        core::algebra::Vector2,
+        graph::BaseSceneGraph,
+        gui::{border::BorderBuilder, widget::WidgetBuilder, UserInterface},
+    };
+    use crate::ui_scene::{
+        commands::graph::{DialogButton, MakeModalDialogCommand},
+        UiSelection,
+    };
+
+    #[test]
+    fn test_modal_dialog_backdrop_covers_screen() {
+        let screen_size = Vector2::new(800.0, 600.0);
+        let mut ui = UserInterface::new(screen_size);
+        let widget = BorderBuilder::new(WidgetBuilder::new().with_width(50.0).with_height(20.0))
+            .build(&mut ui.build_ctx());
+
+        let mut command = MakeModalDialogCommand::new(
+            &UiSelection {
+                widgets: vec![widget],
+            },
+            vec![DialogButton::Ok],
+        );
+        command.create_dialog(&mut ui).unwrap();
+        let root = ui.root();
+        ui.link_nodes(command.backdrop, root, false);
+        ui.update(screen_size, 0.0, &Default::default());
+
+        let backdrop = ui.node(command.backdrop);
+        assert_eq!(backdrop.actual_local_size(), screen_size);
+        assert_eq!(backdrop.actual_local_position(), Vector2::default());
+
+        // The panel is centered on the backdrop.
+        let panel = ui.node(backdrop.children()[0]);
+        let panel_center = panel.actual_local_position() + panel.actual_local_size().scale(0.5);
+        assert!((panel_center - screen_size.scale(0.5)).norm() < 1.0);
+    }
+}
@@ -19,12 +19,14 @@
 // SOFTWARE.
 
 pub mod graph;
+pub mod navigation;
+pub mod style;
 pub mod widget;
 
 use crate::fyrox::{core::type_traits::prelude::*, gui::UserInterface};
 use crate::{
     command::CommandContext, message::MessageSender, scene::Selection,
-    ui_scene::clipboard::Clipboard,
+    ui_scene::clipboard::Clipboard, Message,
 };
 
 #[derive(ComponentProvider)]
@@ -59,6 +61,24 @@ impl UiSceneContext {
             }
         });
     }
+
+    /// Sets the new selection and returns the previous one. See [`Self::swap_selection`] for
+    /// more info.
+    pub fn set_selection(&mut self, mut selection: Selection) -> Selection {
+        self.swap_selection(&mut selection);
+        selection
+    }
+
+    /// Swaps the current selection with the given one. The editor is notified about the change of
+    /// the selection, and it is forced to sync its views (the world viewer, the inspector, etc.)
+    /// with the UI, because commands that change the selection usually change the hierarchy too.
+    pub fn swap_selection(&mut self, selection: &mut Selection) {
+        std::mem::swap(self.selection, selection);
+        self.message_sender.send(Message::SelectionChanged {
+            old_selection: selection.clone(),
+        });
+        self.message_sender.send(Message::ForceSync);
+    }
 }
 
 impl CommandContext for UiSceneContext {}
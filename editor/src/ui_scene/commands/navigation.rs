@@ -0,0 +1,139 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Keyboard and gamepad navigation commands.
+
+use crate::command::{CommandContext, CommandTrait};
+use crate::fyrox::{
+    core::{log::Log, pool::Handle},
+    graph::{BaseSceneGraph, SceneGraph},
+    gui::{UiNode, UserInterface},
+};
+use crate::{ui_scene::commands::UiSceneContext, Message};
+
+/// Defines how tab indices of widgets that are not part of [`SetTabOrderCommand`] are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabOrderMode {
+    /// Only the given widgets will get new tab indices, starting from `start`. Tab indices of other
+    /// widgets stay untouched, which could lead to duplicated tab indices.
+    Local {
+        /// Tab index of the first widget.
+        start: usize,
+    },
+    /// The given widgets will be inserted at the `position` of the tab order of the whole UI and
+    /// then each widget that has a tab index will be renumbered, so the indices are contiguous.
+    Global {
+        /// Position in the tab order of the UI at which the widgets will be inserted.
+        position: usize,
+    },
+}
+
+/// Assigns incrementing tab indices to a set of widgets in the given order.
+#[derive(Debug)]
+pub struct SetTabOrderCommand {
+    widgets: Vec<Handle<UiNode>>,
+    mode: TabOrderMode,
+    old_values: Vec<(Handle<UiNode>, Option<usize>)>,
+}
+
+impl SetTabOrderCommand {
+    /// Creates a new command that assigns tab indices to the given widgets. Order of the widgets
+    /// defines their order in the tab navigation.
+    pub fn new(widgets: Vec<Handle<UiNode>>, mode: TabOrderMode) -> Self {
+        Self {
+            widgets,
+            mode,
+            old_values: Default::default(),
+        }
+    }
+
+    fn new_tab_order(&self, ui: &UserInterface) -> Vec<(Handle<UiNode>, Option<usize>)> {
+        match self.mode {
+            TabOrderMode::Local { start } => self
+                .widgets
+                .iter()
+                .enumerate()
+                .map(|(i, handle)| (*handle, Some(start + i)))
+                .collect(),
+            TabOrderMode::Global { position } => {
+                let mut others = ui
+                    .pair_iter()
+                    .filter(|(handle, _)| !self.widgets.contains(handle))
+                    .filter_map(|(handle, widget)| widget.tab_index.map(|index| (index, handle)))
+                    .collect::<Vec<_>>();
+                // Sort by handle as well to make the order deterministic when there are duplicates.
+                others.sort_unstable_by_key(|(index, handle)| (*index, handle.index()));
+
+                let position = position.min(others.len());
+                let mut order = others[..position]
+                    .iter()
+                    .map(|(_, handle)| *handle)
+                    .collect::<Vec<_>>();
+                order.extend_from_slice(&self.widgets);
+                order.extend(others[position..].iter().map(|(_, handle)| *handle));
+
+                order
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, handle)| (handle, Some(i)))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetTabOrderCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Tab Order".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+        let ui = &mut *context.ui;
+
+        if let Some(invalid) = self.widgets.iter().find(|h| !ui.is_valid_handle(**h)) {
+            Log::err(format!(
+                "Unable to set tab order, because {invalid} is not a valid widget!"
+            ));
+            return;
+        }
+
+        for (handle, tab_index) in self.new_tab_order(ui) {
+            let widget = ui.node_mut(handle);
+            let old_value = widget.tab_index.set_value_and_mark_modified(tab_index);
+            self.old_values.push((handle, old_value));
+        }
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+        let ui = &mut *context.ui;
+
+        for (handle, old_value) in self.old_values.drain(..).rev() {
+            if let Some(widget) = ui.try_get_node_mut(handle) {
+                widget.tab_index.set_value_and_mark_modified(old_value);
+            }
+        }
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
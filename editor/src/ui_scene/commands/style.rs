@@ -0,0 +1,422 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::command::{CommandContext, CommandTrait};
+use crate::fyrox::{
+    asset::ResourceData,
+    core::{color::Color, log::Log, pool::Handle, reflect::prelude::*, sstorage::ImmutableString},
+    fxhash::FxHashMap,
+    graph::BaseSceneGraph,
+    gui::{
+        brush::Brush,
+        style::{resource::StyleResource, Style, StyleProperty, StyledProperty},
+        texture::TextureResource,
+        Thickness, UiNode, UserInterface,
+    },
+};
+use crate::{ui_scene::commands::UiSceneContext, Message};
+use std::{any::Any, path::PathBuf};
+
+// Styled properties are stored either directly in a widget or in its inner structures (such as
+// formatted text), so there's no need to go deeper.
+const MAX_DEPTH: usize = 3;
+
+fn as_style_property(any: &dyn Any) -> Option<StyleProperty> {
+    if let Some(value) = any.downcast_ref::<StyledProperty<f32>>() {
+        Some(value.property.into())
+    } else if let Some(value) = any.downcast_ref::<StyledProperty<Thickness>>() {
+        Some(value.property.into())
+    } else if let Some(value) = any.downcast_ref::<StyledProperty<Color>>() {
+        Some(value.property.into())
+    } else if let Some(value) = any.downcast_ref::<StyledProperty<Brush>>() {
+        Some(value.property.clone().into())
+    } else {
+        any.downcast_ref::<StyledProperty<TextureResource>>()
+            .map(|value| value.property.clone().into())
+    }
+}
+
+fn styled_property_name(any: &dyn Any) -> Option<ImmutableString> {
+    if let Some(value) = any.downcast_ref::<StyledProperty<f32>>() {
+        Some(value.name.clone())
+    } else if let Some(value) = any.downcast_ref::<StyledProperty<Thickness>>() {
+        Some(value.name.clone())
+    } else if let Some(value) = any.downcast_ref::<StyledProperty<Color>>() {
+        Some(value.name.clone())
+    } else if let Some(value) = any.downcast_ref::<StyledProperty<Brush>>() {
+        Some(value.name.clone())
+    } else {
+        any.downcast_ref::<StyledProperty<TextureResource>>()
+            .map(|value| value.name.clone())
+    }
+}
+
+fn make_styled_property(property: StyleProperty, name: ImmutableString) -> Box<dyn Reflect> {
+    match property {
+        StyleProperty::Number(value) => Box::new(StyledProperty::new(value, name)),
+        StyleProperty::Thickness(value) => Box::new(StyledProperty::new(value, name)),
+        StyleProperty::Color(value) => Box::new(StyledProperty::new(value, name)),
+        StyleProperty::Brush(value) => Box::new(StyledProperty::new(value, name)),
+        StyleProperty::Texture(value) => Box::new(StyledProperty::new(value, name)),
+    }
+}
+
+// Calls the given function for every styled property with its path, name in a style and value.
+fn for_each_styled_property(
+    object: &dyn Reflect,
+    parent_path: &str,
+    depth: usize,
+    func: &mut dyn FnMut(String, ImmutableString, StyleProperty),
+) {
+    object.fields_ref(&mut |fields_ref| {
+        for field_info in fields_ref.iter() {
+            let path = if parent_path.is_empty() {
+                field_info.name.to_owned()
+            } else {
+                format!("{}.{}", parent_path, field_info.name)
+            };
+
+            let field = field_info.value.field_value_as_reflect();
+
+            let mut property = None;
+            field.as_any(&mut |any| {
+                property = as_style_property(any).zip(styled_property_name(any))
+            });
+
+            if let Some((property, name)) = property {
+                func(path, name, property);
+            } else if depth < MAX_DEPTH {
+                for_each_styled_property(field, &path, depth + 1, func);
+            }
+        }
+    })
+}
+
+fn collect_styled_properties(
+    object: &dyn Reflect,
+    properties: &mut FxHashMap<String, StyleProperty>,
+) {
+    for_each_styled_property(object, "", 0, &mut |path, _, property| {
+        properties.insert(path, property);
+    })
+}
+
+/// Searches for styled properties, that have the same value across all the given widgets. Returns
+/// a list of pairs `(property path, value)` sorted by property path. The list could be used to let
+/// the user decide which properties should be included in a style created by
+/// [`CreateStyleFromSelectionCommand`].
+pub fn find_common_style_properties(
+    ui: &UserInterface,
+    widgets: &[Handle<UiNode>],
+) -> Vec<(String, StyleProperty)> {
+    let mut common: Option<FxHashMap<String, StyleProperty>> = None;
+
+    for &handle in widgets {
+        let Some(node) = ui.try_get(handle) else {
+            continue;
+        };
+
+        let mut properties = FxHashMap::default();
+        node.as_reflect(&mut |reflect| collect_styled_properties(reflect, &mut properties));
+
+        match common.as_mut() {
+            Some(common) => {
+                common.retain(|path, value| properties.get(path) == Some(value));
+            }
+            None => common = Some(properties),
+        }
+    }
+
+    let mut common = common.unwrap_or_default().into_iter().collect::<Vec<_>>();
+    common.sort_by(|(a, _), (b, _)| a.cmp(b));
+    common
+}
+
+/// Creates a new style resource at the given path from the styled properties that have the same
+/// value across all the given widgets. Style variables are named by the paths of the respective
+/// properties. Reverting the command deletes the created resource.
+#[derive(Debug)]
+pub struct CreateStyleFromSelectionCommand {
+    widgets: Vec<Handle<UiNode>>,
+    path: PathBuf,
+    included: Option<Vec<String>>,
+    created: bool,
+}
+
+impl CreateStyleFromSelectionCommand {
+    /// Creates a new command. `included` allows you to specify which of the common properties
+    /// (see [`find_common_style_properties`]) should be written to the style, [`None`] means all
+    /// of them.
+    pub fn new(widgets: Vec<Handle<UiNode>>, path: PathBuf, included: Option<Vec<String>>) -> Self {
+        Self {
+            widgets,
+            path,
+            included,
+            created: false,
+        }
+    }
+}
+
+impl CommandTrait for CreateStyleFromSelectionCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Create Style From Selection".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.path.exists() {
+            Log::err(format!(
+                "Unable to create a style, because {} already exists!",
+                self.path.display()
+            ));
+            return;
+        }
+
+        let properties = find_common_style_properties(context.ui, &self.widgets)
+            .into_iter()
+            .filter(|(path, _)| match self.included {
+                Some(ref included) => included.contains(path),
+                None => true,
+            })
+            .collect::<Vec<_>>();
+
+        if properties.is_empty() {
+            Log::err(
+                "Unable to create a style, because the selected widgets do not have \
+                any styled properties with the same values!",
+            );
+            return;
+        }
+
+        let mut style = Style::default();
+        for (path, property) in properties {
+            style.set(path, property);
+        }
+
+        match style.save(&self.path) {
+            Ok(_) => {
+                self.created = true;
+                context.message_sender.send(Message::ForceSync);
+            }
+            Err(err) => Log::err(format!(
+                "Unable to save a style to {}. Reason: {err:?}",
+                self.path.display()
+            )),
+        }
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if std::mem::take(&mut self.created) {
+            Log::verify(std::fs::remove_file(&self.path));
+            context.message_sender.send(Message::ForceSync);
+        }
+    }
+}
+
+/// A summary of changes, that [`ApplyThemeCommand`] makes (or would make) to a UI document, see
+/// [`preview_theme`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ThemeReport {
+    /// Amount of widgets with at least one changed property.
+    pub affected_widgets: usize,
+    /// Total amount of changed properties.
+    pub changed_properties: usize,
+    /// Amount of widgets that were left untouched, because they or their ancestors have their own
+    /// styling (see [`crate::fyrox::gui::widget::Widget::theme_override`]).
+    pub skipped_widgets: usize,
+}
+
+#[derive(Debug)]
+struct ThemeChange {
+    handle: Handle<UiNode>,
+    path: String,
+    name: ImmutableString,
+    property: StyleProperty,
+}
+
+// Collects the styled properties of the document, that are bound to a variable of the theme and
+// have different value.
+fn collect_theme_changes(ui: &UserInterface, theme: &Style) -> (Vec<ThemeChange>, ThemeReport) {
+    let mut changes = Vec::new();
+    let mut report = ThemeReport::default();
+
+    let mut stack = vec![ui.root()];
+    while let Some(handle) = stack.pop() {
+        let Some(node) = ui.try_get(handle) else {
+            continue;
+        };
+
+        // Descendants of a widget with its own styling are its parts, so they keep their styling
+        // too.
+        if node.has_theme_override() {
+            let mut subtree = vec![handle];
+            while let Some(handle) = subtree.pop() {
+                report.skipped_widgets += 1;
+                subtree.extend_from_slice(ui.node(handle).children());
+            }
+            continue;
+        }
+
+        let count = changes.len();
+        node.as_reflect(&mut |reflect| {
+            for_each_styled_property(reflect, "", 0, &mut |path, name, property| {
+                if name.is_empty() {
+                    return;
+                }
+                let Some(new_property) = theme.get_raw(name.clone()) else {
+                    return;
+                };
+                if std::mem::discriminant(&new_property) == std::mem::discriminant(&property)
+                    && new_property != property
+                {
+                    changes.push(ThemeChange {
+                        handle,
+                        path,
+                        name,
+                        property: new_property,
+                    });
+                }
+            })
+        });
+        if changes.len() > count {
+            report.affected_widgets += 1;
+            report.changed_properties += changes.len() - count;
+        }
+
+        stack.extend_from_slice(node.children());
+    }
+
+    (changes, report)
+}
+
+/// Calculates what [`ApplyThemeCommand`] would change in the UI document without changing anything.
+/// The report could be shown to the user before the theme is applied.
+pub fn preview_theme(ui: &UserInterface, theme: &Style) -> ThemeReport {
+    collect_theme_changes(ui, theme).1
+}
+
+#[derive(Debug)]
+struct ThemeEntry {
+    handle: Handle<UiNode>,
+    path: String,
+    // Value that will be set on next execute/revert.
+    value: Option<Box<dyn Reflect>>,
+}
+
+/// Applies a theme (for example, [`Style::dark_style`] or [`Style::light_style`]) to every widget of
+/// a UI document. Every styled property, that is bound to a variable of the theme, takes the value
+/// of the variable. Widgets with their own styling (see
+/// [`crate::fyrox::gui::widget::Widget::theme_override`]) and their descendants are left untouched.
+/// All the changes are reverted in a single step.
+#[derive(Debug)]
+pub struct ApplyThemeCommand {
+    theme: StyleResource,
+    // `None` if the command was never executed.
+    entries: Option<Vec<ThemeEntry>>,
+}
+
+impl ApplyThemeCommand {
+    pub fn new(theme: StyleResource) -> Self {
+        Self {
+            theme,
+            entries: None,
+        }
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        for entry in self.entries.iter_mut().flatten() {
+            let Some(node) = ui.try_get_mut(entry.handle) else {
+                continue;
+            };
+
+            let Some(value) = entry.value.take() else {
+                continue;
+            };
+            let mut value = Some(value);
+            node.as_reflect_mut(&mut |node| {
+                node.set_field_by_path(&entry.path, value.take().unwrap(), &mut |result| {
+                    match result {
+                        Ok(old_value) => entry.value = Some(old_value),
+                        Err(_) => Log::err(format!(
+                            "Unable to apply a theme to {} property. Reason: no such property!",
+                            entry.path
+                        )),
+                    }
+                });
+            });
+
+            node.invalidate_layout();
+        }
+    }
+}
+
+impl CommandTrait for ApplyThemeCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Apply Theme".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            if !self.theme.is_ok() {
+                Log::err(format!(
+                    "Unable to apply theme {}, because it is not loaded!",
+                    self.theme.kind()
+                ));
+                return;
+            }
+
+            let (changes, report) = collect_theme_changes(context.ui, &self.theme.data_ref());
+            Log::info(format!(
+                "Applying theme {}: {} properties of {} widgets will be changed, {} widgets are skipped.",
+                self.theme.kind(),
+                report.changed_properties,
+                report.affected_widgets,
+                report.skipped_widgets
+            ));
+
+            self.entries = Some(
+                changes
+                    .into_iter()
+                    .map(|change| ThemeEntry {
+                        handle: change.handle,
+                        path: change.path,
+                        value: Some(make_styled_property(change.property, change.name)),
+                    })
+                    .collect(),
+            );
+        }
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
@@ -20,11 +20,46 @@
 
 use crate::command::{CommandContext, CommandTrait};
 use crate::fyrox::{
-    core::{algebra::Vector2, log::Log, pool::Handle, reflect::Reflect},
-    graph::SceneGraphNode,
-    gui::{UiNode, UserInterface},
+    core::{
+        algebra::{Matrix3, Vector2},
+        log::Log,
+        pool::Handle,
+        reflect::Reflect,
+        sstorage::ImmutableString,
+    },
+    fxhash::FxHashMap,
+    graph::{BaseSceneGraph, SceneGraph, SceneGraphNode},
+    gui::{
+        accessibility::{Accessibility, AccessibilityRole},
+        brush::Brush,
+        canvas::Canvas,
+        check_box::{CheckBox, CheckBoxMessage},
+        event_handler::{EventHandlerBinding, WidgetEvent},
+        font::FontResource,
+        formatted_text::{FormattedText, WrapMode},
+        grid::Grid,
+        menu::{ContextMenu, ContextMenuBuilder, MenuItemBuilder, MenuItemContent},
+        message::{CursorIcon, MessageDirection},
+        navigation::{is_focusable, FocusDirection, FocusNavigation},
+        popup::{Popup, PopupBuilder},
+        stack_panel::{StackPanel, StackPanelBuilder},
+        style::StyledProperty,
+        text::Text,
+        text_box::TextBox,
+        utils::make_simple_tooltip,
+        widget::{DragBehavior, WidgetBuilder},
+        wrap_panel::WrapPanel,
+        HorizontalAlignment, RcUiNodeHandle, SubGraph, Thickness, UiNode, UserInterface,
+        VerticalAlignment,
+    },
 };
-use crate::ui_scene::commands::UiSceneContext;
+use crate::{
+    scene::Selection,
+    ui_scene::{commands::UiSceneContext, UiSelection},
+    Message,
+};
+use std::{cell::RefCell, str::FromStr};
+use strum::VariantNames;
 
 #[derive(Debug)]
 pub struct MoveWidgetCommand {
@@ -121,3 +156,3717 @@ impl CommandTrait for RevertWidgetPropertyCommand {
         }
     }
 }
+
+/// A point of the parent widget to which a widget could be pinned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    fn alignments(self) -> (HorizontalAlignment, VerticalAlignment) {
+        use HorizontalAlignment as H;
+        use VerticalAlignment as V;
+        match self {
+            Anchor::TopLeft => (H::Left, V::Top),
+            Anchor::Top => (H::Center, V::Top),
+            Anchor::TopRight => (H::Right, V::Top),
+            Anchor::Left => (H::Left, V::Center),
+            Anchor::Center => (H::Center, V::Center),
+            Anchor::Right => (H::Right, V::Center),
+            Anchor::BottomLeft => (H::Left, V::Bottom),
+            Anchor::Bottom => (H::Center, V::Bottom),
+            Anchor::BottomRight => (H::Right, V::Bottom),
+        }
+    }
+}
+
+/// Defines how the offset of an anchored widget is calculated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnchorOffset {
+    /// Offset (in pixels) from the anchor towards the center of the parent. For centered axes it
+    /// is a shift from the center along the axis.
+    Fixed(Vector2<f32>),
+    /// The offset will be derived from the current position of the widget, so it will stay at
+    /// the same place at the current size of the parent.
+    KeepCurrent,
+}
+
+/// Computes margins (start, end) along an axis for the given alignment (-1 - start, 0 - center,
+/// 1 - end) and offset.
+fn axis_margins(alignment: i8, offset: f32) -> (f32, f32) {
+    match alignment {
+        -1 => (offset, 0.0),
+        1 => (0.0, offset),
+        _ => {
+            // The widget is centered within the space left after margins, so a shift by `offset`
+            // requires twice larger margin on one of the sides.
+            if offset >= 0.0 {
+                (offset * 2.0, 0.0)
+            } else {
+                (0.0, -offset * 2.0)
+            }
+        }
+    }
+}
+
+/// Computes an offset along an axis from the current position of a widget.
+fn axis_offset(alignment: i8, position: f32, size: f32, parent_size: f32) -> f32 {
+    match alignment {
+        -1 => position,
+        1 => parent_size - (position + size),
+        _ => position - (parent_size - size) * 0.5,
+    }
+}
+
+/// Sets alignment and margins of a widget, so it will be pinned to the given anchor of its parent.
+#[derive(Debug)]
+pub struct AnchorWidgetCommand {
+    handle: Handle<UiNode>,
+    anchor: Anchor,
+    offset: AnchorOffset,
+    old_layout: Option<(HorizontalAlignment, VerticalAlignment, Thickness)>,
+}
+
+impl AnchorWidgetCommand {
+    pub fn new(handle: Handle<UiNode>, anchor: Anchor, offset: AnchorOffset) -> Self {
+        Self {
+            handle,
+            anchor,
+            offset,
+            old_layout: None,
+        }
+    }
+
+    fn validate(&self, ui: &UserInterface) -> Result<(), String> {
+        let Some(widget) = ui.try_get(self.handle) else {
+            return Err(format!("{} is not a valid widget!", self.handle));
+        };
+
+        let parent = widget.parent();
+        if parent != ui.root() {
+            if let Some(parent_ref) = ui.try_get(parent) {
+                // These panels do not respect alignment of their children, so anchoring is meaningless.
+                if parent_ref.cast::<StackPanel>().is_some()
+                    || parent_ref.cast::<WrapPanel>().is_some()
+                    || parent_ref.cast::<Canvas>().is_some()
+                {
+                    return Err(format!(
+                        "{} cannot be anchored, because its parent is a layout panel that \
+                        ignores alignment of its children!",
+                        widget.name()
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn margin(&self, ui: &UserInterface) -> Thickness {
+        let (horizontal, vertical) = self.anchor.alignments();
+        let horizontal = match horizontal {
+            HorizontalAlignment::Left => -1,
+            HorizontalAlignment::Right => 1,
+            _ => 0,
+        };
+        let vertical = match vertical {
+            VerticalAlignment::Top => -1,
+            VerticalAlignment::Bottom => 1,
+            _ => 0,
+        };
+
+        let offset = match self.offset {
+            AnchorOffset::Fixed(offset) => offset,
+            AnchorOffset::KeepCurrent => {
+                let widget = ui.node(self.handle);
+                let position = widget.actual_local_position();
+                let size = widget.actual_local_size();
+                let parent_size = ui
+                    .try_get(widget.parent())
+                    .map(|parent| parent.actual_local_size())
+                    .unwrap_or_else(|| ui.screen_size());
+                Vector2::new(
+                    axis_offset(horizontal, position.x, size.x, parent_size.x),
+                    axis_offset(vertical, position.y, size.y, parent_size.y),
+                )
+            }
+        };
+
+        let (left, right) = axis_margins(horizontal, offset.x);
+        let (top, bottom) = axis_margins(vertical, offset.y);
+
+        Thickness {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+}
+
+impl CommandTrait for AnchorWidgetCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Anchor Widget".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+        let ui = &mut *context.ui;
+
+        if let Err(err) = self.validate(ui) {
+            Log::err(format!("Unable to anchor the widget. Reason: {err}"));
+            return;
+        }
+
+        let margin = self.margin(ui);
+        let (horizontal_alignment, vertical_alignment) = self.anchor.alignments();
+
+        let widget = ui.node_mut(self.handle);
+        self.old_layout = Some((
+            widget
+                .horizontal_alignment
+                .set_value_and_mark_modified(horizontal_alignment),
+            widget
+                .vertical_alignment
+                .set_value_and_mark_modified(vertical_alignment),
+            widget.margin.set_value_and_mark_modified(margin),
+        ));
+        widget.invalidate_layout();
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some((horizontal_alignment, vertical_alignment, margin)) = self.old_layout.take() {
+            if let Some(widget) = context.ui.try_get_node_mut(self.handle) {
+                widget
+                    .horizontal_alignment
+                    .set_value_and_mark_modified(horizontal_alignment);
+                widget
+                    .vertical_alignment
+                    .set_value_and_mark_modified(vertical_alignment);
+                widget.margin.set_value_and_mark_modified(margin);
+                widget.invalidate_layout();
+            }
+        }
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+/// Enables or disables a widget and, optionally, all its descendants.
+#[derive(Debug)]
+pub struct SetEnabledCommand {
+    handle: Handle<UiNode>,
+    enabled: bool,
+    cascade: bool,
+    old_states: Vec<(Handle<UiNode>, bool)>,
+}
+
+impl SetEnabledCommand {
+    pub fn new(handle: Handle<UiNode>, enabled: bool, cascade: bool) -> Self {
+        Self {
+            handle,
+            enabled,
+            cascade,
+            old_states: Default::default(),
+        }
+    }
+}
+
+impl CommandTrait for SetEnabledCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        if self.enabled {
+            "Enable Widget".to_owned()
+        } else {
+            "Disable Widget".to_owned()
+        }
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+        let ui = &mut *context.ui;
+
+        if !ui.is_valid_handle(self.handle) {
+            Log::err(format!(
+                "Unable to change enabled state, because {} is not a valid widget!",
+                self.handle
+            ));
+            return;
+        }
+
+        let handles = if self.cascade {
+            ui.traverse_handle_iter(self.handle).collect::<Vec<_>>()
+        } else {
+            vec![self.handle]
+        };
+
+        // Remember the state of every widget, so the revert will restore it exactly.
+        for handle in handles {
+            let old_state = ui
+                .node_mut(handle)
+                .enabled
+                .set_value_and_mark_modified(self.enabled);
+            self.old_states.push((handle, old_state));
+        }
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        for (handle, old_state) in self.old_states.drain(..).rev() {
+            if let Some(widget) = context.ui.try_get_node_mut(handle) {
+                widget.enabled.set_value_and_mark_modified(old_state);
+            }
+        }
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+/// Sets a simple text tooltip for a set of widgets. Widgets that are not hit-test visible cannot
+/// show tooltips, so they're skipped.
+#[derive(Debug)]
+pub struct SetTooltipCommand {
+    widgets: Vec<Handle<UiNode>>,
+    text: String,
+    overrides: FxHashMap<Handle<UiNode>, String>,
+    // Tooltips that will be set on next execute/revert. `None` if the command was never executed.
+    tooltips: Option<Vec<(Handle<UiNode>, Option<RcUiNodeHandle>)>>,
+}
+
+impl SetTooltipCommand {
+    /// Creates a new command that sets the given text as a tooltip for every widget. `overrides`
+    /// allows you to specify a different text for particular widgets.
+    pub fn new(
+        widgets: Vec<Handle<UiNode>>,
+        text: String,
+        overrides: FxHashMap<Handle<UiNode>, String>,
+    ) -> Self {
+        Self {
+            widgets,
+            text,
+            overrides,
+            tooltips: None,
+        }
+    }
+
+    fn create_tooltips(
+        &self,
+        ui: &mut UserInterface,
+    ) -> Vec<(Handle<UiNode>, Option<RcUiNodeHandle>)> {
+        let mut tooltips = Vec::new();
+        let mut skipped = Vec::new();
+
+        for &handle in self.widgets.iter() {
+            let Some(widget) = ui.try_get(handle) else {
+                continue;
+            };
+
+            if !widget.is_hit_test_visible() {
+                skipped.push(widget.name().to_owned());
+                continue;
+            }
+
+            let text = self.overrides.get(&handle).unwrap_or(&self.text);
+            let tooltip = make_simple_tooltip(&mut ui.build_ctx(), text);
+            tooltips.push((handle, Some(tooltip)));
+        }
+
+        if !skipped.is_empty() {
+            Log::warn(format!(
+                "The following widgets do not support tooltips, because they're not hit-test \
+                visible: {}",
+                skipped.join(", ")
+            ));
+        }
+
+        tooltips
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(tooltips) = self.tooltips.as_mut() {
+            for (handle, tooltip) in tooltips.iter_mut() {
+                if let Some(widget) = ui.try_get_node_mut(*handle) {
+                    std::mem::swap(&mut widget.tooltip, tooltip);
+                }
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetTooltipCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Tooltip".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.tooltips.is_none() {
+            self.tooltips = Some(self.create_tooltips(context.ui));
+        }
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+fn formatted_text(node: &UiNode) -> Option<&RefCell<FormattedText>> {
+    if let Some(text) = node.cast::<Text>() {
+        Some(&text.formatted_text)
+    } else {
+        node.cast::<TextBox>()
+            .map(|text_box| &text_box.formatted_text)
+    }
+}
+
+#[derive(Debug)]
+struct FontEntry {
+    handle: Handle<UiNode>,
+    font: FontResource,
+    font_size: StyledProperty<f32>,
+}
+
+/// Sets font and/or font size for every text-bearing widget ([`Text`] and [`TextBox`]) in the given
+/// list. Other widgets are skipped.
+#[derive(Debug)]
+pub struct SetFontCommand {
+    widgets: Vec<Handle<UiNode>>,
+    font: Option<FontResource>,
+    font_size: Option<f32>,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<FontEntry>>,
+}
+
+impl SetFontCommand {
+    /// Creates a new command. If `font` or `font_size` is [`None`], then the respective property
+    /// will be left unchanged.
+    pub fn new(
+        widgets: Vec<Handle<UiNode>>,
+        font: Option<FontResource>,
+        font_size: Option<f32>,
+    ) -> Self {
+        Self {
+            widgets,
+            font,
+            font_size,
+            entries: None,
+        }
+    }
+
+    fn create_entries(&self, ui: &UserInterface) -> Vec<FontEntry> {
+        let mut entries = Vec::<FontEntry>::new();
+        let mut skipped = Vec::new();
+
+        for &handle in self.widgets.iter() {
+            let Some(node) = ui.try_get(handle) else {
+                continue;
+            };
+
+            // Every widget must be swapped exactly once.
+            if entries.iter().any(|entry| entry.handle == handle) {
+                continue;
+            }
+
+            let Some(formatted_text) = formatted_text(node) else {
+                skipped.push(node.name().to_owned());
+                continue;
+            };
+
+            let formatted_text = formatted_text.borrow();
+            entries.push(FontEntry {
+                handle,
+                font: self
+                    .font
+                    .clone()
+                    .unwrap_or_else(|| formatted_text.get_font()),
+                font_size: self
+                    .font_size
+                    .map(StyledProperty::from)
+                    .unwrap_or_else(|| formatted_text.font_size().clone()),
+            });
+        }
+
+        if !skipped.is_empty() {
+            Log::warn(format!(
+                "The following widgets were skipped, because they do not have text: {}",
+                skipped.join(", ")
+            ));
+        }
+
+        entries
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for entry in entries.iter_mut() {
+                let Some(node) = ui.try_get(entry.handle) else {
+                    continue;
+                };
+
+                if let Some(formatted_text) = formatted_text(node) {
+                    let mut formatted_text = formatted_text.borrow_mut();
+
+                    let old_font = formatted_text.get_font();
+                    formatted_text.set_font(std::mem::replace(&mut entry.font, old_font));
+
+                    let old_font_size = formatted_text.font_size().clone();
+                    formatted_text
+                        .set_font_size(std::mem::replace(&mut entry.font_size, old_font_size));
+                }
+
+                node.invalidate_layout();
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetFontCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Font".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            if let Some(font) = self.font.as_ref() {
+                if !font.is_ok() {
+                    Log::err(format!(
+                        "Unable to set font {}, because it is not loaded!",
+                        font.kind()
+                    ));
+                    return;
+                }
+            }
+
+            self.entries = Some(self.create_entries(context.ui));
+        }
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+#[derive(Debug)]
+struct TextAlignmentEntry {
+    handle: Handle<UiNode>,
+    horizontal_alignment: HorizontalAlignment,
+    vertical_alignment: VerticalAlignment,
+}
+
+/// Sets horizontal and/or vertical alignment of text for every text-bearing widget ([`Text`] and
+/// [`TextBox`]) in the given list. Other widgets are skipped.
+#[derive(Debug)]
+pub struct SetTextAlignmentCommand {
+    widgets: Vec<Handle<UiNode>>,
+    horizontal_alignment: Option<HorizontalAlignment>,
+    vertical_alignment: Option<VerticalAlignment>,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<TextAlignmentEntry>>,
+}
+
+impl SetTextAlignmentCommand {
+    /// Creates a new command. If `horizontal_alignment` or `vertical_alignment` is [`None`], then
+    /// the respective alignment will be left unchanged.
+    pub fn new(
+        widgets: Vec<Handle<UiNode>>,
+        horizontal_alignment: Option<HorizontalAlignment>,
+        vertical_alignment: Option<VerticalAlignment>,
+    ) -> Self {
+        Self {
+            widgets,
+            horizontal_alignment,
+            vertical_alignment,
+            entries: None,
+        }
+    }
+
+    fn create_entries(&self, ui: &UserInterface) -> Vec<TextAlignmentEntry> {
+        let mut entries = Vec::<TextAlignmentEntry>::new();
+        let mut skipped = Vec::new();
+
+        for &handle in self.widgets.iter() {
+            let Some(node) = ui.try_get(handle) else {
+                continue;
+            };
+
+            // Every widget must be swapped exactly once.
+            if entries.iter().any(|entry| entry.handle == handle) {
+                continue;
+            }
+
+            let Some(formatted_text) = formatted_text(node) else {
+                skipped.push(node.name().to_owned());
+                continue;
+            };
+
+            let formatted_text = formatted_text.borrow();
+            entries.push(TextAlignmentEntry {
+                handle,
+                horizontal_alignment: self
+                    .horizontal_alignment
+                    .unwrap_or_else(|| formatted_text.horizontal_alignment()),
+                vertical_alignment: self
+                    .vertical_alignment
+                    .unwrap_or_else(|| formatted_text.vertical_alignment()),
+            });
+        }
+
+        if !skipped.is_empty() {
+            Log::warn(format!(
+                "The following widgets were skipped, because they do not have text: {}",
+                skipped.join(", ")
+            ));
+        }
+
+        entries
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for entry in entries.iter_mut() {
+                let Some(node) = ui.try_get(entry.handle) else {
+                    continue;
+                };
+
+                if let Some(formatted_text) = formatted_text(node) {
+                    let mut formatted_text = formatted_text.borrow_mut();
+
+                    let old_horizontal_alignment = formatted_text.horizontal_alignment();
+                    formatted_text.set_horizontal_alignment(std::mem::replace(
+                        &mut entry.horizontal_alignment,
+                        old_horizontal_alignment,
+                    ));
+
+                    let old_vertical_alignment = formatted_text.vertical_alignment();
+                    formatted_text.set_vertical_alignment(std::mem::replace(
+                        &mut entry.vertical_alignment,
+                        old_vertical_alignment,
+                    ));
+                }
+
+                node.invalidate_layout();
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetTextAlignmentCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Text Alignment".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            self.entries = Some(self.create_entries(context.ui));
+        }
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+#[derive(Debug)]
+struct TextWrapEntry {
+    handle: Handle<UiNode>,
+    wrap: WrapMode,
+    // Only text boxes have multiline mode.
+    multiline: Option<bool>,
+    clip_to_bounds: bool,
+}
+
+/// Switches text-bearing widgets ([`Text`] and [`TextBox`]) between wrapped (multi-line) and
+/// single-line text. Wrapped text is laid out within the width of the widget and text boxes are
+/// switched to multiline mode. Single-line text ([`WrapMode::NoWrap`]) is clipped by the bounds of
+/// the widget, text boxes are switched to single line mode and scroll their content to the caret.
+/// Other widgets are skipped. Revert restores the exact prior state of every widget. On execution,
+/// the selection is changed to the affected widgets.
+#[derive(Debug)]
+pub struct SetTextWrapCommand {
+    widgets: Vec<Handle<UiNode>>,
+    wrap: WrapMode,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<TextWrapEntry>>,
+    prev_selection: Selection,
+}
+
+impl SetTextWrapCommand {
+    pub fn new(handle: Handle<UiNode>, wrap: WrapMode) -> Self {
+        Self {
+            widgets: vec![handle],
+            wrap,
+            entries: None,
+            prev_selection: Default::default(),
+        }
+    }
+
+    /// Creates a command that sets the same wrapping mode for every selected widget.
+    pub fn new_batch(selection: &UiSelection, wrap: WrapMode) -> Self {
+        Self {
+            widgets: selection.widgets.clone(),
+            wrap,
+            entries: None,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn create_entries(&self, ui: &UserInterface) -> Vec<TextWrapEntry> {
+        let mut entries = Vec::<TextWrapEntry>::new();
+        let mut skipped = Vec::new();
+
+        let wrapped = self.wrap != WrapMode::NoWrap;
+        for &handle in self.widgets.iter() {
+            let Some(node) = ui.try_get(handle) else {
+                continue;
+            };
+
+            // Every widget must be swapped exactly once.
+            if entries.iter().any(|entry| entry.handle == handle) {
+                continue;
+            }
+
+            if formatted_text(node).is_none() {
+                skipped.push(node.name().to_owned());
+                continue;
+            }
+
+            entries.push(TextWrapEntry {
+                handle,
+                wrap: self.wrap,
+                multiline: node.cast::<TextBox>().map(|_| wrapped),
+                // Wrapped text never exceeds the width of the widget, so clipping is left as is.
+                clip_to_bounds: !wrapped || node.clip_to_bounds(),
+            });
+        }
+
+        if !skipped.is_empty() {
+            Log::warn(format!(
+                "The following widgets were skipped, because they do not have text: {}",
+                skipped.join(", ")
+            ));
+        }
+
+        entries
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for entry in entries.iter_mut() {
+                let Some(node) = ui.try_get_mut(entry.handle) else {
+                    continue;
+                };
+
+                if let Some(formatted_text) = formatted_text(node) {
+                    let mut formatted_text = formatted_text.borrow_mut();
+                    let old_wrap = formatted_text.wrap_mode();
+                    formatted_text.set_wrap(std::mem::replace(&mut entry.wrap, old_wrap));
+                }
+
+                if let (Some(multiline), Some(text_box)) =
+                    (entry.multiline.as_mut(), node.cast_mut::<TextBox>())
+                {
+                    let old_multiline = *text_box.multiline;
+                    text_box
+                        .multiline
+                        .set_value_and_mark_modified(std::mem::replace(multiline, old_multiline));
+                }
+
+                let old_clip_to_bounds = node.clip_to_bounds();
+                node.set_clip_to_bounds(std::mem::replace(
+                    &mut entry.clip_to_bounds,
+                    old_clip_to_bounds,
+                ));
+
+                node.invalidate_layout();
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetTextWrapCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Text Wrap".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            self.entries = Some(self.create_entries(context.ui));
+        }
+
+        let widgets = self
+            .entries
+            .iter()
+            .flatten()
+            .map(|entry| entry.handle)
+            .collect::<Vec<_>>();
+        if widgets.is_empty() {
+            Log::err("Unable to set text wrap, because there are no valid text widgets!");
+            return;
+        }
+        self.swap(context.ui);
+
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if self.entries.as_ref().is_none_or(Vec::is_empty) {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+fn format_widget_name(
+    pattern: &str,
+    index: usize,
+    name: &str,
+    type_name: &str,
+) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+
+        let Some(length) = rest[start..].find('}') else {
+            return Err(format!("Unclosed placeholder in {pattern} pattern!"));
+        };
+
+        let placeholder = &rest[(start + 1)..(start + length)];
+        match placeholder.split_once(':') {
+            None if placeholder == "index" => result.push_str(&index.to_string()),
+            Some(("index", width)) => {
+                let Ok(width) = width.trim_start_matches('0').parse::<usize>() else {
+                    return Err(format!("Invalid index format {width}!"));
+                };
+                result.push_str(&format!("{index:0width$}"));
+            }
+            None if placeholder == "name" => result.push_str(name),
+            None if placeholder == "type" => result.push_str(type_name),
+            _ => return Err(format!("Unknown placeholder {{{placeholder}}}!")),
+        }
+
+        rest = &rest[(start + length + 1)..];
+    }
+    result.push_str(rest);
+
+    Ok(result)
+}
+
+/// Renames a set of widgets using a pattern. The pattern could contain the following placeholders:
+///
+/// - `{index}` - index of a widget in the list, `{index:03}` gives zero-padded index (`007`).
+/// - `{name}` - current name of a widget.
+/// - `{type}` - type name of a widget (`Button`, `Text`, etc.).
+///
+/// New names are unique among siblings, a suffix is added to the name on collision. Patterns that
+/// produce an empty name are rejected.
+#[derive(Debug)]
+pub struct BatchRenameCommand {
+    widgets: Vec<Handle<UiNode>>,
+    pattern: String,
+    // Names that will be set on next execute/revert. `None` if the command was never executed.
+    names: Option<Vec<(Handle<UiNode>, String)>>,
+}
+
+impl BatchRenameCommand {
+    pub fn new(widgets: Vec<Handle<UiNode>>, pattern: String) -> Self {
+        Self {
+            widgets,
+            pattern,
+            names: None,
+        }
+    }
+
+    fn make_names(&self, ui: &UserInterface) -> Result<Vec<(Handle<UiNode>, String)>, String> {
+        let mut names = Vec::<(Handle<UiNode>, String)>::new();
+
+        for (index, &handle) in self.widgets.iter().enumerate() {
+            let Some(widget) = ui.try_get(handle) else {
+                continue;
+            };
+
+            let type_name = Reflect::type_name(widget)
+                .rsplit("::")
+                .next()
+                .unwrap_or_default();
+            let name = format_widget_name(&self.pattern, index, widget.name(), type_name)?;
+            if name.trim().is_empty() {
+                return Err(format!(
+                    "{} pattern produces an empty name for {} widget!",
+                    self.pattern,
+                    widget.name()
+                ));
+            }
+
+            let is_taken = |candidate: &str| {
+                ui.try_get(widget.parent()).is_some_and(|parent| {
+                    parent.children().iter().any(|&sibling| {
+                        sibling != handle
+                            && !self.widgets.contains(&sibling)
+                            && ui.node(sibling).name() == candidate
+                    })
+                }) || names.iter().any(|(other, other_name)| {
+                    other_name == candidate && ui.node(*other).parent() == widget.parent()
+                })
+            };
+
+            let mut unique_name = name.clone();
+            let mut suffix = 1;
+            while is_taken(&unique_name) {
+                unique_name = format!("{name} ({suffix})");
+                suffix += 1;
+            }
+
+            names.push((handle, unique_name));
+        }
+
+        Ok(names)
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(names) = self.names.as_mut() {
+            for (handle, name) in names.iter_mut() {
+                if let Some(widget) = ui.try_get_node_mut(*handle) {
+                    let old_name = widget.name().to_owned();
+                    widget.set_name(std::mem::replace(name, old_name));
+                }
+            }
+        }
+    }
+}
+
+impl CommandTrait for BatchRenameCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Batch Rename".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.names.is_none() {
+            match self.make_names(context.ui) {
+                Ok(names) => self.names = Some(names),
+                Err(err) => {
+                    Log::err(format!("Unable to rename widgets. Reason: {err}"));
+                    return;
+                }
+            }
+        }
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+/// A brush property of a widget, that could be changed by [`SetBrushCommand`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrushProperty {
+    /// Background brush of a widget, every widget has it.
+    Background,
+    /// Foreground brush of a widget, every widget has it.
+    Foreground,
+    /// A path to a widget-specific property of [`StyledProperty<Brush>`] type (for example,
+    /// `border.widget.background` or `normal_brush` of a decorator).
+    Path(String),
+}
+
+impl BrushProperty {
+    fn name(&self) -> &str {
+        match self {
+            BrushProperty::Background => "background",
+            BrushProperty::Foreground => "foreground",
+            BrushProperty::Path(path) => path,
+        }
+    }
+
+    // Sets the new value of the property and returns the old one. Returns `None` if the widget
+    // does not have the property.
+    fn set(
+        &self,
+        node: &mut UiNode,
+        value: StyledProperty<Brush>,
+    ) -> Option<StyledProperty<Brush>> {
+        match self {
+            BrushProperty::Background => Some(node.background.set_value_and_mark_modified(value)),
+            BrushProperty::Foreground => Some(node.foreground.set_value_and_mark_modified(value)),
+            BrushProperty::Path(path) => {
+                let mut value = Some(value);
+                let mut old_value = None;
+                node.as_reflect_mut(&mut |node| {
+                    node.set_field_by_path(path, Box::new(value.take().unwrap()), &mut |result| {
+                        old_value = result.ok().and_then(|old_value| old_value.take().ok());
+                    });
+                });
+                old_value
+            }
+        }
+    }
+}
+
+fn validate_brush(brush: &Brush) -> Result<(), String> {
+    let stops = match brush {
+        Brush::Solid(_) => return Ok(()),
+        Brush::LinearGradient { stops, .. } | Brush::RadialGradient { stops, .. } => stops,
+    };
+
+    if let Some(point) = stops
+        .iter()
+        .find(|point| !(0.0..=1.0).contains(&point.stop))
+    {
+        return Err(format!(
+            "Gradient stop {} is out of [0; 1] range!",
+            point.stop
+        ));
+    }
+
+    if stops.windows(2).any(|pair| pair[0].stop > pair[1].stop) {
+        return Err("Gradient stops must be sorted in ascending order!".to_owned());
+    }
+
+    Ok(())
+}
+
+/// Sets a brush (solid color, linear or radial gradient) of the given property for every widget in
+/// the given list. Widgets that do not have the property are skipped. Gradient stops must be sorted
+/// in ascending order and must be in `[0; 1]` range, otherwise the command does nothing.
+#[derive(Debug)]
+pub struct SetBrushCommand {
+    widgets: Vec<Handle<UiNode>>,
+    property: BrushProperty,
+    brush: Brush,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<(Handle<UiNode>, StyledProperty<Brush>)>>,
+}
+
+impl SetBrushCommand {
+    /// Creates a new command, that sets the brush of a single widget.
+    pub fn new(widget: Handle<UiNode>, property: BrushProperty, brush: Brush) -> Self {
+        Self::new_batch(vec![widget], property, brush)
+    }
+
+    /// Creates a new command, that sets the brush of every widget in the given list (usually, the
+    /// current selection).
+    pub fn new_batch(widgets: Vec<Handle<UiNode>>, property: BrushProperty, brush: Brush) -> Self {
+        Self {
+            widgets,
+            property,
+            brush,
+            entries: None,
+        }
+    }
+
+    fn apply(&self, ui: &mut UserInterface) -> Vec<(Handle<UiNode>, StyledProperty<Brush>)> {
+        let mut entries = Vec::new();
+        let mut skipped = Vec::new();
+
+        for &handle in self.widgets.iter() {
+            let Some(node) = ui.try_get_mut(handle) else {
+                continue;
+            };
+
+            // Explicitly set brush detaches the property from a style.
+            match self
+                .property
+                .set(node, StyledProperty::from(self.brush.clone()))
+            {
+                Some(old_value) => entries.push((handle, old_value)),
+                None => skipped.push(node.name().to_owned()),
+            }
+        }
+
+        if !skipped.is_empty() {
+            Log::warn(format!(
+                "The following widgets were skipped, because they do not have {} brush property: {}",
+                self.property.name(),
+                skipped.join(", ")
+            ));
+        }
+
+        entries
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for (handle, value) in entries.iter_mut() {
+                let Some(node) = ui.try_get_mut(*handle) else {
+                    continue;
+                };
+
+                if let Some(old_value) = self.property.set(node, value.clone()) {
+                    *value = old_value;
+                }
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetBrushCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        format!("Set {} Brush", self.property.name())
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            if let Err(err) = validate_brush(&self.brush) {
+                Log::err(format!("Unable to set brush. Reason: {err}"));
+                return;
+            }
+
+            self.entries = Some(self.apply(context.ui));
+        } else {
+            self.swap(context.ui);
+        }
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+/// A path along which widgets could be arranged by [`LayoutAlongPathCommand`]. Coordinates are in
+/// local space of the parent canvas of the widgets.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayoutPath {
+    /// A closed circle. The first widget is placed at the rightmost point of the circle, the rest
+    /// are placed clockwise (in screen space, where Y axis goes down).
+    Circle { center: Vector2<f32>, radius: f32 },
+    /// An open polyline, that must have at least two points. The first and the last widgets are
+    /// placed at the ends of the polyline.
+    Polyline(Vec<Vector2<f32>>),
+}
+
+impl LayoutPath {
+    /// Calculates `count` points (and tangent vectors at these points), that are evenly distributed
+    /// along the path.
+    fn sample(&self, count: usize) -> Result<Vec<(Vector2<f32>, Vector2<f32>)>, String> {
+        match self {
+            LayoutPath::Circle { center, radius } => {
+                if *radius < 0.0 {
+                    return Err(format!("Circle radius {radius} must not be negative!"));
+                }
+
+                Ok((0..count)
+                    .map(|i| {
+                        let angle = std::f32::consts::TAU * i as f32 / count as f32;
+                        let (sin, cos) = angle.sin_cos();
+                        (
+                            center + Vector2::new(cos, sin).scale(*radius),
+                            Vector2::new(-sin, cos),
+                        )
+                    })
+                    .collect())
+            }
+            LayoutPath::Polyline(points) => {
+                if points.len() < 2 {
+                    return Err("Polyline must have at least two points!".to_owned());
+                }
+
+                let length = points
+                    .windows(2)
+                    .map(|segment| (segment[1] - segment[0]).norm())
+                    .sum::<f32>();
+
+                Ok((0..count)
+                    .map(|i| {
+                        // A single widget is placed at the middle of the path.
+                        let distance = if count > 1 {
+                            length * i as f32 / (count - 1) as f32
+                        } else {
+                            length * 0.5
+                        };
+                        polyline_point_at(points, distance)
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+fn polyline_point_at(points: &[Vector2<f32>], distance: f32) -> (Vector2<f32>, Vector2<f32>) {
+    let mut tangent = Vector2::x();
+    let mut remaining = distance;
+
+    for segment in points.windows(2) {
+        let (begin, end) = (segment[0], segment[1]);
+        let segment_length = (end - begin).norm();
+        if segment_length <= f32::EPSILON {
+            continue;
+        }
+
+        tangent = (end - begin).scale(1.0 / segment_length);
+        if remaining <= segment_length {
+            return (begin + tangent.scale(remaining), tangent);
+        }
+        remaining -= segment_length;
+    }
+
+    // Accumulated precision loss might put the last point slightly past the end of the path.
+    (*points.last().unwrap(), tangent)
+}
+
+#[derive(Debug)]
+struct PathLayoutEntry {
+    handle: Handle<UiNode>,
+    position: Vector2<f32>,
+    render_transform: Matrix3<f32>,
+}
+
+/// Arranges widgets evenly along a path (see [`LayoutPath`]), so that center of each widget lies on
+/// the path. Optionally, the widgets could be rotated to follow the tangent of the path. Only the
+/// widgets that are placed on a [`Canvas`] are arranged, other widgets are positioned by layout
+/// panels and they're skipped. On execution, the selection is changed to the arranged widgets.
+#[derive(Debug)]
+pub struct LayoutAlongPathCommand {
+    widgets: Vec<Handle<UiNode>>,
+    path: LayoutPath,
+    follow_tangent: bool,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<PathLayoutEntry>>,
+    prev_selection: Selection,
+}
+
+impl LayoutAlongPathCommand {
+    pub fn new(selection: &UiSelection, path: LayoutPath, follow_tangent: bool) -> Self {
+        Self {
+            widgets: selection.widgets.clone(),
+            path,
+            follow_tangent,
+            entries: None,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn make_entries(&self, ui: &UserInterface) -> Result<Vec<PathLayoutEntry>, String> {
+        let mut widgets = Vec::new();
+        let mut skipped = Vec::new();
+
+        for &handle in self.widgets.iter() {
+            let Some(widget) = ui.try_get(handle) else {
+                continue;
+            };
+
+            if ui
+                .try_get(widget.parent())
+                .is_some_and(|parent| parent.cast::<Canvas>().is_some())
+            {
+                widgets.push(widget);
+            } else {
+                skipped.push(widget.name().to_owned());
+            }
+        }
+
+        if !skipped.is_empty() {
+            Log::warn(format!(
+                "The following widgets were skipped, because they're positioned by a layout panel: {}",
+                skipped.join(", ")
+            ));
+        }
+
+        let samples = self.path.sample(widgets.len())?;
+
+        Ok(widgets
+            .into_iter()
+            .zip(samples)
+            .map(|(widget, (point, tangent))| {
+                let half_size = widget.actual_local_size().scale(0.5);
+                let render_transform = if self.follow_tangent {
+                    // Rotate around the center of the widget.
+                    Matrix3::new_translation(&half_size)
+                        * Matrix3::new_rotation(tangent.y.atan2(tangent.x))
+                        * Matrix3::new_translation(&-half_size)
+                } else {
+                    widget.render_transform
+                };
+                PathLayoutEntry {
+                    handle: widget.handle(),
+                    position: point - half_size,
+                    render_transform,
+                }
+            })
+            .collect())
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for entry in entries.iter_mut() {
+                let Some(widget) = ui.try_get_mut(entry.handle) else {
+                    continue;
+                };
+
+                let old_position = widget.desired_local_position();
+                widget.set_desired_local_position(std::mem::replace(
+                    &mut entry.position,
+                    old_position,
+                ));
+                std::mem::swap(&mut widget.render_transform, &mut entry.render_transform);
+                widget.invalidate_layout();
+            }
+        }
+    }
+}
+
+impl CommandTrait for LayoutAlongPathCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Layout Along Path".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            match self.make_entries(context.ui) {
+                Ok(entries) => self.entries = Some(entries),
+                Err(err) => {
+                    Log::err(format!(
+                        "Unable to layout widgets along the path. Reason: {err}"
+                    ));
+                    return;
+                }
+            }
+        }
+        self.swap(context.ui);
+
+        let widgets = self
+            .entries
+            .iter()
+            .flatten()
+            .map(|entry| entry.handle)
+            .collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+#[derive(Debug)]
+struct RadioGroupEntry {
+    handle: Handle<UiNode>,
+    group: ImmutableString,
+    checked: Option<bool>,
+}
+
+/// Puts every check box in the given list in a radio group (see [`CheckBox::group`]), so only one
+/// of them could be checked at a time. Exactly one check box of the group is checked: the one with
+/// the given index among the check boxes of the list, or the first one. Other widgets are skipped.
+#[derive(Debug)]
+pub struct MakeRadioGroupCommand {
+    widgets: Vec<Handle<UiNode>>,
+    group: ImmutableString,
+    selected: Option<usize>,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<RadioGroupEntry>>,
+}
+
+impl MakeRadioGroupCommand {
+    pub fn new(widgets: Vec<Handle<UiNode>>, group: &str, selected: Option<usize>) -> Self {
+        Self {
+            widgets,
+            group: ImmutableString::new(group),
+            selected,
+            entries: None,
+        }
+    }
+
+    fn make_entries(&self, ui: &UserInterface) -> Result<Vec<RadioGroupEntry>, String> {
+        if self.group.is_empty() {
+            return Err("Group name must not be empty!".to_owned());
+        }
+
+        let mut check_boxes = Vec::new();
+        let mut skipped = Vec::new();
+
+        for &handle in self.widgets.iter() {
+            let Some(node) = ui.try_get(handle) else {
+                continue;
+            };
+
+            if node.cast::<CheckBox>().is_some() {
+                check_boxes.push(handle);
+            } else {
+                skipped.push(node.name().to_owned());
+            }
+        }
+
+        if !skipped.is_empty() {
+            Log::warn(format!(
+                "The following widgets were skipped, because they're not check boxes: {}",
+                skipped.join(", ")
+            ));
+        }
+
+        let selected = self.selected.unwrap_or_default();
+        if selected >= check_boxes.len() {
+            return Err(format!(
+                "Unable to select check box {selected}, there are only {} check boxes!",
+                check_boxes.len()
+            ));
+        }
+
+        Ok(check_boxes
+            .into_iter()
+            .enumerate()
+            .map(|(index, handle)| RadioGroupEntry {
+                handle,
+                group: self.group.clone(),
+                checked: Some(index == selected),
+            })
+            .collect())
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for entry in entries.iter_mut() {
+                let Some(check_box) = ui.try_get_mut_of_type::<CheckBox>(entry.handle) else {
+                    continue;
+                };
+
+                let old_group = check_box
+                    .group
+                    .set_value_and_mark_modified(entry.group.clone());
+                entry.group = old_group;
+
+                // The state is changed using a message, so the check box could update its marks.
+                let old_checked = *check_box.checked;
+                ui.send_message(CheckBoxMessage::checked(
+                    entry.handle,
+                    MessageDirection::ToWidget,
+                    entry.checked,
+                ));
+                entry.checked = old_checked;
+            }
+        }
+    }
+}
+
+impl CommandTrait for MakeRadioGroupCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Make Radio Group".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            match self.make_entries(context.ui) {
+                Ok(entries) => self.entries = Some(entries),
+                Err(err) => {
+                    Log::err(format!("Unable to make a radio group. Reason: {err}"));
+                    return;
+                }
+            }
+        }
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+/// Render order (see [`crate::fyrox::gui::widget::Widget::render_order`]) that will be set by
+/// [`SetZOrderCommand`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZOrder {
+    /// Explicit render order.
+    Value(i32),
+    /// Render order that puts the widget on top of all its siblings.
+    BringToFront,
+    /// Render order that puts the widget below all its siblings.
+    SendToBack,
+}
+
+/// Sets render order of a widget, so it could be drawn on top of (or below) overlapping siblings
+/// without changing the order of children of its parent.
+#[derive(Debug)]
+pub struct SetZOrderCommand {
+    widget: Handle<UiNode>,
+    z_order: ZOrder,
+    // A value that will be set on next execute/revert. `None` if the command was never executed.
+    value: Option<i32>,
+}
+
+impl SetZOrderCommand {
+    pub fn new(widget: Handle<UiNode>, z_order: ZOrder) -> Self {
+        Self {
+            widget,
+            z_order,
+            value: None,
+        }
+    }
+
+    fn resolve(&self, ui: &UserInterface) -> Option<i32> {
+        let Some(widget_ref) = ui.try_get(self.widget) else {
+            Log::err(format!(
+                "Unable to set z-order of {}, because it is not a valid widget!",
+                self.widget
+            ));
+            return None;
+        };
+
+        let current = *widget_ref.render_order;
+        let siblings = ui
+            .try_get(widget_ref.parent())
+            .map(|parent| {
+                parent
+                    .children()
+                    .iter()
+                    .filter(|&&child| child != self.widget)
+                    .filter_map(|&child| ui.try_get(child).map(|child| *child.render_order))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let value = match self.z_order {
+            ZOrder::Value(value) => {
+                if siblings.contains(&value) {
+                    Log::warn(format!(
+                        "{} has the same z-order {value} as some of its siblings, the order of \
+                        children will be used to draw them.",
+                        widget_ref.name()
+                    ));
+                }
+                value
+            }
+            ZOrder::BringToFront => match siblings.iter().max() {
+                Some(&max) if max >= current => max.saturating_add(1),
+                _ => current,
+            },
+            ZOrder::SendToBack => match siblings.iter().min() {
+                Some(&min) if min <= current => min.saturating_sub(1),
+                _ => current,
+            },
+        };
+
+        Some(value)
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let (Some(value), Some(widget)) = (self.value.as_mut(), ui.try_get_mut(self.widget)) {
+            *value = widget.render_order.set_value_and_mark_modified(*value);
+        }
+    }
+}
+
+impl CommandTrait for SetZOrderCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        match self.z_order {
+            ZOrder::Value(_) => "Set Z-Order",
+            ZOrder::BringToFront => "Bring To Front",
+            ZOrder::SendToBack => "Send To Back",
+        }
+        .to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.value.is_none() {
+            self.value = self.resolve(context.ui);
+        }
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+/// Binds an event of a widget to a named handler (see [`EventHandlerBinding`]). If the event is
+/// already bound to some other handler, the binding is replaced and reverting the command restores
+/// the previous handler.
+#[derive(Debug)]
+pub struct AttachHandlerCommand {
+    widget: Handle<UiNode>,
+    event: WidgetEvent,
+    // A handler that will be set on next execute/revert, `None` means that the binding will be
+    // removed.
+    handler: Option<String>,
+    validated: bool,
+}
+
+impl AttachHandlerCommand {
+    pub fn new(widget: Handle<UiNode>, event: WidgetEvent, handler: String) -> Self {
+        Self {
+            widget,
+            event,
+            handler: Some(handler),
+            validated: false,
+        }
+    }
+
+    fn validate(&self, ui: &UserInterface) -> Result<(), String> {
+        let Some(widget) = ui.try_get(self.widget) else {
+            return Err(format!("{} is not a valid widget!", self.widget));
+        };
+
+        if self
+            .handler
+            .as_ref()
+            .is_some_and(|handler| handler.is_empty())
+        {
+            return Err("Handler name must not be empty!".to_owned());
+        }
+
+        if !self.event.is_emitted_by(widget) {
+            return Err(format!(
+                "{} does not emit {} event!",
+                widget.name(),
+                self.event.as_ref()
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        let Some(widget) = ui.try_get_mut(self.widget) else {
+            return;
+        };
+
+        let bindings = widget.event_handlers.get_value_mut_and_mark_modified();
+        let position = bindings
+            .iter()
+            .position(|binding| binding.event == self.event);
+        self.handler = match (position, self.handler.take()) {
+            (Some(position), Some(handler)) => {
+                Some(std::mem::replace(&mut bindings[position].handler, handler))
+            }
+            (Some(position), None) => Some(bindings.remove(position).handler),
+            (None, Some(handler)) => {
+                bindings.push(EventHandlerBinding {
+                    event: self.event,
+                    handler,
+                });
+                None
+            }
+            (None, None) => None,
+        };
+    }
+}
+
+impl CommandTrait for AttachHandlerCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Attach Event Handler".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if !self.validated {
+            if let Err(err) = self.validate(context.ui) {
+                Log::err(format!("Unable to attach event handler. Reason: {err}"));
+                return;
+            }
+            self.validated = true;
+        }
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.validated {
+            self.swap(context.ui);
+        }
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+/// A context menu for [`SetContextMenuCommand`].
+#[derive(Debug, Clone)]
+pub enum ContextMenuSource {
+    /// A new context menu with menu items with the given texts.
+    Create(Vec<String>),
+    /// An existing context menu widget.
+    Existing(Handle<UiNode>),
+}
+
+/// Sets a context menu of a widget, the menu is either created from a list of menu items or an
+/// existing one is used. The previous context menu of the widget is restored on revert, and the
+/// created context menu (if any) is removed.
+#[derive(Debug)]
+pub struct SetContextMenuCommand {
+    target: Handle<UiNode>,
+    source: ContextMenuSource,
+    // A context menu that will be set on next execute/revert.
+    context_menu: Option<RcUiNodeHandle>,
+    initialized: bool,
+    created: Handle<UiNode>,
+    sub_graph: Option<SubGraph>,
+    prev_selection: Selection,
+}
+
+impl SetContextMenuCommand {
+    pub fn new(target: Handle<UiNode>, source: ContextMenuSource) -> Self {
+        Self {
+            target,
+            source,
+            context_menu: None,
+            initialized: false,
+            created: Handle::NONE,
+            sub_graph: None,
+            prev_selection: Selection::new_empty(),
+        }
+    }
+
+    fn init(&mut self, ui: &mut UserInterface) -> Result<(), String> {
+        let Some(target_ref) = ui.try_get(self.target) else {
+            return Err(format!("{} is not a valid widget!", self.target));
+        };
+
+        // Popups (and context menus as well) are closed when clicked outside, so they cannot open
+        // context menus of their own.
+        if target_ref.cast::<Popup>().is_some() || target_ref.cast::<ContextMenu>().is_some() {
+            return Err(format!(
+                "{} does not support context menus, because it is a popup!",
+                target_ref.name()
+            ));
+        }
+
+        let context_menu = match &self.source {
+            ContextMenuSource::Create(items) => {
+                if items.is_empty() {
+                    return Err("A context menu must have at least one item!".to_owned());
+                }
+
+                let ctx = &mut ui.build_ctx();
+                let items = items
+                    .iter()
+                    .map(|item| {
+                        MenuItemBuilder::new(WidgetBuilder::new().with_name(item))
+                            .with_content(MenuItemContent::text(item))
+                            .build(ctx)
+                    })
+                    .collect::<Vec<_>>();
+                self.created = ContextMenuBuilder::new(
+                    PopupBuilder::new(
+                        WidgetBuilder::new()
+                            .with_name("Context Menu")
+                            .with_visibility(false),
+                    )
+                    .with_content(
+                        StackPanelBuilder::new(WidgetBuilder::new().with_children(items))
+                            .build(ctx),
+                    ),
+                )
+                .build(ctx);
+                self.created
+            }
+            ContextMenuSource::Existing(context_menu) => {
+                if ui.try_get_of_type::<ContextMenu>(*context_menu).is_none() {
+                    return Err(format!("{context_menu} is not a context menu!"));
+                }
+                if ui.is_node_child_of(self.target, *context_menu) {
+                    return Err("A widget cannot open the context menu it is in!".to_owned());
+                }
+                *context_menu
+            }
+        };
+
+        // A shared handle is needed here, because context menus could be shared across multiple
+        // widgets.
+        let shared = find_shared_context_menu(ui, context_menu)
+            .unwrap_or_else(|| RcUiNodeHandle::new(context_menu, ui.sender()));
+        self.context_menu = Some(shared);
+
+        Ok(())
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(target) = ui.try_get_mut(self.target) {
+            std::mem::swap(&mut target.context_menu, &mut self.context_menu);
+        }
+    }
+}
+
+/// Searches for a shared handle of the given context menu among the widgets, that already use it.
+fn find_shared_context_menu(
+    ui: &UserInterface,
+    context_menu: Handle<UiNode>,
+) -> Option<RcUiNodeHandle> {
+    ui.nodes()
+        .iter()
+        .filter_map(|node| node.context_menu.as_ref())
+        .find(|shared| shared.handle() == context_menu)
+        .cloned()
+}
+
+impl CommandTrait for SetContextMenuCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Context Menu".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.put_sub_graph_back(sub_graph);
+        } else if !self.initialized {
+            if let Err(err) = self.init(context.ui) {
+                Log::err(format!("Unable to set context menu. Reason: {err}"));
+                return;
+            }
+            self.initialized = true;
+        }
+
+        self.swap(context.ui);
+
+        self.prev_selection =
+            context.set_selection(Selection::new(UiSelection::single_or_empty(self.target)));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if !self.initialized {
+            return;
+        }
+
+        self.swap(context.ui);
+
+        if self.created.is_some() {
+            self.sub_graph = Some(context.ui.take_reserve_sub_graph(self.created));
+        }
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+
+    fn finalize(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if let Some(sub_graph) = self.sub_graph.take() {
+            context.ui.forget_sub_graph(sub_graph)
+        }
+    }
+}
+
+/// Sets accessibility metadata (a role and a label) of a set of widgets. The role is given by its
+/// name and it is validated against [`AccessibilityRole`] variants, [`None`] role means that the
+/// role is implied by the type of each widget (see [`AccessibilityRole::of_widget`]). Revert
+/// restores the exact prior metadata of every widget, including the absence of the metadata.
+#[derive(Debug)]
+pub struct SetAccessibilityCommand {
+    role: Option<String>,
+    label: String,
+    // Metadata that will be set to the widgets on next execute/revert.
+    accessibility: Vec<(Handle<UiNode>, Option<Accessibility>)>,
+    initialized: bool,
+}
+
+impl SetAccessibilityCommand {
+    pub fn new(widgets: Vec<Handle<UiNode>>, role: Option<String>, label: String) -> Self {
+        let mut accessibility = Vec::<(Handle<UiNode>, Option<Accessibility>)>::new();
+        for widget in widgets {
+            // Every widget must be swapped exactly once.
+            if !accessibility
+                .iter()
+                .any(|(existing, _)| *existing == widget)
+            {
+                accessibility.push((widget, None));
+            }
+        }
+
+        Self {
+            role,
+            label,
+            accessibility,
+            initialized: false,
+        }
+    }
+
+    fn init(&mut self, ui: &UserInterface) -> Result<(), String> {
+        if self.accessibility.is_empty() {
+            return Err("There are no widgets selected!".to_owned());
+        }
+
+        let role = match self.role {
+            Some(ref role) => Some(AccessibilityRole::from_str(role).map_err(|_| {
+                format!(
+                    "{role} is not a valid accessibility role! Valid roles are: {}",
+                    AccessibilityRole::VARIANTS.join(", ")
+                )
+            })?),
+            None => None,
+        };
+
+        for (widget, accessibility) in self.accessibility.iter_mut() {
+            if !ui.is_valid_handle(*widget) {
+                return Err(format!("{widget} is not a valid widget!"));
+            }
+
+            *accessibility = Some(Accessibility {
+                role,
+                label: self.label.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        for (widget, accessibility) in self.accessibility.iter_mut() {
+            if let Some(widget) = ui.try_get_mut(*widget) {
+                std::mem::swap(
+                    widget.accessibility.get_value_mut_and_mark_modified(),
+                    accessibility,
+                );
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetAccessibilityCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Accessibility".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if !self.initialized {
+            if let Err(err) = self.init(context.ui) {
+                Log::err(format!("Unable to set accessibility. Reason: {err}"));
+                return;
+            }
+            self.initialized = true;
+        }
+        self.swap(context.ui);
+
+        context.message_sender.send(Message::ForceSync);
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.initialized {
+            self.swap(context.ui);
+        }
+
+        context.message_sender.send(Message::ForceSync);
+    }
+}
+
+/// Defines the size to which widgets are resized by [`NormalizeSizeCommand`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeNormalization {
+    /// Every widget gets the largest width and the largest height among the widgets. The width
+    /// and the height could come from different widgets.
+    ToLargest,
+    /// Every widget gets the smallest width and the smallest height among the widgets. The width
+    /// and the height could come from different widgets.
+    ToSmallest,
+    /// Every widget gets the given size.
+    ToFixed(Vector2<f32>),
+}
+
+#[derive(Debug)]
+struct SizeEntry {
+    handle: Handle<UiNode>,
+    width: f32,
+    height: f32,
+}
+
+/// Sets explicit width and height of every widget in the list, so they all have the same size (see
+/// [`SizeNormalization`]). The largest and the smallest sizes are taken from the actual sizes of
+/// the widgets. Widgets that are placed in a [`Grid`] are skipped, because their size is defined by
+/// the cells of the grid. The size is clamped to the min/max size constraints of each widget, such
+/// widgets will have a different size and they're reported in the log. On execution, the selection
+/// is changed to the resized widgets.
+#[derive(Debug)]
+pub struct NormalizeSizeCommand {
+    widgets: Vec<Handle<UiNode>>,
+    normalization: SizeNormalization,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<SizeEntry>>,
+    prev_selection: Selection,
+}
+
+impl NormalizeSizeCommand {
+    pub fn new(selection: &UiSelection, normalization: SizeNormalization) -> Self {
+        Self {
+            widgets: selection.widgets.clone(),
+            normalization,
+            entries: None,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn make_entries(&self, ui: &UserInterface) -> Result<Vec<SizeEntry>, String> {
+        let mut widgets = Vec::<&UiNode>::new();
+        let mut skipped = Vec::new();
+
+        for &handle in self.widgets.iter() {
+            let Some(widget) = ui.try_get(handle) else {
+                continue;
+            };
+
+            if widgets.iter().any(|existing| existing.handle() == handle) {
+                continue;
+            }
+
+            if ui
+                .try_get(widget.parent())
+                .is_some_and(|parent| parent.cast::<Grid>().is_some())
+            {
+                skipped.push(widget.name().to_owned());
+            } else {
+                widgets.push(widget);
+            }
+        }
+
+        if !skipped.is_empty() {
+            Log::warn(format!(
+                "The following widgets were skipped, because their size is defined by a grid: {}",
+                skipped.join(", ")
+            ));
+        }
+
+        if widgets.is_empty() {
+            return Err("There are no widgets that could be resized!".to_owned());
+        }
+
+        let size = match self.normalization {
+            SizeNormalization::ToLargest => widgets
+                .iter()
+                .map(|widget| widget.actual_local_size())
+                .reduce(|a, b| a.sup(&b))
+                .unwrap_or_default(),
+            SizeNormalization::ToSmallest => widgets
+                .iter()
+                .map(|widget| widget.actual_local_size())
+                .reduce(|a, b| a.inf(&b))
+                .unwrap_or_default(),
+            SizeNormalization::ToFixed(size) => {
+                if !size.x.is_finite() || !size.y.is_finite() || size.x < 0.0 || size.y < 0.0 {
+                    return Err(format!(
+                        "{}x{} is not a valid size! The size must be finite and non-negative.",
+                        size.x, size.y
+                    ));
+                }
+                size
+            }
+        };
+
+        let mut clamped = Vec::new();
+        let entries = widgets
+            .into_iter()
+            .map(|widget| {
+                let min_size = widget.min_size();
+                let max_size = widget.max_size();
+                let width = size.x.clamp(min_size.x, max_size.x);
+                let height = size.y.clamp(min_size.y, max_size.y);
+                if width != size.x || height != size.y {
+                    clamped.push(format!("{} ({width}x{height})", widget.name()));
+                }
+                SizeEntry {
+                    handle: widget.handle(),
+                    width,
+                    height,
+                }
+            })
+            .collect();
+
+        if !clamped.is_empty() {
+            Log::warn(format!(
+                "The size of the following widgets was clamped to their min/max size: {}",
+                clamped.join(", ")
+            ));
+        }
+
+        Ok(entries)
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for entry in entries.iter_mut() {
+                let Some(widget) = ui.try_get_mut(entry.handle) else {
+                    continue;
+                };
+
+                std::mem::swap(
+                    widget.width.get_value_mut_and_mark_modified(),
+                    &mut entry.width,
+                );
+                std::mem::swap(
+                    widget.height.get_value_mut_and_mark_modified(),
+                    &mut entry.height,
+                );
+                widget.invalidate_layout();
+            }
+        }
+    }
+}
+
+impl CommandTrait for NormalizeSizeCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Normalize Size".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            match self.make_entries(context.ui) {
+                Ok(entries) => self.entries = Some(entries),
+                Err(err) => {
+                    Log::err(format!(
+                        "Unable to normalize size of widgets. Reason: {err}"
+                    ));
+                    return;
+                }
+            }
+        }
+        self.swap(context.ui);
+
+        let widgets = self
+            .entries
+            .iter()
+            .flatten()
+            .map(|entry| entry.handle)
+            .collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+/// Defines which axes are fitted by [`AutoSizeToContentCommand`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FitAxis {
+    Width,
+    Height,
+    Both,
+}
+
+impl FitAxis {
+    fn fits_width(self) -> bool {
+        matches!(self, FitAxis::Width | FitAxis::Both)
+    }
+
+    fn fits_height(self) -> bool {
+        matches!(self, FitAxis::Height | FitAxis::Both)
+    }
+}
+
+/// Sets explicit width and/or height (see [`FitAxis`]) of every container in the list, so it fits
+/// its direct children. The extent of the children is calculated using their actual (after layout)
+/// positions, sizes and margins; the given padding is added to the extent. Children could not be
+/// moved by the command, so the fitted size starts at the origin of the container. The size is
+/// clamped to the min/max size constraints of each container.
+///
+/// Containers without children are left as is. Containers with unset (automatic) size on every
+/// fitted axis are skipped too, because their size is already defined by their content (or by
+/// their parent). Both cases are reported in the log. On execution, the selection is changed to
+/// the resized containers.
+#[derive(Debug)]
+pub struct AutoSizeToContentCommand {
+    widgets: Vec<Handle<UiNode>>,
+    axis: FitAxis,
+    padding: f32,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<SizeEntry>>,
+    prev_selection: Selection,
+}
+
+impl AutoSizeToContentCommand {
+    pub fn new(selection: &UiSelection, axis: FitAxis, padding: f32) -> Self {
+        Self {
+            widgets: selection.widgets.clone(),
+            axis,
+            padding,
+            entries: None,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn make_entries(&self, ui: &UserInterface) -> Result<Vec<SizeEntry>, String> {
+        if !self.padding.is_finite() || self.padding < 0.0 {
+            return Err(format!(
+                "{} is not a valid padding! The padding must be finite and non-negative.",
+                self.padding
+            ));
+        }
+
+        let mut entries = Vec::<SizeEntry>::new();
+        let mut empty = Vec::new();
+        let mut content_driven = Vec::new();
+        for &handle in self.widgets.iter() {
+            let Some(widget) = ui.try_get(handle) else {
+                continue;
+            };
+
+            if entries.iter().any(|entry| entry.handle == handle) {
+                continue;
+            }
+
+            if widget.children().is_empty() {
+                empty.push(widget.name().to_owned());
+                continue;
+            }
+
+            if (!self.axis.fits_width() || widget.width().is_nan())
+                && (!self.axis.fits_height() || widget.height().is_nan())
+            {
+                content_driven.push(widget.name().to_owned());
+                continue;
+            }
+
+            let extent = widget
+                .children()
+                .iter()
+                .filter_map(|child| ui.try_get(*child))
+                .map(|child| {
+                    let margin = child.margin();
+                    child.actual_local_position()
+                        + child.actual_local_size()
+                        + Vector2::new(margin.right, margin.bottom)
+                })
+                .fold(Vector2::<f32>::default(), |a, b| a.sup(&b));
+
+            let min_size = widget.min_size();
+            let max_size = widget.max_size();
+            entries.push(SizeEntry {
+                handle,
+                width: if self.axis.fits_width() {
+                    (extent.x + self.padding).clamp(min_size.x, max_size.x)
+                } else {
+                    widget.width()
+                },
+                height: if self.axis.fits_height() {
+                    (extent.y + self.padding).clamp(min_size.y, max_size.y)
+                } else {
+                    widget.height()
+                },
+            });
+        }
+
+        if !empty.is_empty() {
+            Log::warn(format!(
+                "The following widgets were skipped, because they have no children: {}",
+                empty.join(", ")
+            ));
+        }
+
+        if !content_driven.is_empty() {
+            Log::warn(format!(
+                "The following widgets were skipped, because their size is already defined by \
+                their content: {}",
+                content_driven.join(", ")
+            ));
+        }
+
+        if entries.is_empty() {
+            return Err("There are no widgets that could be resized!".to_owned());
+        }
+
+        Ok(entries)
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for entry in entries.iter_mut() {
+                let Some(widget) = ui.try_get_mut(entry.handle) else {
+                    continue;
+                };
+
+                std::mem::swap(
+                    widget.width.get_value_mut_and_mark_modified(),
+                    &mut entry.width,
+                );
+                std::mem::swap(
+                    widget.height.get_value_mut_and_mark_modified(),
+                    &mut entry.height,
+                );
+                widget.invalidate_layout();
+            }
+        }
+    }
+}
+
+impl CommandTrait for AutoSizeToContentCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Auto Size To Content".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            match self.make_entries(context.ui) {
+                Ok(entries) => self.entries = Some(entries),
+                Err(err) => {
+                    Log::err(format!(
+                        "Unable to auto size widgets to content. Reason: {err}"
+                    ));
+                    return;
+                }
+            }
+        }
+        self.swap(context.ui);
+
+        let widgets = self
+            .entries
+            .iter()
+            .flatten()
+            .map(|entry| entry.handle)
+            .collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if self.entries.is_none() {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+/// Defines the aspect ratio that is set by [`SetAspectRatioCommand`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AspectRatioSource {
+    /// Every widget gets the aspect ratio of its current actual size.
+    FromCurrentSize,
+    /// Every widget gets the given aspect ratio (width divided by height).
+    Explicit(f32),
+}
+
+#[derive(Debug)]
+struct AspectRatioEntry {
+    handle: Handle<UiNode>,
+    aspect_ratio: Option<f32>,
+    width: f32,
+    height: f32,
+}
+
+/// Sets the aspect ratio constraint of every widget in the list (see [`AspectRatioSource`]). The
+/// layout system keeps the proportions of such widgets on every subsequent resize. Widgets that have
+/// both explicit width and height are resized to match the ratio (the width is preserved), the
+/// prior size is restored on revert. On execution, the selection is changed to the affected widgets.
+#[derive(Debug)]
+pub struct SetAspectRatioCommand {
+    widgets: Vec<Handle<UiNode>>,
+    source: AspectRatioSource,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<AspectRatioEntry>>,
+    prev_selection: Selection,
+}
+
+impl SetAspectRatioCommand {
+    pub fn new(selection: &UiSelection, source: AspectRatioSource) -> Self {
+        Self {
+            widgets: selection.widgets.clone(),
+            source,
+            entries: None,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn make_entries(&self, ui: &UserInterface) -> Result<Vec<AspectRatioEntry>, String> {
+        if let AspectRatioSource::Explicit(aspect_ratio) = self.source {
+            if !aspect_ratio.is_finite() || aspect_ratio <= 0.0 {
+                return Err(format!(
+                    "{aspect_ratio} is not a valid aspect ratio! The ratio must be finite and positive."
+                ));
+            }
+        }
+
+        let mut entries = Vec::<AspectRatioEntry>::new();
+        let mut skipped = Vec::new();
+
+        for &handle in self.widgets.iter() {
+            let Some(widget) = ui.try_get(handle) else {
+                continue;
+            };
+
+            if entries.iter().any(|entry| entry.handle == handle) {
+                continue;
+            }
+
+            let aspect_ratio = match self.source {
+                AspectRatioSource::FromCurrentSize => {
+                    let size = widget.actual_local_size();
+                    let aspect_ratio = size.x / size.y;
+                    if !aspect_ratio.is_finite() || aspect_ratio <= 0.0 {
+                        skipped.push(widget.name().to_owned());
+                        continue;
+                    }
+                    aspect_ratio
+                }
+                AspectRatioSource::Explicit(aspect_ratio) => aspect_ratio,
+            };
+
+            let width = widget.width();
+            let mut height = widget.height();
+            if width > 0.0 && height > 0.0 {
+                height = (width / aspect_ratio).clamp(widget.min_height(), widget.max_height());
+            }
+
+            entries.push(AspectRatioEntry {
+                handle,
+                aspect_ratio: Some(aspect_ratio),
+                width,
+                height,
+            });
+        }
+
+        if !skipped.is_empty() {
+            Log::warn(format!(
+                "The following widgets were skipped, because they have zero size: {}",
+                skipped.join(", ")
+            ));
+        }
+
+        if entries.is_empty() {
+            return Err("There are no widgets that could be constrained!".to_owned());
+        }
+
+        Ok(entries)
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for entry in entries.iter_mut() {
+                let Some(widget) = ui.try_get_mut(entry.handle) else {
+                    continue;
+                };
+
+                std::mem::swap(
+                    widget.aspect_ratio.get_value_mut_and_mark_modified(),
+                    &mut entry.aspect_ratio,
+                );
+                std::mem::swap(
+                    widget.width.get_value_mut_and_mark_modified(),
+                    &mut entry.width,
+                );
+                std::mem::swap(
+                    widget.height.get_value_mut_and_mark_modified(),
+                    &mut entry.height,
+                );
+                widget.invalidate_layout();
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetAspectRatioCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Aspect Ratio".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            match self.make_entries(context.ui) {
+                Ok(entries) => self.entries = Some(entries),
+                Err(err) => {
+                    Log::err(format!(
+                        "Unable to set aspect ratio of widgets. Reason: {err}"
+                    ));
+                    return;
+                }
+            }
+        }
+        self.swap(context.ui);
+
+        let widgets = self
+            .entries
+            .iter()
+            .flatten()
+            .map(|entry| entry.handle)
+            .collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+/// Sets clip-to-bounds flag of a set of widgets. Clipped widget clips its descendants by its
+/// bounds, the clipping bounds are updated on the next layout pass. On execution, the selection is
+/// changed to the affected widgets.
+#[derive(Debug)]
+pub struct SetClipToBoundsCommand {
+    // Values that will be set on next execute/revert.
+    entries: Vec<(Handle<UiNode>, bool)>,
+    prev_selection: Selection,
+}
+
+impl SetClipToBoundsCommand {
+    pub fn new(handle: Handle<UiNode>, clip_to_bounds: bool) -> Self {
+        Self {
+            entries: vec![(handle, clip_to_bounds)],
+            prev_selection: Default::default(),
+        }
+    }
+
+    /// Creates a command that sets the same flag for every selected widget.
+    pub fn new_batch(selection: &UiSelection, clip_to_bounds: bool) -> Self {
+        let mut entries = Vec::<(Handle<UiNode>, bool)>::new();
+        for &handle in selection.widgets.iter() {
+            if !entries.iter().any(|(existing, _)| *existing == handle) {
+                entries.push((handle, clip_to_bounds));
+            }
+        }
+        Self {
+            entries,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        for (handle, clip_to_bounds) in self.entries.iter_mut() {
+            if let Some(widget) = ui.try_get_mut(*handle) {
+                let prev = widget.clip_to_bounds();
+                widget.set_clip_to_bounds(*clip_to_bounds);
+                *clip_to_bounds = prev;
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetClipToBoundsCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Clip To Bounds".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.entries
+            .retain(|(handle, _)| context.ui.try_get(*handle).is_some());
+        if self.entries.is_empty() {
+            Log::err("Unable to set clip-to-bounds flag, because there are no valid widgets!");
+            return;
+        }
+        self.swap(context.ui);
+
+        let widgets = self.entries.iter().map(|(handle, _)| *handle).collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+/// Defines how [`SetFocusNavigationCommand`] calculates navigation links.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FocusNavigationMode {
+    /// Every widget gets the given links.
+    Explicit(FocusNavigation),
+    /// Links of every widget are computed from the screen positions of the other focusable
+    /// widgets in the list, see [`FocusNavigation::compute`].
+    Auto,
+}
+
+/// Sets directional focus navigation links (see [`FocusNavigation`]) of a set of widgets. Every
+/// link must point to an existing focusable widget (see [`is_focusable`]) other than the widget
+/// itself, otherwise the command does nothing. On execution, the selection is changed to the
+/// affected widgets.
+#[derive(Debug)]
+pub struct SetFocusNavigationCommand {
+    widgets: Vec<Handle<UiNode>>,
+    mode: FocusNavigationMode,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<(Handle<UiNode>, FocusNavigation)>>,
+    prev_selection: Selection,
+}
+
+impl SetFocusNavigationCommand {
+    pub fn new(widgets: Vec<Handle<UiNode>>, mode: FocusNavigationMode) -> Self {
+        Self {
+            widgets,
+            mode,
+            entries: None,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn make_entries(
+        &self,
+        ui: &UserInterface,
+    ) -> Result<Vec<(Handle<UiNode>, FocusNavigation)>, String> {
+        let mut widgets = Vec::new();
+        for &handle in self.widgets.iter() {
+            if ui.try_get(handle).is_some() && !widgets.contains(&handle) {
+                widgets.push(handle);
+            }
+        }
+
+        if widgets.is_empty() {
+            return Err("There are no widgets to set navigation for!".to_owned());
+        }
+
+        let entries = match self.mode {
+            FocusNavigationMode::Explicit(navigation) => widgets
+                .iter()
+                .map(|&handle| (handle, navigation))
+                .collect::<Vec<_>>(),
+            FocusNavigationMode::Auto => {
+                let candidates = widgets
+                    .iter()
+                    .copied()
+                    .filter(|&handle| is_focusable(ui.node(handle)))
+                    .collect::<Vec<_>>();
+                widgets
+                    .iter()
+                    .map(|&handle| (handle, FocusNavigation::compute(ui, handle, &candidates)))
+                    .collect()
+            }
+        };
+
+        for (handle, navigation) in entries.iter() {
+            for direction in FocusDirection::ALL {
+                let target = navigation.target(direction);
+                if target.is_none() {
+                    continue;
+                }
+                if target == *handle {
+                    return Err(format!(
+                        "{} widget cannot be linked to itself!",
+                        ui.node(*handle).name()
+                    ));
+                }
+                match ui.try_get(target) {
+                    Some(target_ref) if is_focusable(target_ref) => (),
+                    Some(target_ref) => {
+                        return Err(format!(
+                            "{} widget is not focusable! It must accept input or have a tab index.",
+                            target_ref.name()
+                        ))
+                    }
+                    None => return Err(format!("{target} is not a valid widget!")),
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for (handle, navigation) in entries.iter_mut() {
+                if let Some(widget) = ui.try_get_mut(*handle) {
+                    std::mem::swap(
+                        widget.focus_navigation.get_value_mut_and_mark_modified(),
+                        navigation,
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetFocusNavigationCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Focus Navigation".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            match self.make_entries(context.ui) {
+                Ok(entries) => self.entries = Some(entries),
+                Err(err) => {
+                    Log::err(format!("Unable to set focus navigation. Reason: {err}"));
+                    return;
+                }
+            }
+        }
+        self.swap(context.ui);
+
+        let widgets = self
+            .entries
+            .iter()
+            .flatten()
+            .map(|(handle, _)| *handle)
+            .collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if self.entries.is_none() {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+#[derive(Debug)]
+struct SizeConstraintsEntry {
+    handle: Handle<UiNode>,
+    min_size: Vector2<f32>,
+    max_size: Vector2<f32>,
+    // Explicit size of the widget, `None` if it already satisfies the constraints.
+    size: Option<Vector2<f32>>,
+}
+
+/// Sets minimum and maximum size of a set of widgets. These constraints are honored by the layout
+/// system on every subsequent layout pass. Widgets, which explicit size violates the new constraints,
+/// are clamped to fit them, the prior size is restored on revert. On execution, the selection is
+/// changed to the affected widgets.
+#[derive(Debug)]
+pub struct SetSizeConstraintsCommand {
+    widgets: Vec<Handle<UiNode>>,
+    min_size: Vector2<f32>,
+    max_size: Vector2<f32>,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<SizeConstraintsEntry>>,
+    prev_selection: Selection,
+}
+
+impl SetSizeConstraintsCommand {
+    pub fn new(handle: Handle<UiNode>, min_size: Vector2<f32>, max_size: Vector2<f32>) -> Self {
+        Self {
+            widgets: vec![handle],
+            min_size,
+            max_size,
+            entries: None,
+            prev_selection: Default::default(),
+        }
+    }
+
+    /// Creates a command that sets the same constraints for every selected widget.
+    pub fn new_batch(
+        selection: &UiSelection,
+        min_size: Vector2<f32>,
+        max_size: Vector2<f32>,
+    ) -> Self {
+        Self {
+            widgets: selection.widgets.clone(),
+            min_size,
+            max_size,
+            entries: None,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn make_entries(&self, ui: &UserInterface) -> Result<Vec<SizeConstraintsEntry>, String> {
+        for (axis, min, max) in [
+            ("width", self.min_size.x, self.max_size.x),
+            ("height", self.min_size.y, self.max_size.y),
+        ] {
+            if min.is_nan() || max.is_nan() || min < 0.0 || min > max {
+                return Err(format!(
+                    "Invalid {axis} constraints [{min}; {max}]! Minimum must be non-negative \
+                    and must not exceed maximum."
+                ));
+            }
+        }
+
+        let mut entries = Vec::<SizeConstraintsEntry>::new();
+        for &handle in self.widgets.iter() {
+            let Some(widget) = ui.try_get(handle) else {
+                continue;
+            };
+
+            if entries.iter().any(|entry| entry.handle == handle) {
+                continue;
+            }
+
+            // Unset (NaN) size stays unset, because clamping keeps NaN as is.
+            let current_size = Vector2::new(widget.width(), widget.height());
+            let clamped_size = Vector2::new(
+                current_size.x.clamp(self.min_size.x, self.max_size.x),
+                current_size.y.clamp(self.min_size.y, self.max_size.y),
+            );
+            let size = if clamped_size.x.to_bits() != current_size.x.to_bits()
+                || clamped_size.y.to_bits() != current_size.y.to_bits()
+            {
+                Some(clamped_size)
+            } else {
+                None
+            };
+
+            entries.push(SizeConstraintsEntry {
+                handle,
+                min_size: self.min_size,
+                max_size: self.max_size,
+                size,
+            });
+        }
+
+        if entries.is_empty() {
+            return Err("There are no valid widgets!".to_owned());
+        }
+
+        Ok(entries)
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for entry in entries.iter_mut() {
+                let Some(widget) = ui.try_get_mut(entry.handle) else {
+                    continue;
+                };
+
+                std::mem::swap(
+                    widget.min_size.get_value_mut_and_mark_modified(),
+                    &mut entry.min_size,
+                );
+                std::mem::swap(
+                    widget.max_size.get_value_mut_and_mark_modified(),
+                    &mut entry.max_size,
+                );
+                if let Some(size) = entry.size.as_mut() {
+                    std::mem::swap(widget.width.get_value_mut_and_mark_modified(), &mut size.x);
+                    std::mem::swap(widget.height.get_value_mut_and_mark_modified(), &mut size.y);
+                }
+                widget.invalidate_layout();
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetSizeConstraintsCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Size Constraints".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            match self.make_entries(context.ui) {
+                Ok(entries) => self.entries = Some(entries),
+                Err(err) => {
+                    Log::err(format!(
+                        "Unable to set size constraints of widgets. Reason: {err}"
+                    ));
+                    return;
+                }
+            }
+        }
+        self.swap(context.ui);
+
+        let widgets = self
+            .entries
+            .iter()
+            .flatten()
+            .map(|entry| entry.handle)
+            .collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if self.entries.is_none() {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+#[derive(Debug)]
+struct PixelSnapEntry {
+    handle: Handle<UiNode>,
+    // `None` values are left untouched.
+    width: Option<f32>,
+    height: Option<f32>,
+    margin: Option<Thickness>,
+}
+
+/// Rounds the positions and sizes of every widget in a hierarchy to whole pixels, which removes
+/// blurriness of text and images caused by sub-pixel placement. Snapping is done top-down, layout
+/// is updated before each level of the hierarchy is snapped, so every widget is snapped at its
+/// final position. Explicit width and height are rounded directly, positions are corrected by
+/// shifting the margin of a widget, so it works with any layout panel and alignment. `dpi_scale`
+/// defines the size of a pixel: `1.0` snaps to logical pixels, the DPI scale of the target display
+/// snaps to physical pixels. Widgets with scaling or rotating render transform (and their
+/// descendants) cannot be snapped and they're skipped. Revert restores the exact prior values.
+/// On execution, the selection is changed to the root of the hierarchy.
+#[derive(Debug)]
+pub struct PixelSnapHierarchyCommand {
+    root: Handle<UiNode>,
+    dpi_scale: f32,
+    // Values that will be set on next execute/revert. `None` if the command was never executed.
+    entries: Option<Vec<PixelSnapEntry>>,
+    prev_selection: Selection,
+}
+
+impl PixelSnapHierarchyCommand {
+    pub fn new(root: Handle<UiNode>, dpi_scale: f32) -> Self {
+        Self {
+            root,
+            dpi_scale,
+            entries: None,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn snap(&self, value: f32) -> f32 {
+        (value * self.dpi_scale).round() / self.dpi_scale
+    }
+
+    // Snaps the hierarchy and returns the prior values of the changed widgets.
+    fn snap_hierarchy(&self, ui: &mut UserInterface) -> Result<Vec<PixelSnapEntry>, String> {
+        if !self.dpi_scale.is_finite() || self.dpi_scale <= 0.0 {
+            return Err(format!(
+                "{} is not a valid DPI scale! The scale must be finite and positive.",
+                self.dpi_scale
+            ));
+        }
+
+        if ui.try_get(self.root).is_none() {
+            return Err("The root widget does not exist!".to_owned());
+        }
+
+        let mut entries = Vec::<PixelSnapEntry>::new();
+        let mut skipped = Vec::new();
+        let mut level = vec![self.root];
+
+        while !level.is_empty() {
+            // Sizes go first, because they affect positions of centered and stretched widgets.
+            ui.update_layout(ui.screen_size());
+            for &handle in level.iter() {
+                let widget = ui.node_mut(handle);
+                let width = *widget.width;
+                let height = *widget.height;
+                let snapped_width = self.snap(width);
+                let snapped_height = self.snap(height);
+                // Unset (NaN) size is never snapped, because NaN is not equal to itself.
+                let width_changed = width.is_finite() && snapped_width != width;
+                let height_changed = height.is_finite() && snapped_height != height;
+                if width_changed || height_changed {
+                    let mut entry = PixelSnapEntry {
+                        handle,
+                        width: None,
+                        height: None,
+                        margin: None,
+                    };
+                    if width_changed {
+                        widget.width.set_value_and_mark_modified(snapped_width);
+                        entry.width = Some(width);
+                    }
+                    if height_changed {
+                        widget.height.set_value_and_mark_modified(snapped_height);
+                        entry.height = Some(height);
+                    }
+                    widget.invalidate_layout();
+                    entries.push(entry);
+                }
+            }
+
+            ui.update_layout(ui.screen_size());
+            let mut next_level = Vec::new();
+            for &handle in level.iter() {
+                let widget = ui.node_mut(handle);
+                let transform = widget.visual_transform();
+                if transform[0] != 1.0
+                    || transform[1] != 0.0
+                    || transform[3] != 0.0
+                    || transform[4] != 1.0
+                {
+                    skipped.push(widget.name().to_owned());
+                    continue;
+                }
+
+                let position = widget.screen_position();
+                let offset = position.map(|v| self.snap(v)) - position;
+                if offset.x != 0.0 || offset.y != 0.0 {
+                    // Moving both sides keeps the available space, so the widget is just shifted.
+                    let margin = *widget.margin;
+                    widget.margin.set_value_and_mark_modified(Thickness {
+                        left: margin.left + offset.x,
+                        top: margin.top + offset.y,
+                        right: margin.right - offset.x,
+                        bottom: margin.bottom - offset.y,
+                    });
+                    widget.invalidate_layout();
+                    if let Some(entry) = entries.iter_mut().find(|entry| entry.handle == handle) {
+                        entry.margin = Some(margin);
+                    } else {
+                        entries.push(PixelSnapEntry {
+                            handle,
+                            width: None,
+                            height: None,
+                            margin: Some(margin),
+                        });
+                    }
+                }
+
+                next_level.extend_from_slice(widget.children());
+            }
+
+            level = next_level;
+        }
+
+        if !skipped.is_empty() {
+            Log::warn(format!(
+                "The following widgets (and their descendants) were skipped, because they're \
+                scaled or rotated: {}",
+                skipped.join(", ")
+            ));
+        }
+
+        Ok(entries)
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        if let Some(entries) = self.entries.as_mut() {
+            for entry in entries.iter_mut() {
+                let Some(widget) = ui.try_get_mut(entry.handle) else {
+                    continue;
+                };
+
+                if let Some(width) = entry.width.as_mut() {
+                    std::mem::swap(widget.width.get_value_mut_and_mark_modified(), width);
+                }
+                if let Some(height) = entry.height.as_mut() {
+                    std::mem::swap(widget.height.get_value_mut_and_mark_modified(), height);
+                }
+                if let Some(margin) = entry.margin.as_mut() {
+                    std::mem::swap(widget.margin.get_value_mut_and_mark_modified(), margin);
+                }
+                widget.invalidate_layout();
+            }
+        }
+    }
+}
+
+impl CommandTrait for PixelSnapHierarchyCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Pixel Snap Hierarchy".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if self.entries.is_none() {
+            // The first execution snaps the widgets in place and remembers the prior values.
+            match self.snap_hierarchy(context.ui) {
+                Ok(entries) => self.entries = Some(entries),
+                Err(err) => {
+                    Log::err(format!(
+                        "Unable to pixel-snap widget hierarchy. Reason: {err}"
+                    ));
+                    return;
+                }
+            }
+        } else {
+            self.swap(context.ui);
+        }
+
+        self.prev_selection = context.set_selection(Selection::new(UiSelection {
+            widgets: vec![self.root],
+        }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if self.entries.is_none() {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+/// Sets hit-test visibility flag of a set of widgets. Hit-test invisible widgets are click-through,
+/// they do not intercept mouse events and the events are received by the widgets beneath. Keep in
+/// mind, that the flag affects the entire hierarchy of a widget: descendants of a hit-test invisible
+/// container are click-through as well, even if their own flag is set. On execution, the selection
+/// is changed to the affected widgets.
+#[derive(Debug)]
+pub struct SetHitTestVisibleCommand {
+    // Values that will be set on next execute/revert.
+    entries: Vec<(Handle<UiNode>, bool)>,
+    prev_selection: Selection,
+}
+
+impl SetHitTestVisibleCommand {
+    pub fn new(handle: Handle<UiNode>, visible: bool) -> Self {
+        Self {
+            entries: vec![(handle, visible)],
+            prev_selection: Default::default(),
+        }
+    }
+
+    /// Creates a command that sets the same flag for every selected widget.
+    pub fn new_batch(selection: &UiSelection, visible: bool) -> Self {
+        let mut entries = Vec::<(Handle<UiNode>, bool)>::new();
+        for &handle in selection.widgets.iter() {
+            if !entries.iter().any(|(existing, _)| *existing == handle) {
+                entries.push((handle, visible));
+            }
+        }
+        Self {
+            entries,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        for (handle, visible) in self.entries.iter_mut() {
+            if let Some(widget) = ui.try_get_mut(*handle) {
+                let prev = widget.is_hit_test_visible();
+                widget.set_hit_test_visibility(*visible);
+                *visible = prev;
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetHitTestVisibleCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Hit Test Visibility".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.entries
+            .retain(|(handle, _)| context.ui.try_get(*handle).is_some());
+        if self.entries.is_empty() {
+            Log::err("Unable to set hit-test visibility, because there are no valid widgets!");
+            return;
+        }
+        self.swap(context.ui);
+
+        let widgets = self.entries.iter().map(|(handle, _)| *handle).collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+/// Makes a set of widgets draggable (or non-draggable, if the behavior is [`None`]). Draggable widgets
+/// are moved by the user interface when they're dragged with the left mouse button, the behavior
+/// defines whether the movement is constrained to an axis and/or to the bounds of the parent, see
+/// [`DragBehavior`] for more info. Revert restores the exact prior behavior of every widget. On
+/// execution, the selection is changed to the affected widgets.
+#[derive(Debug)]
+pub struct SetDraggableCommand {
+    // Values that will be set on next execute/revert.
+    entries: Vec<(Handle<UiNode>, Option<DragBehavior>)>,
+    prev_selection: Selection,
+}
+
+impl SetDraggableCommand {
+    pub fn new(handle: Handle<UiNode>, drag_behavior: Option<DragBehavior>) -> Self {
+        Self {
+            entries: vec![(handle, drag_behavior)],
+            prev_selection: Default::default(),
+        }
+    }
+
+    /// Creates a command that sets the same behavior for every selected widget.
+    pub fn new_batch(selection: &UiSelection, drag_behavior: Option<DragBehavior>) -> Self {
+        let mut entries = Vec::<(Handle<UiNode>, Option<DragBehavior>)>::new();
+        for &handle in selection.widgets.iter() {
+            if !entries.iter().any(|(existing, _)| *existing == handle) {
+                entries.push((handle, drag_behavior));
+            }
+        }
+        Self {
+            entries,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        for (handle, drag_behavior) in self.entries.iter_mut() {
+            if let Some(widget) = ui.try_get_mut(*handle) {
+                let prev = widget.drag_behavior();
+                widget.set_drag_behavior(*drag_behavior);
+                *drag_behavior = prev;
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetDraggableCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Draggable".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.entries
+            .retain(|(handle, _)| context.ui.try_get(*handle).is_some());
+        if self.entries.is_empty() {
+            Log::err("Unable to set drag behavior, because there are no valid widgets!");
+            return;
+        }
+        self.swap(context.ui);
+
+        let widgets = self.entries.iter().map(|(handle, _)| *handle).collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+#[derive(Debug)]
+struct OpacityEntry {
+    handle: Handle<UiNode>,
+    // Captured on first execution, before the opacity is changed. Undo and redo never read the
+    // opacity back from the widget, because it could be in the middle of a fade.
+    old_opacity: Option<f32>,
+    new_opacity: f32,
+}
+
+/// Sets opacity of a set of widgets. Opacity of a widget is multiplied into the opacity of its
+/// descendants, so the whole hierarchy of the widget fades. The opacity must be in `[0.0; 1.0]`
+/// range, otherwise the command does nothing and reports an error. Optionally, the opacity could be
+/// changed smoothly over the given duration (see [`UserInterface::fade_opacity`]), in this case the
+/// fade is played on execution only, undo and redo restore the exact values immediately. Revert
+/// restores the exact prior opacity of every widget, including the absence of the opacity. On
+/// execution, the selection is changed to the affected widgets.
+#[derive(Debug)]
+pub struct SetOpacityCommand {
+    entries: Vec<OpacityEntry>,
+    fade_duration: f32,
+    executed: bool,
+    prev_selection: Selection,
+}
+
+impl SetOpacityCommand {
+    pub fn new(handle: Handle<UiNode>, opacity: f32) -> Self {
+        Self {
+            entries: vec![OpacityEntry {
+                handle,
+                old_opacity: None,
+                new_opacity: opacity,
+            }],
+            fade_duration: 0.0,
+            executed: false,
+            prev_selection: Default::default(),
+        }
+    }
+
+    /// Creates a command that sets the same opacity for every selected widget.
+    pub fn new_batch(selection: &UiSelection, opacity: f32) -> Self {
+        let mut entries = Vec::<OpacityEntry>::new();
+        for &handle in selection.widgets.iter() {
+            if !entries.iter().any(|entry| entry.handle == handle) {
+                entries.push(OpacityEntry {
+                    handle,
+                    old_opacity: None,
+                    new_opacity: opacity,
+                });
+            }
+        }
+        Self {
+            entries,
+            fade_duration: 0.0,
+            executed: false,
+            prev_selection: Default::default(),
+        }
+    }
+
+    /// Sets the duration (in seconds) of the fade to the new opacity. Zero duration (default) sets
+    /// the opacity immediately.
+    pub fn with_fade(mut self, duration: f32) -> Self {
+        self.fade_duration = duration;
+        self
+    }
+
+    fn apply(&self, ui: &mut UserInterface, fade: bool) {
+        for entry in self.entries.iter() {
+            if ui.try_get(entry.handle).is_none() {
+                continue;
+            }
+            if fade {
+                ui.fade_opacity(entry.handle, entry.new_opacity, self.fade_duration);
+            } else {
+                ui.cancel_opacity_fade(entry.handle);
+                ui.node_mut(entry.handle)
+                    .set_opacity(Some(entry.new_opacity));
+            }
+        }
+    }
+
+    fn restore(&self, ui: &mut UserInterface) {
+        for entry in self.entries.iter() {
+            if ui.try_get(entry.handle).is_none() {
+                continue;
+            }
+            ui.cancel_opacity_fade(entry.handle);
+            ui.node_mut(entry.handle).set_opacity(entry.old_opacity);
+        }
+    }
+}
+
+impl CommandTrait for SetOpacityCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Opacity".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if !self.executed {
+            if let Some(opacity) = self
+                .entries
+                .iter()
+                .map(|entry| entry.new_opacity)
+                .find(|opacity| !(0.0..=1.0).contains(opacity))
+            {
+                Log::err(format!(
+                    "Unable to set opacity {opacity}, it must be in [0.0; 1.0] range!"
+                ));
+                self.entries.clear();
+                return;
+            }
+
+            if !self.fade_duration.is_finite() || self.fade_duration < 0.0 {
+                Log::err(format!(
+                    "Unable to set opacity, fade duration {} is invalid!",
+                    self.fade_duration
+                ));
+                self.entries.clear();
+                return;
+            }
+        }
+
+        self.entries
+            .retain(|entry| context.ui.try_get(entry.handle).is_some());
+        if self.entries.is_empty() {
+            Log::err("Unable to set opacity, because there are no valid widgets!");
+            return;
+        }
+        if !self.executed {
+            for entry in self.entries.iter_mut() {
+                // An unfinished fade of another command must not leak its intermediate value.
+                entry.old_opacity = match context.ui.opacity_fade_target(entry.handle) {
+                    Some(target) => Some(target),
+                    None => context.ui.node(entry.handle).opacity(),
+                };
+            }
+        }
+        // The fade is played only once, redo sets the opacity immediately.
+        self.apply(context.ui, !self.executed);
+        self.executed = true;
+
+        let widgets = self.entries.iter().map(|entry| entry.handle).collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.restore(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+/// Sets hover cursor of a set of widgets. The cursor is given by its name and it is validated
+/// against [`CursorIcon`] variants, [`None`] cursor means that the widget has no cursor of its own
+/// and the cursor of the closest ancestor with a cursor (or the default one) is used. The user
+/// interface switches the OS cursor when the mouse is over such widgets. Revert restores the exact
+/// prior cursor of every widget, including the absence of the cursor. On execution, the selection
+/// is changed to the affected widgets.
+#[derive(Debug)]
+pub struct SetCursorCommand {
+    cursor: Option<String>,
+    // Values that will be set on next execute/revert.
+    entries: Vec<(Handle<UiNode>, Option<CursorIcon>)>,
+    initialized: bool,
+    prev_selection: Selection,
+}
+
+impl SetCursorCommand {
+    pub fn new(handle: Handle<UiNode>, cursor: Option<String>) -> Self {
+        Self {
+            cursor,
+            entries: vec![(handle, None)],
+            initialized: false,
+            prev_selection: Default::default(),
+        }
+    }
+
+    /// Creates a command that sets the same cursor for every selected widget.
+    pub fn new_batch(selection: &UiSelection, cursor: Option<String>) -> Self {
+        let mut entries = Vec::<(Handle<UiNode>, Option<CursorIcon>)>::new();
+        for &handle in selection.widgets.iter() {
+            // Every widget must be swapped exactly once.
+            if !entries.iter().any(|(existing, _)| *existing == handle) {
+                entries.push((handle, None));
+            }
+        }
+        Self {
+            cursor,
+            entries,
+            initialized: false,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn init(&mut self, ui: &UserInterface) -> Result<(), String> {
+        let cursor = match self.cursor {
+            Some(ref cursor) => Some(CursorIcon::from_str(cursor).map_err(|_| {
+                format!(
+                    "{cursor} is not a valid cursor! Valid cursors are: {}",
+                    CursorIcon::VARIANTS.join(", ")
+                )
+            })?),
+            None => None,
+        };
+
+        self.entries
+            .retain(|(handle, _)| ui.try_get(*handle).is_some());
+        if self.entries.is_empty() {
+            return Err("There are no valid widgets!".to_owned());
+        }
+
+        for (_, entry_cursor) in self.entries.iter_mut() {
+            *entry_cursor = cursor;
+        }
+
+        Ok(())
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        for (handle, cursor) in self.entries.iter_mut() {
+            if let Some(widget) = ui.try_get_mut(*handle) {
+                std::mem::swap(widget.cursor.get_value_mut_and_mark_modified(), cursor);
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetCursorCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Cursor".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if !self.initialized {
+            if let Err(err) = self.init(context.ui) {
+                Log::err(format!("Unable to set cursor. Reason: {err}"));
+                return;
+            }
+            self.initialized = true;
+        }
+        self.swap(context.ui);
+
+        let widgets = self.entries.iter().map(|(handle, _)| *handle).collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if !self.initialized {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
+
+/// A 2D transform of a widget, see [`SetWidgetTransformCommand`]. The transform is applied around
+/// the center of the widget in the following order: scale, shear, rotation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WidgetTransform {
+    /// Rotation angle in radians.
+    pub rotation: f32,
+    /// Scale along the local axes of the widget.
+    pub scale: Vector2<f32>,
+    /// Optional shear factors: `x` shifts X coordinates proportionally to Y, `y` - vice versa.
+    pub shear: Option<Vector2<f32>>,
+}
+
+impl Default for WidgetTransform {
+    fn default() -> Self {
+        Self {
+            rotation: 0.0,
+            scale: Vector2::new(1.0, 1.0),
+            shear: None,
+        }
+    }
+}
+
+impl WidgetTransform {
+    fn validate(&self) -> Result<(), String> {
+        let shear = self.shear.unwrap_or_default();
+        if !self.rotation.is_finite()
+            || !self.scale.iter().all(|v| v.is_finite())
+            || !shear.iter().all(|v| v.is_finite())
+        {
+            return Err("Transform must have finite components!".to_owned());
+        }
+
+        // A degenerate transform cannot be inverted, which breaks conversion of screen points to
+        // local coordinates of the widget.
+        if self.matrix(Vector2::default()).try_inverse().is_none() {
+            return Err("Transform must be invertible!".to_owned());
+        }
+
+        Ok(())
+    }
+
+    /// Calculates a render transform matrix for a widget of the given size.
+    pub fn matrix(&self, size: Vector2<f32>) -> Matrix3<f32> {
+        let half_size = size.scale(0.5);
+        let shear = self.shear.unwrap_or_default();
+        let shear = Matrix3::new(1.0, shear.x, 0.0, shear.y, 1.0, 0.0, 0.0, 0.0, 1.0);
+        Matrix3::new_translation(&half_size)
+            * Matrix3::new_rotation(self.rotation)
+            * shear
+            * Matrix3::new_nonuniform_scaling(&self.scale)
+            * Matrix3::new_translation(&-half_size)
+    }
+}
+
+/// Sets a 2D transform (see [`WidgetTransform`]) of a set of widgets using their render transform.
+/// Render transform affects only the drawing stage, layout of the widgets remains the same; the
+/// transform is applied to the widget and all its descendants. The center of the rotation is
+/// calculated using the current size of every widget.
+///
+/// Hit testing respects the transform, because it uses the transformed geometry of the widgets.
+/// However, the clipping bounds of the descendants are calculated as axis-aligned bounds of the
+/// transformed widget, so the clipping of rotated or sheared widgets is approximate.
+///
+/// Revert restores the exact prior render transform of every widget. On execution, the selection
+/// is changed to the affected widgets.
+#[derive(Debug)]
+pub struct SetWidgetTransformCommand {
+    transform: WidgetTransform,
+    // Values that will be set on next execute/revert.
+    entries: Vec<(Handle<UiNode>, Matrix3<f32>)>,
+    initialized: bool,
+    prev_selection: Selection,
+}
+
+impl SetWidgetTransformCommand {
+    pub fn new(handle: Handle<UiNode>, transform: WidgetTransform) -> Self {
+        Self {
+            transform,
+            entries: vec![(handle, Matrix3::identity())],
+            initialized: false,
+            prev_selection: Default::default(),
+        }
+    }
+
+    /// Creates a command that sets the same transform for every selected widget.
+    pub fn new_batch(selection: &UiSelection, transform: WidgetTransform) -> Self {
+        let mut entries = Vec::<(Handle<UiNode>, Matrix3<f32>)>::new();
+        for &handle in selection.widgets.iter() {
+            // Every widget must be swapped exactly once.
+            if !entries.iter().any(|(existing, _)| *existing == handle) {
+                entries.push((handle, Matrix3::identity()));
+            }
+        }
+        Self {
+            transform,
+            entries,
+            initialized: false,
+            prev_selection: Default::default(),
+        }
+    }
+
+    fn init(&mut self, ui: &UserInterface) -> Result<(), String> {
+        self.transform.validate()?;
+
+        self.entries
+            .retain(|(handle, _)| ui.try_get(*handle).is_some());
+        if self.entries.is_empty() {
+            return Err("There are no valid widgets!".to_owned());
+        }
+
+        for (handle, matrix) in self.entries.iter_mut() {
+            *matrix = self.transform.matrix(ui.node(*handle).actual_local_size());
+        }
+
+        Ok(())
+    }
+
+    fn swap(&mut self, ui: &mut UserInterface) {
+        for (handle, matrix) in self.entries.iter_mut() {
+            if let Some(widget) = ui.try_get_mut(*handle) {
+                std::mem::swap(&mut widget.render_transform, matrix);
+                widget.invalidate_layout();
+            }
+        }
+    }
+}
+
+impl CommandTrait for SetWidgetTransformCommand {
+    fn name(&mut self, _context: &dyn CommandContext) -> String {
+        "Set Widget Transform".to_owned()
+    }
+
+    fn execute(&mut self, context: &mut dyn CommandContext) {
+        let context = context.get_mut::<UiSceneContext>();
+
+        if !self.initialized {
+            if let Err(err) = self.init(context.ui) {
+                Log::err(format!("Unable to set widget transform. Reason: {err}"));
+                return;
+            }
+            self.initialized = true;
+        }
+        self.swap(context.ui);
+
+        let widgets = self.entries.iter().map(|(handle, _)| *handle).collect();
+        self.prev_selection = context.set_selection(Selection::new(UiSelection { widgets }));
+    }
+
+    fn revert(&mut self, context: &mut dyn CommandContext) {
+        if !self.initialized {
+            return;
+        }
+
+        let context = context.get_mut::<UiSceneContext>();
+
+        self.swap(context.ui);
+
+        context.swap_selection(&mut self.prev_selection);
+    }
+}
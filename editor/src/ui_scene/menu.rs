@@ -35,7 +35,10 @@ use crate::{
     message::MessageSender,
     scene::{controller::SceneController, Selection},
     ui_scene::{
-        commands::graph::{PasteWidgetCommand, SetUiRootCommand},
+        commands::{
+            graph::{PasteWidgetCommand, SetUiRootCommand},
+            navigation::{SetTabOrderCommand, TabOrderMode},
+        },
         UiScene,
     },
     utils,
@@ -56,6 +59,8 @@ pub struct WidgetContextMenu {
     paste: Handle<UiNode>,
     make_root: Handle<UiNode>,
     open_asset: Handle<UiNode>,
+    set_tab_order: Handle<UiNode>,
+    insert_into_tab_order: Handle<UiNode>,
 }
 
 impl WorldViewerItemContextMenu for WidgetContextMenu {
@@ -89,6 +94,8 @@ impl WidgetContextMenu {
         let paste;
         let make_root;
         let open_asset;
+        let set_tab_order;
+        let insert_into_tab_order;
 
         let widgets_menu = UiMenu::new(widget_constructors_container, "Create Child Widget", ctx);
 
@@ -118,6 +125,15 @@ impl WidgetContextMenu {
                             open_asset = create_menu_item("Open Asset", vec![], ctx);
                             open_asset
                         })
+                        .with_child({
+                            set_tab_order = create_menu_item("Set Tab Order", vec![], ctx);
+                            set_tab_order
+                        })
+                        .with_child({
+                            insert_into_tab_order =
+                                create_menu_item("Insert Into Tab Order", vec![], ctx);
+                            insert_into_tab_order
+                        })
                         .with_child(widgets_menu.menu),
                 )
                 .build(ctx),
@@ -135,6 +151,8 @@ impl WidgetContextMenu {
             paste,
             make_root,
             open_asset,
+            set_tab_order,
+            insert_into_tab_order,
         }
     }
 
@@ -190,6 +208,28 @@ impl WidgetContextMenu {
                             sender.send(Message::LoadScene(path));
                         }
                     }
+                } else if message.destination() == self.set_tab_order {
+                    if let Some(selection) = editor_selection.as_ui() {
+                        sender.do_command(SetTabOrderCommand::new(
+                            selection.widgets.clone(),
+                            TabOrderMode::Local { start: 0 },
+                        ));
+                    }
+                } else if message.destination() == self.insert_into_tab_order {
+                    if let Some(selection) = editor_selection.as_ui() {
+                        // Insert the selection at the place of its first widget, or at the end of
+                        // the existing order if the widget has no tab index.
+                        let position = selection
+                            .widgets
+                            .first()
+                            .and_then(|first| ui_scene.ui.try_get(*first))
+                            .and_then(|widget| *widget.tab_index)
+                            .unwrap_or(usize::MAX);
+                        sender.do_command(SetTabOrderCommand::new(
+                            selection.widgets.clone(),
+                            TabOrderMode::Global { position },
+                        ));
+                    }
                 }
             } else if let Some(PopupMessage::Placement(Placement::Cursor(target))) = message.data()
             {
@@ -43,8 +43,8 @@ use fyrox_graphics::{
     server::{GraphicsServer, ServerCapabilities, SharedGraphicsServer},
     stats::PipelineStatistics,
     BlendEquation, BlendFactor, BlendFunc, BlendMode, ColorMask, CompareFunc, CullFace,
-    DrawParameters, PolygonFace, PolygonFillMode, ScissorBox, StencilAction, StencilFunc,
-    StencilOp,
+    DrawParameters, PolygonFace, PolygonFillMode, PolygonOffset, ScissorBox, StencilAction,
+    StencilFunc, StencilOp,
 };
 use glow::HasContext;
 #[cfg(not(target_arch = "wasm32"))]
@@ -190,6 +190,8 @@ pub(crate) struct InnerState {
     clear_stencil: i32,
     clear_depth: f32,
     scissor_test: bool,
+    polygon_offset: Option<PolygonOffset>,
+    alpha_to_coverage: bool,
 
     polygon_face: PolygonFace,
     polygon_fill_mode: PolygonFillMode,
@@ -239,6 +241,8 @@ impl InnerState {
             clear_stencil: 0,
             clear_depth: 1.0,
             scissor_test: false,
+            polygon_offset: None,
+            alpha_to_coverage: false,
             polygon_face: Default::default(),
             polygon_fill_mode: Default::default(),
             framebuffer: None,
@@ -944,6 +948,41 @@ impl GlGraphicsServer {
         }
     }
 
+    pub(crate) fn set_polygon_offset(&self, polygon_offset: Option<PolygonOffset>) {
+        let mut state = self.state.borrow_mut();
+        if state.polygon_offset != polygon_offset {
+            let was_enabled = state.polygon_offset.is_some();
+            state.polygon_offset = polygon_offset;
+
+            unsafe {
+                if let Some(polygon_offset) = polygon_offset {
+                    if !was_enabled {
+                        self.gl.enable(glow::POLYGON_OFFSET_FILL);
+                    }
+                    self.gl
+                        .polygon_offset(polygon_offset.factor, polygon_offset.units);
+                } else {
+                    self.gl.disable(glow::POLYGON_OFFSET_FILL);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn set_alpha_to_coverage(&self, alpha_to_coverage: bool) {
+        let mut state = self.state.borrow_mut();
+        if state.alpha_to_coverage != alpha_to_coverage {
+            state.alpha_to_coverage = alpha_to_coverage;
+
+            unsafe {
+                if alpha_to_coverage {
+                    self.gl.enable(glow::SAMPLE_ALPHA_TO_COVERAGE);
+                } else {
+                    self.gl.disable(glow::SAMPLE_ALPHA_TO_COVERAGE);
+                }
+            }
+        }
+    }
+
     pub(crate) fn apply_draw_parameters(&self, draw_params: &DrawParameters) {
         let DrawParameters {
             cull_face,
@@ -954,6 +993,8 @@ impl GlGraphicsServer {
             blend,
             stencil_op,
             scissor_box,
+            polygon_offset,
+            alpha_to_coverage,
         } = draw_params;
 
         if let Some(ref blend_params) = blend {
@@ -996,6 +1037,9 @@ impl GlGraphicsServer {
         } else {
             self.set_scissor_test(false);
         }
+
+        self.set_polygon_offset(*polygon_offset);
+        self.set_alpha_to_coverage(*alpha_to_coverage);
     }
 }
 
@@ -21,7 +21,10 @@
 #![allow(clippy::too_many_arguments)]
 
 pub use fyrox_core as core;
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+};
 
 use crate::core::{reflect::prelude::*, type_traits::prelude::*, visitor::prelude::*};
 use serde::{Deserialize, Serialize};
@@ -503,6 +506,30 @@ pub struct ScissorBox {
     pub height: i32,
 }
 
+/// Polygon offset parameters. The offset is added to the depth value of every rasterized fragment
+/// of a polygon and it is equal to `factor * DZ + units * r`, where `DZ` is the maximum depth slope
+/// of the polygon and `r` is the smallest value that is guaranteed to produce a resolvable difference
+/// in the depth buffer. Negative values pull the polygon towards the viewer. The offset is the
+/// standard way to fight z-fighting of coplanar geometry, such as decals baked into a mesh or
+/// road markings on a terrain.
+#[derive(Serialize, Deserialize, Default, Visit, Debug, PartialEq, Clone, Copy, Reflect)]
+pub struct PolygonOffset {
+    /// Scale factor of the maximum depth slope of a polygon.
+    pub factor: f32,
+    /// Constant offset in units of the smallest resolvable depth difference.
+    pub units: f32,
+}
+
+// The offset is never NaN in practice, so it is safe to treat it as a type with total equality.
+impl Eq for PolygonOffset {}
+
+impl Hash for PolygonOffset {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.factor.to_bits().hash(state);
+        self.units.to_bits().hash(state);
+    }
+}
+
 /// A set of drawing parameters, that are used during draw call. It defines pretty much all pipeline
 /// settings all at once.
 #[derive(Serialize, Deserialize, Visit, Debug, PartialEq, Clone, Eq, Reflect)]
@@ -523,6 +550,15 @@ pub struct DrawParameters {
     pub stencil_op: StencilOp,
     /// Optional scissor box. If [`None`], then the scissor test is disabled.
     pub scissor_box: Option<ScissorBox>,
+    /// Optional polygon offset. If [`None`], then the polygon offset is disabled.
+    #[serde(default)]
+    #[visit(optional)]
+    pub polygon_offset: Option<PolygonOffset>,
+    /// A flag, that defines whether the alpha of fragments should define the coverage of the samples
+    /// of multisampled pixels. It has effect only when rendering to a multisampled target.
+    #[serde(default)]
+    #[visit(optional)]
+    pub alpha_to_coverage: bool,
 }
 
 impl Default for DrawParameters {
@@ -536,6 +572,8 @@ impl Default for DrawParameters {
             blend: None,
             stencil_op: Default::default(),
             scissor_box: None,
+            polygon_offset: None,
+            alpha_to_coverage: false,
         }
     }
 }
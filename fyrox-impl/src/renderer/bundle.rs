@@ -29,12 +29,19 @@ use crate::{
         color::{self, Color},
         err_once,
         log::Log,
-        math::{frustum::Frustum, Matrix4Ext, Rect},
+        math::{
+            aabb::AxisAlignedBoundingBox, frustum::Frustum, ray::Ray, Matrix4Ext, Rect,
+            TriangleDefinition,
+        },
         pool::Handle,
         sstorage::ImmutableString,
+        value_as_u8_slice,
     },
     graph::BaseSceneGraph,
-    material::{self, shader::ShaderDefinition, Material, MaterialPropertyRef, MaterialResource},
+    material::{
+        self, shader::ShaderDefinition, CoverageMode, Material, MaterialPropertyRef,
+        MaterialResource, MaterialResourceExtension,
+    },
     renderer::{
         cache::{
             geometry::GeometryCache,
@@ -43,6 +50,7 @@ use crate::{
             uniform::{UniformBlockLocation, UniformMemoryAllocator},
             DynamicSurfaceCache, TimeToLive,
         },
+        compression::{CompressedGeometry, GeometryCompression, GeometryCompressionError},
         framework::{
             error::FrameworkError,
             framebuffer::{GpuFrameBuffer, ResourceBindGroup, ResourceBinding},
@@ -50,7 +58,8 @@ use crate::{
             gpu_texture::GpuTexture,
             server::GraphicsServer,
             uniform::{ByteStorage, StaticUniformBuffer, UniformBuffer},
-            ElementRange,
+            BlendFactor, BlendFunc, BlendParameters, CompareFunc, CullFace, DrawParameters,
+            ElementRange, PolygonOffset, ScissorBox, StencilFunc, StencilOp,
         },
         observer::ObserverPosition,
         FallbackResources, RenderPassStatistics,
@@ -58,6 +67,7 @@ use crate::{
     resource::texture::TextureResource,
     scene::{
         collider::BitMask,
+        dim2::rectangle::RectangleVertex,
         graph::Graph,
         light::{
             directional::{CsmOptions, DirectionalLight},
@@ -66,22 +76,32 @@ use crate::{
             BaseLight,
         },
         mesh::{
-            buffer::{TriangleBufferRefMut, VertexAttributeDescriptor, VertexBufferRefMut},
-            surface::SurfaceResource,
+            buffer::{
+                TriangleBufferRefMut, VertexAttributeDescriptor, VertexAttributeUsage,
+                VertexBufferRefMut, VertexTrait,
+            },
+            surface::{self, SurfaceResource},
             RenderPath,
         },
         node::{Node, NodeTrait, RdcControlFlow},
         probe::ReflectionProbe,
+        sprite::SpriteVertex,
     },
 };
-use fxhash::{FxBuildHasher, FxHashMap, FxHasher};
+use base64::Engine;
+use fxhash::{FxBuildHasher, FxHashMap, FxHashSet, FxHasher};
 use fyrox_graph::{SceneGraph, SceneGraphNode};
 use fyrox_graphics::gpu_program::{SamplerFallback, ShaderResourceDefinition};
 use std::{
-    fmt::{Debug, Formatter},
+    cmp::{Ordering, Reverse},
+    collections::hash_map::Entry,
+    fmt::{Debug, Display, Formatter},
     hash::{Hash, Hasher},
 };
 
+/// A custom culling callback of surface instances, see [`RenderContext::instance_filter`].
+pub type InstanceFilterFn = dyn Fn(&SurfaceInstanceData, Handle<Node>) -> bool;
+
 /// Render context is used to collect render data from the scene nodes. It provides all required information about
 /// the observer (camera, light source virtual camera, etc.), that could be used for culling.
 pub struct RenderContext<'a> {
@@ -105,6 +125,15 @@ pub struct RenderContext<'a> {
     /// A name of the render pass for which the context was created for.
     pub render_pass_name: &'a ImmutableString,
     pub dynamic_surface_cache: &'a mut DynamicSurfaceCache,
+    /// Optional custom culling callback (for example, to hide objects in fog-of-war). When set,
+    /// every surface instance that is pushed to the storage by [`RenderDataBundleStorage::from_graph`]
+    /// is passed to the filter along with the handle of its node, the instance is dropped if the
+    /// filter returns `false`. Triangles pushed using [`RenderDataBundleStorageTrait::push_triangles`]
+    /// are not filtered.
+    ///
+    /// The filter is called for every instance in the hot path of the renderer, so it must be
+    /// cheap. Default is [`None`], which has no cost at all.
+    pub instance_filter: Option<&'a InstanceFilterFn>,
 }
 
 impl RenderContext<'_> {
@@ -123,6 +152,138 @@ impl RenderContext<'_> {
     }
 }
 
+/// A structured sorting key of a render data bundle. Bundles are sorted by the packed key in
+/// ascending order, which means that the fields are compared in the following order (from the
+/// highest precedence to the lowest): pass priority, material group, depth bucket, tie-break.
+///
+/// The key is packed into [`u64`] using the following bit layout:
+///
+/// | Bits    | Field             |
+/// |---------|-------------------|
+/// | 56..64  | `pass_priority`   |
+/// | 40..56  | `material_group`  |
+/// | 8..40   | `depth_bucket`    |
+/// | 0..8    | `tie_break`       |
+///
+/// The fields cover all the bits of the packed value, so any [`u64`] can be converted to the key
+/// and back without any loss. This is used to keep plain sorting indices (for example, the ones
+/// calculated by [`RenderContext::calculate_sorting_index`]) working as is.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SortKey {
+    /// The most significant part of the key. Could be used to force some bundles to be drawn
+    /// before others, regardless of other fields.
+    pub pass_priority: u8,
+    /// A group of materials. Could be used to minimize amount of state changes, by drawing
+    /// bundles with similar materials one after another.
+    pub material_group: u16,
+    /// Quantized depth of the bundle, see [`SortKey::depth_bucket_from_view_z`].
+    pub depth_bucket: u32,
+    /// The least significant part of the key, that is used to resolve ties between bundles with
+    /// the same other fields.
+    pub tie_break: u8,
+}
+
+impl SortKey {
+    const PASS_PRIORITY_SHIFT: u64 = 56;
+    const MATERIAL_GROUP_SHIFT: u64 = 40;
+    const DEPTH_BUCKET_SHIFT: u64 = 8;
+
+    /// Creates a new sorting key from the given fields.
+    pub fn new(pass_priority: u8, material_group: u16, depth_bucket: u32, tie_break: u8) -> Self {
+        Self {
+            pass_priority,
+            material_group,
+            depth_bucket,
+            tie_break,
+        }
+    }
+
+    /// Sets the pass priority of the key.
+    pub fn with_pass_priority(mut self, pass_priority: u8) -> Self {
+        self.pass_priority = pass_priority;
+        self
+    }
+
+    /// Sets the material group of the key.
+    pub fn with_material_group(mut self, material_group: u16) -> Self {
+        self.material_group = material_group;
+        self
+    }
+
+    /// Sets the depth bucket of the key.
+    pub fn with_depth_bucket(mut self, depth_bucket: u32) -> Self {
+        self.depth_bucket = depth_bucket;
+        self
+    }
+
+    /// Sets the tie-break value of the key.
+    pub fn with_tie_break(mut self, tie_break: u8) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// Quantizes the given view-space Z coordinate into a depth bucket with the precision of
+    /// 1/1000 units. The order of the buckets matches the order of the coordinates, so the
+    /// bundles that are farther away from the observer (with more negative Z) are drawn first.
+    /// Coordinates that do not fit in the range of the buckets are clamped.
+    pub fn depth_bucket_from_view_z(view_z: f32) -> u32 {
+        const RANGE_CENTER: i64 = (u32::MAX / 2) as i64;
+        const GRANULARITY: f32 = 1000.0;
+
+        RANGE_CENTER
+            .saturating_add((view_z * GRANULARITY) as i64)
+            .clamp(0, u32::MAX as i64) as u32
+    }
+
+    /// Quantizes the given painter's order Z coordinate of a 2D object into a depth bucket, so the
+    /// objects with higher Z are drawn after the objects with lower Z. Z is rounded to the nearest
+    /// multiple of 1/1000 units, which means that the coordinates that are closer than 0.0005 units
+    /// to each other may land in the same bucket and the coordinates that are at least 0.001 units
+    /// apart always land in different buckets. Rounding (instead of truncation) makes every bucket
+    /// the same size, including the one around zero, and it makes the result stable for tiny
+    /// floating-point errors (for example, `0.1 + 0.2` and `0.3` land in the same bucket). There
+    /// are 2^32 distinguishable values, covering approximately [-2147483.0; 2147483.0] range, other
+    /// coordinates are clamped. NaN is treated as zero.
+    pub fn depth_bucket_from_paint_z(z: f32) -> u32 {
+        const RANGE_CENTER: i64 = (u32::MAX / 2) as i64;
+        const GRANULARITY: f32 = 1000.0;
+
+        RANGE_CENTER
+            .saturating_add((z * GRANULARITY).round() as i64)
+            .clamp(0, u32::MAX as i64) as u32
+    }
+
+    /// Packs the key into [`u64`], see the docs of the [`SortKey`] for the bit layout.
+    pub fn pack(self) -> u64 {
+        ((self.pass_priority as u64) << Self::PASS_PRIORITY_SHIFT)
+            | ((self.material_group as u64) << Self::MATERIAL_GROUP_SHIFT)
+            | ((self.depth_bucket as u64) << Self::DEPTH_BUCKET_SHIFT)
+            | self.tie_break as u64
+    }
+
+    /// Unpacks the key from [`u64`], see the docs of the [`SortKey`] for the bit layout.
+    pub fn unpack(packed: u64) -> Self {
+        Self {
+            pass_priority: (packed >> Self::PASS_PRIORITY_SHIFT) as u8,
+            material_group: (packed >> Self::MATERIAL_GROUP_SHIFT) as u16,
+            depth_bucket: (packed >> Self::DEPTH_BUCKET_SHIFT) as u32,
+            tie_break: packed as u8,
+        }
+    }
+}
+
+impl From<u64> for SortKey {
+    fn from(packed: u64) -> Self {
+        Self::unpack(packed)
+    }
+}
+
+impl From<SortKey> for u64 {
+    fn from(key: SortKey) -> Self {
+        key.pack()
+    }
+}
+
 #[allow(missing_docs)] // TODO
 pub struct BundleRenderContext<'a> {
     pub texture_cache: &'a mut TextureCache,
@@ -139,9 +300,13 @@ pub struct BundleRenderContext<'a> {
     // renderer to have access to depth buffer that is available from G-Buffer.
     pub scene_depth: Option<&'a GpuTexture>,
     pub fallback_resources: &'a FallbackResources,
+    /// Forces the depth test to be disabled for every draw call, regardless of the draw parameters
+    /// of the render pass of the shader.
+    pub disable_depth_test: bool,
 }
 
 /// A set of data of a surface for rendering.
+#[derive(Clone)]
 pub struct SurfaceInstanceData {
     /// A world matrix.
     pub world_transform: Matrix4<f32>,
@@ -154,6 +319,352 @@ pub struct SurfaceInstanceData {
     pub element_range: ElementRange,
     /// A handle of a node that emitted this surface data. Could be none, if there's no info about scene node.
     pub node_handle: Handle<Node>,
+    /// An index of a LOD level that was selected for the node that emitted this surface data (or
+    /// for one of its ancestors). It is [`None`] if the node is not a part of any LOD group. Could
+    /// be used for debug visualization of LOD levels.
+    pub lod_level: Option<u8>,
+    /// Optional clipping rectangle of the instance. It is defined in screen space (in pixels) with
+    /// the origin at the top-left corner of the viewport, the same as the clipping bounds of the UI.
+    /// Every fragment of the instance outside the rectangle will be discarded using scissor test.
+    /// [`None`] means that the instance is not clipped. The clipping rectangle does not affect
+    /// bundle selection, so clipped and unclipped instances could share the same bundle.
+    pub clip_bounds: Option<Rect<f32>>,
+    /// Animation phase of the instance in `[0; 1)` range. It is passed to shaders as
+    /// `fyrox_instanceData.animationPhase` and could be used to desynchronize procedural animation
+    /// (for example, wind sway of foliage) of identical instances. Use
+    /// [`SurfaceInstanceData::animation_phase_from_position`] to get a deterministic phase for a
+    /// world position. The phase does not affect bundle selection.
+    pub animation_phase: f32,
+    /// A set of arbitrary parameters of the instance (for example, dissolve amount or health
+    /// ratio). They're passed to shaders as `fyrox_instanceData.instanceParams` (`vec4`) and
+    /// allow to vary the look of the instances without cloning the material. The parameters do
+    /// not affect bundle selection, so the instances with different parameters are still batched
+    /// together. Keep in mind, that the shader of the material must declare the
+    /// `fyrox_instanceData` property group and read the parameters explicitly, otherwise they
+    /// have no effect.
+    pub instance_params: [f32; 4],
+    /// Optional stencil state of the instance, see [`StencilState`] for more info. [`None`] means
+    /// that the stencil test is not used.
+    pub stencil: Option<StencilState>,
+    /// Optional polygon offset of the instance, see [`PolygonOffset`] for more info. The offset is
+    /// applied by the rasterizer for every draw call of the bundle and instances with different
+    /// offsets are put in separate bundles. Use it for geometry that is coplanar with some other
+    /// geometry (decals baked into a mesh, road markings on a terrain, etc.) to prevent z-fighting.
+    /// Unlike moving the instance towards the camera, the offset depends on the depth slope of the
+    /// polygons and it does not change the position of the instance in the world, so it works at
+    /// any distance and does not cause visible gaps. It has no effect on lines and points.
+    pub polygon_offset: Option<PolygonOffset>,
+    /// Optional face culling mode of the instance, that overrides the culling mode of the render
+    /// passes of the material. [`None`] means that the culling mode of the material is used. Use it
+    /// to render some instances of a shared material two-sided (for example, a flag), without
+    /// cloning the material. Instances with different overrides are put in separate bundles, so
+    /// every distinct override adds at least one extra draw call batch per material and surface.
+    pub cull_override: Option<CullMode>,
+    /// Optional depth state of the instance, that overrides the depth test and depth write state
+    /// of the render passes of the material. [`None`] means that the standard depth state of the
+    /// render path is used. See [`DepthState`] for more info. Instances with different states are
+    /// put in separate bundles.
+    pub depth_state: Option<DepthState>,
+}
+
+/// Depth test and depth write state of an instance, see [`SurfaceInstanceData::depth_state`]. It
+/// could be used for effects like X-ray overlays (the test is disabled) or always visible markers
+/// (the test is disabled and the write is disabled, so the marker does not occlude anything).
+///
+/// Keep in mind, that bundles without the depth test are not occluded by anything, so they're
+/// simply drawn over everything that was drawn before them. In this case the draw order defines the
+/// final image and it must be set explicitly using the sort key (see [`SortKey`]) of the bundle.
+/// Also, a bundle that does not write depth is overdrawn by every bundle that is drawn after it.
+/// The override is applied in every pass (including shadow passes) of the material, unless the
+/// depth test is disabled for the whole render pass.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DepthState {
+    /// Depth test function. [`None`] disables the depth test.
+    pub test: Option<CompareFunc>,
+    /// A flag, that defines whether the depth values should be written to the depth buffer or not.
+    pub write: bool,
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self {
+            test: Some(CompareFunc::Less),
+            write: true,
+        }
+    }
+}
+
+/// Face culling mode of an instance, see [`SurfaceInstanceData::cull_override`]. Mirrored instances
+/// (see [`SurfaceInstanceData::is_mirrored`]) have their culled faces swapped, just like with the
+/// culling mode of the material.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CullMode {
+    /// Both sides of the triangles are rendered.
+    TwoSided,
+    /// The given side of the triangles is culled.
+    Cull(CullFace),
+}
+
+impl CullMode {
+    /// Converts the mode to the cull face of [`DrawParameters`].
+    pub fn cull_face(self) -> Option<CullFace> {
+        match self {
+            CullMode::TwoSided => None,
+            CullMode::Cull(cull_face) => Some(cull_face),
+        }
+    }
+}
+
+/// An attribute of [`SurfaceInstanceData`], that could be written to the instance uniform block
+/// (`fyrox_instanceData`), see [`InstanceLayout`]. Names of the attributes match the names of the
+/// properties of the block in shaders.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum InstanceAttribute {
+    /// `worldMatrix` (`mat4`) - world transform of the instance.
+    WorldMatrix,
+    /// `worldViewProjection` (`mat4`) - world transform of the instance multiplied by the
+    /// view-projection matrix.
+    WorldViewProjection,
+    /// `blendShapesCount` (`int`) - amount of blend shapes of the instance.
+    BlendShapesCount,
+    /// `useSkeletalAnimation` (`bool`) - whether the instance has bone matrices or not.
+    UseSkeletalAnimation,
+    /// `blendShapesWeights` (`vec4[]`) - weights of blend shapes packed in groups of four.
+    BlendShapesWeights,
+    /// `animationPhase` (`float`), see [`SurfaceInstanceData::animation_phase`].
+    AnimationPhase,
+    /// `instanceParams` (`vec4`), see [`SurfaceInstanceData::instance_params`].
+    InstanceParams,
+}
+
+impl InstanceAttribute {
+    /// Every attribute in the default order, see [`InstanceLayout::default`].
+    pub const ALL: [InstanceAttribute; 7] = [
+        InstanceAttribute::WorldMatrix,
+        InstanceAttribute::WorldViewProjection,
+        InstanceAttribute::BlendShapesCount,
+        InstanceAttribute::UseSkeletalAnimation,
+        InstanceAttribute::BlendShapesWeights,
+        InstanceAttribute::AnimationPhase,
+        InstanceAttribute::InstanceParams,
+    ];
+
+    /// Returns the name of the attribute in shaders.
+    pub fn name(self) -> &'static str {
+        match self {
+            InstanceAttribute::WorldMatrix => "worldMatrix",
+            InstanceAttribute::WorldViewProjection => "worldViewProjection",
+            InstanceAttribute::BlendShapesCount => "blendShapesCount",
+            InstanceAttribute::UseSkeletalAnimation => "useSkeletalAnimation",
+            InstanceAttribute::BlendShapesWeights => "blendShapesWeights",
+            InstanceAttribute::AnimationPhase => "animationPhase",
+            InstanceAttribute::InstanceParams => "instanceParams",
+        }
+    }
+
+    /// Finds an attribute by its name in shaders.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|attribute| attribute.name() == name)
+    }
+
+    fn write<S: ByteStorage>(
+        self,
+        instance: &SurfaceInstanceData,
+        view_projection_matrix: &Matrix4<f32>,
+        buffer: &mut UniformBuffer<S>,
+    ) {
+        match self {
+            InstanceAttribute::WorldMatrix => {
+                buffer.push(&instance.world_transform);
+            }
+            InstanceAttribute::WorldViewProjection => {
+                buffer.push(&(view_projection_matrix * instance.world_transform));
+            }
+            InstanceAttribute::BlendShapesCount => {
+                buffer.push(&(instance.blend_shapes_weights.len() as i32));
+            }
+            InstanceAttribute::UseSkeletalAnimation => {
+                buffer.push(&(!instance.bone_matrices.is_empty()));
+            }
+            InstanceAttribute::BlendShapesWeights => {
+                let mut packed_blend_shape_weights =
+                    [Vector4::<f32>::default(); ShaderDefinition::MAX_BLEND_SHAPE_WEIGHT_GROUPS];
+
+                for (i, blend_shape_weight) in instance.blend_shapes_weights.iter().enumerate() {
+                    let n = i / 4;
+                    let c = i % 4;
+                    packed_blend_shape_weights[n][c] = *blend_shape_weight;
+                }
+
+                buffer.push_slice_with_max_size(
+                    &packed_blend_shape_weights,
+                    ShaderDefinition::MAX_BLEND_SHAPE_WEIGHT_GROUPS,
+                );
+            }
+            InstanceAttribute::AnimationPhase => {
+                buffer.push(&instance.animation_phase);
+            }
+            InstanceAttribute::InstanceParams => {
+                buffer.push(&Vector4::from(instance.instance_params));
+            }
+        }
+    }
+}
+
+/// An error that may occur when creating an [`InstanceLayout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstanceLayoutError {
+    /// A layout must have at least one attribute.
+    Empty,
+    /// There's no attribute with the given name.
+    UnknownAttribute(String),
+    /// The attribute is listed more than once.
+    DuplicateAttribute(InstanceAttribute),
+}
+
+impl Display for InstanceLayoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "An instance layout must have at least one attribute."),
+            Self::UnknownAttribute(name) => {
+                write!(
+                    f,
+                    "There's no instance attribute named {name}. Valid names are: {}",
+                    InstanceAttribute::ALL
+                        .iter()
+                        .map(|attribute| attribute.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            }
+            Self::DuplicateAttribute(attribute) => {
+                write!(
+                    f,
+                    "Instance attribute {} is listed more than once.",
+                    attribute.name()
+                )
+            }
+        }
+    }
+}
+
+/// A set of attributes of [`SurfaceInstanceData`] and their order, that defines how instance data
+/// is packed in the instance uniform block (`fyrox_instanceData`) using `std140` layout. The
+/// default layout contains every attribute in the order of [`InstanceAttribute::ALL`], which matches
+/// the autogenerated declaration of the block in shaders. Custom layouts are meant to be used with
+/// shaders that declare the block manually, the declaration must list the same properties in the
+/// same order.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InstanceLayout {
+    attributes: Vec<InstanceAttribute>,
+}
+
+impl Default for InstanceLayout {
+    fn default() -> Self {
+        Self {
+            attributes: InstanceAttribute::ALL.to_vec(),
+        }
+    }
+}
+
+impl InstanceLayout {
+    /// Creates a new layout from the given list of attributes. The list must not be empty and
+    /// every attribute must be listed at most once.
+    pub fn new(attributes: Vec<InstanceAttribute>) -> Result<Self, InstanceLayoutError> {
+        if attributes.is_empty() {
+            return Err(InstanceLayoutError::Empty);
+        }
+
+        for (i, attribute) in attributes.iter().enumerate() {
+            if attributes[..i].contains(attribute) {
+                return Err(InstanceLayoutError::DuplicateAttribute(*attribute));
+            }
+        }
+
+        Ok(Self { attributes })
+    }
+
+    /// Creates a new layout from the given list of attribute names (see
+    /// [`InstanceAttribute::name`]). Every name must match an attribute of [`SurfaceInstanceData`].
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Self, InstanceLayoutError> {
+        let attributes = names
+            .iter()
+            .map(|name| {
+                InstanceAttribute::from_name(name.as_ref())
+                    .ok_or_else(|| InstanceLayoutError::UnknownAttribute(name.as_ref().to_owned()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(attributes)
+    }
+
+    /// Returns the attributes of the layout in their order.
+    pub fn attributes(&self) -> &[InstanceAttribute] {
+        &self.attributes
+    }
+
+    /// Writes the attributes of the given instance to the buffer.
+    pub fn write<S: ByteStorage>(
+        &self,
+        instance: &SurfaceInstanceData,
+        view_projection_matrix: &Matrix4<f32>,
+        buffer: &mut UniformBuffer<S>,
+    ) {
+        write_instance_attributes(&self.attributes, instance, view_projection_matrix, buffer)
+    }
+}
+
+fn write_instance_attributes<S: ByteStorage>(
+    attributes: &[InstanceAttribute],
+    instance: &SurfaceInstanceData,
+    view_projection_matrix: &Matrix4<f32>,
+    buffer: &mut UniformBuffer<S>,
+) {
+    for attribute in attributes {
+        attribute.write(instance, view_projection_matrix, buffer);
+    }
+}
+
+/// Stencil state of a render data bundle. It defines a stencil test (comparison function, reference
+/// value and mask) and actions with the stencil buffer, that are applied for every draw call of the
+/// bundle, overriding the stencil state of the render pass of the material. Instances with
+/// different stencil states are put in separate bundles.
+///
+/// Keep in mind, that the stencil buffer is shared between passes:
+///
+/// - Deferred lighting uses the stencil buffer of the G-Buffer to mark the pixels lit by light
+///   volumes and it clears the buffer for each light, so stencil values written by bundles in the
+///   G-Buffer pass are not available to the forward pass.
+/// - Decals are projected on the G-Buffer in screen space and they ignore stencil state of the
+///   bundles they're projected on.
+/// - Shadow maps have no stencil buffer, so the stencil test always passes in shadow passes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct StencilState {
+    /// Stencil test function with the reference value and the mask.
+    pub func: StencilFunc,
+    /// Actions with the stencil buffer.
+    pub op: StencilOp,
+}
+
+impl SurfaceInstanceData {
+    /// Returns `true` if the world transform of the instance is mirrored (has negative
+    /// determinant), which means that the winding of the triangles is flipped.
+    pub fn is_mirrored(&self) -> bool {
+        self.world_transform.fixed_view::<3, 3>(0, 0).determinant() < 0.0
+    }
+
+    /// Calculates a deterministic animation phase in `[0; 1)` range from the given world position.
+    /// The same position always gives the same phase.
+    pub fn animation_phase_from_position(position: &Vector3<f32>) -> f32 {
+        let mut hasher = FxHasher::default();
+        for component in position.iter() {
+            // Treat -0.0 and 0.0 as the same position.
+            hasher.write_u32((component + 0.0).to_bits());
+        }
+        // Take 24 bits of the hash, so the value could be represented exactly by f32.
+        (hasher.finish() >> 40) as f32 / (1u32 << 24) as f32
+    }
 }
 
 impl Default for SurfaceInstanceData {
@@ -164,11 +675,20 @@ impl Default for SurfaceInstanceData {
             blend_shapes_weights: Default::default(),
             element_range: Default::default(),
             node_handle: Default::default(),
+            lod_level: None,
+            clip_bounds: None,
+            animation_phase: Self::animation_phase_from_position(&Vector3::default()),
+            instance_params: Default::default(),
+            stencil: None,
+            polygon_offset: None,
+            cull_override: None,
+            depth_state: None,
         }
     }
 }
 
 /// A set of surface instances that share the same vertex/index data and a material.
+#[derive(Clone)]
 pub struct RenderDataBundle {
     /// A pointer to shared surface data.
     pub data: SurfaceResource,
@@ -181,6 +701,35 @@ pub struct RenderDataBundle {
     pub material: MaterialResource,
     /// A render path of the bundle.
     pub render_path: RenderPath,
+    /// A flag, that defines whether the material of the bundle produces premultiplied-alpha
+    /// colors. It is used to select blending function.
+    pub premultiplied_alpha: bool,
+    /// Coverage mode of the bundle, it is taken from the material of the bundle. See [`CoverageMode`]
+    /// for more info and for the multisampling requirements of [`CoverageMode::AlphaToCoverage`].
+    pub coverage_mode: CoverageMode,
+    /// A flag, that defines whether the instances of the bundle have mirrored world transforms
+    /// (with negative determinant). Mirroring flips the winding of triangles, so the renderer
+    /// swaps culled faces for such bundles.
+    pub flip_winding: bool,
+    /// Stencil state of the bundle, see [`StencilState`] for more info.
+    pub stencil: Option<StencilState>,
+    /// Polygon offset of the bundle, see [`SurfaceInstanceData::polygon_offset`] for more info.
+    pub polygon_offset: Option<PolygonOffset>,
+    /// Face culling mode of the bundle, see [`SurfaceInstanceData::cull_override`] for more info.
+    pub cull_override: Option<CullMode>,
+    /// Depth state of the bundle, see [`SurfaceInstanceData::depth_state`] for more info.
+    pub depth_state: Option<DepthState>,
+    /// Layout of the instance uniform block of the bundle, see [`InstanceLayout`] for more info.
+    /// [`None`] means the default layout.
+    pub instance_layout: Option<InstanceLayout>,
+    /// Generation of the frame in which the bundle was created or received its last instance, see
+    /// [`RenderDataBundleStorage::frame_generation`].
+    pub frame_generation: u64,
+    /// Compressed vertex data of the bundle. It is produced by
+    /// [`RenderDataBundleStorageTrait::push_triangles`] if
+    /// [`RenderDataBundleStorage::geometry_compression`] is set, otherwise it is [`None`]. See also
+    /// [`Self::compress_geometry`].
+    pub compressed_geometry: Option<CompressedGeometry>,
     sort_index: u64,
 }
 
@@ -195,6 +744,288 @@ impl Debug for RenderDataBundle {
     }
 }
 
+/// An error that may occur during building of a render data bundle using [`RenderDataBundleBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderDataBundleError {
+    /// A bundle must have at least one instance.
+    NoInstances,
+    /// An instance has bone matrices while the bundle is not skinned, or vice versa.
+    SkinningMismatch {
+        /// Index of the instance.
+        instance: usize,
+        /// Skinning flag of the bundle.
+        skinned: bool,
+    },
+    /// An instance has more bone matrices than the renderer supports
+    /// ([`ShaderDefinition::MAX_BONE_MATRICES`]).
+    TooManyBones {
+        /// Index of the instance.
+        instance: usize,
+        /// Actual amount of bone matrices of the instance.
+        count: usize,
+    },
+}
+
+impl Display for RenderDataBundleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoInstances => {
+                write!(f, "A render data bundle must have at least one instance.")
+            }
+            Self::SkinningMismatch { instance, skinned } => {
+                if *skinned {
+                    write!(
+                        f,
+                        "Instance {instance} has no bone matrices, but the bundle is skinned."
+                    )
+                } else {
+                    write!(
+                        f,
+                        "Instance {instance} has bone matrices, but the bundle is not skinned."
+                    )
+                }
+            }
+            Self::TooManyBones { instance, count } => {
+                write!(
+                    f,
+                    "Instance {instance} has {count} bone matrices, but only {} is supported.",
+                    ShaderDefinition::MAX_BONE_MATRICES
+                )
+            }
+        }
+    }
+}
+
+/// A problem with a bundle found by [`RenderDataBundleStorage::validate`]. Bundles are identified by
+/// their keys (see [`RenderDataBundle::key`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleValidationError {
+    /// A bundle has no instances.
+    NoInstances {
+        /// Key of the bundle.
+        bundle_key: u64,
+    },
+    /// An instance has different amount of blend shape weights than the amount of blend shapes of
+    /// the surface data of the bundle.
+    BlendShapeWeightsMismatch {
+        /// Key of the bundle.
+        bundle_key: u64,
+        /// Index of the instance.
+        instance: usize,
+        /// Amount of blend shapes of the surface data.
+        expected: usize,
+        /// Amount of blend shape weights of the instance.
+        actual: usize,
+    },
+    /// Element range of an instance is out of bounds of the triangle buffer of the surface data.
+    InvalidElementRange {
+        /// Key of the bundle.
+        bundle_key: u64,
+        /// Index of the instance.
+        instance: usize,
+        /// Amount of triangles of the surface data.
+        triangle_count: usize,
+    },
+    /// World transform of an instance contains NaN or infinite values.
+    NonFiniteTransform {
+        /// Key of the bundle.
+        bundle_key: u64,
+        /// Index of the instance.
+        instance: usize,
+    },
+    /// An instance has bone matrices while the first instance of the bundle does not, or vice
+    /// versa.
+    SkinningMismatch {
+        /// Key of the bundle.
+        bundle_key: u64,
+        /// Index of the instance.
+        instance: usize,
+        /// Skinning flag of the bundle (defined by its first instance).
+        skinned: bool,
+    },
+}
+
+impl Display for BundleValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoInstances { bundle_key } => {
+                write!(f, "Bundle {bundle_key} has no instances.")
+            }
+            Self::BlendShapeWeightsMismatch {
+                bundle_key,
+                instance,
+                expected,
+                actual,
+            } => {
+                write!(
+                    f,
+                    "Instance {instance} of bundle {bundle_key} has {actual} blend shape weights, \
+                    but the surface has {expected} blend shapes."
+                )
+            }
+            Self::InvalidElementRange {
+                bundle_key,
+                instance,
+                triangle_count,
+            } => {
+                write!(
+                    f,
+                    "Element range of instance {instance} of bundle {bundle_key} is out of \
+                    bounds, the surface has {triangle_count} triangles."
+                )
+            }
+            Self::NonFiniteTransform {
+                bundle_key,
+                instance,
+            } => {
+                write!(
+                    f,
+                    "Instance {instance} of bundle {bundle_key} has non-finite world transform."
+                )
+            }
+            Self::SkinningMismatch {
+                bundle_key,
+                instance,
+                skinned,
+            } => {
+                if *skinned {
+                    write!(
+                        f,
+                        "Instance {instance} of bundle {bundle_key} has no bone matrices, but the \
+                        bundle is skinned."
+                    )
+                } else {
+                    write!(
+                        f,
+                        "Instance {instance} of bundle {bundle_key} has bone matrices, but the \
+                        bundle is not skinned."
+                    )
+                }
+            }
+        }
+    }
+}
+
+/// Render data bundle builder allows you to create render data bundles manually (for example, to
+/// do custom batching in a plugin) and add them to a storage using
+/// [`RenderDataBundleStorage::push_bundle`]. [`RenderDataBundleBuilder::build`] checks the
+/// following invariants:
+///
+/// - A bundle must have at least one instance.
+/// - Every instance of a skinned bundle must have bone matrices and, vice versa, instances of
+///   a non-skinned bundle must not have them.
+/// - Every instance must have at most [`ShaderDefinition::MAX_BONE_MATRICES`] bone matrices.
+pub struct RenderDataBundleBuilder {
+    data: SurfaceResource,
+    material: MaterialResource,
+    render_path: RenderPath,
+    sort_index: u64,
+    skinned: bool,
+    instances: Vec<SurfaceInstanceData>,
+    instance_layout: Option<InstanceLayout>,
+}
+
+impl RenderDataBundleBuilder {
+    /// Creates a new builder with the given surface data and material.
+    pub fn new(data: SurfaceResource, material: MaterialResource) -> Self {
+        Self {
+            data,
+            material,
+            render_path: Default::default(),
+            sort_index: 0,
+            skinned: false,
+            instances: Default::default(),
+            instance_layout: None,
+        }
+    }
+
+    /// Sets the layout of the instance uniform block of the bundle, see [`InstanceLayout`].
+    pub fn with_instance_layout(mut self, instance_layout: InstanceLayout) -> Self {
+        self.instance_layout = Some(instance_layout);
+        self
+    }
+
+    /// Sets the surface data of the bundle.
+    pub fn with_data(mut self, data: SurfaceResource) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Sets the material of the bundle.
+    pub fn with_material(mut self, material: MaterialResource) -> Self {
+        self.material = material;
+        self
+    }
+
+    /// Sets the render path of the bundle. [`RenderPath::Auto`] is resolved using the pass hint
+    /// of the material, see [`RenderPath::resolve`] for more info. Default is
+    /// [`RenderPath::Deferred`].
+    pub fn with_render_path(mut self, render_path: RenderPath) -> Self {
+        self.render_path = render_path;
+        self
+    }
+
+    /// Sets the sort index of the bundle. Default is zero.
+    pub fn with_sort_index(mut self, sort_index: u64) -> Self {
+        self.sort_index = sort_index;
+        self
+    }
+
+    /// Defines whether the instances of the bundle use skeletal animation or not. Default is
+    /// `false`.
+    pub fn with_skinned(mut self, skinned: bool) -> Self {
+        self.skinned = skinned;
+        self
+    }
+
+    /// Sets the instances of the bundle.
+    pub fn with_instances(mut self, instances: Vec<SurfaceInstanceData>) -> Self {
+        self.instances = instances;
+        self
+    }
+
+    /// Adds a new instance to the bundle.
+    pub fn with_instance(mut self, instance: SurfaceInstanceData) -> Self {
+        self.instances.push(instance);
+        self
+    }
+
+    /// Validates the invariants (see [`RenderDataBundleBuilder`] docs) and creates the bundle.
+    pub fn build(self) -> Result<RenderDataBundle, RenderDataBundleError> {
+        if self.instances.is_empty() {
+            return Err(RenderDataBundleError::NoInstances);
+        }
+
+        for (index, instance) in self.instances.iter().enumerate() {
+            if instance.bone_matrices.is_empty() == self.skinned {
+                return Err(RenderDataBundleError::SkinningMismatch {
+                    instance: index,
+                    skinned: self.skinned,
+                });
+            }
+
+            if instance.bone_matrices.len() > ShaderDefinition::MAX_BONE_MATRICES {
+                return Err(RenderDataBundleError::TooManyBones {
+                    instance: index,
+                    count: instance.bone_matrices.len(),
+                });
+            }
+        }
+
+        let render_path = self.render_path.resolve(&self.material);
+
+        let mut bundle = RenderDataBundle::new(
+            self.data,
+            self.material,
+            render_path,
+            self.sort_index,
+            self.instances,
+        );
+        bundle.instance_layout = self.instance_layout;
+        Ok(bundle)
+    }
+}
+
 /// Describes where to the actual uniform data is located in the memory backed by the uniform
 /// memory allocator on per-instance basis.
 pub struct InstanceUniformData {
@@ -380,6 +1211,85 @@ pub fn make_texture_binding(
 }
 
 impl RenderDataBundle {
+    /// Creates a new render data bundle. Usually there's no need to create bundles manually, use
+    /// [`RenderDataBundleStorageTrait`] methods instead. Manually created bundles could be added
+    /// to a storage using [`RenderDataBundleStorage::push_bundle`].
+    pub fn new(
+        data: SurfaceResource,
+        material: MaterialResource,
+        render_path: RenderPath,
+        sort_index: u64,
+        instances: Vec<SurfaceInstanceData>,
+    ) -> Self {
+        Self {
+            data,
+            time_to_live: Default::default(),
+            premultiplied_alpha: material.is_premultiplied_alpha(),
+            coverage_mode: material.coverage_mode(),
+            flip_winding: instances
+                .first()
+                .is_some_and(|instance| instance.is_mirrored()),
+            stencil: instances.first().and_then(|instance| instance.stencil),
+            polygon_offset: instances
+                .first()
+                .and_then(|instance| instance.polygon_offset),
+            cull_override: instances
+                .first()
+                .and_then(|instance| instance.cull_override),
+            depth_state: instances.first().and_then(|instance| instance.depth_state),
+            instance_layout: None,
+            frame_generation: 0,
+            compressed_geometry: None,
+            instances,
+            material,
+            render_path,
+            sort_index,
+        }
+    }
+
+    /// Compresses vertex data of the bundle using the given settings, see [`GeometryCompression`]
+    /// for more info. Compressed data could be uploaded instead of the original vertex data on
+    /// bandwidth-limited platforms, but only if the material of the bundle has a shader that decodes
+    /// the compressed attributes.
+    pub fn compress_geometry(
+        &self,
+        compression: &GeometryCompression,
+    ) -> Result<CompressedGeometry, GeometryCompressionError> {
+        compression.compress(&self.data.data_ref().vertex_buffer)
+    }
+
+    /// Returns sort index of the bundle.
+    pub fn sort_index(&self) -> u64 {
+        self.sort_index
+    }
+
+    /// Returns a key of the bundle, that is used by [`RenderDataBundleStorageTrait::push`] to find
+    /// a bundle for a surface instance. The key is stable across frames as long as the surface
+    /// data, the material and the render parameters of the bundle stay the same, so it could be
+    /// used to identify the bundle between frames (see [`InstanceCountCache`]).
+    pub fn key(&self) -> u64 {
+        calculate_bundle_key(
+            &self.material,
+            &self.data,
+            self.render_path,
+            self.premultiplied_alpha,
+            self.coverage_mode,
+            self.flip_winding,
+            self.stencil,
+            self.polygon_offset,
+            self.cull_override,
+            self.depth_state,
+        )
+    }
+
+    /// Returns `true` if at least one instance of the bundle has bone matrices, which means that
+    /// the bundle will be skinned on GPU.
+    pub fn is_skinned(&self) -> bool {
+        self.instances
+            .iter()
+            .any(|instance| !instance.bone_matrices.is_empty())
+    }
+
     /// Writes all the required uniform data of the bundle to uniform memory allocator.
     pub fn write_uniforms(
         &self,
@@ -435,26 +1345,19 @@ impl RenderDataBundle {
         let light_data_block = render_context.uniform_memory_allocator.allocate(light_data);
 
         // Upload instance uniforms.
+        let instance_attributes = self
+            .instance_layout
+            .as_ref()
+            .map_or(&InstanceAttribute::ALL[..], |layout| layout.attributes());
         let mut instance_blocks = Vec::with_capacity(self.instances.len());
         for instance in self.instances.iter() {
-            let mut packed_blend_shape_weights =
-                [Vector4::<f32>::default(); ShaderDefinition::MAX_BLEND_SHAPE_WEIGHT_GROUPS];
-
-            for (i, blend_shape_weight) in instance.blend_shapes_weights.iter().enumerate() {
-                let n = i / 4;
-                let c = i % 4;
-                packed_blend_shape_weights[n][c] = *blend_shape_weight;
-            }
-
-            let instance_buffer = StaticUniformBuffer::<1024>::new()
-                .with(&instance.world_transform)
-                .with(&(view_projection_matrix * instance.world_transform))
-                .with(&(instance.blend_shapes_weights.len() as i32))
-                .with(&(!instance.bone_matrices.is_empty()))
-                .with_slice_with_max_size(
-                    &packed_blend_shape_weights,
-                    ShaderDefinition::MAX_BLEND_SHAPE_WEIGHT_GROUPS,
-                );
+            let mut instance_buffer = StaticUniformBuffer::<1024>::new();
+            write_instance_attributes(
+                instance_attributes,
+                instance,
+                view_projection_matrix,
+                &mut instance_buffer,
+            );
 
             let mut instance_uniform_data = InstanceUniformData {
                 instance_block: render_context
@@ -640,6 +1543,112 @@ impl RenderDataBundle {
             }
         }
 
+        // Premultiplied-alpha colors must not be multiplied by alpha once again.
+        let premultiplied_draw_params;
+        let bundle_draw_params = match render_pass.draw_params.blend {
+            Some(ref blend) if self.premultiplied_alpha => {
+                premultiplied_draw_params = DrawParameters {
+                    blend: Some(BlendParameters {
+                        func: BlendFunc::new(BlendFactor::One, BlendFactor::OneMinusSrcAlpha),
+                        equation: blend.equation,
+                    }),
+                    ..render_pass.draw_params.clone()
+                };
+                &premultiplied_draw_params
+            }
+            _ => &render_pass.draw_params,
+        };
+
+        let cull_override_draw_params;
+        let bundle_draw_params = match self.cull_override {
+            Some(cull_override) => {
+                cull_override_draw_params = DrawParameters {
+                    cull_face: cull_override.cull_face(),
+                    ..bundle_draw_params.clone()
+                };
+                &cull_override_draw_params
+            }
+            None => bundle_draw_params,
+        };
+
+        // Mirrored instances have flipped winding of triangles, so the opposite faces must be culled.
+        let flipped_draw_params;
+        let bundle_draw_params = match bundle_draw_params.cull_face {
+            Some(cull_face) if self.flip_winding => {
+                flipped_draw_params = DrawParameters {
+                    cull_face: Some(match cull_face {
+                        CullFace::Back => CullFace::Front,
+                        CullFace::Front => CullFace::Back,
+                    }),
+                    ..bundle_draw_params.clone()
+                };
+                &flipped_draw_params
+            }
+            _ => bundle_draw_params,
+        };
+
+        let stencil_draw_params;
+        let bundle_draw_params = match self.stencil {
+            Some(stencil) => {
+                stencil_draw_params = DrawParameters {
+                    stencil_test: Some(stencil.func),
+                    stencil_op: stencil.op,
+                    ..bundle_draw_params.clone()
+                };
+                &stencil_draw_params
+            }
+            None => bundle_draw_params,
+        };
+
+        let polygon_offset_draw_params;
+        let bundle_draw_params = match self.polygon_offset {
+            Some(polygon_offset) => {
+                polygon_offset_draw_params = DrawParameters {
+                    polygon_offset: Some(polygon_offset),
+                    ..bundle_draw_params.clone()
+                };
+                &polygon_offset_draw_params
+            }
+            None => bundle_draw_params,
+        };
+
+        let depth_state_draw_params;
+        let bundle_draw_params = match self.depth_state {
+            Some(depth_state) => {
+                depth_state_draw_params = DrawParameters {
+                    depth_test: depth_state.test,
+                    depth_write: depth_state.write,
+                    ..bundle_draw_params.clone()
+                };
+                &depth_state_draw_params
+            }
+            None => bundle_draw_params,
+        };
+
+        // Conservative rasterization is not supported by graphics servers yet, so such bundles are
+        // drawn with standard coverage.
+        let alpha_to_coverage_draw_params;
+        let bundle_draw_params = if self.coverage_mode == CoverageMode::AlphaToCoverage {
+            alpha_to_coverage_draw_params = DrawParameters {
+                alpha_to_coverage: true,
+                ..bundle_draw_params.clone()
+            };
+            &alpha_to_coverage_draw_params
+        } else {
+            bundle_draw_params
+        };
+
+        let no_depth_test_draw_params;
+        let bundle_draw_params = if render_context.disable_depth_test {
+            no_depth_test_draw_params = DrawParameters {
+                depth_test: None,
+                ..bundle_draw_params.clone()
+            };
+            &no_depth_test_draw_params
+        } else {
+            bundle_draw_params
+        };
+
         for (instance, uniform_data) in self
             .instances
             .iter()
@@ -688,11 +1697,26 @@ impl RenderDataBundle {
                 };
             }
 
-            stats += render_context.frame_buffer.draw(
-                geometry,
+            let clipped_draw_params;
+            let draw_params = match instance.clip_bounds {
+                Some(ref clip_bounds) => {
+                    clipped_draw_params = DrawParameters {
+                        scissor_box: Some(clip_bounds_to_scissor_box(
+                            clip_bounds,
+                            &render_context.viewport,
+                        )),
+                        ..bundle_draw_params.clone()
+                    };
+                    &clipped_draw_params
+                }
+                None => bundle_draw_params,
+            };
+
+            stats += render_context.frame_buffer.draw(
+                geometry,
                 render_context.viewport,
                 &render_pass.program,
-                &render_pass.draw_params,
+                draw_params,
                 &[
                     ResourceBindGroup {
                         bindings: &material_bindings,
@@ -709,6 +1733,22 @@ impl RenderDataBundle {
     }
 }
 
+/// Converts clipping bounds of an instance (in screen space with the origin at the top-left corner
+/// of the viewport) to a scissor box (with the origin at the bottom-left corner of the frame buffer).
+fn clip_bounds_to_scissor_box(clip_bounds: &Rect<f32>, viewport: &Rect<i32>) -> ScissorBox {
+    let x = clip_bounds.position.x.floor();
+    let y = clip_bounds.position.y.floor();
+    let width = clip_bounds.size.x.ceil();
+    let height = clip_bounds.size.y.ceil();
+
+    ScissorBox {
+        x: viewport.position.x + x as i32,
+        y: viewport.position.y + viewport.size.y - (y + height) as i32,
+        width: width as i32,
+        height: height as i32,
+    }
+}
+
 /// A trait for an entity that can collect render data.
 pub trait RenderDataBundleStorageTrait {
     /// Adds a new mesh to the bundle storage using the given set of vertices and triangles. This
@@ -722,6 +1762,9 @@ pub trait RenderDataBundleStorageTrait {
     /// the given vertices and indices. If an appropriate bundle exists, the method will store
     /// the given vertices and the triangles in it.
     ///
+    /// [`RenderPath::Auto`] is resolved using the pass hint of the material, see
+    /// [`RenderPath::resolve`] for more info.
+    ///
     /// ## When to use
     ///
     /// This method is used to reduce amount of draw calls of underlying GAPI, by merging small
@@ -737,7 +1780,7 @@ pub trait RenderDataBundleStorageTrait {
         layout: &[VertexAttributeDescriptor],
         material: &MaterialResource,
         render_path: RenderPath,
-        sort_index: u64,
+        sort_key: SortKey,
         node_handle: Handle<Node>,
         func: &mut dyn FnMut(VertexBufferRefMut, TriangleBufferRefMut),
     );
@@ -745,17 +1788,220 @@ pub trait RenderDataBundleStorageTrait {
     /// Adds a new surface instance to the storage. The method will automatically put the instance
     /// in the appropriate bundle. Bundle selection is done using the material, surface data, render
     /// path. If only one of these parameters is different, then the surface instance will be put
-    /// in a separate bundle.
+    /// in a separate bundle. [`RenderPath::Auto`] is resolved using the pass hint of the material,
+    /// see [`RenderPath::resolve`] for more info. Instances with mirrored world transforms (see
+    /// [`SurfaceInstanceData::is_mirrored`]) are put in separate bundles with flipped winding.
+    /// A bundle takes the packed `sort_key` (see [`SortKey::pack`]) of the first instance that
+    /// was put in it.
     fn push(
         &mut self,
         data: &SurfaceResource,
         material: &MaterialResource,
         render_path: RenderPath,
-        sort_index: u64,
+        sort_key: SortKey,
         instance_data: SurfaceInstanceData,
     );
+
+    /// Does the same as [`Self::push`], but uses the given bundle key instead of calculating it.
+    /// This is a fast path for scene nodes that cache the keys of their surfaces, see
+    /// [`RenderDataBundleStorage::bundle_key`]. The caller must guarantee that the key matches the
+    /// parameters, otherwise the instance will be put in a wrong bundle. The key is validated in
+    /// debug builds only. Storages that do not use keys ignore it.
+    fn push_with_key(
+        &mut self,
+        #[allow(unused_variables)] key: u64,
+        data: &SurfaceResource,
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        instance_data: SurfaceInstanceData,
+    ) {
+        self.push(data, material, render_path, sort_key, instance_data)
+    }
+
+    /// Adds a surface, that consists of multiple parts (submeshes) with different materials, to
+    /// the storage. Each submesh is defined by a range of triangles of the surface and a material,
+    /// it is pushed as a separate instance (using [`Self::push`]) with the element range scoped to
+    /// the submesh. Every instance will land in a bundle of its material, but all of them share
+    /// the same surface data, which means that the geometry is uploaded to GPU only once.
+    ///
+    /// The ranges must not overlap and must cover all the triangles of the surface, otherwise
+    /// the surface won't be pushed at all and an error will be logged.
+    fn push_multi_material(
+        &mut self,
+        data: &SurfaceResource,
+        submeshes: &[(ElementRange, MaterialResource)],
+        render_path: RenderPath,
+        sort_key: SortKey,
+        instance_data: SurfaceInstanceData,
+    ) {
+        let triangle_count = data.data_ref().geometry_buffer.len();
+        if let Err(err) =
+            validate_submesh_ranges(submeshes.iter().map(|(range, _)| *range), triangle_count)
+        {
+            Log::err(format!(
+                "Unable to push a multi-material surface {}. Reason: {err}",
+                data.kind()
+            ));
+            return;
+        }
+
+        for (element_range, material) in submeshes {
+            self.push(
+                data,
+                material,
+                render_path,
+                sort_key,
+                SurfaceInstanceData {
+                    element_range: *element_range,
+                    ..instance_data.clone()
+                },
+            );
+        }
+    }
+
+    /// Adds a new deferred decal projector to the storage. Decals with the same material are put
+    /// in the same [`DecalBatch`], decal batches are drawn by the decal pass of the deferred
+    /// renderer after the G-Buffer is filled. The storage sorts the decals by layer and then by
+    /// `sort_index`. Storages that do not support decals ignore them.
+    fn push_decal(
+        &mut self,
+        #[allow(unused_variables)] material: &DecalMaterial,
+        #[allow(unused_variables)] projector_transform: Matrix4<f32>,
+        #[allow(unused_variables)] color: Color,
+        #[allow(unused_variables)] layer: u8,
+        #[allow(unused_variables)] sort_index: u64,
+    ) {
+    }
+
+    /// Adds a new surface instance of a 2D object to the storage using [`Self::push`] with
+    /// [`RenderPath::Forward`] render path. The painter's order Z coordinate is converted to the
+    /// depth bucket of the sorting key (see [`SortKey::depth_bucket_from_paint_z`] for the
+    /// resolution), so after the storage is sorted the bundles with higher Z are drawn after the
+    /// bundles with lower Z.
+    ///
+    /// Keep in mind, that the order is defined per bundle: a bundle takes the sort key of the first
+    /// instance that was put in it, so the instances of the same surface and material are drawn
+    /// together at the Z of the first of them.
+    fn push_2d(
+        &mut self,
+        data: &SurfaceResource,
+        material: &MaterialResource,
+        z: f32,
+        instance_data: SurfaceInstanceData,
+    ) {
+        self.push(
+            data,
+            material,
+            RenderPath::Forward,
+            SortKey::default().with_depth_bucket(SortKey::depth_bucket_from_paint_z(z)),
+            instance_data,
+        );
+    }
+
+    /// Adds a new 2D sprite (a textured quad) to the storage. Sprites that share the same texture
+    /// are merged into a single bundle, which uses the standard 2D material with the texture
+    /// bound to `diffuseTexture`. The four vertices of the quad are generated on CPU and rotated
+    /// around Z axis by `rotation` (in radians) around the `position`, the quad is then pushed
+    /// using [`Self::push_triangles`].
+    ///
+    /// `sort_key` defines painter's order of the bundles: a bundle takes the sort key of the
+    /// first sprite that was put in it. Sprites within a bundle are drawn in the order they were
+    /// pushed.
+    fn push_sprite(
+        &mut self,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+        position: Vector3<f32>,
+        size: Vector2<f32>,
+        rotation: f32,
+        uv_rect: Rect<f32>,
+        texture: &TextureResource,
+        sort_key: SortKey,
+        node_handle: Handle<Node>,
+    ) {
+        let material = dynamic_surface_cache.get_or_create_sprite_material(texture);
+
+        let (sin, cos) = rotation.sin_cos();
+
+        type Vertex = RectangleVertex;
+
+        // Use the same corner mapping as the rectangle node, so sprites are not mirrored.
+        let vertices = Vertex::quad(
+            |x, y| {
+                let x = x * size.x;
+                let y = y * size.y;
+                position + Vector3::new(x * cos - y * sin, x * sin + y * cos, 0.0)
+            },
+            &uv_rect,
+            Color::WHITE,
+        );
+
+        let triangles = [TriangleDefinition([0, 1, 2]), TriangleDefinition([2, 3, 0])];
+
+        self.push_triangles(
+            dynamic_surface_cache,
+            Vertex::layout(),
+            &material,
+            RenderPath::Forward,
+            sort_key,
+            node_handle,
+            &mut move |mut vertex_buffer, mut triangle_buffer| {
+                let start_vertex_index = vertex_buffer.vertex_count();
+
+                for vertex in vertices.iter() {
+                    vertex_buffer
+                        .push_vertex_raw(value_as_u8_slice(vertex))
+                        .unwrap();
+                }
+
+                triangle_buffer
+                    .push_triangles_iter_with_offset(start_vertex_index, triangles.into_iter());
+            },
+        );
+    }
+}
+
+/// Checks that the given ranges do not overlap and cover exactly `triangle_count` triangles.
+fn validate_submesh_ranges(
+    ranges: impl Iterator<Item = ElementRange>,
+    triangle_count: usize,
+) -> Result<(), String> {
+    let mut ranges = ranges
+        .map(|range| match range {
+            ElementRange::Full => (0, triangle_count),
+            ElementRange::Specific { offset, count } => (offset, count),
+        })
+        .collect::<Vec<_>>();
+    ranges.sort_unstable();
+
+    let mut end = 0;
+    for (offset, count) in ranges {
+        if offset < end {
+            return Err(format!(
+                "Range {offset}..{} overlaps with other range.",
+                offset + count
+            ));
+        }
+        if offset > end {
+            return Err(format!(
+                "Triangles {end}..{offset} are not covered by any range."
+            ));
+        }
+        end = offset + count;
+    }
+
+    match end.cmp(&triangle_count) {
+        Ordering::Less => Err(format!(
+            "Triangles {end}..{triangle_count} are not covered by any range."
+        )),
+        Ordering::Greater => Err(format!(
+            "Ranges are out of bounds, the surface has only {triangle_count} triangles."
+        )),
+        Ordering::Equal => Ok(()),
+    }
 }
 
+#[derive(Clone)]
 pub enum LightSourceKind {
     Spot {
         full_cone_angle: f32,
@@ -795,6 +2041,108 @@ impl<const N: usize> Default for LightData<N> {
     }
 }
 
+/// A set of textures of a deferred decal, see [`RenderDataBundleStorageTrait::push_decal`].
+/// Decals with the same textures are put in the same [`DecalBatch`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DecalMaterial {
+    /// A texture, that will be projected on the diffuse texture of the G-Buffer. White texture is
+    /// used if there's no texture.
+    pub diffuse_texture: Option<TextureResource>,
+    /// A texture, that will be projected on the normal texture of the G-Buffer. Flat normal map is
+    /// used if there's no texture.
+    pub normal_texture: Option<TextureResource>,
+}
+
+impl DecalMaterial {
+    fn key(&self) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.diffuse_texture
+            .as_ref()
+            .map(|texture| texture.key())
+            .hash(&mut hasher);
+        self.normal_texture
+            .as_ref()
+            .map(|texture| texture.key())
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// An instance of a decal projector, see [`DecalBatch`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecalInstance {
+    /// World transform of the projector. The projector is a unit cube transformed by the matrix.
+    pub projector_transform: Matrix4<f32>,
+    /// Inverse of the world transform of the projector. It is used to reproject the positions
+    /// restored from the depth of the G-Buffer into the local space of the projector.
+    pub inv_projector_transform: Matrix4<f32>,
+    /// Color of the decal, it is multiplied with the diffuse texture.
+    pub color: Color,
+    /// Index of the decal layer, the decal is projected only on the surfaces of the same layer.
+    pub layer: u8,
+    /// Sorting index of the decal within its layer.
+    pub sort_index: u64,
+}
+
+/// A set of decal projectors that share the same [`DecalMaterial`]. Decal batches are drawn by the
+/// decal pass of the deferred renderer, after the G-Buffer is filled. Unlike regular bundles, decal
+/// transforms are always in world space, even if origin rebasing is on.
+#[derive(Clone, Debug)]
+pub struct DecalBatch {
+    /// Textures of the decals.
+    pub material: DecalMaterial,
+    /// A set of projectors. [`RenderDataBundleStorage::sort`] sorts them by layer and then by
+    /// sorting index.
+    pub instances: Vec<DecalInstance>,
+}
+
+impl DecalBatch {
+    fn sort_key(&self) -> (u8, u64) {
+        self.instances
+            .iter()
+            .map(|instance| (instance.layer, instance.sort_index))
+            .min()
+            .unwrap_or_default()
+    }
+}
+
+/// A line segment for debug visualization, see [`RenderDataBundleStorage::push_lines`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DebugLine {
+    /// World-space position of the beginning of the line.
+    pub start: Vector3<f32>,
+    /// World-space position of the end of the line.
+    pub end: Vector3<f32>,
+    /// Color of the line.
+    pub color: Color,
+    /// Thickness of the line in world units.
+    pub thickness: f32,
+}
+
+impl DebugLine {
+    /// Expands the line to a quad of the line's thickness, that faces the observer at the given
+    /// position. Returns the corners of the quad in winding order.
+    fn quad_corners(&self, observer_position: Vector3<f32>) -> [Vector3<f32>; 4] {
+        let direction = self.end - self.start;
+        let to_observer = observer_position - (self.start + self.end).scale(0.5);
+        let side = direction
+            .cross(&to_observer)
+            .try_normalize(f32::EPSILON)
+            // The line points at the observer (or has zero length), any perpendicular will do.
+            .or_else(|| direction.cross(&Vector3::y()).try_normalize(f32::EPSILON))
+            .or_else(|| direction.cross(&Vector3::x()).try_normalize(f32::EPSILON))
+            .unwrap_or_else(Vector3::x)
+            .scale(self.thickness * 0.5);
+        [
+            self.start - side,
+            self.start + side,
+            self.end + side,
+            self.end - side,
+        ]
+    }
+}
+
+#[derive(Clone)]
 pub struct LightSource {
     pub handle: Handle<Node>,
     pub global_transform: Matrix4<f32>,
@@ -821,424 +2169,5799 @@ pub struct RenderDataBundleStorage {
     pub bundles: Vec<RenderDataBundle>,
     pub light_sources: Vec<LightSource>,
     pub environment_map: Option<TextureResource>,
+    /// Amount of triangles contributed by each scene node. It is collected only if
+    /// [`RenderDataBundleStorageOptions::collect_triangle_stats`] is set, otherwise it is [`None`].
+    pub node_triangle_counts: Option<FxHashMap<Handle<Node>, u64>>,
+    /// Maximum amount of vertices in a bundle created by
+    /// [`RenderDataBundleStorageTrait::push_triangles`]. When a bundle reaches the limit, the
+    /// next triangles will be put in a sibling bundle with the same parameters. Keep in mind, that
+    /// the limit is checked before the triangles are added, so a bundle may slightly exceed it.
+    /// [`None`] means no limit.
+    pub max_vertices_per_batch: Option<u32>,
+    /// Compression settings of the vertex data of the bundles created by
+    /// [`RenderDataBundleStorageTrait::push_triangles`]. When set, the vertex data of such bundles
+    /// is compressed after every call of the method and stored in
+    /// [`RenderDataBundle::compressed_geometry`]. The whole vertex buffer of the bundle is
+    /// compressed every time, because quantization depends on the range of the values. Standard
+    /// shaders cannot decode compressed data, so it is meant for custom renderers. [`None`] means
+    /// no compression.
+    pub geometry_compression: Option<GeometryCompression>,
+    /// World-space bounds of every instance in the storage, see [`Self::scene_bounds`].
+    scene_bounds: Option<AxisAlignedBoundingBox>,
+    /// Local bounds of surface data, indexed by the key of the surface resource. Used to avoid
+    /// iterating over the same vertex buffer for every instance of a surface.
+    local_bounds_cache: FxHashMap<u64, AxisAlignedBoundingBox>,
+    /// A list of decal batches, see [`RenderDataBundleStorageTrait::push_decal`].
+    pub decal_batches: Vec<DecalBatch>,
+    /// Generation of the current frame, it should increase monotonically from frame to frame. Every
+    /// bundle that is created or receives an instance is stamped with the generation (see
+    /// [`RenderDataBundle::frame_generation`]), so the bundles that were not refreshed during the
+    /// current frame could be detected when the storage is reused across frames, see
+    /// [`Self::stale_identifiers`].
+    pub frame_generation: u64,
+    decal_batch_map: FxHashMap<u64, usize>,
+    // Initial capacity of instance lists of new bundles, see `Self::reserve_instances`.
+    instance_capacity_hints: FxHashMap<u64, usize>,
+    instance_count_cache: Option<InstanceCountCache>,
 }
 
-pub struct RenderDataBundleStorageOptions {
-    pub collect_lights: bool,
+/// Amounts of instances per bundle, that are remembered from a previous frame and used to pre-size
+/// instance lists of the bundles on the next frame, which reduces the amount of reallocations when
+/// instance counts are stable (for example, in crowds). Pass the cache to
+/// [`RenderDataBundleStorage::from_graph`] using [`RenderDataBundleStorageOptions::instance_count_cache`],
+/// the storage reserves the remembered capacities and then updates the cache with the new counts.
+/// Take the updated cache back using [`RenderDataBundleStorage::take_instance_count_cache`] and
+/// pass it to the next frame. Bundles are identified by [`RenderDataBundle::key`], the keys of the
+/// bundles that disappeared are removed from the cache on update.
+#[derive(Debug, Default, Clone)]
+pub struct InstanceCountCache {
+    counts: FxHashMap<u64, usize>,
 }
 
-impl Default for RenderDataBundleStorageOptions {
-    fn default() -> Self {
-        Self {
-            collect_lights: true,
-        }
+impl InstanceCountCache {
+    /// Replaces the remembered counts with the instance counts of the bundles of the storage.
+    pub fn update(&mut self, storage: &RenderDataBundleStorage) {
+        self.counts.clear();
+        self.record(storage);
     }
-}
 
-impl RenderDataBundleStorage {
-    pub fn new_empty(observer_position: ObserverPosition) -> Self {
-        Self {
-            bundle_map: Default::default(),
-            observer_position,
-            bundles: Default::default(),
-            light_sources: Default::default(),
-            environment_map: None,
+    fn record(&mut self, storage: &RenderDataBundleStorage) {
+        for bundle in storage.bundles.iter() {
+            *self.counts.entry(bundle.key()).or_default() += bundle.instances.len();
         }
     }
 
-    /// Creates a new render bundle storage from the given graph and observer info. It "asks" every node in the
-    /// graph one-by-one to give render data which is then put in the storage, sorted and ready for rendering.
-    /// Frustum culling is done on scene node side ([`crate::scene::node::NodeTrait::collect_render_data`]).
-    pub fn from_graph(
-        graph: &Graph,
-        render_mask: BitMask,
-        elapsed_time: f32,
-        observer_position: &ObserverPosition,
-        render_pass_name: ImmutableString,
-        options: RenderDataBundleStorageOptions,
-        dynamic_surface_cache: &mut DynamicSurfaceCache,
-    ) -> Self {
-        // Aim for the worst-case scenario when every node has unique render data.
-        let capacity = graph.node_count() as usize;
-        let mut storage = Self {
-            bundle_map: FxHashMap::with_capacity_and_hasher(capacity, FxBuildHasher::default()),
-            observer_position: observer_position.clone(),
-            bundles: Vec::with_capacity(capacity),
-            light_sources: Default::default(),
-            environment_map: None,
-        };
+    /// Returns remembered amount of instances of a bundle with the given key.
+    pub fn instance_count(&self, bundle_key: u64) -> Option<usize> {
+        self.counts.get(&bundle_key).copied()
+    }
 
-        let frustum = Frustum::from_view_projection_matrix(
-            observer_position.projection_matrix * observer_position.view_matrix,
-        )
-        .unwrap_or_default();
+    /// Returns amount of remembered bundles.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
 
-        let mut lod_filter = vec![true; graph.capacity() as usize];
-        for (node_handle, node) in graph.pair_iter() {
-            if let Some(lod_group) = node.lod_group() {
-                for level in lod_group.levels.iter() {
-                    for &object in level.objects.iter() {
-                        if let Some(object_ref) = graph.try_get(object) {
-                            let distance = observer_position
-                                .translation
-                                .metric_distance(&object_ref.global_position());
-                            let z_range = observer_position.z_far - observer_position.z_near;
-                            let normalized_distance =
-                                (distance - observer_position.z_near) / z_range;
-                            let visible = normalized_distance >= level.begin()
-                                && normalized_distance <= level.end();
-                            lod_filter[object.index() as usize] = visible;
-                        }
-                    }
-                }
-            }
+    /// Returns `true` if the cache has no remembered bundles.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
 
-            if let Some(reflection_probe) = node.component_ref::<ReflectionProbe>() {
-                if (reflection_probe as &dyn NodeTrait)
-                    .world_bounding_box()
-                    .is_contains_point(observer_position.translation)
-                {
-                    storage.environment_map = Some(reflection_probe.render_target().clone());
-                }
-            }
+/// A copy of the render data of a [`RenderDataBundleStorage`], that is decoupled from the scene
+/// graph and could be sent to other thread (it is `Send + 'static`), see
+/// [`RenderDataBundleStorage::snapshot`].
+#[derive(Clone)]
+pub struct RenderSnapshot {
+    /// Position of the observer for which the storage was created.
+    pub observer_position: ObserverPosition,
+    /// A sorted list of bundles.
+    pub bundles: Vec<RenderDataBundle>,
+    /// A list of light sources.
+    pub light_sources: Vec<LightSource>,
+    /// An environment map of the scene.
+    pub environment_map: Option<TextureResource>,
+    /// A list of decal batches.
+    pub decal_batches: Vec<DecalBatch>,
+}
 
-            if options.collect_lights {
-                if let Some(base_light) = node.component_ref::<BaseLight>() {
-                    if frustum.is_intersects_aabb(&node.world_bounding_box())
-                        && base_light.global_visibility()
-                        && base_light.is_globally_enabled()
-                    {
-                        let kind = if let Some(spot_light) = node.cast::<SpotLight>() {
-                            LightSourceKind::Spot {
-                                full_cone_angle: spot_light.full_cone_angle(),
-                                hotspot_cone_angle: spot_light.hotspot_cone_angle(),
-                                distance: spot_light.distance(),
-                                shadow_bias: spot_light.shadow_bias(),
-                                cookie_texture: spot_light.cookie_texture(),
-                            }
-                        } else if let Some(point_light) = node.cast::<PointLight>() {
-                            LightSourceKind::Point {
-                                radius: point_light.radius(),
-                                shadow_bias: point_light.shadow_bias(),
-                            }
-                        } else if let Some(directional_light) = node.cast::<DirectionalLight>() {
-                            LightSourceKind::Directional {
-                                csm_options: (*directional_light.csm_options).clone(),
-                            }
-                        } else {
-                            LightSourceKind::Unknown
-                        };
+impl RenderSnapshot {
+    /// Returns total amount of instances in every bundle of the snapshot.
+    pub fn instance_count(&self) -> usize {
+        self.bundles
+            .iter()
+            .map(|bundle| bundle.instances.len())
+            .sum()
+    }
+}
 
-                        let source = LightSource {
-                            handle: node_handle,
-                            global_transform: base_light.global_transform(),
-                            kind,
-                            position: base_light.global_position(),
-                            up_vector: base_light.up_vector(),
-                            side_vector: base_light.side_vector(),
-                            look_vector: base_light.look_vector(),
-                            cast_shadows: base_light.cast_shadows(),
-                            local_scale: **base_light.local_transform().scale(),
-                            color: base_light.color(),
-                            intensity: base_light.intensity(),
-                            scatter_enabled: base_light.is_scatter_enabled(),
-                            scatter: base_light.scatter(),
-                        };
+fn calculate_bundle_key(
+    material: &MaterialResource,
+    data: &SurfaceResource,
+    render_path: RenderPath,
+    premultiplied_alpha: bool,
+    coverage_mode: CoverageMode,
+    flip_winding: bool,
+    stencil: Option<StencilState>,
+    polygon_offset: Option<PolygonOffset>,
+    cull_override: Option<CullMode>,
+    depth_state: Option<DepthState>,
+) -> u64 {
+    let mut hasher = FxHasher::default();
+    hasher.write_u64(material.key());
+    hasher.write_u64(data.key());
+    hasher.write_u32(render_path as u32);
+    hasher.write_u8(premultiplied_alpha as u8);
+    coverage_mode.hash(&mut hasher);
+    hasher.write_u8(flip_winding as u8);
+    stencil.hash(&mut hasher);
+    polygon_offset.hash(&mut hasher);
+    cull_override.hash(&mut hasher);
+    depth_state.hash(&mut hasher);
+    hasher.finish()
+}
 
-                        storage.light_sources.push(source);
-                    }
-                }
-            }
-        }
+/// Returns a key of the shader of the given material or zero if the material is not loaded.
+fn shader_key(material: &MaterialResource) -> u64 {
+    material
+        .state()
+        .data_ref()
+        .map(|material| material.shader().key())
+        .unwrap_or_default()
+}
 
-        let mut ctx = RenderContext {
-            render_mask,
-            elapsed_time,
-            observer_position,
-            frustum: Some(&frustum),
-            storage: &mut storage,
-            graph,
-            render_pass_name: &render_pass_name,
-            dynamic_surface_cache,
-        };
+/// Calculates world-space bounds of an instance. Skinned instances are bounded by the surface
+/// bounds transformed by every bone matrix.
+fn instance_bounds(
+    local_bounds: &AxisAlignedBoundingBox,
+    instance: &SurfaceInstanceData,
+) -> AxisAlignedBoundingBox {
+    if instance.bone_matrices.is_empty() {
+        local_bounds.transform(&instance.world_transform)
+    } else {
+        let mut bounds = AxisAlignedBoundingBox::default();
+        for bone_matrix in instance.bone_matrices.iter() {
+            bounds.add_box(local_bounds.transform(bone_matrix));
+        }
+        bounds
+    }
+}
 
-        #[inline(always)]
-        fn iterate_recursive(
-            node_handle: Handle<Node>,
-            graph: &Graph,
-            lod_filter: &[bool],
-            ctx: &mut RenderContext,
-        ) {
-            if lod_filter[node_handle.index() as usize] {
-                let node = graph.node(node_handle);
-                if let RdcControlFlow::Continue = node.collect_render_data(ctx) {
-                    for child in node.children() {
-                        iterate_recursive(*child, graph, lod_filter, ctx);
-                    }
-                }
-            }
+fn surface_local_bounds(data: &SurfaceResource) -> AxisAlignedBoundingBox {
+    let mut bounds = AxisAlignedBoundingBox::default();
+    let data = data.data_ref();
+    if let Some(positions) = data
+        .vertex_buffer
+        .attribute_view::<Vector3<f32>>(VertexAttributeUsage::Position)
+    {
+        for i in 0..data.vertex_buffer.vertex_count() as usize {
+            bounds.add_point(*positions.get(i).unwrap());
         }
+    }
+    bounds
+}
 
-        iterate_recursive(graph.root(), graph, &lod_filter, &mut ctx);
+/// A format of a geometry snapshot, see [`RenderDataBundleStorage::export_geometry`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Wavefront OBJ text format. Every bundle is written as a separate group (`g`) that uses its
+    /// own material (`usemtl`), material library is not written.
+    Obj,
+    /// glTF 2.0 JSON format with an embedded (base64) buffer. Every bundle is written as a separate
+    /// mesh with its own material.
+    Gltf,
+}
 
-        storage.sort();
+/// An error that may occur during geometry export, see [`RenderDataBundleStorage::export_geometry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportError {
+    /// Surface data of a bundle has no position attribute.
+    NoPositions {
+        /// Index of the bundle.
+        bundle: usize,
+    },
+    /// Element range of an instance is out of bounds of the triangle buffer of its bundle.
+    InvalidElementRange {
+        /// Index of the bundle.
+        bundle: usize,
+        /// Index of the instance.
+        instance: usize,
+    },
+    /// A triangle of a bundle references a vertex that does not exist.
+    InvalidIndex {
+        /// Index of the bundle.
+        bundle: usize,
+        /// Index of the vertex.
+        index: u32,
+    },
+}
 
-        storage
+impl Display for ExportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoPositions { bundle } => {
+                write!(f, "Surface data of bundle {bundle} has no positions.")
+            }
+            Self::InvalidElementRange { bundle, instance } => {
+                write!(
+                    f,
+                    "Element range of instance {instance} of bundle {bundle} is out of bounds."
+                )
+            }
+            Self::InvalidIndex { bundle, index } => {
+                write!(
+                    f,
+                    "A triangle of bundle {bundle} references vertex {index} that does not exist."
+                )
+            }
+        }
     }
+}
 
-    /// Sorts the bundles by their respective sort index.
-    pub fn sort(&mut self) {
-        self.bundles.sort_unstable_by_key(|b| b.sort_index);
+/// World-space geometry of a single bundle, that is ready to be exported.
+struct ExportGroup {
+    name: String,
+    material: String,
+    positions: Vec<Vector3<f32>>,
+    indices: Vec<u32>,
+}
+
+fn write_obj(groups: &[ExportGroup]) -> Vec<u8> {
+    use std::fmt::Write;
+
+    let mut obj = String::from("# Geometry snapshot of a render data bundle storage\n");
+    let mut base = 1;
+    for group in groups {
+        let _ = writeln!(obj, "g {}", group.name);
+        let _ = writeln!(obj, "usemtl {}", group.material);
+        for position in group.positions.iter() {
+            let _ = writeln!(obj, "v {} {} {}", position.x, position.y, position.z);
+        }
+        for triangle in group.indices.chunks_exact(3) {
+            let _ = writeln!(
+                obj,
+                "f {} {} {}",
+                triangle[0] + base,
+                triangle[1] + base,
+                triangle[2] + base
+            );
+        }
+        base += group.positions.len() as u32;
     }
+    obj.into_bytes()
+}
 
-    pub fn write_global_uniform_blocks(
-        &self,
-        render_context: &mut BundleRenderContext,
-    ) -> GlobalUniformData {
-        let mut light_data = LightData::<{ ShaderDefinition::MAX_LIGHTS }>::default();
+fn write_gltf(groups: &[ExportGroup]) -> Vec<u8> {
+    let mut buffer = Vec::<u8>::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut materials = Vec::<&str>::new();
+    let mut meshes = Vec::new();
+    let mut nodes = Vec::new();
 
-        for (i, light) in self
-            .light_sources
-            .iter()
-            .enumerate()
-            .take(ShaderDefinition::MAX_LIGHTS)
-        {
-            let color = light.color.as_frgb();
+    for (index, group) in groups.iter().enumerate() {
+        let mut min = Vector3::repeat(f32::MAX);
+        let mut max = Vector3::repeat(-f32::MAX);
+        let positions_offset = buffer.len();
+        for position in group.positions.iter() {
+            min = min.inf(position);
+            max = max.sup(position);
+            buffer.extend(position.iter().flat_map(|v| v.to_le_bytes()));
+        }
+        let indices_offset = buffer.len();
+        buffer.extend(group.indices.iter().flat_map(|i| i.to_le_bytes()));
 
-            light_data.color_radius[i] = Vector4::new(color.x, color.y, color.z, 0.0);
-            light_data.position[i] = light.position;
-            light_data.direction[i] = light.up_vector;
+        buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{positions_offset},"byteLength":{},"target":34962}}"#,
+            indices_offset - positions_offset
+        ));
+        buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{indices_offset},"byteLength":{},"target":34963}}"#,
+            buffer.len() - indices_offset
+        ));
+        accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":5126,"count":{},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}}"#,
+            2 * index,
+            group.positions.len(),
+            min.x,
+            min.y,
+            min.z,
+            max.x,
+            max.y,
+            max.z
+        ));
+        accessors.push(format!(
+            r#"{{"bufferView":{},"componentType":5125,"count":{},"type":"SCALAR"}}"#,
+            2 * index + 1,
+            group.indices.len()
+        ));
 
-            match light.kind {
-                LightSourceKind::Spot {
-                    full_cone_angle,
-                    hotspot_cone_angle,
-                    distance,
-                    ..
-                } => {
-                    light_data.color_radius[i].w = distance;
-                    light_data.parameters[i].x = (hotspot_cone_angle * 0.5).cos();
-                    light_data.parameters[i].y = (full_cone_angle * 0.5).cos();
-                }
-                LightSourceKind::Point { radius, .. } => {
-                    light_data.color_radius[i].w = radius;
-                    light_data.parameters[i].x = std::f32::consts::PI.cos();
-                    light_data.parameters[i].y = std::f32::consts::PI.cos();
-                }
-                LightSourceKind::Directional { .. } => {
-                    light_data.color_radius[i].w = f32::INFINITY;
-                    light_data.parameters[i].x = std::f32::consts::PI.cos();
-                    light_data.parameters[i].y = std::f32::consts::PI.cos();
-                }
-                LightSourceKind::Unknown => {}
+        let material = match materials.iter().position(|m| *m == group.material) {
+            Some(material) => material,
+            None => {
+                materials.push(&group.material);
+                materials.len() - 1
             }
+        };
+        meshes.push(format!(
+            r#"{{"name":"{}","primitives":[{{"attributes":{{"POSITION":{}}},"indices":{},"material":{material}}}]}}"#,
+            group.name,
+            2 * index,
+            2 * index + 1
+        ));
+        nodes.push(format!(r#"{{"name":"{}","mesh":{index}}}"#, group.name));
+    }
 
-            light_data.count += 1;
+    let materials = materials
+        .iter()
+        .map(|name| format!(r#"{{"name":"{name}"}}"#))
+        .collect::<Vec<_>>();
+    let scene_nodes = (0..nodes.len())
+        .map(|index| index.to_string())
+        .collect::<Vec<_>>();
+
+    let mut gltf = format!(
+        r#"{{"asset":{{"version":"2.0","generator":"Fyrox"}},"scene":0,"scenes":[{{"nodes":[{}]}}],"nodes":[{}],"meshes":[{}],"materials":[{}],"accessors":[{}],"bufferViews":[{}]"#,
+        scene_nodes.join(","),
+        nodes.join(","),
+        meshes.join(","),
+        materials.join(","),
+        accessors.join(","),
+        buffer_views.join(",")
+    );
+    if !buffer.is_empty() {
+        gltf += &format!(
+            r#","buffers":[{{"byteLength":{},"uri":"data:application/octet-stream;base64,{}"}}]"#,
+            buffer.len(),
+            base64::engine::general_purpose::STANDARD.encode(&buffer)
+        );
+    }
+    gltf += "}";
+    gltf.into_bytes()
+}
+
+/// Projects the given bounds to normalized device coordinates and returns the min and max corners
+/// of the projected rectangle. [`None`] is returned if the bounds are off-screen.
+fn project_bounds_to_ndc(
+    bounds: &AxisAlignedBoundingBox,
+    view_projection: &Matrix4<f32>,
+) -> Option<(Vector2<f32>, Vector2<f32>)> {
+    let mut min = Vector2::repeat(f32::MAX);
+    let mut max = Vector2::repeat(-f32::MAX);
+    for corner in bounds.corners() {
+        let clip_space = view_projection * Vector4::new(corner.x, corner.y, corner.z, 1.0);
+        if clip_space.w <= f32::EPSILON {
+            // The bounds cross the plane of the observer, assume that they cover the whole screen.
+            return Some((Vector2::repeat(-1.0), Vector2::repeat(1.0)));
         }
+        let ndc_space = clip_space.xy() / clip_space.w;
+        min = min.inf(&ndc_space);
+        max = max.sup(&ndc_space);
+    }
 
-        let lights_data = StaticUniformBuffer::<2048>::new()
-            .with(&(light_data.count as i32))
-            .with(&light_data.color_radius)
-            .with(&light_data.parameters)
-            .with(&light_data.position)
-            .with(&light_data.direction);
-        let lights_block = render_context
-            .uniform_memory_allocator
-            .allocate(lights_data);
+    if max.x < -1.0 || min.x > 1.0 || max.y < -1.0 || min.y > 1.0 {
+        None
+    } else {
+        Some((min, max))
+    }
+}
 
-        // Upload camera uniforms.
-        let inv_view = self
-            .observer_position
-            .view_matrix
-            .try_inverse()
-            .unwrap_or_default();
-        let view_projection =
-            self.observer_position.projection_matrix * self.observer_position.view_matrix;
-        let camera_up = inv_view.up();
-        let camera_side = inv_view.side();
-        let camera_uniforms = StaticUniformBuffer::<512>::new()
-            .with(&view_projection)
-            .with(&self.observer_position.translation)
-            .with(&camera_up)
-            .with(&camera_side)
-            .with(&self.observer_position.z_near)
-            .with(&self.observer_position.z_far)
-            .with(&(self.observer_position.z_far - self.observer_position.z_near));
-        let camera_block = render_context
-            .uniform_memory_allocator
-            .allocate(camera_uniforms);
+/// A grid of equally sized screen tiles, see [`RenderDataBundleStorage::partition_by_tiles`].
+/// Tiles are numbered row by row, starting from the top-left corner of the screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TileGrid {
+    /// Amount of tiles along the horizontal axis.
+    pub columns: u32,
+    /// Amount of tiles along the vertical axis.
+    pub rows: u32,
+}
 
-        let graphics_settings = StaticUniformBuffer::<256>::new().with(&render_context.use_pom);
-        let graphics_settings_block = render_context
-            .uniform_memory_allocator
-            .allocate(graphics_settings);
+impl TileGrid {
+    /// Creates a new tile grid with the given amount of columns and rows.
+    pub fn new(columns: u32, rows: u32) -> Self {
+        Self { columns, rows }
+    }
 
-        GlobalUniformData {
-            camera_block,
-            lights_block,
-            graphics_settings_block,
-        }
+    /// Returns total amount of tiles in the grid.
+    pub fn tile_count(&self) -> usize {
+        self.columns as usize * self.rows as usize
     }
+}
 
-    /// Draws the entire bundle set to the specified frame buffer with the specified rendering environment.
-    pub fn render_to_frame_buffer<BundleFilter, InstanceFilter>(
-        &self,
-        server: &dyn GraphicsServer,
-        geometry_cache: &mut GeometryCache,
-        shader_cache: &mut ShaderCache,
-        mut bundle_filter: BundleFilter,
-        mut instance_filter: InstanceFilter,
-        mut render_context: BundleRenderContext,
-    ) -> Result<RenderPassStatistics, FrameworkError>
-    where
-        BundleFilter: FnMut(&RenderDataBundle) -> bool,
-        InstanceFilter: FnMut(&SurfaceInstanceData) -> bool,
-    {
-        let global_uniforms = self.write_global_uniform_blocks(&mut render_context);
+/// A bundle with pending geometry upload, see [`UploadPlan`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PendingUpload {
+    /// Index of the bundle in the storage.
+    pub bundle_index: usize,
+    /// Amount of bytes, that will be uploaded to GPU.
+    pub size: usize,
+    /// A fraction of the screen covered by the bundle, in `[0.0; 1.0]` range.
+    pub priority: f32,
+}
 
-        let view_projection =
-            self.observer_position.projection_matrix * self.observer_position.view_matrix;
-        let mut bundle_uniform_data_set = Vec::with_capacity(self.bundles.len());
-        for bundle in self.bundles.iter() {
-            if !bundle_filter(bundle) {
-                continue;
-            }
-            bundle_uniform_data_set
-                .push(bundle.write_uniforms(&view_projection, &mut render_context));
-        }
-        render_context.uniform_memory_allocator.upload(server)?;
+/// A list of bundles, whose geometry buffers must be uploaded to GPU before they can be rendered,
+/// see [`RenderDataBundleStorage::upload_plan`]. The bundles are sorted by their priority, the most
+/// important bundles go first.
+///
+/// A renderer may upload the first bundles of the plan and defer the rest to next frames (or to a
+/// transfer queue). Keep in mind, that deferring an upload delays the first appearance of the
+/// bundle (or shows outdated geometry of it), so uploads should not be deferred for too long.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UploadPlan {
+    /// Pending uploads sorted by their priority in descending order.
+    pub uploads: Vec<PendingUpload>,
+}
 
-        let mut stats = RenderPassStatistics::default();
-        for (bundle, bundle_uniform_data) in self
-            .bundles
-            .iter()
-            .filter(|bundle| bundle_filter(bundle))
-            .zip(bundle_uniform_data_set)
-        {
-            if let Some(bundle_uniform_data) = bundle_uniform_data {
-                stats += bundle.render_to_frame_buffer(
-                    server,
-                    geometry_cache,
-                    shader_cache,
-                    &mut instance_filter,
-                    &mut render_context,
-                    bundle_uniform_data,
-                    &global_uniforms,
-                )?
-            }
-        }
-        Ok(stats)
+impl UploadPlan {
+    /// Returns total amount of bytes, that will be uploaded to GPU.
+    pub fn total_size(&self) -> usize {
+        self.uploads.iter().map(|upload| upload.size).sum()
     }
 }
 
-impl RenderDataBundleStorageTrait for RenderDataBundleStorage {
-    /// Adds a new mesh to the bundle storage using the given set of vertices and triangles. This
-    /// method automatically creates a render bundle according to a hash of the following parameters:
-    ///
-    /// - Material
-    /// - Vertex Type
-    /// - Render Path
-    ///
-    /// If one of these parameters is different, then a new bundle will be created and used to store
-    /// the given vertices and indices. If an appropriate bundle exists, the method will store the
-    /// given vertices and the triangles in it.
-    ///
-    /// ## When to use
-    ///
-    /// This method is used to reduce amount of draw calls of underlying GAPI, by merging small
-    /// portions of data into one big block that shares drawing parameters and can be rendered in
-    /// a single draw call. The vertices in this case should be pre-processed by applying world
-    /// transform to them.
-    ///
-    /// Do not use this method if you have a mesh with lots of vertices and triangles, because
-    /// pre-processing them on CPU could take more time than rendering them directly on GPU one-by-one.
-    fn push_triangles(
-        &mut self,
-        dynamic_surface_cache: &mut DynamicSurfaceCache,
-        layout: &[VertexAttributeDescriptor],
-        material: &MaterialResource,
-        render_path: RenderPath,
-        sort_index: u64,
+/// Parameters of an indexed indirect draw call, see
+/// [`RenderDataBundleStorage::build_indirect_commands`]. The layout matches the layout expected by
+/// `glMultiDrawElementsIndirect` (and `vkCmdDrawIndexedIndirect`), so a list of commands could be
+/// uploaded to an indirect buffer as is.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct DrawElementsIndirectCommand {
+    /// Amount of indices to draw.
+    pub count: u32,
+    /// Amount of instances to draw.
+    pub instance_count: u32,
+    /// Offset (in indices) of the first index in the shared index buffer.
+    pub first_index: u32,
+    /// A value, that is added to every index before fetching a vertex from the shared vertex buffer.
+    pub base_vertex: i32,
+    /// Index of the first instance in the shared per-instance buffer.
+    pub base_instance: u32,
+}
+
+#[derive(Debug, Clone)]
+enum PickBvhNode {
+    Leaf {
+        bounds: AxisAlignedBoundingBox,
         node_handle: Handle<Node>,
-        func: &mut dyn FnMut(VertexBufferRefMut, TriangleBufferRefMut),
-    ) {
-        let mut hasher = FxHasher::default();
-        hasher.write_u64(material.key());
-        layout.hash(&mut hasher);
-        hasher.write_u32(render_path as u32);
-        let key = hasher.finish();
+    },
+    Branch {
+        bounds: AxisAlignedBoundingBox,
+        left: usize,
+        right: usize,
+    },
+}
 
-        let bundle = if let Some(&bundle_index) = self.bundle_map.get(&key) {
-            self.bundles.get_mut(bundle_index).unwrap()
-        } else {
-            self.bundle_map.insert(key, self.bundles.len());
-            self.bundles.push(RenderDataBundle {
-                data: dynamic_surface_cache.get_or_create(key, layout),
-                sort_index,
-                instances: vec![
-                    // Each bundle must have at least one instance to be rendered.
-                    SurfaceInstanceData {
-                        node_handle,
-                        ..Default::default()
-                    },
-                ],
-                material: material.clone(),
-                render_path,
-                time_to_live: Default::default(),
+impl PickBvhNode {
+    fn bounds(&self) -> &AxisAlignedBoundingBox {
+        match self {
+            PickBvhNode::Leaf { bounds, .. } | PickBvhNode::Branch { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// Bounding volume hierarchy over world-space bounds of surface instances, that allows to find an
+/// instance hit by a ray without testing every instance. See
+/// [`RenderDataBundleStorage::build_pick_bvh`].
+///
+/// The hierarchy works with bounding boxes only, so a hit is a hit of the bounds of an instance,
+/// not of its actual geometry. Use it as a broad phase and then test the triangles of the hit
+/// instances if precise picking is needed.
+#[derive(Debug, Clone, Default)]
+pub struct PickBvh {
+    nodes: Vec<PickBvhNode>,
+}
+
+impl PickBvh {
+    fn build(mut entries: Vec<(AxisAlignedBoundingBox, Handle<Node>)>) -> Self {
+        let mut bvh = Self::default();
+        if !entries.is_empty() {
+            bvh.build_recursive(&mut entries);
+        }
+        bvh
+    }
+
+    fn build_recursive(&mut self, entries: &mut [(AxisAlignedBoundingBox, Handle<Node>)]) -> usize {
+        if let [(bounds, node_handle)] = entries {
+            self.nodes.push(PickBvhNode::Leaf {
+                bounds: *bounds,
+                node_handle: *node_handle,
             });
-            self.bundles.last_mut().unwrap()
-        };
+            return self.nodes.len() - 1;
+        }
 
-        let mut data = bundle.data.data_ref();
-        let data = &mut *data;
+        let mut bounds = AxisAlignedBoundingBox::default();
+        let mut centers = AxisAlignedBoundingBox::default();
+        for (entry_bounds, _) in entries.iter() {
+            bounds.add_box(*entry_bounds);
+            centers.add_point(entry_bounds.center());
+        }
 
-        let vertex_buffer = data.vertex_buffer.modify();
-        let triangle_buffer = data.geometry_buffer.modify();
+        // Split by the median along the axis with the largest spread of the centers.
+        let extents = centers.max - centers.min;
+        let axis = extents.imax();
+        let middle = entries.len() / 2;
+        entries.select_nth_unstable_by(middle, |(a, _), (b, _)| {
+            a.center()[axis].total_cmp(&b.center()[axis])
+        });
 
-        func(vertex_buffer, triangle_buffer);
+        // Reserve a slot for the branch, so the root is always the first node.
+        let index = self.nodes.len();
+        self.nodes.push(PickBvhNode::Branch {
+            bounds,
+            left: 0,
+            right: 0,
+        });
+        let (left_entries, right_entries) = entries.split_at_mut(middle);
+        let left = self.build_recursive(left_entries);
+        let right = self.build_recursive(right_entries);
+        self.nodes[index] = PickBvhNode::Branch {
+            bounds,
+            left,
+            right,
+        };
+        index
     }
 
-    /// Adds a new surface instance to the storage. The method will automatically put the instance in the appropriate
-    /// bundle. Bundle selection is done using the material, surface data, render path. If only one
-    /// of these parameters is different, then the surface instance will be put in a separate bundle.
-    fn push(
-        &mut self,
-        data: &SurfaceResource,
-        material: &MaterialResource,
-        render_path: RenderPath,
-        sort_index: u64,
-        instance_data: SurfaceInstanceData,
-    ) {
-        let mut hasher = FxHasher::default();
-        hasher.write_u64(material.key());
-        hasher.write_u64(data.key());
-        hasher.write_u32(render_path as u32);
-        let key = hasher.finish();
+    /// Returns `true` if there are no instances in the hierarchy.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
 
-        let bundle = if let Some(&bundle_index) = self.bundle_map.get(&key) {
-            self.bundles.get_mut(bundle_index).unwrap()
-        } else {
-            self.bundle_map.insert(key, self.bundles.len());
-            self.bundles.push(RenderDataBundle {
-                data: data.clone(),
-                sort_index,
-                instances: Default::default(),
-                material: material.clone(),
-                render_path,
-                time_to_live: Default::default(),
-            });
-            self.bundles.last_mut().unwrap()
+    /// Finds the nearest instance, whose bounds are hit by the given ray, and returns the handle
+    /// of its node and the distance from the origin of the ray to the hit point. The ray is a
+    /// segment from its origin to `origin + dir`, so only hits within the segment are reported.
+    /// The distance is zero if the origin of the ray is inside the bounds.
+    pub fn raycast(&self, ray: &Ray) -> Option<(Handle<Node>, f32)> {
+        let entry_param = |bounds: &AxisAlignedBoundingBox| {
+            ray.aabb_intersection(bounds)
+                .map(|result| result.min.max(0.0))
         };
 
-        bundle.instances.push(instance_data)
+        let mut closest: Option<(Handle<Node>, f32)> = None;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let Some(node) = self.nodes.get(index) else {
+                continue;
+            };
+
+            let Some(param) = entry_param(node.bounds()) else {
+                continue;
+            };
+            if closest.is_some_and(|(_, closest_param)| param >= closest_param) {
+                continue;
+            }
+
+            match node {
+                PickBvhNode::Leaf { node_handle, .. } => closest = Some((*node_handle, param)),
+                PickBvhNode::Branch { left, right, .. } => {
+                    stack.push(*left);
+                    stack.push(*right);
+                }
+            }
+        }
+
+        closest.map(|(node_handle, param)| (node_handle, param * ray.dir.norm()))
     }
 }
 
-#[cfg(test)]
-mod test {
-    use crate::renderer::bundle::{RenderContext, RenderDataBundleStorage};
-    use crate::renderer::observer::ObserverPosition;
-    use fyrox_core::algebra::{Matrix4, Vector3};
+/// Skinning statistics of a render data bundle storage, see
+/// [`RenderDataBundleStorage::skinning_stats`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct SkinningStats {
+    /// Amount of bundles that have at least one skinned instance.
+    pub skinned_bundles: usize,
+    /// Total amount of instances with bone matrices.
+    pub skinned_instances: usize,
+    /// Total amount of bone matrices that will be uploaded to GPU.
+    pub bone_matrices: usize,
+}
+
+/// Amount of GPU state changes between adjacent bundles of a render data bundle storage, see
+/// [`RenderDataBundleStorage::state_transitions`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct StateTransitions {
+    /// Amount of switches of the shader.
+    pub shaders: usize,
+    /// Amount of switches of the material (including its textures).
+    pub materials: usize,
+}
+
+/// A summary of statistics of a render data bundle storage, that could be shown in an overlay,
+/// see [`RenderDataBundleStorage::overlay_stats`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct OverlayStats {
+    /// Total amount of bundles.
+    pub bundles: usize,
+    /// Total amount of instances in all bundles.
+    pub instances: usize,
+    /// Amount of triangles of the scene, see [`RenderDataBundleStorage::total_triangles`].
+    pub triangles: usize,
+    /// Amount of draw calls per render pass. Every instance is drawn by a separate draw call.
+    pub draw_calls: usize,
+    /// Skinning statistics, see [`RenderDataBundleStorage::skinning_stats`].
+    pub skinning: SkinningStats,
+}
+
+impl Display for OverlayStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Bundles: {}\n\
+            Instances: {}\n\
+            Triangles: {}\n\
+            Draw Calls: {}\n\
+            Skinned Instances: {} ({} bones)\n",
+            self.bundles,
+            self.instances,
+            self.triangles,
+            self.draw_calls,
+            self.skinning.skinned_instances,
+            self.skinning.bone_matrices
+        )
+    }
+}
+
+/// Identifier of a bundle in [`StorageDiff`]. Bundles are identified by their surface data and
+/// render parameters (render path, winding, stencil state, polygon offset and cull override), but
+/// not by their material, so material changes could be detected. The sorting index is not a part of
+/// the identifier either, because it usually depends on the position of the observer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BundleKey {
+    /// A hash of the surface data key and the render parameters of the bundle.
+    pub hash: u64,
+    /// Index of the bundle among the bundles with the same hash (for example, when the same surface
+    /// is rendered with multiple materials). Such bundles are ordered by the key of their material.
+    pub index: usize,
+}
+
+impl Display for BundleKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}#{}", self.hash, self.index)
+    }
+}
+
+/// Change of the amount of instances of a bundle, see [`StorageDiff`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InstanceCountChange {
+    pub key: BundleKey,
+    pub before: usize,
+    pub after: usize,
+}
+
+/// Change of the material of a bundle, see [`StorageDiff`]. Materials are identified by their
+/// resource keys.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MaterialChange {
+    pub key: BundleKey,
+    pub before: u64,
+    pub after: u64,
+}
+
+/// A structural difference between two render data bundle storages, see
+/// [`RenderDataBundleStorage::diff`]. Every list is sorted by bundle key, so the diff does not
+/// depend on the order of the bundles in the storages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageDiff {
+    /// Bundles that are present in the other storage only, along with their amount of instances.
+    pub added: Vec<(BundleKey, usize)>,
+    /// Bundles that are present in this storage only, along with their amount of instances.
+    pub removed: Vec<(BundleKey, usize)>,
+    /// Bundles that are present in both storages, but have different amount of instances.
+    pub instance_count_changes: Vec<InstanceCountChange>,
+    /// Bundles that are present in both storages, but have different materials.
+    pub material_changes: Vec<MaterialChange>,
+}
+
+impl StorageDiff {
+    /// Returns `true` if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.instance_count_changes.is_empty()
+            && self.material_changes.is_empty()
+    }
+}
+
+impl Display for StorageDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No Changes");
+        }
+
+        for (key, instances) in self.added.iter() {
+            writeln!(f, "+ Bundle {key}: {instances} instances")?;
+        }
+        for (key, instances) in self.removed.iter() {
+            writeln!(f, "- Bundle {key}: {instances} instances")?;
+        }
+        for change in self.instance_count_changes.iter() {
+            writeln!(
+                f,
+                "~ Bundle {}: {} -> {} instances",
+                change.key, change.before, change.after
+            )?;
+        }
+        for change in self.material_changes.iter() {
+            writeln!(
+                f,
+                "~ Bundle {}: material {:016x} -> {:016x}",
+                change.key, change.before, change.after
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct RenderDataBundleStorageOptions {
+    pub collect_lights: bool,
+    /// Enables per-node triangle statistics, see [`RenderDataBundleStorage::node_triangle_counts`].
+    /// Disabled by default.
+    pub collect_triangle_stats: bool,
+    /// See [`RenderDataBundleStorage::max_vertices_per_batch`]. Default is [`None`] (no limit).
+    /// The renderer takes the limit of the scene pass from
+    /// [`crate::renderer::QualitySettings::max_vertices_per_batch`], shadow passes are not limited.
+    pub max_vertices_per_batch: Option<u32>,
+    /// See [`RenderDataBundleStorage::geometry_compression`]. Default is [`None`] (no
+    /// compression).
+    pub geometry_compression: Option<GeometryCompression>,
+    /// See [`RenderContext::instance_filter`]. Default is [`None`] (no filtering).
+    pub instance_filter: Option<Box<InstanceFilterFn>>,
+    /// See [`InstanceCountCache`]. Default is [`None`] (instance lists are not pre-sized).
+    pub instance_count_cache: Option<InstanceCountCache>,
+    /// See [`RenderDataBundleStorage::frame_generation`]. Default is 0.
+    pub frame_generation: u64,
+    /// A value that is added to the normalized distance from the observer to the objects of LOD
+    /// groups, positive values select coarser levels. The bias never hides an object that is
+    /// visible without it, the distance is clamped to the range of the levels of the group. See
+    /// also [`RenderDataBundleStorage::from_graph_budgeted`]. Default is 0.0 (no bias).
+    pub lod_bias: f32,
+}
+
+impl Default for RenderDataBundleStorageOptions {
+    fn default() -> Self {
+        Self {
+            collect_lights: true,
+            collect_triangle_stats: false,
+            max_vertices_per_batch: None,
+            geometry_compression: None,
+            instance_filter: None,
+            instance_count_cache: None,
+            frame_generation: 0,
+            lod_bias: 0.0,
+        }
+    }
+}
+
+/// Defines where the bundles of an appended graph are placed in the drawing order, see
+/// [`RenderDataBundleStorage::append_graph`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GraphAppendOrder {
+    /// Sorting indices of the appended bundles are offset, so the bundles are drawn after every
+    /// bundle that is already in the storage. The order of the appended bundles is preserved.
+    /// Indices that do not fit in [`u64`] are clamped, which may break the order of the farthest
+    /// bundles on overflow.
+    After,
+    /// The appended bundles get the given pass priority (see [`SortKey::pass_priority`]), which
+    /// means that they're drawn in a band of the drawing order and could be drawn before, after or
+    /// among the bundles that are already in the storage.
+    Band(u8),
+}
+
+impl RenderDataBundleStorage {
+    /// Size (in pixels) of a cell of the coverage accumulator used by [`Self::estimate_overdraw`].
+    pub const OVERDRAW_CELL_SIZE: u32 = 8;
+
+    /// Increment of the LOD bias between collection passes of [`Self::from_graph_budgeted`].
+    pub const LOD_BIAS_STEP: f32 = 0.1;
+
+    pub fn new_empty(observer_position: ObserverPosition) -> Self {
+        Self {
+            bundle_map: Default::default(),
+            observer_position,
+            bundles: Default::default(),
+            light_sources: Default::default(),
+            environment_map: None,
+            node_triangle_counts: None,
+            max_vertices_per_batch: None,
+            geometry_compression: None,
+            scene_bounds: None,
+            local_bounds_cache: Default::default(),
+            decal_batches: Default::default(),
+            decal_batch_map: Default::default(),
+            frame_generation: 0,
+            instance_capacity_hints: Default::default(),
+            instance_count_cache: None,
+        }
+    }
+
+    fn with_options(
+        observer_position: &ObserverPosition,
+        capacity: usize,
+        options: &RenderDataBundleStorageOptions,
+    ) -> Self {
+        Self {
+            bundle_map: FxHashMap::with_capacity_and_hasher(capacity, FxBuildHasher::default()),
+            observer_position: observer_position.clone(),
+            bundles: Vec::with_capacity(capacity),
+            light_sources: Default::default(),
+            environment_map: None,
+            node_triangle_counts: options.collect_triangle_stats.then(FxHashMap::default),
+            max_vertices_per_batch: options.max_vertices_per_batch,
+            geometry_compression: options.geometry_compression.clone(),
+            scene_bounds: None,
+            local_bounds_cache: Default::default(),
+            decal_batches: Default::default(),
+            decal_batch_map: Default::default(),
+            frame_generation: options.frame_generation,
+            instance_capacity_hints: Default::default(),
+            instance_count_cache: None,
+        }
+    }
+
+    /// Creates a new render bundle storage from the given graph and observer info. It "asks" every node in the
+    /// graph one-by-one to give render data which is then put in the storage, sorted and ready for rendering.
+    /// Frustum culling is done on scene node side ([`crate::scene::node::NodeTrait::collect_render_data`]).
+    pub fn from_graph(
+        graph: &Graph,
+        render_mask: BitMask,
+        elapsed_time: f32,
+        observer_position: &ObserverPosition,
+        render_pass_name: ImmutableString,
+        mut options: RenderDataBundleStorageOptions,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+    ) -> Self {
+        Self::from_graph_internal(
+            graph,
+            render_mask,
+            elapsed_time,
+            observer_position,
+            render_pass_name,
+            &mut options,
+            dynamic_surface_cache,
+            None,
+        )
+    }
+
+    /// Does the same as [`Self::from_graph`], but keeps the vertex memory of the frame (see
+    /// [`Self::vertex_memory`]) within the given budget (in bytes). If the collected render data
+    /// exceeds the budget, the graph is collected again with an increased LOD bias (see
+    /// [`RenderDataBundleStorageOptions::lod_bias`]), which selects coarser levels of LOD groups.
+    /// The bias is increased by [`Self::LOD_BIAS_STEP`] until the data fits in the budget or every
+    /// LOD group uses its coarsest level, in the latter case the storage may still exceed the budget.
+    ///
+    /// Keep in mind, that the budget is met at the cost of visual quality: coarse levels could be
+    /// selected for objects that are close to the observer, which makes the simplification clearly
+    /// visible and may cause popping when the amount of visible geometry changes. Objects outside
+    /// of LOD groups are not affected. Every additional collection pass has the same cost as
+    /// [`Self::from_graph`], so the budget should be chosen to be exceeded rarely.
+    pub fn from_graph_budgeted(
+        graph: &Graph,
+        render_mask: BitMask,
+        elapsed_time: f32,
+        observer_position: &ObserverPosition,
+        render_pass_name: ImmutableString,
+        mut options: RenderDataBundleStorageOptions,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+        vertex_budget: usize,
+    ) -> Self {
+        loop {
+            let storage = Self::from_graph_internal(
+                graph,
+                render_mask,
+                elapsed_time,
+                observer_position,
+                render_pass_name.clone(),
+                &mut options,
+                dynamic_surface_cache,
+                None,
+            );
+
+            // A bias of 1.0 moves every object to the end of the range of its LOD group.
+            if storage.vertex_memory() <= vertex_budget || options.lod_bias >= 1.0 {
+                return storage;
+            }
+
+            options.lod_bias = (options.lod_bias + Self::LOD_BIAS_STEP).min(1.0);
+            // Reuse the recorded instance counts, the next pass will record them again.
+            options.instance_count_cache = storage.instance_count_cache;
+        }
+    }
+
+    /// Returns the amount of memory (in bytes) occupied by vertices of the bundles. Bundles sharing
+    /// the same surface data are counted once, because the data is uploaded to GPU once.
+    pub fn vertex_memory(&self) -> usize {
+        let mut counted = FxHashSet::default();
+        self.bundles
+            .iter()
+            .filter(|bundle| counted.insert(bundle.data.key()))
+            .map(|bundle| bundle.data.data_ref().vertex_buffer.raw_data().len())
+            .sum()
+    }
+
+    /// Does the same as [`Self::from_graph`], but puts the render data in two separate storages:
+    /// the first one contains bundles with opaque materials and the second one contains bundles
+    /// with transparent materials (see [`crate::material::PassHint::is_transparent`]). Bundles are
+    /// routed at the moment when they're pushed to the storage, so the renderer could iterate over
+    /// the appropriate list directly. The opaque storage is sorted front-to-back (see
+    /// [`Self::sort_front_to_back`]) and the transparent storage is sorted back-to-front (see
+    /// [`Self::sort`]). Light sources and the environment map are put in both storages, decals are
+    /// put in the opaque storage.
+    pub fn from_graph_split(
+        graph: &Graph,
+        render_mask: BitMask,
+        elapsed_time: f32,
+        observer_position: &ObserverPosition,
+        render_pass_name: ImmutableString,
+        mut options: RenderDataBundleStorageOptions,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+    ) -> (Self, Self) {
+        let mut transparent = Self::with_options(observer_position, 0, &options);
+        let opaque = Self::from_graph_internal(
+            graph,
+            render_mask,
+            elapsed_time,
+            observer_position,
+            render_pass_name,
+            &mut options,
+            dynamic_surface_cache,
+            Some(&mut transparent),
+        );
+        (opaque, transparent)
+    }
+
+    /// Collects render data of one more graph into the storage, which is useful to composite a few
+    /// scenes in one frame (for example, a 3D world and a separate scene with weapon view model).
+    /// The graph is collected using the observer of the storage, just like [`Self::from_graph`]
+    /// does it. The appended bundles are placed in the drawing order according to `order` (see
+    /// [`GraphAppendOrder`]), the storage is sorted afterwards. Light sources and decals are
+    /// appended as well, the environment map of the storage is replaced only if there's none.
+    ///
+    /// Appended bundles are never merged with existing ones and they do not receive instances from
+    /// subsequent [`RenderDataBundleStorageTrait::push`] calls. Keep in mind, that node handles of
+    /// the instances (and the keys of [`Self::node_triangle_counts`]) are handles in their own
+    /// graph, so handles from different graphs may collide. Anything that maps the instances back
+    /// to the nodes (picking, for example) must check which graph the bundle came from.
+    pub fn append_graph(
+        &mut self,
+        graph: &Graph,
+        render_mask: BitMask,
+        elapsed_time: f32,
+        render_pass_name: ImmutableString,
+        options: RenderDataBundleStorageOptions,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+        order: GraphAppendOrder,
+    ) {
+        let observer_position = self.observer_position.clone();
+        let other = Self::from_graph(
+            graph,
+            render_mask,
+            elapsed_time,
+            &observer_position,
+            render_pass_name,
+            options,
+            dynamic_surface_cache,
+        );
+
+        let offset = match order {
+            GraphAppendOrder::After => {
+                let last = self.bundles.iter().map(|bundle| bundle.sort_index).max();
+                let first = other.bundles.iter().map(|bundle| bundle.sort_index).min();
+                match (last, first) {
+                    (Some(last), Some(first)) => last.saturating_add(1).saturating_sub(first),
+                    _ => 0,
+                }
+            }
+            GraphAppendOrder::Band(_) => 0,
+        };
+        for mut bundle in other.bundles {
+            bundle.sort_index = match order {
+                GraphAppendOrder::After => bundle.sort_index.saturating_add(offset),
+                GraphAppendOrder::Band(pass_priority) => SortKey::unpack(bundle.sort_index)
+                    .with_pass_priority(pass_priority)
+                    .pack(),
+            };
+            self.bundles.push(bundle);
+        }
+
+        for batch in other.decal_batches {
+            for instance in batch.instances {
+                self.push_decal(
+                    &batch.material,
+                    instance.projector_transform,
+                    instance.color,
+                    instance.layer,
+                    instance.sort_index,
+                );
+            }
+        }
+
+        self.light_sources.extend(other.light_sources);
+        if self.environment_map.is_none() {
+            self.environment_map = other.environment_map;
+        }
+        if let Some(bounds) = other.scene_bounds {
+            self.add_scene_bounds(bounds);
+        }
+        if let (Some(counts), Some(other_counts)) = (
+            self.node_triangle_counts.as_mut(),
+            other.node_triangle_counts,
+        ) {
+            for (handle, count) in other_counts {
+                *counts.entry(handle).or_default() += count;
+            }
+        }
+
+        self.sort();
+    }
+
+    fn from_graph_internal(
+        graph: &Graph,
+        render_mask: BitMask,
+        elapsed_time: f32,
+        observer_position: &ObserverPosition,
+        render_pass_name: ImmutableString,
+        options: &mut RenderDataBundleStorageOptions,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+        mut transparent: Option<&mut Self>,
+    ) -> Self {
+        // Aim for the worst-case scenario when every node has unique render data.
+        let capacity = graph.node_count() as usize;
+        let mut storage = Self::with_options(observer_position, capacity, options);
+
+        let mut instance_count_cache = options.instance_count_cache.take();
+        if let Some(cache) = instance_count_cache.as_mut() {
+            if let Some(transparent) = transparent.as_deref_mut() {
+                transparent.instance_capacity_hints = cache.counts.clone();
+            }
+            storage.instance_capacity_hints = std::mem::take(&mut cache.counts);
+        }
+
+        let mut frustum = Frustum::from_view_projection_matrix(
+            observer_position.projection_matrix * observer_position.view_matrix,
+        )
+        .unwrap_or_default();
+
+        let z_range = observer_position.z_far - observer_position.z_near;
+        let normalized_distance = |position: &Vector3<f32>| {
+            let distance = observer_position.translation.metric_distance(position);
+            (distance - observer_position.z_near) / z_range
+        };
+
+        // LOD ranges are normalized, so the padding must be normalized too.
+        let mut lod_padding = 0.0;
+        if observer_position.cull_padding != 0.0 {
+            frustum = frustum.inflated(observer_position.cull_padding);
+            lod_padding = observer_position.cull_padding / z_range;
+        }
+
+        let mut lod_filter = vec![true; graph.capacity() as usize];
+        let mut lod_levels = vec![None; graph.capacity() as usize];
+        let mut impostors = Vec::new();
+        for (node_handle, node) in graph.pair_iter() {
+            if let Some(lod_group) = node.lod_group() {
+                // The impostor replaces every level of the group.
+                let impostor = lod_group.impostor.as_ref().filter(|impostor| {
+                    impostor.is_in_range(normalized_distance(&node.global_position()))
+                });
+                if let Some(impostor) = impostor {
+                    if node.should_be_rendered(None, render_mask) {
+                        impostors.push((node_handle, node.global_position(), impostor));
+                    }
+                }
+
+                // The bias must not hide objects that are visible without it.
+                let (range_begin, range_end) = lod_group
+                    .levels
+                    .iter()
+                    .fold((f32::MAX, f32::MIN), |(begin, end), level| {
+                        (begin.min(level.begin()), end.max(level.end()))
+                    });
+
+                for (level_index, level) in lod_group.levels.iter().enumerate() {
+                    for &object in level.objects.iter() {
+                        if let Some(object_ref) = graph.try_get(object) {
+                            let mut normalized_distance =
+                                normalized_distance(&object_ref.global_position());
+                            if options.lod_bias != 0.0
+                                && normalized_distance >= range_begin - lod_padding
+                                && normalized_distance <= range_end + lod_padding
+                            {
+                                normalized_distance = (normalized_distance + options.lod_bias)
+                                    .clamp(range_begin, range_end);
+                            }
+                            let visible = (impostor.is_none()
+                                && normalized_distance >= level.begin() - lod_padding
+                                && normalized_distance <= level.end() + lod_padding)
+                                || object_ref.always_render();
+                            lod_filter[object.index() as usize] = visible;
+                            lod_levels[object.index() as usize] =
+                                visible.then_some(level_index as u8);
+                        }
+                    }
+                }
+            }
+
+            if let Some(reflection_probe) = node.component_ref::<ReflectionProbe>() {
+                if (reflection_probe as &dyn NodeTrait)
+                    .world_bounding_box()
+                    .is_contains_point(observer_position.translation)
+                {
+                    storage.environment_map = Some(reflection_probe.render_target().clone());
+                }
+            }
+
+            if options.collect_lights {
+                if let Some(base_light) = node.component_ref::<BaseLight>() {
+                    if frustum.is_intersects_aabb(&node.world_bounding_box())
+                        && base_light.global_visibility()
+                        && base_light.is_globally_enabled()
+                    {
+                        let kind = if let Some(spot_light) = node.cast::<SpotLight>() {
+                            LightSourceKind::Spot {
+                                full_cone_angle: spot_light.full_cone_angle(),
+                                hotspot_cone_angle: spot_light.hotspot_cone_angle(),
+                                distance: spot_light.distance(),
+                                shadow_bias: spot_light.shadow_bias(),
+                                cookie_texture: spot_light.cookie_texture(),
+                            }
+                        } else if let Some(point_light) = node.cast::<PointLight>() {
+                            LightSourceKind::Point {
+                                radius: point_light.radius(),
+                                shadow_bias: point_light.shadow_bias(),
+                            }
+                        } else if let Some(directional_light) = node.cast::<DirectionalLight>() {
+                            LightSourceKind::Directional {
+                                csm_options: (*directional_light.csm_options).clone(),
+                            }
+                        } else {
+                            LightSourceKind::Unknown
+                        };
+
+                        let source = LightSource {
+                            handle: node_handle,
+                            global_transform: base_light.global_transform(),
+                            kind,
+                            position: base_light.global_position(),
+                            up_vector: base_light.up_vector(),
+                            side_vector: base_light.side_vector(),
+                            look_vector: base_light.look_vector(),
+                            cast_shadows: base_light.cast_shadows(),
+                            local_scale: **base_light.local_transform().scale(),
+                            color: base_light.color(),
+                            intensity: base_light.intensity(),
+                            scatter_enabled: base_light.is_scatter_enabled(),
+                            scatter: base_light.scatter(),
+                        };
+
+                        storage.light_sources.push(source);
+                    }
+                }
+            }
+        }
+
+        let mut split_storage;
+        let ctx_storage: &mut dyn RenderDataBundleStorageTrait = match transparent.as_deref_mut() {
+            Some(transparent) => {
+                split_storage = SplitStorage {
+                    opaque: &mut storage,
+                    transparent,
+                };
+                &mut split_storage
+            }
+            None => &mut storage,
+        };
+
+        let mut ctx = RenderContext {
+            render_mask,
+            elapsed_time,
+            observer_position,
+            frustum: Some(&frustum),
+            storage: ctx_storage,
+            graph,
+            render_pass_name: &render_pass_name,
+            dynamic_surface_cache,
+            instance_filter: options.instance_filter.as_deref(),
+        };
+
+        if !impostors.is_empty() {
+            // Impostors are camera-facing, so they use the basis of the observer. The quad faces -Z,
+            // so its Z axis must look in the same direction as the observer.
+            let view = &observer_position.view_matrix;
+            let side = Vector3::new(view.m11, view.m12, view.m13);
+            let up = Vector3::new(view.m21, view.m22, view.m23);
+            let look = Vector3::new(view.m31, view.m32, view.m33);
+            for (node_handle, position, impostor) in impostors {
+                let world_transform = Matrix4::from_columns(&[
+                    side.scale(impostor.size.x).push(0.0),
+                    up.scale(impostor.size.y).push(0.0),
+                    look.push(0.0),
+                    position.push(1.0),
+                ]);
+                let instance_data = SurfaceInstanceData {
+                    world_transform,
+                    node_handle,
+                    animation_phase: SurfaceInstanceData::animation_phase_from_position(&position),
+                    ..Default::default()
+                };
+                if ctx
+                    .instance_filter
+                    .as_ref()
+                    .is_none_or(|filter| filter(&instance_data, node_handle))
+                {
+                    ctx.storage.push(
+                        &surface::QUAD.resource,
+                        &impostor.material,
+                        impostor.render_path,
+                        ctx.calculate_sorting_index(position).into(),
+                        instance_data,
+                    );
+                }
+            }
+        }
+
+        #[inline(always)]
+        fn iterate_recursive(
+            node_handle: Handle<Node>,
+            graph: &Graph,
+            lod_filter: &[bool],
+            lod_levels: &mut [Option<u8>],
+            parent_lod_level: Option<u8>,
+            ctx: &mut RenderContext,
+        ) {
+            let index = node_handle.index() as usize;
+            if lod_filter[index] {
+                // Descendants of a LOD object inherit its level.
+                let lod_level = lod_levels[index].or(parent_lod_level);
+                lod_levels[index] = lod_level;
+
+                let node = graph.node(node_handle);
+                let control_flow = match ctx.instance_filter {
+                    Some(filter) => {
+                        let mut storage = FilteredStorage {
+                            storage: &mut *ctx.storage,
+                            filter,
+                        };
+                        node.collect_render_data(&mut RenderContext {
+                            render_mask: ctx.render_mask,
+                            elapsed_time: ctx.elapsed_time,
+                            observer_position: ctx.observer_position,
+                            frustum: ctx.frustum,
+                            storage: &mut storage,
+                            graph: ctx.graph,
+                            render_pass_name: ctx.render_pass_name,
+                            dynamic_surface_cache: &mut *ctx.dynamic_surface_cache,
+                            instance_filter: None,
+                        })
+                    }
+                    None => node.collect_render_data(ctx),
+                };
+                if let RdcControlFlow::Continue = control_flow {
+                    for child in node.children() {
+                        iterate_recursive(*child, graph, lod_filter, lod_levels, lod_level, ctx);
+                    }
+                }
+            }
+        }
+
+        iterate_recursive(
+            graph.root(),
+            graph,
+            &lod_filter,
+            &mut lod_levels,
+            None,
+            &mut ctx,
+        );
+
+        storage.assign_lod_levels(&lod_levels);
+
+        if let Some(mut cache) = instance_count_cache {
+            // Reuse the memory of the hints.
+            cache.counts = std::mem::take(&mut storage.instance_capacity_hints);
+            cache.counts.clear();
+            cache.record(&storage);
+            if let Some(transparent) = transparent.as_deref() {
+                cache.record(transparent);
+            }
+            storage.instance_count_cache = Some(cache);
+        }
+
+        if let Some(transparent) = transparent {
+            transparent.assign_lod_levels(&lod_levels);
+            transparent.light_sources = storage.light_sources.clone();
+            transparent.environment_map = storage.environment_map.clone();
+            transparent.sort();
+            storage.sort_front_to_back();
+        } else {
+            storage.sort();
+        }
+
+        storage
+    }
+
+    /// Returns world-space bounds of everything that was collected in the storage. Bundles with
+    /// [`RenderPath::EditorOverlay`] render path are not included. [`None`] is returned if the
+    /// storage has no such instances.
+    pub fn scene_bounds(&self) -> Option<AxisAlignedBoundingBox> {
+        self.scene_bounds
+    }
+
+    /// Returns a set of handles of the nodes that emitted at least one surface instance into the
+    /// storage, which means that the nodes are rendered. It could be used by asset streaming to
+    /// prioritize the resources of visible nodes. Instances without a node handle are ignored. The
+    /// set is built on demand from [`SurfaceInstanceData::node_handle`], so the collection of the
+    /// storage itself does not do any extra work.
+    pub fn visible_node_set(&self) -> FxHashSet<Handle<Node>> {
+        self.bundles
+            .iter()
+            .flat_map(|bundle| bundle.instances.iter())
+            .map(|instance| instance.node_handle)
+            .filter(|node_handle| node_handle.is_some())
+            .collect()
+    }
+
+    /// Checks whether every bundle of the storage could be rendered correctly and collects all the
+    /// problems found (see [`BundleValidationError`]), instead of stopping at the first one. The
+    /// check is relatively expensive (it visits every instance), so it is meant to be used by tools
+    /// (for example, to show content errors before a preview) and in debug builds.
+    pub fn validate(&self) -> Result<(), Vec<BundleValidationError>> {
+        let mut errors = Vec::new();
+
+        for bundle in self.bundles.iter() {
+            let bundle_key = bundle.key();
+            let Some(first) = bundle.instances.first() else {
+                errors.push(BundleValidationError::NoInstances { bundle_key });
+                continue;
+            };
+            let skinned = !first.bone_matrices.is_empty();
+
+            let data = bundle.data.data_ref();
+            let triangle_count = data.geometry_buffer.len();
+            let blend_shape_count = data
+                .blend_shapes_container
+                .as_ref()
+                .map_or(0, |container| container.blend_shapes.len());
+
+            for (instance_index, instance) in bundle.instances.iter().enumerate() {
+                if instance.blend_shapes_weights.len() != blend_shape_count {
+                    errors.push(BundleValidationError::BlendShapeWeightsMismatch {
+                        bundle_key,
+                        instance: instance_index,
+                        expected: blend_shape_count,
+                        actual: instance.blend_shapes_weights.len(),
+                    });
+                }
+
+                if let ElementRange::Specific { offset, count } = instance.element_range {
+                    if offset
+                        .checked_add(count)
+                        .is_none_or(|end| end > triangle_count)
+                    {
+                        errors.push(BundleValidationError::InvalidElementRange {
+                            bundle_key,
+                            instance: instance_index,
+                            triangle_count,
+                        });
+                    }
+                }
+
+                if instance.world_transform.iter().any(|v| !v.is_finite()) {
+                    errors.push(BundleValidationError::NonFiniteTransform {
+                        bundle_key,
+                        instance: instance_index,
+                    });
+                }
+
+                if instance.bone_matrices.is_empty() == skinned {
+                    errors.push(BundleValidationError::SkinningMismatch {
+                        bundle_key,
+                        instance: instance_index,
+                        skinned,
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn add_scene_bounds(&mut self, bounds: AxisAlignedBoundingBox) {
+        if !bounds.is_valid() {
+            return;
+        }
+        match self.scene_bounds.as_mut() {
+            Some(scene_bounds) => scene_bounds.add_box(bounds),
+            None => self.scene_bounds = Some(bounds),
+        }
+    }
+
+    fn add_node_triangles(&mut self, node_handle: Handle<Node>, count: u64) {
+        if let Some(node_triangle_counts) = self.node_triangle_counts.as_mut() {
+            *node_triangle_counts.entry(node_handle).or_default() += count;
+        }
+    }
+
+    /// Assigns LOD levels to every surface instance using the handle of the node that emitted the
+    /// instance. `lod_levels` must be indexed by node handle index.
+    fn assign_lod_levels(&mut self, lod_levels: &[Option<u8>]) {
+        for bundle in self.bundles.iter_mut() {
+            for instance in bundle.instances.iter_mut() {
+                instance.lod_level = lod_levels
+                    .get(instance.node_handle.index() as usize)
+                    .copied()
+                    .flatten();
+            }
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more instances in the bundle with the given key
+    /// (see [`RenderDataBundle::key`]). If there's no such bundle yet, the capacity will be reserved
+    /// when the bundle is created by [`RenderDataBundleStorageTrait::push`]. Use this method before
+    /// pushing the instances, when the amount of instances is known in advance, see also
+    /// [`InstanceCountCache`].
+    pub fn reserve_instances(&mut self, bundle_key: u64, additional: usize) {
+        // The map is not updated on sorting, so the index must be checked.
+        if let Some(bundle) = self
+            .bundle_map
+            .get(&bundle_key)
+            .and_then(|index| self.bundles.get_mut(*index))
+            .filter(|bundle| bundle.key() == bundle_key)
+        {
+            bundle.instances.reserve(additional);
+        } else {
+            *self.instance_capacity_hints.entry(bundle_key).or_default() += additional;
+        }
+    }
+
+    /// Takes the instance count cache, that was passed to [`Self::from_graph`] and updated with the
+    /// instance counts of this storage. Pass it to the next frame to pre-size instance lists, see
+    /// [`InstanceCountCache`] for more info.
+    pub fn take_instance_count_cache(&mut self) -> Option<InstanceCountCache> {
+        self.instance_count_cache.take()
+    }
+
+    /// Returns identifiers (see [`PersistentIdentifier`]) of the instances of the bundles, that were
+    /// not refreshed during the given frame generation (see [`Self::frame_generation`]). It is
+    /// meant to be used when the storage is reused across frames: the renderer could evict the
+    /// GPU resources associated with such instances.
+    ///
+    /// Staleness is tracked per bundle, not per instance. The storage never removes instances on
+    /// its own, so a bundle that received at least one instance during the frame keeps (and draws)
+    /// the instances of the previous frames as well, and none of them are reported, even if their
+    /// nodes are gone. This means that the method detects only the objects that do not share a
+    /// bundle (material, surface data and render parameters) with any refreshed object; a storage
+    /// should be rebuilt instead of being reused, if exact per-instance tracking is needed.
+    pub fn stale_identifiers(&self, current_gen: u64) -> Vec<PersistentIdentifier> {
+        let mut identifiers = Vec::new();
+        self.for_each_persistent_instance(|id, bundle, _| {
+            if bundle.frame_generation < current_gen {
+                identifiers.push(id);
+            }
+        });
+        identifiers
+    }
+
+    /// Calls the given closure for every instance of every bundle along with its persistent
+    /// identifier, see [`PersistentIdentifier`].
+    fn for_each_persistent_instance<F>(&self, mut func: F)
+    where
+        F: FnMut(PersistentIdentifier, &RenderDataBundle, &SurfaceInstanceData),
+    {
+        let mut counters = FxHashMap::<(Handle<Node>, u64), usize>::default();
+        for bundle in self.bundles.iter() {
+            let surface_key = bundle.data.key();
+            for instance in bundle.instances.iter() {
+                let counter = counters
+                    .entry((instance.node_handle, surface_key))
+                    .or_default();
+                let id = PersistentIdentifier {
+                    node_handle: instance.node_handle,
+                    surface_key,
+                    index: *counter,
+                };
+                *counter += 1;
+
+                func(id, bundle, instance);
+            }
+        }
+    }
+
+    /// Adds the given bundle to the storage as is, bypassing hashing and merging that is done by
+    /// [`RenderDataBundleStorageTrait::push`] and [`RenderDataBundleStorageTrait::push_triangles`].
+    /// This is useful for plugins that do their own batching. The bundle is not registered in
+    /// the internal bundle map, which means that it won't receive any instances or triangles from
+    /// subsequent `push`/`push_triangles` calls, even if they use the same material and surface.
+    pub fn push_bundle(&mut self, mut bundle: RenderDataBundle) {
+        bundle.frame_generation = self.frame_generation;
+        self.bundles.push(bundle);
+    }
+
+    /// Calculates a hash of the content of the storage. It could be used to check whether the
+    /// render data has changed since the last frame or not. The hash includes material, surface
+    /// data and render parameters of each bundle, as well as transforms and element ranges of its
+    /// instances. Surface data is taken into account using modification counters of its buffers,
+    /// so the bundles created by [`RenderDataBundleStorageTrait::push_triangles`] (which are
+    /// refilled every frame) will produce a different hash every frame.
+    ///
+    /// The hash is order-independent (per-bundle hashes are combined using wrapping addition), so
+    /// it does not matter whether the storage is sorted or not.
+    pub fn content_hash(&self) -> u64 {
+        let mut content_hash = 0u64;
+
+        for bundle in self.bundles.iter() {
+            let mut hasher = FxHasher::default();
+            hasher.write_u64(bundle.material.key());
+            hasher.write_u64(bundle.data.key());
+            hasher.write_u32(bundle.render_path as u32);
+            hasher.write_u8(bundle.premultiplied_alpha as u8);
+            bundle.coverage_mode.hash(&mut hasher);
+            hasher.write_u8(bundle.flip_winding as u8);
+            bundle.stencil.hash(&mut hasher);
+            bundle.polygon_offset.hash(&mut hasher);
+            bundle.cull_override.hash(&mut hasher);
+            bundle.depth_state.hash(&mut hasher);
+            bundle.instance_layout.hash(&mut hasher);
+            hasher.write_u64(bundle.sort_index);
+
+            {
+                let data = bundle.data.data_ref();
+                hasher.write_u64(data.vertex_buffer.modifications_count());
+                hasher.write_u64(data.geometry_buffer.modifications_count());
+            }
+
+            hasher.write_usize(bundle.instances.len());
+            for instance in bundle.instances.iter() {
+                for value in instance
+                    .world_transform
+                    .iter()
+                    .chain(instance.bone_matrices.iter().flat_map(|m| m.iter()))
+                    .chain(instance.blend_shapes_weights.iter())
+                {
+                    hasher.write_u32(value.to_bits());
+                }
+                match instance.element_range {
+                    ElementRange::Full => hasher.write_u8(0),
+                    ElementRange::Specific { offset, count } => {
+                        hasher.write_u8(1);
+                        hasher.write_usize(offset);
+                        hasher.write_usize(count);
+                    }
+                }
+            }
+
+            content_hash = content_hash.wrapping_add(hasher.finish());
+        }
+
+        content_hash
+    }
+
+    /// Calculates total amount of triangles that will be rendered for the scene. Bundles with
+    /// [`RenderPath::EditorOverlay`] render path are excluded, because they're not part of the
+    /// scene.
+    pub fn total_triangles(&self) -> usize {
+        self.bundles
+            .iter()
+            .filter(|bundle| bundle.render_path != RenderPath::EditorOverlay)
+            .map(|bundle| {
+                let triangle_count = bundle.data.data_ref().geometry_buffer.len();
+                bundle
+                    .instances
+                    .iter()
+                    .map(|instance| match instance.element_range {
+                        ElementRange::Full => triangle_count,
+                        ElementRange::Specific { count, .. } => count,
+                    })
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Calculates skinning statistics of the storage. It could be used to check whether the amount
+    /// of skinned instances fits in a budget, before the storage is rendered.
+    pub fn skinning_stats(&self) -> SkinningStats {
+        let mut stats = SkinningStats::default();
+        for bundle in self.bundles.iter().filter(|bundle| bundle.is_skinned()) {
+            stats.skinned_bundles += 1;
+            for instance in bundle.instances.iter() {
+                if !instance.bone_matrices.is_empty() {
+                    stats.skinned_instances += 1;
+                    stats.bone_matrices += instance.bone_matrices.len();
+                }
+            }
+        }
+        stats
+    }
+
+    /// Collects statistics of the storage in a single structure, see [`OverlayStats`].
+    pub fn overlay_stats(&self) -> OverlayStats {
+        let instances = self
+            .bundles
+            .iter()
+            .map(|bundle| bundle.instances.len())
+            .sum();
+        OverlayStats {
+            bundles: self.bundles.len(),
+            instances,
+            triangles: self.total_triangles(),
+            draw_calls: instances,
+            skinning: self.skinning_stats(),
+        }
+    }
+
+    /// Creates a multi-line summary of statistics of the storage (see [`OverlayStats`]), that is
+    /// ready to be shown in an overlay. Use [`Self::write_overlay_text`] to reuse the same string
+    /// every frame.
+    pub fn overlay_text(&self) -> String {
+        let mut text = String::new();
+        self.write_overlay_text(&mut text);
+        text
+    }
+
+    /// Writes a multi-line summary of statistics of the storage (see [`OverlayStats`]) to the
+    /// given string. The previous content of the string is cleared, but its memory is reused, so
+    /// there are no allocations after the string is grown to the size of the summary.
+    pub fn write_overlay_text(&self, text: &mut String) {
+        use std::fmt::Write;
+
+        text.clear();
+        // Writing to a string never fails.
+        let _ = write!(text, "{}", self.overlay_stats());
+    }
+
+    /// Compares the bundles of this storage (before) with the bundles of the other storage (after)
+    /// and returns the difference, see [`StorageDiff`]. Bundles are matched by [`BundleKey`], the
+    /// order of the bundles in the storages does not matter. It is useful for regression tests
+    /// that check whether batching of a scene was changed unintentionally.
+    pub fn diff(&self, other: &RenderDataBundleStorage) -> StorageDiff {
+        let before = self.keyed_bundles();
+        let mut after = other.keyed_bundles();
+
+        let mut diff = StorageDiff::default();
+        for (key, bundle) in before {
+            if let Some(other_bundle) = after.remove(&key) {
+                if bundle.instances.len() != other_bundle.instances.len() {
+                    diff.instance_count_changes.push(InstanceCountChange {
+                        key,
+                        before: bundle.instances.len(),
+                        after: other_bundle.instances.len(),
+                    });
+                }
+                if bundle.material.key() != other_bundle.material.key() {
+                    diff.material_changes.push(MaterialChange {
+                        key,
+                        before: bundle.material.key(),
+                        after: other_bundle.material.key(),
+                    });
+                }
+            } else {
+                diff.removed.push((key, bundle.instances.len()));
+            }
+        }
+        diff.added.extend(
+            after
+                .into_iter()
+                .map(|(key, bundle)| (key, bundle.instances.len())),
+        );
+
+        diff.added.sort_unstable_by_key(|(key, _)| *key);
+        diff.removed.sort_unstable_by_key(|(key, _)| *key);
+        diff.instance_count_changes
+            .sort_unstable_by_key(|change| change.key);
+        diff.material_changes
+            .sort_unstable_by_key(|change| change.key);
+
+        diff
+    }
+
+    fn keyed_bundles(&self) -> FxHashMap<BundleKey, &RenderDataBundle> {
+        let mut groups = FxHashMap::<u64, Vec<&RenderDataBundle>>::default();
+        for bundle in self.bundles.iter() {
+            let mut hasher = FxHasher::default();
+            hasher.write_u64(bundle.data.key());
+            hasher.write_u32(bundle.render_path as u32);
+            hasher.write_u8(bundle.flip_winding as u8);
+            bundle.stencil.hash(&mut hasher);
+            bundle.polygon_offset.hash(&mut hasher);
+            bundle.cull_override.hash(&mut hasher);
+            bundle.depth_state.hash(&mut hasher);
+            groups.entry(hasher.finish()).or_default().push(bundle);
+        }
+
+        let mut keyed_bundles = FxHashMap::default();
+        for (hash, mut bundles) in groups {
+            bundles.sort_by_key(|bundle| (bundle.material.key(), bundle.instances.len()));
+            for (index, bundle) in bundles.into_iter().enumerate() {
+                keyed_bundles.insert(BundleKey { hash, index }, bundle);
+            }
+        }
+        keyed_bundles
+    }
+
+    /// Calculates world-space bounds of every instance of the given bundle. Skinned instances are
+    /// bounded by the surface bounds transformed by every bone matrix. Keep in mind, that instances
+    /// are rebased relative to the observer if origin rebasing is on.
+    fn bundle_bounds(&self, bundle: &RenderDataBundle) -> AxisAlignedBoundingBox {
+        let mut bounds = AxisAlignedBoundingBox::default();
+        let local_bounds = self.bundle_local_bounds(bundle);
+        if !local_bounds.is_valid() {
+            return bounds;
+        }
+        for instance in bundle.instances.iter() {
+            bounds.add_box(instance_bounds(&local_bounds, instance));
+        }
+        bounds
+    }
+
+    fn bundle_local_bounds(&self, bundle: &RenderDataBundle) -> AxisAlignedBoundingBox {
+        self.local_bounds_cache
+            .get(&bundle.data.key())
+            .cloned()
+            .unwrap_or_else(|| surface_local_bounds(&bundle.data))
+    }
+
+    /// Estimates the overdraw produced by transparent bundles (bundles with a material that has a
+    /// transparent pass hint). Returns an average amount of transparent layers per pixel, among
+    /// pixels covered by at least one layer: 1.0 means that there is no overdraw at all and 0.0
+    /// means that there is nothing transparent on the screen. `view_projection` must match world
+    /// transforms of the instances, use [`ObserverPosition::instance_view_projection_matrix`] for
+    /// that.
+    ///
+    /// It is a rough approximation: every instance covers the screen-space rectangle of its
+    /// projected bounds (not its actual geometry), and the coverage is accumulated in cells of
+    /// [`Self::OVERDRAW_CELL_SIZE`] pixels, partially covered cells are counted as fully covered.
+    /// Still, it is good enough to spot pathological stacks of transparent objects.
+    pub fn estimate_overdraw(
+        &self,
+        view_projection: Matrix4<f32>,
+        screen_size: Vector2<u32>,
+    ) -> f32 {
+        let cell_size = Self::OVERDRAW_CELL_SIZE;
+        let columns = screen_size.x.div_ceil(cell_size) as usize;
+        let rows = screen_size.y.div_ceil(cell_size) as usize;
+        if columns == 0 || rows == 0 {
+            return 0.0;
+        }
+
+        let mut layers = vec![0u32; columns * rows];
+        let to_cell = |normalized: f32, size: u32, count: usize, round_up: bool| {
+            let cell = normalized * size as f32 / cell_size as f32;
+            let cell = if round_up { cell.ceil() } else { cell.floor() };
+            (cell.max(0.0) as usize).min(count)
+        };
+
+        for bundle in self
+            .bundles
+            .iter()
+            .filter(|bundle| bundle.material.pass_hint().is_transparent())
+        {
+            let local_bounds = self.bundle_local_bounds(bundle);
+            if !local_bounds.is_valid() {
+                continue;
+            }
+
+            for instance in bundle.instances.iter() {
+                let Some((min, max)) = project_bounds_to_ndc(
+                    &instance_bounds(&local_bounds, instance),
+                    &view_projection,
+                ) else {
+                    continue;
+                };
+
+                // Y axis of the screen goes down, unlike Y axis of normalized device coordinates.
+                let first_column = to_cell((min.x + 1.0) * 0.5, screen_size.x, columns, false);
+                let last_column = to_cell((max.x + 1.0) * 0.5, screen_size.x, columns, true);
+                let first_row = to_cell((1.0 - max.y) * 0.5, screen_size.y, rows, false);
+                let last_row = to_cell((1.0 - min.y) * 0.5, screen_size.y, rows, true);
+
+                for row in first_row..last_row {
+                    for cell in
+                        &mut layers[row * columns + first_column..row * columns + last_column]
+                    {
+                        *cell += 1;
+                    }
+                }
+            }
+        }
+
+        let (total, covered) = layers
+            .iter()
+            .filter(|&&count| count > 0)
+            .fold((0u64, 0u64), |(total, covered), &count| {
+                (total + count as u64, covered + 1)
+            });
+
+        if covered == 0 {
+            0.0
+        } else {
+            total as f32 / covered as f32
+        }
+    }
+
+    /// Makes a snapshot of the render data of the storage, that could be sent to a render thread
+    /// and rendered there without borrowing the scene graph, see [`RenderSnapshot`].
+    ///
+    /// Surface data, materials and textures are shared resources, so only their handles are
+    /// cloned (which is just a reference counter increment). Everything else is copied: the
+    /// instance data of every bundle (including bone matrices and blend shape weights), the light
+    /// sources and the decals. So the cost of a snapshot is proportional to the amount of
+    /// instances and bones. Keep in mind, that the shared resources are still locked when they're
+    /// accessed, and modifications of their contents (for example, by a dynamic surface) are
+    /// visible through the snapshot.
+    pub fn snapshot(&self) -> RenderSnapshot {
+        RenderSnapshot {
+            observer_position: self.observer_position.clone(),
+            bundles: self.bundles.clone(),
+            light_sources: self.light_sources.clone(),
+            environment_map: self.environment_map.clone(),
+            decal_batches: self.decal_batches.clone(),
+        }
+    }
+
+    /// Exports world-space geometry, that the storage would draw, to the given format. Every
+    /// instance is transformed by its world transform (the origin rebase is undone), only the
+    /// triangles in the element range of the instance are written. The geometry is grouped by
+    /// bundles, each group references the material of its bundle. Bundles of the editor overlay
+    /// are not exported.
+    ///
+    /// Only positions are exported. Skinned instances are exported in bind pose and blend shapes
+    /// are not applied, because skinning is done on GPU.
+    pub fn export_geometry(&self, format: ExportFormat) -> Result<Vec<u8>, ExportError> {
+        let mut groups = Vec::new();
+        for (bundle_index, bundle) in self.bundles.iter().enumerate() {
+            if bundle.render_path == RenderPath::EditorOverlay {
+                continue;
+            }
+
+            let data = bundle.data.data_ref();
+            let Some(positions) = data
+                .vertex_buffer
+                .attribute_view::<Vector3<f32>>(VertexAttributeUsage::Position)
+            else {
+                return Err(ExportError::NoPositions {
+                    bundle: bundle_index,
+                });
+            };
+            let vertex_count = data.vertex_buffer.vertex_count();
+            let triangles = data.geometry_buffer.triangles_ref();
+
+            let mut group = ExportGroup {
+                name: format!("bundle_{bundle_index}"),
+                material: format!("material_{}", bundle.material.key()),
+                positions: Default::default(),
+                indices: Default::default(),
+            };
+            for (instance_index, instance) in bundle.instances.iter().enumerate() {
+                let triangles = match instance.element_range {
+                    ElementRange::Full => Some(triangles),
+                    ElementRange::Specific { offset, count } => offset
+                        .checked_add(count)
+                        .and_then(|end| triangles.get(offset..end)),
+                }
+                .ok_or(ExportError::InvalidElementRange {
+                    bundle: bundle_index,
+                    instance: instance_index,
+                })?;
+
+                let mut world_transform = instance.world_transform;
+                if self.observer_position.origin_rebase {
+                    world_transform.append_translation_mut(&self.observer_position.translation);
+                }
+
+                // Only the vertices, that are referenced by the triangles, are exported.
+                let mut remap = FxHashMap::default();
+                for &index in triangles.iter().flat_map(|triangle| triangle.0.iter()) {
+                    if index >= vertex_count {
+                        return Err(ExportError::InvalidIndex {
+                            bundle: bundle_index,
+                            index,
+                        });
+                    }
+                    let new_index = *remap.entry(index).or_insert_with(|| {
+                        let position = *positions.get(index as usize).unwrap();
+                        group
+                            .positions
+                            .push(world_transform.transform_point(&position.into()).coords);
+                        group.positions.len() as u32 - 1
+                    });
+                    group.indices.push(new_index);
+                }
+            }
+            groups.push(group);
+        }
+
+        Ok(match format {
+            ExportFormat::Obj => write_obj(&groups),
+            ExportFormat::Gltf => write_gltf(&groups),
+        })
+    }
+
+    /// Builds a bounding volume hierarchy over world-space bounds of every instance in the storage,
+    /// that could be used to find an instance under the cursor quickly, see [`PickBvh`] for more
+    /// info. The hierarchy is built in world space, even if origin rebasing is on. Instances of
+    /// the editor overlay and instances without a node are not included.
+    ///
+    /// Bundles made by [`RenderDataBundleStorageTrait::push_triangles`] merge geometry of multiple
+    /// nodes into a single instance, such instance is represented by the node that produced the
+    /// bundle and it is bounded by the whole merged geometry.
+    pub fn build_pick_bvh(&self) -> PickBvh {
+        let mut entries = Vec::new();
+        for bundle in self.bundles.iter() {
+            if bundle.render_path == RenderPath::EditorOverlay {
+                continue;
+            }
+
+            let local_bounds = self.bundle_local_bounds(bundle);
+            if !local_bounds.is_valid() {
+                continue;
+            }
+
+            for instance in bundle.instances.iter() {
+                if instance.node_handle.is_none() {
+                    continue;
+                }
+
+                let mut bounds = instance_bounds(&local_bounds, instance);
+                if self.observer_position.origin_rebase {
+                    bounds.offset(self.observer_position.translation);
+                }
+                entries.push((bounds, instance.node_handle));
+            }
+        }
+        PickBvh::build(entries)
+    }
+
+    /// Partitions the bundles by screen tiles. Returns a list of bundle indices for every tile of
+    /// the grid (see [`TileGrid`] for tile numbering), that contains the indices of the bundles
+    /// whose projected bounds overlap the tile. A bundle that spans multiple tiles is put in each
+    /// of them. `view_projection` must match world transforms of the instances, use
+    /// [`ObserverPosition::instance_view_projection_matrix`] for that.
+    ///
+    /// The partitioning is conservative: screen-space rectangle of projected world-space bounds of
+    /// a bundle (which itself bounds all the instances of the bundle) is used, so a bundle may be
+    /// put in a tile that it does not actually touch. Bundles that cross the plane of the observer
+    /// are put in every tile.
+    pub fn partition_by_tiles(
+        &self,
+        tile_grid: TileGrid,
+        view_projection: Matrix4<f32>,
+    ) -> Vec<Vec<usize>> {
+        let mut tiles = vec![Vec::new(); tile_grid.tile_count()];
+        if tiles.is_empty() {
+            return tiles;
+        }
+
+        let to_tile = |normalized: f32, count: u32| {
+            ((normalized * count as f32).floor() as i64).clamp(0, count as i64 - 1) as u32
+        };
+
+        for (bundle_index, bundle) in self.bundles.iter().enumerate() {
+            let bounds = self.bundle_bounds(bundle);
+            if !bounds.is_valid() {
+                continue;
+            }
+
+            let Some((min, max)) = project_bounds_to_ndc(&bounds, &view_projection) else {
+                continue;
+            };
+
+            // Y axis of the screen goes down, unlike Y axis of normalized device coordinates.
+            let first_column = to_tile((min.x + 1.0) * 0.5, tile_grid.columns);
+            let last_column = to_tile((max.x + 1.0) * 0.5, tile_grid.columns);
+            let first_row = to_tile((1.0 - max.y) * 0.5, tile_grid.rows);
+            let last_row = to_tile((1.0 - min.y) * 0.5, tile_grid.rows);
+
+            for row in first_row..=last_row {
+                for column in first_column..=last_column {
+                    tiles[(row * tile_grid.columns + column) as usize].push(bundle_index);
+                }
+            }
+        }
+
+        tiles
+    }
+
+    /// Creates a plan of geometry uploads for the bundles, whose geometry buffers are not
+    /// up-to-date in the given cache (new or modified surface data). The priority of a bundle is
+    /// its on-screen coverage, calculated using projected bounds of the bundle, which is a
+    /// conservative estimate. Bundles sharing the same surface data are uploaded once, using the
+    /// highest priority among them. See [`UploadPlan`] docs for more info.
+    pub fn upload_plan(&self, geometry_cache: &GeometryCache) -> UploadPlan {
+        let view_projection = self.observer_position.instance_view_projection_matrix();
+        let mut uploads = Vec::<PendingUpload>::new();
+        let mut data_uploads = FxHashMap::<u64, usize>::default();
+
+        for (bundle_index, bundle) in self.bundles.iter().enumerate() {
+            let size = geometry_cache.pending_upload_size(&bundle.data.data_ref());
+            if size == 0 {
+                continue;
+            }
+
+            let bounds = self.bundle_bounds(bundle);
+            let priority = if bounds.is_valid() {
+                project_bounds_to_ndc(&bounds, &view_projection)
+                    .map(|(min, max)| {
+                        let min = min.map(|v| v.clamp(-1.0, 1.0));
+                        let max = max.map(|v| v.clamp(-1.0, 1.0));
+                        // Area of the whole screen in normalized device coordinates is 4.
+                        (max.x - min.x) * (max.y - min.y) * 0.25
+                    })
+                    .unwrap_or_default()
+            } else {
+                0.0
+            };
+
+            match data_uploads.entry(bundle.data.key()) {
+                Entry::Occupied(entry) => {
+                    let upload = &mut uploads[*entry.get()];
+                    upload.priority = upload.priority.max(priority);
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(uploads.len());
+                    uploads.push(PendingUpload {
+                        bundle_index,
+                        size,
+                        priority,
+                    });
+                }
+            }
+        }
+
+        uploads.sort_by(|a, b| b.priority.total_cmp(&a.priority));
+
+        UploadPlan { uploads }
+    }
+
+    /// Builds a list of indirect draw commands for the bundles, which allows a renderer to draw the
+    /// whole storage with a few multi-draw-indirect calls. The commands are built under the
+    /// following assumptions about the layout of shared GPU buffers:
+    ///
+    /// - Vertices and triangles of every unique surface data are put in shared vertex and index
+    /// buffers in the order of the first use of the data by the bundles. Bundles sharing the same
+    /// surface data share the same region of the buffers.
+    /// - Instances of every bundle are put in a shared per-instance buffer in the order of the
+    /// bundles, without gaps. `base_instance` of a command is the index of its first instance in
+    /// this buffer, which means that the per-instance data of the instance `i` of the command is
+    /// located at `(base_instance + i) * instance_size` bytes, where `instance_size` is the size of
+    /// the per-instance data (see [`InstanceLayout`]).
+    ///
+    /// Element range of an instance (see [`SurfaceInstanceData::element_range`]) is respected, a
+    /// command is emitted for every run of consecutive instances of a bundle with the same range.
+    /// Ranges are clamped to the amount of triangles of the surface data.
+    pub fn build_indirect_commands(&self) -> Vec<DrawElementsIndirectCommand> {
+        let mut commands = Vec::new();
+        let mut data_offsets = FxHashMap::<u64, (u32, i32)>::default();
+        let mut next_first_index = 0u32;
+        let mut next_base_vertex = 0i32;
+        let mut base_instance = 0u32;
+
+        for bundle in self.bundles.iter() {
+            let data = bundle.data.data_ref();
+            let triangle_count = data.geometry_buffer.len();
+            let (first_index, base_vertex) =
+                *data_offsets.entry(bundle.data.key()).or_insert_with(|| {
+                    let offsets = (next_first_index, next_base_vertex);
+                    next_first_index += triangle_count as u32 * 3;
+                    next_base_vertex += data.vertex_buffer.vertex_count() as i32;
+                    offsets
+                });
+
+            for run in bundle
+                .instances
+                .chunk_by(|a, b| a.element_range == b.element_range)
+            {
+                let (offset, count) = match run[0].element_range {
+                    ElementRange::Full => (0, triangle_count),
+                    ElementRange::Specific { offset, count } => {
+                        let offset = offset.min(triangle_count);
+                        (offset, count.min(triangle_count - offset))
+                    }
+                };
+                commands.push(DrawElementsIndirectCommand {
+                    count: count as u32 * 3,
+                    instance_count: run.len() as u32,
+                    first_index: first_index + offset as u32 * 3,
+                    base_vertex,
+                    base_instance,
+                });
+                base_instance += run.len() as u32;
+            }
+        }
+
+        commands
+    }
+
+    /// Returns the amount of indirect draw commands, that is needed to draw the storage using
+    /// instancing. Instances of a bundle are merged in a single instanced draw, unless they use
+    /// different element ranges (see [`SurfaceInstanceData::element_range`]) - a bundle is split
+    /// into a command for every run of consecutive instances with the same range. The result always
+    /// matches the amount of commands produced by [`Self::build_indirect_commands`], but the method
+    /// does not allocate. Keep in mind, that the renderer itself does not submit indirect commands,
+    /// it draws every instance separately - see [`OverlayStats::draw_calls`] for the amount of draw
+    /// calls it actually issues.
+    pub fn indirect_draw_call_count(&self) -> usize {
+        self.bundles
+            .iter()
+            .map(|bundle| {
+                bundle
+                    .instances
+                    .chunk_by(|a, b| a.element_range == b.element_range)
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Sorts the bundles by their respective sort index.
+    pub fn sort(&mut self) {
+        self.bundles.sort_unstable_by_key(|b| b.sort_index);
+        self.sort_decals();
+    }
+
+    /// Sorts the bundles by their respective sort index, but the order of depth is reversed, so
+    /// the bundles that are closer to the observer are drawn first. Pass priority and material
+    /// group (see [`SortKey`]) keep ascending order. Front-to-back order reduces overdraw of opaque
+    /// objects, because the depth test rejects occluded fragments early.
+    pub fn sort_front_to_back(&mut self) {
+        self.bundles.sort_unstable_by_key(|b| {
+            let key = SortKey::unpack(b.sort_index);
+            (
+                key.pass_priority,
+                key.material_group,
+                Reverse((key.depth_bucket, key.tie_break)),
+            )
+        });
+        self.sort_decals();
+    }
+
+    /// Counts the state changes, that the GPU will do when the bundles are drawn in the current
+    /// order, see [`StateTransitions`].
+    pub fn state_transitions(&self) -> StateTransitions {
+        let mut transitions = StateTransitions::default();
+        for pair in self.bundles.windows(2) {
+            if pair[0].material.key() != pair[1].material.key() {
+                transitions.materials += 1;
+            }
+            if shader_key(&pair[0].material) != shader_key(&pair[1].material) {
+                transitions.shaders += 1;
+            }
+        }
+        transitions
+    }
+
+    /// Reorders the bundles to minimize the amount of shader and material switches (see
+    /// [`Self::state_transitions`]), this method must be called after the storage is sorted (see
+    /// [`Self::sort`] and [`Self::sort_front_to_back`]). The bundles are reordered only within
+    /// the runs of adjacent bundles, that could be drawn in any order:
+    ///
+    /// - Opaque bundles with the same pass priority and material group (see [`SortKey`]), their
+    ///   order affects only overdraw.
+    /// - Transparent bundles with exactly the same sorting index, so the back-to-front order of
+    ///   transparent objects is preserved.
+    ///
+    /// Within a run, the bundles are clustered by their shader and then by their material, the
+    /// cluster that matches the bundle right before the run is put first.
+    pub fn optimize_state_changes(&mut self) {
+        let run_key = |bundle: &RenderDataBundle| {
+            if bundle.material.pass_hint().is_transparent() {
+                (true, bundle.sort_index)
+            } else {
+                let key = SortKey::unpack(bundle.sort_index);
+                (
+                    false,
+                    SortKey::new(key.pass_priority, key.material_group, 0, 0).pack(),
+                )
+            }
+        };
+
+        let mut run_start = 0;
+        while run_start < self.bundles.len() {
+            let key = run_key(&self.bundles[run_start]);
+            let run_end = run_start
+                + self.bundles[run_start..]
+                    .iter()
+                    .take_while(|bundle| run_key(bundle) == key)
+                    .count();
+
+            let (prev_shader, prev_material) = run_start
+                .checked_sub(1)
+                .map(|prev| {
+                    let material = &self.bundles[prev].material;
+                    (shader_key(material), material.key())
+                })
+                .unwrap_or_default();
+            self.bundles[run_start..run_end].sort_by_cached_key(|bundle| {
+                let shader = shader_key(&bundle.material);
+                let material = bundle.material.key();
+                (
+                    shader != prev_shader,
+                    shader,
+                    material != prev_material,
+                    material,
+                )
+            });
+
+            run_start = run_end;
+        }
+    }
+
+    fn sort_decals(&mut self) {
+        // Batches are drawn one after another, so the order of the layers is respected only within
+        // a batch and between the batches, that do not share layers.
+        for batch in self.decal_batches.iter_mut() {
+            batch
+                .instances
+                .sort_by_key(|instance| (instance.layer, instance.sort_index));
+        }
+        self.decal_batches.sort_by_key(|batch| batch.sort_key());
+        self.decal_batch_map = self
+            .decal_batches
+            .iter()
+            .enumerate()
+            .map(|(index, batch)| (batch.material.key(), index))
+            .collect();
+    }
+
+    pub fn write_global_uniform_blocks(
+        &self,
+        render_context: &mut BundleRenderContext,
+    ) -> GlobalUniformData {
+        let mut light_data = LightData::<{ ShaderDefinition::MAX_LIGHTS }>::default();
+
+        for (i, light) in self
+            .light_sources
+            .iter()
+            .enumerate()
+            .take(ShaderDefinition::MAX_LIGHTS)
+        {
+            let color = light.color.as_frgb();
+
+            light_data.color_radius[i] = Vector4::new(color.x, color.y, color.z, 0.0);
+            light_data.position[i] = light.position;
+            light_data.direction[i] = light.up_vector;
+
+            match light.kind {
+                LightSourceKind::Spot {
+                    full_cone_angle,
+                    hotspot_cone_angle,
+                    distance,
+                    ..
+                } => {
+                    light_data.color_radius[i].w = distance;
+                    light_data.parameters[i].x = (hotspot_cone_angle * 0.5).cos();
+                    light_data.parameters[i].y = (full_cone_angle * 0.5).cos();
+                }
+                LightSourceKind::Point { radius, .. } => {
+                    light_data.color_radius[i].w = radius;
+                    light_data.parameters[i].x = std::f32::consts::PI.cos();
+                    light_data.parameters[i].y = std::f32::consts::PI.cos();
+                }
+                LightSourceKind::Directional { .. } => {
+                    light_data.color_radius[i].w = f32::INFINITY;
+                    light_data.parameters[i].x = std::f32::consts::PI.cos();
+                    light_data.parameters[i].y = std::f32::consts::PI.cos();
+                }
+                LightSourceKind::Unknown => {}
+            }
+
+            light_data.count += 1;
+        }
+
+        let lights_data = StaticUniformBuffer::<2048>::new()
+            .with(&(light_data.count as i32))
+            .with(&light_data.color_radius)
+            .with(&light_data.parameters)
+            .with(&light_data.position)
+            .with(&light_data.direction);
+        let lights_block = render_context
+            .uniform_memory_allocator
+            .allocate(lights_data);
+
+        // Upload camera uniforms.
+        let inv_view = self
+            .observer_position
+            .view_matrix
+            .try_inverse()
+            .unwrap_or_default();
+        let view_projection = self.observer_position.instance_view_projection_matrix();
+        let camera_up = inv_view.up();
+        let camera_side = inv_view.side();
+        let camera_uniforms = StaticUniformBuffer::<512>::new()
+            .with(&view_projection)
+            .with(&self.observer_position.translation)
+            .with(&camera_up)
+            .with(&camera_side)
+            .with(&self.observer_position.z_near)
+            .with(&self.observer_position.z_far)
+            .with(&(self.observer_position.z_far - self.observer_position.z_near));
+        let camera_block = render_context
+            .uniform_memory_allocator
+            .allocate(camera_uniforms);
+
+        let graphics_settings = StaticUniformBuffer::<256>::new().with(&render_context.use_pom);
+        let graphics_settings_block = render_context
+            .uniform_memory_allocator
+            .allocate(graphics_settings);
+
+        GlobalUniformData {
+            camera_block,
+            lights_block,
+            graphics_settings_block,
+        }
+    }
+
+    /// Draws the entire bundle set to the specified frame buffer with the specified rendering environment.
+    pub fn render_to_frame_buffer<BundleFilter, InstanceFilter>(
+        &self,
+        server: &dyn GraphicsServer,
+        geometry_cache: &mut GeometryCache,
+        shader_cache: &mut ShaderCache,
+        mut bundle_filter: BundleFilter,
+        mut instance_filter: InstanceFilter,
+        mut render_context: BundleRenderContext,
+    ) -> Result<RenderPassStatistics, FrameworkError>
+    where
+        BundleFilter: FnMut(&RenderDataBundle) -> bool,
+        InstanceFilter: FnMut(&SurfaceInstanceData) -> bool,
+    {
+        let global_uniforms = self.write_global_uniform_blocks(&mut render_context);
+
+        let view_projection = self.observer_position.instance_view_projection_matrix();
+        let mut bundle_uniform_data_set = Vec::with_capacity(self.bundles.len());
+        for bundle in self.bundles.iter() {
+            if !bundle_filter(bundle) {
+                continue;
+            }
+            bundle_uniform_data_set
+                .push(bundle.write_uniforms(&view_projection, &mut render_context));
+        }
+        render_context.uniform_memory_allocator.upload(server)?;
+
+        let mut stats = RenderPassStatistics::default();
+        for (bundle, bundle_uniform_data) in self
+            .bundles
+            .iter()
+            .filter(|bundle| bundle_filter(bundle))
+            .zip(bundle_uniform_data_set)
+        {
+            if let Some(bundle_uniform_data) = bundle_uniform_data {
+                stats += bundle.render_to_frame_buffer(
+                    server,
+                    geometry_cache,
+                    shader_cache,
+                    &mut instance_filter,
+                    &mut render_context,
+                    bundle_uniform_data,
+                    &global_uniforms,
+                )?
+            }
+        }
+        Ok(stats)
+    }
+}
+
+impl RenderDataBundleStorageTrait for RenderDataBundleStorage {
+    fn push_decal(
+        &mut self,
+        material: &DecalMaterial,
+        projector_transform: Matrix4<f32>,
+        color: Color,
+        layer: u8,
+        sort_index: u64,
+    ) {
+        let key = material.key();
+        let batch_index = match self.decal_batch_map.entry(key) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                self.decal_batches.push(DecalBatch {
+                    material: material.clone(),
+                    instances: Default::default(),
+                });
+                *entry.insert(self.decal_batches.len() - 1)
+            }
+        };
+
+        self.decal_batches[batch_index]
+            .instances
+            .push(DecalInstance {
+                inv_projector_transform: projector_transform.try_inverse().unwrap_or_default(),
+                projector_transform,
+                color,
+                layer,
+                sort_index,
+            });
+    }
+
+    /// Adds a new mesh to the bundle storage using the given set of vertices and triangles. This
+    /// method automatically creates a render bundle according to a hash of the following parameters:
+    ///
+    /// - Material
+    /// - Vertex Type
+    /// - Render Path
+    ///
+    /// If one of these parameters is different, then a new bundle will be created and used to store
+    /// the given vertices and indices. If an appropriate bundle exists, the method will store the
+    /// given vertices and the triangles in it.
+    ///
+    /// ## When to use
+    ///
+    /// This method is used to reduce amount of draw calls of underlying GAPI, by merging small
+    /// portions of data into one big block that shares drawing parameters and can be rendered in
+    /// a single draw call. The vertices in this case should be pre-processed by applying world
+    /// transform to them.
+    ///
+    /// Do not use this method if you have a mesh with lots of vertices and triangles, because
+    /// pre-processing them on CPU could take more time than rendering them directly on GPU one-by-one.
+    fn push_triangles(
+        &mut self,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+        layout: &[VertexAttributeDescriptor],
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        node_handle: Handle<Node>,
+        func: &mut dyn FnMut(VertexBufferRefMut, TriangleBufferRefMut),
+    ) {
+        let render_path = render_path.resolve(material);
+
+        let mut hasher = FxHasher::default();
+        hasher.write_u64(material.key());
+        layout.hash(&mut hasher);
+        hasher.write_u32(render_path as u32);
+        let premultiplied_alpha = material.is_premultiplied_alpha();
+        hasher.write_u8(premultiplied_alpha as u8);
+        let coverage_mode = material.coverage_mode();
+        coverage_mode.hash(&mut hasher);
+        let base_key = hasher.finish();
+
+        // Find a bundle that has some space left, spill into sibling bundles if needed.
+        let mut key = base_key;
+        if let Some(max_vertices) = self.max_vertices_per_batch {
+            let mut overflow_counter = 0u64;
+            while let Some(&bundle_index) = self.bundle_map.get(&key) {
+                let vertex_count = self.bundles[bundle_index]
+                    .data
+                    .data_ref()
+                    .vertex_buffer
+                    .vertex_count();
+                if vertex_count < max_vertices {
+                    break;
+                }
+                overflow_counter += 1;
+                let mut hasher = FxHasher::default();
+                hasher.write_u64(base_key);
+                hasher.write_u64(overflow_counter);
+                key = hasher.finish();
+            }
+        }
+
+        let frame_generation = self.frame_generation;
+        let bundle = if let Some(&bundle_index) = self.bundle_map.get(&key) {
+            self.bundles.get_mut(bundle_index).unwrap()
+        } else {
+            self.bundle_map.insert(key, self.bundles.len());
+            self.bundles.push(RenderDataBundle {
+                data: dynamic_surface_cache.get_or_create(key, layout),
+                sort_index: sort_key.pack(),
+                instances: vec![
+                    // Each bundle must have at least one instance to be rendered.
+                    self.observer_position.rebase_instance(SurfaceInstanceData {
+                        node_handle,
+                        ..Default::default()
+                    }),
+                ],
+                material: material.clone(),
+                render_path,
+                premultiplied_alpha,
+                coverage_mode,
+                // Vertices are already transformed, so the winding is correct.
+                flip_winding: false,
+                stencil: None,
+                polygon_offset: None,
+                cull_override: None,
+                depth_state: None,
+                instance_layout: None,
+                frame_generation,
+                compressed_geometry: None,
+                time_to_live: Default::default(),
+            });
+            self.bundles.last_mut().unwrap()
+        };
+        bundle.frame_generation = frame_generation;
+
+        let (triangle_count, bounds) = {
+            let mut data = bundle.data.data_ref();
+            let data = &mut *data;
+
+            let triangle_count_before = data.geometry_buffer.len();
+            let vertex_count_before = data.vertex_buffer.vertex_count() as usize;
+
+            let vertex_buffer = data.vertex_buffer.modify();
+            let triangle_buffer = data.geometry_buffer.modify();
+
+            func(vertex_buffer, triangle_buffer);
+
+            // Vertices are already in world space, so their bounds can be used directly.
+            let mut bounds = AxisAlignedBoundingBox::default();
+            if render_path != RenderPath::EditorOverlay {
+                if let Some(positions) = data
+                    .vertex_buffer
+                    .attribute_view::<Vector3<f32>>(VertexAttributeUsage::Position)
+                {
+                    for i in vertex_count_before..data.vertex_buffer.vertex_count() as usize {
+                        bounds.add_point(*positions.get(i).unwrap());
+                    }
+                }
+            }
+
+            if let Some(compression) = self.geometry_compression.as_ref() {
+                bundle.compressed_geometry = match compression.compress(&data.vertex_buffer) {
+                    Ok(compressed) => Some(compressed),
+                    Err(err) => {
+                        Log::err(format!(
+                            "Unable to compress dynamic geometry. Reason: {err}"
+                        ));
+                        None
+                    }
+                };
+            }
+
+            (data.geometry_buffer.len() - triangle_count_before, bounds)
+        };
+
+        self.add_node_triangles(node_handle, triangle_count as u64);
+        self.add_scene_bounds(bounds);
+    }
+
+    /// Adds a new surface instance to the storage. The method will automatically put the instance in the appropriate
+    /// bundle. Bundle selection is done using the material, surface data, render path. If only one
+    /// of these parameters is different, then the surface instance will be put in a separate bundle.
+    fn push(
+        &mut self,
+        data: &SurfaceResource,
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        instance_data: SurfaceInstanceData,
+    ) {
+        let key = Self::bundle_key(data, material, render_path, &instance_data);
+        self.push_keyed(key, data, material, render_path, sort_key, instance_data)
+    }
+
+    fn push_with_key(
+        &mut self,
+        key: u64,
+        data: &SurfaceResource,
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        instance_data: SurfaceInstanceData,
+    ) {
+        debug_assert_eq!(
+            key,
+            Self::bundle_key(data, material, render_path, &instance_data),
+            "The cached bundle key does not match the parameters of the instance!"
+        );
+        self.push_keyed(key, data, material, render_path, sort_key, instance_data)
+    }
+}
+
+impl RenderDataBundleStorage {
+    /// Calculates a key of the bundle (see [`RenderDataBundle::key`]) that an instance with the
+    /// given parameters will be put in by [`RenderDataBundleStorageTrait::push`]. The key depends
+    /// on the material, the surface data, the render path and the render state of the instance
+    /// (stencil, polygon offset, cull override, depth state and whether its transform is mirrored).
+    /// It could be cached by a scene node and passed to
+    /// [`RenderDataBundleStorageTrait::push_with_key`] as long as the parameters stay the same.
+    pub fn bundle_key(
+        data: &SurfaceResource,
+        material: &MaterialResource,
+        render_path: RenderPath,
+        instance_data: &SurfaceInstanceData,
+    ) -> u64 {
+        calculate_bundle_key(
+            material,
+            data,
+            render_path.resolve(material),
+            material.is_premultiplied_alpha(),
+            material.coverage_mode(),
+            instance_data.is_mirrored(),
+            instance_data.stencil,
+            instance_data.polygon_offset,
+            instance_data.cull_override,
+            instance_data.depth_state,
+        )
+    }
+
+    fn push_keyed(
+        &mut self,
+        key: u64,
+        data: &SurfaceResource,
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        instance_data: SurfaceInstanceData,
+    ) {
+        let render_path = render_path.resolve(material);
+
+        if self.node_triangle_counts.is_some() {
+            let count = match instance_data.element_range {
+                ElementRange::Full => data.data_ref().geometry_buffer.len(),
+                ElementRange::Specific { count, .. } => count,
+            };
+            self.add_node_triangles(instance_data.node_handle, count as u64);
+        }
+
+        if render_path != RenderPath::EditorOverlay {
+            let local_bounds = *self
+                .local_bounds_cache
+                .entry(data.key())
+                .or_insert_with(|| surface_local_bounds(data));
+            // The instance is not rebased yet, so its transform is still in world space.
+            self.add_scene_bounds(local_bounds.transform(&instance_data.world_transform));
+        }
+
+        let frame_generation = self.frame_generation;
+        let bundle = if let Some(&bundle_index) = self.bundle_map.get(&key) {
+            self.bundles.get_mut(bundle_index).unwrap()
+        } else {
+            self.bundle_map.insert(key, self.bundles.len());
+            self.bundles.push(RenderDataBundle {
+                data: data.clone(),
+                sort_index: sort_key.pack(),
+                instances: Vec::with_capacity(
+                    self.instance_capacity_hints
+                        .get(&key)
+                        .copied()
+                        .unwrap_or_default(),
+                ),
+                material: material.clone(),
+                render_path,
+                premultiplied_alpha: material.is_premultiplied_alpha(),
+                coverage_mode: material.coverage_mode(),
+                flip_winding: instance_data.is_mirrored(),
+                stencil: instance_data.stencil,
+                polygon_offset: instance_data.polygon_offset,
+                cull_override: instance_data.cull_override,
+                depth_state: instance_data.depth_state,
+                instance_layout: None,
+                frame_generation,
+                compressed_geometry: None,
+                time_to_live: Default::default(),
+            });
+            self.bundles.last_mut().unwrap()
+        };
+        bundle.frame_generation = frame_generation;
+
+        bundle
+            .instances
+            .push(self.observer_position.rebase_instance(instance_data))
+    }
+
+    /// Adds a new debug line to the storage, see [`Self::push_lines`] for more info.
+    pub fn push_line(
+        &mut self,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+        start: Vector3<f32>,
+        end: Vector3<f32>,
+        color: Color,
+        thickness: f32,
+        sort_key: SortKey,
+    ) {
+        self.push_lines(
+            dynamic_surface_cache,
+            [DebugLine {
+                start,
+                end,
+                color,
+                thickness,
+            }],
+            sort_key,
+        )
+    }
+
+    /// Adds a set of debug lines (velocity vectors, normals, paths, etc.) to the storage. Every
+    /// line is expanded on CPU to a quad of the line's thickness, that faces the observer, and all
+    /// the quads are merged (using [`RenderDataBundleStorageTrait::push_triangles`]) into a single
+    /// bundle, that uses the shared debug line material (see
+    /// [`DynamicSurfaceCache::get_or_create_debug_line_material`]). This way all the debug lines
+    /// of a frame are drawn in a single draw call.
+    ///
+    /// The bundle uses [`RenderPath::EditorOverlay`] render path, so it is drawn on top of the
+    /// scene and it is not included in the scene bounds and statistics (see
+    /// [`Self::total_triangles`]). `sort_key` is used only if the bundle does not exist yet.
+    pub fn push_lines(
+        &mut self,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+        lines: impl IntoIterator<Item = DebugLine>,
+        sort_key: SortKey,
+    ) {
+        let mut lines = lines.into_iter().peekable();
+        if lines.peek().is_none() {
+            return;
+        }
+
+        let material = dynamic_surface_cache.get_or_create_debug_line_material();
+        let observer_position = self.observer_position.translation;
+
+        self.push_triangles(
+            dynamic_surface_cache,
+            SpriteVertex::layout(),
+            &material,
+            RenderPath::EditorOverlay,
+            sort_key,
+            Handle::NONE,
+            &mut |mut vertex_buffer, mut triangle_buffer| {
+                for line in lines.by_ref() {
+                    let start_vertex_index = vertex_buffer.vertex_count();
+
+                    // Zero size keeps the vertices where they are, the sprite shader does not
+                    // offset them towards the camera then.
+                    for position in line.quad_corners(observer_position) {
+                        vertex_buffer
+                            .push_vertex(&SpriteVertex {
+                                position,
+                                tex_coord: Default::default(),
+                                params: Default::default(),
+                                color: line.color,
+                            })
+                            .unwrap();
+                    }
+
+                    triangle_buffer.push_triangles_iter_with_offset(
+                        start_vertex_index,
+                        [TriangleDefinition([0, 1, 2]), TriangleDefinition([2, 3, 0])].into_iter(),
+                    );
+                }
+            },
+        );
+    }
+}
+
+/// A storage adapter that routes the render data to one of two storages, using transparency of
+/// the material, see [`RenderDataBundleStorage::from_graph_split`].
+struct SplitStorage<'a> {
+    opaque: &'a mut RenderDataBundleStorage,
+    transparent: &'a mut RenderDataBundleStorage,
+}
+
+impl SplitStorage<'_> {
+    fn select(&mut self, material: &MaterialResource) -> &mut RenderDataBundleStorage {
+        if material.pass_hint().is_transparent() {
+            self.transparent
+        } else {
+            self.opaque
+        }
+    }
+}
+
+impl RenderDataBundleStorageTrait for SplitStorage<'_> {
+    fn push_triangles(
+        &mut self,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+        layout: &[VertexAttributeDescriptor],
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        node_handle: Handle<Node>,
+        func: &mut dyn FnMut(VertexBufferRefMut, TriangleBufferRefMut),
+    ) {
+        self.select(material).push_triangles(
+            dynamic_surface_cache,
+            layout,
+            material,
+            render_path,
+            sort_key,
+            node_handle,
+            func,
+        )
+    }
+
+    fn push(
+        &mut self,
+        data: &SurfaceResource,
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        instance_data: SurfaceInstanceData,
+    ) {
+        self.select(material)
+            .push(data, material, render_path, sort_key, instance_data)
+    }
+
+    fn push_with_key(
+        &mut self,
+        key: u64,
+        data: &SurfaceResource,
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        instance_data: SurfaceInstanceData,
+    ) {
+        self.select(material).push_with_key(
+            key,
+            data,
+            material,
+            render_path,
+            sort_key,
+            instance_data,
+        )
+    }
+
+    fn push_decal(
+        &mut self,
+        material: &DecalMaterial,
+        projector_transform: Matrix4<f32>,
+        color: Color,
+        layer: u8,
+        sort_index: u64,
+    ) {
+        self.opaque
+            .push_decal(material, projector_transform, color, layer, sort_index)
+    }
+}
+
+/// A storage adapter that drops surface instances rejected by [`RenderContext::instance_filter`].
+struct FilteredStorage<'a> {
+    storage: &'a mut dyn RenderDataBundleStorageTrait,
+    filter: &'a InstanceFilterFn,
+}
+
+impl RenderDataBundleStorageTrait for FilteredStorage<'_> {
+    fn push_triangles(
+        &mut self,
+        dynamic_surface_cache: &mut DynamicSurfaceCache,
+        layout: &[VertexAttributeDescriptor],
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        node_handle: Handle<Node>,
+        func: &mut dyn FnMut(VertexBufferRefMut, TriangleBufferRefMut),
+    ) {
+        self.storage.push_triangles(
+            dynamic_surface_cache,
+            layout,
+            material,
+            render_path,
+            sort_key,
+            node_handle,
+            func,
+        )
+    }
+
+    fn push(
+        &mut self,
+        data: &SurfaceResource,
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        instance_data: SurfaceInstanceData,
+    ) {
+        if (self.filter)(&instance_data, instance_data.node_handle) {
+            self.storage
+                .push(data, material, render_path, sort_key, instance_data)
+        }
+    }
+
+    fn push_with_key(
+        &mut self,
+        key: u64,
+        data: &SurfaceResource,
+        material: &MaterialResource,
+        render_path: RenderPath,
+        sort_key: SortKey,
+        instance_data: SurfaceInstanceData,
+    ) {
+        if (self.filter)(&instance_data, instance_data.node_handle) {
+            self.storage
+                .push_with_key(key, data, material, render_path, sort_key, instance_data)
+        }
+    }
+
+    fn push_decal(
+        &mut self,
+        material: &DecalMaterial,
+        projector_transform: Matrix4<f32>,
+        color: Color,
+        layer: u8,
+        sort_index: u64,
+    ) {
+        self.storage
+            .push_decal(material, projector_transform, color, layer, sort_index)
+    }
+}
+
+/// Identifier of a surface instance, that stays the same between frames as long as the scene node
+/// of the instance emits its render data in the same order. It is used by [`InstanceMotionTracker`]
+/// to match instances of two frames.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct PersistentIdentifier {
+    /// A handle of the scene node, that produced the instance.
+    pub node_handle: Handle<Node>,
+    /// A key of the surface data of the instance.
+    pub surface_key: u64,
+    /// Index of the instance among all the instances of the same node and surface.
+    pub index: usize,
+}
+
+/// Matches instances of two consecutive frames, it could be used to calculate motion vectors.
+/// World transforms are always in world space, even if origin rebasing is on.
+/// Skinned instances are matched by their world transform only, motion of bones is not tracked.
+#[derive(Debug, Default, Clone)]
+pub struct InstanceMotionTracker {
+    /// Instances that are present in both frames, along with their world transforms in the
+    /// previous and the current frames respectively.
+    pub persistent: FxHashMap<PersistentIdentifier, (Matrix4<f32>, Matrix4<f32>)>,
+    /// Instances that are present in the current frame only. The renderer should use zero motion
+    /// for them.
+    pub appeared: Vec<PersistentIdentifier>,
+    /// Instances that were present in the previous frame only.
+    pub disappeared: Vec<PersistentIdentifier>,
+}
+
+impl InstanceMotionTracker {
+    /// Matches instances of the previous and the current frames.
+    pub fn new(prev: &RenderDataBundleStorage, curr: &RenderDataBundleStorage) -> Self {
+        let mut prev_transforms = Self::world_transforms(prev);
+        let mut tracker = Self::default();
+
+        for (id, curr_world_transform) in Self::world_transforms(curr) {
+            if let Some(prev_world_transform) = prev_transforms.remove(&id) {
+                tracker
+                    .persistent
+                    .insert(id, (prev_world_transform, curr_world_transform));
+            } else {
+                tracker.appeared.push(id);
+            }
+        }
+
+        tracker.disappeared.extend(prev_transforms.into_keys());
+
+        tracker
+    }
+
+    fn world_transforms(
+        storage: &RenderDataBundleStorage,
+    ) -> FxHashMap<PersistentIdentifier, Matrix4<f32>> {
+        let observer_position = &storage.observer_position;
+        let mut transforms = FxHashMap::default();
+        storage.for_each_persistent_instance(|id, _, instance| {
+            let mut world_transform = instance.world_transform;
+            // Undo the rebasing, otherwise the motion of the observer will be added to
+            // every instance.
+            if observer_position.origin_rebase && instance.bone_matrices.is_empty() {
+                let w = world_transform[(3, 3)];
+                for i in 0..3 {
+                    world_transform[(i, 3)] += observer_position.translation[i] * w;
+                }
+            }
+
+            transforms.insert(id, world_transform);
+        });
+        transforms
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::material::{
+        shader::ShaderDefinition, CoverageMode, Material, MaterialResource, PassHint,
+    };
+    use crate::renderer::bundle::{
+        clip_bounds_to_scissor_box, surface_local_bounds, BundleValidationError, CullMode,
+        DebugLine, DecalMaterial, DepthState, DrawElementsIndirectCommand, ExportError,
+        ExportFormat, GraphAppendOrder, InstanceAttribute, InstanceCountCache, InstanceCountChange,
+        InstanceLayout, InstanceLayoutError, InstanceMotionTracker, MaterialChange, OverlayStats,
+        PersistentIdentifier, RenderContext, RenderDataBundle, RenderDataBundleBuilder,
+        RenderDataBundleError, RenderDataBundleStorage, RenderDataBundleStorageOptions,
+        RenderDataBundleStorageTrait, SkinningStats, SortKey, StateTransitions, StencilState,
+        SurfaceInstanceData, TileGrid,
+    };
+    use crate::renderer::cache::geometry::GeometryCache;
+    use crate::renderer::compression::{AttributeEncoding, GeometryCompression};
+    use crate::renderer::{
+        cache::DynamicSurfaceCache,
+        framework::{
+            uniform::StaticUniformBuffer, CompareFunc, CullFace, ElementRange, PolygonOffset,
+            StencilFunc,
+        },
+        observer::ObserverPosition,
+    };
+    use crate::resource::texture::{Texture, TextureResource};
+    use crate::scene::{
+        base::{BaseBuilder, ImpostorLevel, LevelOfDetail, LodGroup},
+        collider::BitMask,
+        graph::Graph,
+        mesh::{
+            buffer::VertexAttributeUsage,
+            surface::{SurfaceBuilder, SurfaceData, SurfaceResource, QUAD},
+            MeshBuilder, RenderPath,
+        },
+        node::Node,
+        pivot::PivotBuilder,
+        transform::TransformBuilder,
+    };
+    use fxhash::FxHashSet;
+    use fyrox_core::{
+        algebra::{Matrix4, Point3, Vector2, Vector3, Vector4},
+        color::Color,
+        math::{ray::Ray, Rect},
+        pool::Handle,
+        sstorage::ImmutableString,
+        uuid::Uuid,
+    };
+    use fyrox_graph::BaseSceneGraph;
+    use fyrox_resource::untyped::ResourceKind;
+
+    fn make_observer_position() -> ObserverPosition {
+        ObserverPosition {
+            translation: Default::default(),
+            z_near: 0.0,
+            z_far: 0.0,
+            view_matrix: Matrix4::identity(),
+            projection_matrix: Matrix4::identity(),
+            view_projection_matrix: Matrix4::identity(),
+            origin_rebase: false,
+            cull_padding: 0.0,
+        }
+    }
+
+    fn make_material(pass_hint: PassHint) -> MaterialResource {
+        let mut material = Material::standard();
+        material.set_pass_hint(pass_hint);
+        MaterialResource::new_ok(Uuid::new_v4(), ResourceKind::Embedded, material)
+    }
+
+    fn make_surface() -> SurfaceResource {
+        SurfaceResource::new_ok(
+            Uuid::new_v4(),
+            ResourceKind::Embedded,
+            SurfaceData::make_cube(Matrix4::identity()),
+        )
+    }
+
+    #[test]
+    fn test_auto_render_path() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let transparent = make_material(PassHint::Transparent);
+        let opaque = make_material(PassHint::Opaque);
+
+        storage.push(
+            &surface,
+            &transparent,
+            RenderPath::Auto,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+        storage.push(
+            &surface,
+            &opaque,
+            RenderPath::Auto,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+        // Explicit render path must override the hint.
+        storage.push(
+            &surface,
+            &transparent,
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+
+        assert_eq!(storage.bundles.len(), 3);
+        assert_eq!(storage.bundles[0].render_path, RenderPath::Forward);
+        assert_eq!(storage.bundles[1].render_path, RenderPath::Deferred);
+        assert_eq!(storage.bundles[2].render_path, RenderPath::Deferred);
+    }
+
+    #[test]
+    fn test_push_sprite_batching() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let mut cache = DynamicSurfaceCache::new();
+        let texture_a =
+            TextureResource::new_ok(Uuid::new_v4(), ResourceKind::Embedded, Texture::default());
+        let texture_b =
+            TextureResource::new_ok(Uuid::new_v4(), ResourceKind::Embedded, Texture::default());
+
+        // Overlapping sprites with interleaved textures.
+        for (i, texture) in [&texture_a, &texture_b, &texture_a, &texture_b, &texture_a]
+            .into_iter()
+            .enumerate()
+        {
+            storage.push_sprite(
+                &mut cache,
+                Vector3::new(i as f32 * 0.25, 0.0, 0.0),
+                Vector2::new(1.0, 1.0),
+                i as f32,
+                Rect::new(0.0, 0.0, 1.0, 1.0),
+                texture,
+                SortKey::from(i as u64),
+                Handle::NONE,
+            );
+        }
+
+        assert_eq!(storage.bundles.len(), 2);
+        assert_eq!(storage.bundles[0].render_path, RenderPath::Forward);
+        assert_eq!(storage.bundles[0].sort_index, 0);
+        assert_eq!(storage.bundles[1].sort_index, 1);
+
+        let data_a = storage.bundles[0].data.data_ref();
+        assert_eq!(data_a.vertex_buffer.vertex_count(), 12);
+        assert_eq!(data_a.geometry_buffer.len(), 6);
+        let data_b = storage.bundles[1].data.data_ref();
+        assert_eq!(data_b.vertex_buffer.vertex_count(), 8);
+        assert_eq!(data_b.geometry_buffer.len(), 4);
+    }
+
+    #[test]
+    fn test_push_sprite_tex_coords() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let mut cache = DynamicSurfaceCache::new();
+        let texture =
+            TextureResource::new_ok(Uuid::new_v4(), ResourceKind::Embedded, Texture::default());
+
+        storage.push_sprite(
+            &mut cache,
+            Vector3::new(1.0, 2.0, 0.0),
+            Vector2::new(2.0, 4.0),
+            0.0,
+            Rect::new(0.25, 0.5, 0.5, 0.25),
+            &texture,
+            SortKey::default(),
+            Handle::NONE,
+        );
+
+        let data = storage.bundles[0].data.data_ref();
+        let positions = data
+            .vertex_buffer
+            .attribute_view::<Vector3<f32>>(VertexAttributeUsage::Position)
+            .unwrap();
+        let tex_coords = data
+            .vertex_buffer
+            .attribute_view::<Vector2<f32>>(VertexAttributeUsage::TexCoord0)
+            .unwrap();
+
+        // X axis points to the left in 2D, so the vertices with smaller X show the right side of
+        // the texture, exactly as the rectangle node does.
+        let expected = [
+            (Vector3::new(0.0, 4.0, 0.0), Vector2::new(0.75, 0.5)),
+            (Vector3::new(2.0, 4.0, 0.0), Vector2::new(0.25, 0.5)),
+            (Vector3::new(2.0, 0.0, 0.0), Vector2::new(0.25, 0.75)),
+            (Vector3::new(0.0, 0.0, 0.0), Vector2::new(0.75, 0.75)),
+        ];
+        for (i, (position, tex_coord)) in expected.into_iter().enumerate() {
+            assert_eq!(*positions.get(i).unwrap(), position);
+            assert_eq!(*tex_coords.get(i).unwrap(), tex_coord);
+        }
+    }
+
+    #[test]
+    fn test_push_lines_batching() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let mut cache = DynamicSurfaceCache::new();
+
+        storage.push_line(
+            &mut cache,
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(1.0, 0.0, 1.0),
+            Color::RED,
+            0.1,
+            SortKey::default(),
+        );
+        storage.push_lines(
+            &mut cache,
+            (0..3).map(|i| DebugLine {
+                start: Vector3::new(0.0, i as f32, 1.0),
+                end: Vector3::new(0.0, i as f32 + 1.0, 1.0),
+                color: Color::GREEN,
+                thickness: 0.2,
+            }),
+            SortKey::default(),
+        );
+        // A line that points at the observer is still expanded to a quad.
+        storage.push_line(
+            &mut cache,
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(0.0, 0.0, 2.0),
+            Color::BLUE,
+            0.1,
+            SortKey::default(),
+        );
+
+        assert_eq!(storage.bundles.len(), 1);
+        let bundle = &storage.bundles[0];
+        assert_eq!(bundle.render_path, RenderPath::EditorOverlay);
+        assert_eq!(bundle.material, cache.get_or_create_debug_line_material());
+        {
+            let data = bundle.data.data_ref();
+            assert_eq!(data.vertex_buffer.vertex_count(), 20);
+            assert_eq!(data.geometry_buffer.len(), 10);
+        }
+
+        // The first quad is perpendicular to the line and to the direction to the observer.
+        let corners = DebugLine {
+            start: Vector3::new(0.0, 0.0, 1.0),
+            end: Vector3::new(1.0, 0.0, 1.0),
+            color: Color::RED,
+            thickness: 0.1,
+        }
+        .quad_corners(Vector3::default());
+        assert!((corners[1] - corners[0]).x.abs() < f32::EPSILON);
+        assert!(((corners[1] - corners[0]).norm() - 0.1).abs() < 1.0e-6);
+
+        // Debug lines are not a part of the scene.
+        assert_eq!(storage.total_triangles(), 0);
+        assert!(storage.scene_bounds().is_none());
+    }
+
+    #[test]
+    fn test_optimize_state_changes() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let make_material = |mut material: Material, pass_hint| {
+            material.set_pass_hint(pass_hint);
+            MaterialResource::new_ok(Uuid::new_v4(), ResourceKind::Embedded, material)
+        };
+        let standard = make_material(Material::standard(), PassHint::Opaque);
+        let material_2d = make_material(Material::standard_2d(), PassHint::Opaque);
+        let transparent_a = make_material(Material::standard_two_sides(), PassHint::Transparent);
+        let transparent_b = make_material(Material::standard_two_sides(), PassHint::Transparent);
+
+        // Interleaved opaque materials with different shaders.
+        for i in 0..6 {
+            let material = if i % 2 == 0 { &standard } else { &material_2d };
+            storage.push(
+                &make_surface(),
+                material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData::default(),
+            );
+        }
+
+        // Transparent bundles, the last two of them are at the same depth.
+        let transparent = [
+            (&transparent_a, 1),
+            (&transparent_b, 2),
+            (&transparent_a, 3),
+            (&transparent_b, 4),
+            (&transparent_a, 4),
+        ];
+        for (material, depth_bucket) in transparent {
+            storage.push(
+                &make_surface(),
+                material,
+                RenderPath::Forward,
+                SortKey::default().with_depth_bucket(depth_bucket),
+                SurfaceInstanceData::default(),
+            );
+        }
+
+        // The bundles are pushed in the sorted order.
+        let before = storage.state_transitions();
+        storage.optimize_state_changes();
+        let after = storage.state_transitions();
+
+        assert_eq!(
+            before,
+            StateTransitions {
+                shaders: 6,
+                materials: 10
+            }
+        );
+        // Opaque bundles are clustered, transparent ones at the same depth continue the run of
+        // the previous material.
+        assert_eq!(
+            after,
+            StateTransitions {
+                shaders: 2,
+                materials: 5
+            }
+        );
+
+        // Back-to-front order of transparent bundles is preserved.
+        let depth_buckets = storage.bundles[6..]
+            .iter()
+            .map(|bundle| SortKey::unpack(bundle.sort_index).depth_bucket)
+            .collect::<Vec<_>>();
+        assert_eq!(depth_buckets, [1, 2, 3, 4, 4]);
+        assert_eq!(storage.bundles[9].material, transparent_a);
+        assert_eq!(storage.bundles[10].material, transparent_b);
+    }
+
+    #[test]
+    fn test_premultiplied_alpha_bundles() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let straight = make_material(PassHint::Transparent);
+        let premultiplied = make_material(PassHint::Transparent);
+        premultiplied.data_ref().set_premultiplied_alpha(true);
+
+        for material in [&straight, &premultiplied, &straight, &premultiplied] {
+            storage.push(
+                &surface,
+                material,
+                RenderPath::Forward,
+                SortKey::default(),
+                SurfaceInstanceData::default(),
+            );
+        }
+
+        assert_eq!(storage.bundles.len(), 2);
+        assert!(!storage.bundles[0].premultiplied_alpha);
+        assert_eq!(storage.bundles[0].instances.len(), 2);
+        assert!(storage.bundles[1].premultiplied_alpha);
+        assert_eq!(storage.bundles[1].instances.len(), 2);
+    }
+
+    #[test]
+    fn test_coverage_mode_bundles() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let standard = make_material(PassHint::Masked);
+        let alpha_to_coverage = make_material(PassHint::Masked);
+        alpha_to_coverage
+            .data_ref()
+            .set_coverage_mode(CoverageMode::AlphaToCoverage);
+
+        for material in [&standard, &alpha_to_coverage, &standard, &alpha_to_coverage] {
+            storage.push(
+                &surface,
+                material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData::default(),
+            );
+        }
+
+        assert_eq!(storage.bundles.len(), 2);
+        assert_eq!(storage.bundles[0].coverage_mode, CoverageMode::None);
+        assert_eq!(storage.bundles[0].instances.len(), 2);
+        assert_eq!(
+            storage.bundles[1].coverage_mode,
+            CoverageMode::AlphaToCoverage
+        );
+        assert_eq!(storage.bundles[1].instances.len(), 2);
+
+        // The mode is a part of the key, even if everything else is the same.
+        let instance = SurfaceInstanceData::default();
+        let key_before = RenderDataBundleStorage::bundle_key(
+            &surface,
+            &standard,
+            RenderPath::Deferred,
+            &instance,
+        );
+        standard
+            .data_ref()
+            .set_coverage_mode(CoverageMode::Conservative);
+        let key_after = RenderDataBundleStorage::bundle_key(
+            &surface,
+            &standard,
+            RenderPath::Deferred,
+            &instance,
+        );
+        assert_ne!(key_before, key_after);
+    }
+
+    #[test]
+    fn test_push_bundle() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        storage.push_bundle(RenderDataBundle::new(
+            surface.clone(),
+            material.clone(),
+            RenderPath::Deferred,
+            SortKey::from(2).pack(),
+            vec![SurfaceInstanceData::default()],
+        ));
+        storage.push(
+            &surface,
+            &material,
+            RenderPath::Deferred,
+            SortKey::from(1),
+            SurfaceInstanceData::default(),
+        );
+        storage.push(
+            &surface,
+            &material,
+            RenderPath::Deferred,
+            SortKey::from(1),
+            SurfaceInstanceData::default(),
+        );
+        storage.sort();
+
+        assert_eq!(storage.bundles.len(), 2);
+        assert_eq!(storage.bundles[0].sort_index(), 1);
+        assert_eq!(storage.bundles[0].instances.len(), 2);
+        assert_eq!(storage.bundles[1].sort_index(), 2);
+        assert_eq!(storage.bundles[1].instances.len(), 1);
+    }
+
+    #[test]
+    fn test_build_indirect_commands() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let cube = make_surface();
+        let quad = SurfaceResource::new_ok(
+            Uuid::new_v4(),
+            ResourceKind::Embedded,
+            SurfaceData::make_quad(&Matrix4::identity()),
+        );
+        let material = make_material(PassHint::Opaque);
+        let (cube_triangles, cube_vertices) = {
+            let data = cube.data_ref();
+            (
+                data.geometry_buffer.len() as u32,
+                data.vertex_buffer.vertex_count() as i32,
+            )
+        };
+
+        storage.push_bundle(RenderDataBundle::new(
+            cube,
+            material.clone(),
+            RenderPath::Deferred,
+            1,
+            vec![SurfaceInstanceData::default(); 3],
+        ));
+        let specific = SurfaceInstanceData {
+            element_range: ElementRange::Specific {
+                offset: 1,
+                count: 1,
+            },
+            ..Default::default()
+        };
+        storage.push_bundle(RenderDataBundle::new(
+            quad,
+            material,
+            RenderPath::Deferred,
+            2,
+            vec![SurfaceInstanceData::default(), specific.clone(), specific],
+        ));
+
+        assert_eq!(
+            storage.build_indirect_commands(),
+            [
+                DrawElementsIndirectCommand {
+                    count: cube_triangles * 3,
+                    instance_count: 3,
+                    first_index: 0,
+                    base_vertex: 0,
+                    base_instance: 0,
+                },
+                DrawElementsIndirectCommand {
+                    count: 6,
+                    instance_count: 1,
+                    first_index: cube_triangles * 3,
+                    base_vertex: cube_vertices,
+                    base_instance: 3,
+                },
+                DrawElementsIndirectCommand {
+                    count: 3,
+                    instance_count: 2,
+                    first_index: cube_triangles * 3 + 3,
+                    base_vertex: cube_vertices,
+                    base_instance: 4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_indirect_draw_call_count() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let cube = make_surface();
+        let quad = SurfaceResource::new_ok(
+            Uuid::new_v4(),
+            ResourceKind::Embedded,
+            SurfaceData::make_quad(&Matrix4::identity()),
+        );
+        let material = make_material(PassHint::Opaque);
+
+        assert_eq!(storage.indirect_draw_call_count(), 0);
+
+        // Instances of the same surface are merged in a single bundle and drawn in one call.
+        for i in 0..5 {
+            storage.push(
+                &cube,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    world_transform: Matrix4::new_translation(&Vector3::new(i as f32, 0.0, 0.0)),
+                    ..Default::default()
+                },
+            );
+        }
+        assert_eq!(storage.bundles.len(), 1);
+        assert_eq!(storage.indirect_draw_call_count(), 1);
+
+        // Different element ranges split the bundle.
+        let specific = SurfaceInstanceData {
+            element_range: ElementRange::Specific {
+                offset: 0,
+                count: 1,
+            },
+            ..Default::default()
+        };
+        storage.push_bundle(RenderDataBundle::new(
+            quad,
+            material,
+            RenderPath::Deferred,
+            0,
+            vec![
+                SurfaceInstanceData::default(),
+                specific.clone(),
+                specific,
+                SurfaceInstanceData::default(),
+            ],
+        ));
+        assert_eq!(storage.indirect_draw_call_count(), 4);
+        assert_eq!(
+            storage.indirect_draw_call_count(),
+            storage.build_indirect_commands().len()
+        );
+        assert_eq!(storage.overlay_stats().draw_calls, 9);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+        let triangle_count = surface.data_ref().geometry_buffer.len();
+
+        storage.push(
+            &surface,
+            &material,
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+        assert_eq!(storage.validate(), Ok(()));
+
+        let skinned = || SurfaceInstanceData {
+            bone_matrices: vec![Matrix4::identity()],
+            ..Default::default()
+        };
+        let mut non_finite = skinned();
+        non_finite.world_transform[(0, 3)] = f32::NAN;
+        storage.push_bundle(RenderDataBundle::new(
+            surface.clone(),
+            material.clone(),
+            RenderPath::Deferred,
+            0,
+            vec![],
+        ));
+        storage.push_bundle(RenderDataBundle::new(
+            surface,
+            material,
+            RenderPath::Deferred,
+            0,
+            vec![
+                skinned(),
+                SurfaceInstanceData::default(),
+                SurfaceInstanceData {
+                    blend_shapes_weights: vec![1.0],
+                    ..skinned()
+                },
+                SurfaceInstanceData {
+                    element_range: ElementRange::Specific {
+                        offset: triangle_count,
+                        count: 1,
+                    },
+                    ..skinned()
+                },
+                non_finite,
+            ],
+        ));
+
+        let empty_key = storage.bundles[1].key();
+        let bundle_key = storage.bundles[2].key();
+        assert_eq!(
+            storage.validate(),
+            Err(vec![
+                BundleValidationError::NoInstances {
+                    bundle_key: empty_key
+                },
+                BundleValidationError::SkinningMismatch {
+                    bundle_key,
+                    instance: 1,
+                    skinned: true,
+                },
+                BundleValidationError::BlendShapeWeightsMismatch {
+                    bundle_key,
+                    instance: 2,
+                    expected: 0,
+                    actual: 1,
+                },
+                BundleValidationError::InvalidElementRange {
+                    bundle_key,
+                    instance: 3,
+                    triangle_count,
+                },
+                BundleValidationError::NonFiniteTransform {
+                    bundle_key,
+                    instance: 4,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_clipped_instances() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        let clip_bounds = Rect::new(10.0, 20.0, 100.0, 50.0);
+
+        storage.push(
+            &surface,
+            &material,
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData {
+                clip_bounds: Some(clip_bounds),
+                ..Default::default()
+            },
+        );
+        storage.push(
+            &surface,
+            &material,
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+
+        // Clipping must not affect bundle selection.
+        assert_eq!(storage.bundles.len(), 1);
+        let instances = &storage.bundles[0].instances;
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances[0].clip_bounds, Some(clip_bounds));
+        assert_eq!(instances[1].clip_bounds, None);
+
+        let scissor_box = clip_bounds_to_scissor_box(&clip_bounds, &Rect::new(0, 0, 800, 600));
+        assert_eq!(scissor_box.x, 10);
+        assert_eq!(scissor_box.y, 530);
+        assert_eq!(scissor_box.width, 100);
+        assert_eq!(scissor_box.height, 50);
+    }
+
+    fn make_mesh(
+        graph: &mut Graph,
+        position: Vector3<f32>,
+        surface: &SurfaceResource,
+    ) -> Handle<Node> {
+        MeshBuilder::new(
+            BaseBuilder::new()
+                .with_frustum_culling(false)
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(position)
+                        .build(),
+                ),
+        )
+        .with_surfaces(vec![SurfaceBuilder::new(surface.clone()).build()])
+        .build(graph)
+    }
+
+    #[test]
+    fn test_lod_level_annotation() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+
+        let near = make_mesh(&mut graph, Vector3::new(0.0, 0.0, 0.0), &surface);
+        let far = make_mesh(&mut graph, Vector3::new(0.7, 0.0, 0.0), &surface);
+        let far_child = make_mesh(&mut graph, Vector3::new(0.0, 0.0, 0.0), &surface);
+        graph.link_nodes(far_child, far);
+        let no_lod = make_mesh(&mut graph, Vector3::new(0.0, 0.2, 0.0), &surface);
+
+        PivotBuilder::new(BaseBuilder::new().with_lod_group(LodGroup {
+            levels: vec![
+                LevelOfDetail::new(0.0, 0.5, vec![near]),
+                LevelOfDetail::new(0.5, 1.0, vec![far]),
+            ],
+            impostor: None,
+        }))
+        .build(&mut graph);
+
+        graph.update_hierarchical_data();
+
+        let mut observer_position = make_observer_position();
+        observer_position.z_far = 1.0;
+
+        let storage = RenderDataBundleStorage::from_graph(
+            &graph,
+            BitMask::all(),
+            0.0,
+            &observer_position,
+            ImmutableString::new("GBuffer"),
+            RenderDataBundleStorageOptions::default(),
+            &mut DynamicSurfaceCache::new(),
+        );
+
+        let lod_level_of = |handle: Handle<Node>| {
+            storage
+                .bundles
+                .iter()
+                .flat_map(|bundle| bundle.instances.iter())
+                .find(|instance| instance.node_handle == handle)
+                .map(|instance| instance.lod_level)
+        };
+
+        assert_eq!(lod_level_of(near), Some(Some(0)));
+        assert_eq!(lod_level_of(far), Some(Some(1)));
+        assert_eq!(lod_level_of(far_child), Some(Some(1)));
+        assert_eq!(lod_level_of(no_lod), Some(None));
+    }
+
+    #[test]
+    fn test_from_graph_budgeted() {
+        let mut graph = Graph::new();
+        let detailed_surface = make_surface();
+        let coarse_surface = SurfaceResource::new_ok(
+            Uuid::new_v4(),
+            ResourceKind::Embedded,
+            SurfaceData::make_quad(&Matrix4::identity()),
+        );
+
+        let detailed = make_mesh(&mut graph, Vector3::new(0.2, 0.0, 0.0), &detailed_surface);
+        let coarse = make_mesh(&mut graph, Vector3::new(0.2, 0.0, 0.0), &coarse_surface);
+
+        PivotBuilder::new(BaseBuilder::new().with_lod_group(LodGroup {
+            levels: vec![
+                LevelOfDetail::new(0.0, 0.5, vec![detailed]),
+                LevelOfDetail::new(0.5, 1.0, vec![coarse]),
+            ],
+            impostor: None,
+        }))
+        .build(&mut graph);
+
+        graph.update_hierarchical_data();
+
+        let mut observer_position = make_observer_position();
+        observer_position.z_far = 1.0;
+
+        let collect = |vertex_budget: usize| {
+            let storage = RenderDataBundleStorage::from_graph_budgeted(
+                &graph,
+                BitMask::all(),
+                0.0,
+                &observer_position,
+                ImmutableString::new("GBuffer"),
+                RenderDataBundleStorageOptions::default(),
+                &mut DynamicSurfaceCache::new(),
+                vertex_budget,
+            );
+            let handles = storage
+                .bundles
+                .iter()
+                .flat_map(|bundle| bundle.instances.iter())
+                .map(|instance| instance.node_handle)
+                .collect::<Vec<_>>();
+            (storage.vertex_memory(), handles)
+        };
+
+        let coarse_memory = coarse_surface.data_ref().vertex_buffer.raw_data().len();
+        let detailed_memory = detailed_surface.data_ref().vertex_buffer.raw_data().len();
+        assert!(coarse_memory < detailed_memory);
+
+        // Unlimited budget selects the same level as the unbudgeted collection.
+        assert_eq!(collect(usize::MAX), (detailed_memory, vec![detailed]));
+
+        // Tight budget forces the coarser level.
+        assert_eq!(collect(coarse_memory), (coarse_memory, vec![coarse]));
+
+        // Impossible budget stops at the coarsest level.
+        assert_eq!(collect(0), (coarse_memory, vec![coarse]));
+    }
+
+    #[test]
+    fn test_impostor_lod() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+
+        let near = make_mesh(&mut graph, Vector3::new(0.0, 0.0, 0.0), &surface);
+        let impostor_material = make_material(PassHint::Opaque);
+        let group = PivotBuilder::new(
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(0.0, 0.0, 0.7))
+                        .build(),
+                )
+                .with_lod_group(LodGroup {
+                    levels: vec![LevelOfDetail::new(0.0, 0.5, vec![near])],
+                    impostor: Some(ImpostorLevel::new(
+                        0.5,
+                        1.0,
+                        Vector2::new(2.0, 3.0),
+                        impostor_material.clone(),
+                    )),
+                }),
+        )
+        .build(&mut graph);
+
+        graph.update_hierarchical_data();
+
+        let mut observer_position = make_observer_position();
+        observer_position.z_far = 1.0;
+
+        let storage = RenderDataBundleStorage::from_graph(
+            &graph,
+            BitMask::all(),
+            0.0,
+            &observer_position,
+            ImmutableString::new("GBuffer"),
+            RenderDataBundleStorageOptions::default(),
+            &mut DynamicSurfaceCache::new(),
+        );
+
+        // The group is beyond the impostor distance, so the mesh is replaced with a single quad.
+        assert_eq!(storage.bundles.len(), 1);
+        let bundle = &storage.bundles[0];
+        assert_eq!(bundle.data.key(), QUAD.resource.key());
+        assert_eq!(bundle.material.key(), impostor_material.key());
+        assert_eq!(bundle.instances.len(), 1);
+        let instance = &bundle.instances[0];
+        assert_eq!(instance.node_handle, group);
+        assert_eq!(
+            instance.world_transform.transform_point(&Point3::origin()),
+            Point3::new(0.0, 0.0, 0.7)
+        );
+    }
+
+    #[test]
+    fn test_instance_filter() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+
+        let near = make_mesh(&mut graph, Vector3::new(1.0, 0.0, 0.0), &surface);
+        let far = make_mesh(&mut graph, Vector3::new(10.0, 0.0, 0.0), &surface);
+
+        graph.update_hierarchical_data();
+
+        // Drops everything beyond the radius around the origin.
+        let radius = 5.0;
+        let storage = RenderDataBundleStorage::from_graph(
+            &graph,
+            BitMask::all(),
+            0.0,
+            &make_observer_position(),
+            ImmutableString::new("GBuffer"),
+            RenderDataBundleStorageOptions {
+                instance_filter: Some(Box::new(move |instance, _| {
+                    instance.world_transform.column(3).xyz().norm() <= radius
+                })),
+                ..Default::default()
+            },
+            &mut DynamicSurfaceCache::new(),
+        );
+
+        let is_rendered = |handle: Handle<Node>| {
+            storage
+                .bundles
+                .iter()
+                .flat_map(|bundle| bundle.instances.iter())
+                .any(|instance| instance.node_handle == handle)
+        };
+
+        assert!(is_rendered(near));
+        assert!(!is_rendered(far));
+    }
+
+    #[test]
+    fn test_from_graph_split() {
+        let mut graph = Graph::new();
+        let opaque = make_material(PassHint::Opaque);
+        let transparent = make_material(PassHint::Transparent);
+
+        let mut add_mesh = |z: f32, material: &MaterialResource| {
+            MeshBuilder::new(
+                BaseBuilder::new()
+                    .with_frustum_culling(false)
+                    .with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(Vector3::new(0.0, 0.0, z))
+                            .build(),
+                    ),
+            )
+            .with_surfaces(vec![SurfaceBuilder::new(make_surface())
+                .with_material(material.clone())
+                .build()])
+            .build(&mut graph)
+        };
+
+        let opaque_far = add_mesh(-5.0, &opaque);
+        let transparent_near = add_mesh(-2.0, &transparent);
+        let opaque_near = add_mesh(-1.0, &opaque);
+        let transparent_far = add_mesh(-4.0, &transparent);
+
+        graph.update_hierarchical_data();
+
+        let (opaque_storage, transparent_storage) = RenderDataBundleStorage::from_graph_split(
+            &graph,
+            BitMask::all(),
+            0.0,
+            &make_observer_position(),
+            ImmutableString::new("GBuffer"),
+            RenderDataBundleStorageOptions::default(),
+            &mut DynamicSurfaceCache::new(),
+        );
+
+        let nodes = |storage: &RenderDataBundleStorage| {
+            storage
+                .bundles
+                .iter()
+                .map(|bundle| bundle.instances[0].node_handle)
+                .collect::<Vec<_>>()
+        };
+
+        // Opaque bundles are drawn front-to-back, transparent ones - back-to-front.
+        assert_eq!(nodes(&opaque_storage), vec![opaque_near, opaque_far]);
+        assert_eq!(
+            nodes(&transparent_storage),
+            vec![transparent_far, transparent_near]
+        );
+    }
+
+    #[test]
+    fn test_instance_count_cache() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        // A stable crowd of objects, that share the same bundle.
+        let crowd = (0..20)
+            .map(|i| {
+                MeshBuilder::new(
+                    BaseBuilder::new()
+                        .with_frustum_culling(false)
+                        .with_local_transform(
+                            TransformBuilder::new()
+                                .with_local_position(Vector3::new(i as f32, 0.0, 0.0))
+                                .build(),
+                        ),
+                )
+                .with_surfaces(vec![SurfaceBuilder::new(surface.clone())
+                    .with_material(material.clone())
+                    .build()])
+                .build(&mut graph)
+            })
+            .collect::<Vec<_>>();
+
+        graph.update_hierarchical_data();
+
+        let build = |graph: &Graph, cache: InstanceCountCache| {
+            RenderDataBundleStorage::from_graph(
+                graph,
+                BitMask::all(),
+                0.0,
+                &make_observer_position(),
+                ImmutableString::new("GBuffer"),
+                RenderDataBundleStorageOptions {
+                    instance_count_cache: Some(cache),
+                    ..Default::default()
+                },
+                &mut DynamicSurfaceCache::new(),
+            )
+        };
+
+        // The first frame knows nothing about the counts, so the list grows as usual.
+        let mut first = build(&graph, InstanceCountCache::default());
+        assert_eq!(first.bundles.len(), 1);
+        let key = first.bundles[0].key();
+        assert!(first.bundles[0].instances.capacity() > 20);
+        let cache = first.take_instance_count_cache().unwrap();
+        assert_eq!(cache.instance_count(key), Some(20));
+
+        // The next frame allocates the list only once, with the exact capacity.
+        let mut second = build(&graph, cache);
+        assert_eq!(second.bundles[0].key(), key);
+        assert_eq!(second.bundles[0].instances.capacity(), 20);
+        let cache = second.take_instance_count_cache().unwrap();
+
+        // Bundles that disappear are removed from the cache.
+        for handle in crowd {
+            graph.remove_node(handle);
+        }
+        let mut third = build(&graph, cache);
+        assert!(third.bundles.is_empty());
+        let cache = third.take_instance_count_cache().unwrap();
+        assert!(cache.is_empty());
+
+        // The capacity could also be reserved manually.
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        storage.reserve_instances(key, 5);
+        storage.push(
+            &surface,
+            &material,
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+        assert_eq!(storage.bundles[0].key(), key);
+        assert_eq!(storage.bundles[0].instances.capacity(), 5);
+        storage.reserve_instances(key, 10);
+        assert!(storage.bundles[0].instances.capacity() >= 11);
+    }
+
+    #[test]
+    fn test_append_graph() {
+        let make_graph = |surface: &SurfaceResource, count: usize| {
+            let mut graph = Graph::new();
+            for i in 0..count {
+                make_mesh(&mut graph, Vector3::new(0.0, 0.0, -(i as f32)), surface);
+            }
+            graph.update_hierarchical_data();
+            graph
+        };
+
+        let world_surface = make_surface();
+        let weapon_surface = make_surface();
+        let world = make_graph(&world_surface, 2);
+        let weapon = make_graph(&weapon_surface, 1);
+
+        let build = |order| {
+            let mut storage = RenderDataBundleStorage::from_graph(
+                &world,
+                BitMask::all(),
+                0.0,
+                &make_observer_position(),
+                ImmutableString::new("GBuffer"),
+                RenderDataBundleStorageOptions::default(),
+                &mut DynamicSurfaceCache::new(),
+            );
+            // Every mesh has its own material, so each of them has its own bundle.
+            assert_eq!(storage.bundles.len(), 2);
+            storage.append_graph(
+                &weapon,
+                BitMask::all(),
+                0.0,
+                ImmutableString::new("GBuffer"),
+                RenderDataBundleStorageOptions::default(),
+                &mut DynamicSurfaceCache::new(),
+                order,
+            );
+            storage
+        };
+
+        let surfaces = |storage: &RenderDataBundleStorage| {
+            storage
+                .bundles
+                .iter()
+                .map(|bundle| bundle.data.key())
+                .collect::<Vec<_>>()
+        };
+
+        // The weapon is drawn after the world.
+        let storage = build(GraphAppendOrder::After);
+        assert_eq!(
+            surfaces(&storage),
+            vec![
+                world_surface.key(),
+                world_surface.key(),
+                weapon_surface.key()
+            ]
+        );
+
+        // The weapon is drawn in the band with the lowest priority, so it goes first.
+        let storage = build(GraphAppendOrder::Band(0));
+        assert_eq!(
+            surfaces(&storage),
+            vec![
+                weapon_surface.key(),
+                world_surface.key(),
+                world_surface.key()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_push_with_key() {
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+        let mirrored = SurfaceInstanceData {
+            world_transform: Matrix4::new_nonuniform_scaling(&Vector3::new(-1.0, 1.0, 1.0)),
+            ..Default::default()
+        };
+
+        // A node caches the keys once and uses them every frame.
+        let cached_keys = [SurfaceInstanceData::default(), mirrored.clone()].map(|instance| {
+            RenderDataBundleStorage::bundle_key(
+                &surface,
+                &material,
+                RenderPath::Deferred,
+                &instance,
+            )
+        });
+        assert_ne!(cached_keys[0], cached_keys[1]);
+
+        let mut recomputed = RenderDataBundleStorage::new_empty(make_observer_position());
+        let mut cached = RenderDataBundleStorage::new_empty(make_observer_position());
+        for _ in 0..3 {
+            for (key, instance) in cached_keys
+                .iter()
+                .zip([SurfaceInstanceData::default(), mirrored.clone()])
+            {
+                recomputed.push(
+                    &surface,
+                    &material,
+                    RenderPath::Deferred,
+                    SortKey::default(),
+                    instance.clone(),
+                );
+                cached.push_with_key(
+                    *key,
+                    &surface,
+                    &material,
+                    RenderPath::Deferred,
+                    SortKey::default(),
+                    instance,
+                );
+            }
+        }
+
+        assert_eq!(recomputed.bundles.len(), 2);
+        assert_eq!(cached.bundles.len(), 2);
+        for (recomputed, cached) in recomputed.bundles.iter().zip(cached.bundles.iter()) {
+            assert_eq!(recomputed.key(), cached.key());
+            assert_eq!(recomputed.flip_winding, cached.flip_winding);
+            assert_eq!(cached.instances.len(), 3);
+        }
+        assert_eq!(cached.bundles[0].key(), cached_keys[0]);
+        assert_eq!(cached.bundles[1].key(), cached_keys[1]);
+    }
+
+    #[test]
+    fn test_always_render() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+
+        let make_far_mesh = |graph: &mut Graph, always_render| {
+            MeshBuilder::new(
+                BaseBuilder::new()
+                    .with_always_render(always_render)
+                    .with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(Vector3::new(10.0, 0.0, 0.0))
+                            .build(),
+                    ),
+            )
+            .with_surfaces(vec![SurfaceBuilder::new(surface.clone()).build()])
+            .build(graph)
+        };
+
+        let culled = make_far_mesh(&mut graph, false);
+        let always_rendered = make_far_mesh(&mut graph, true);
+        let lod_culled = make_far_mesh(&mut graph, true);
+
+        // The object is far outside the LOD range.
+        PivotBuilder::new(BaseBuilder::new().with_lod_group(LodGroup {
+            levels: vec![LevelOfDetail::new(0.0, 0.1, vec![lod_culled])],
+            impostor: None,
+        }))
+        .build(&mut graph);
+
+        graph.update_hierarchical_data();
+
+        let mut observer_position = make_observer_position();
+        observer_position.z_far = 1.0;
+
+        let storage = RenderDataBundleStorage::from_graph(
+            &graph,
+            BitMask::all(),
+            0.0,
+            &observer_position,
+            ImmutableString::new("GBuffer"),
+            RenderDataBundleStorageOptions::default(),
+            &mut DynamicSurfaceCache::new(),
+        );
+
+        let is_rendered = |handle: Handle<Node>| {
+            storage
+                .bundles
+                .iter()
+                .flat_map(|bundle| bundle.instances.iter())
+                .any(|instance| instance.node_handle == handle)
+        };
+
+        assert!(!is_rendered(culled));
+        assert!(is_rendered(always_rendered));
+        assert!(is_rendered(lod_culled));
+    }
+
+    #[test]
+    fn test_cull_padding() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+
+        // The frustum of the identity projection is [-1; 1] on every axis, the cube is 0.1 units
+        // outside of it.
+        let outside = MeshBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(1.6, 0.0, 0.0))
+                    .build(),
+            ),
+        )
+        .with_surfaces(vec![SurfaceBuilder::new(surface.clone()).build()])
+        .build(&mut graph);
+
+        // The object is just outside the LOD range (0.16 vs 0.15).
+        let lod_outside = make_mesh(&mut graph, Vector3::new(0.0, 1.6, 0.0), &surface);
+        PivotBuilder::new(BaseBuilder::new().with_lod_group(LodGroup {
+            levels: vec![LevelOfDetail::new(0.0, 0.15, vec![lod_outside])],
+            impostor: None,
+        }))
+        .build(&mut graph);
+
+        graph.update_hierarchical_data();
+
+        let is_rendered = |cull_padding: f32, handle: Handle<Node>| {
+            let observer_position = ObserverPosition {
+                z_far: 10.0,
+                cull_padding,
+                ..make_observer_position()
+            };
+            RenderDataBundleStorage::from_graph(
+                &graph,
+                BitMask::all(),
+                0.0,
+                &observer_position,
+                ImmutableString::new("GBuffer"),
+                RenderDataBundleStorageOptions::default(),
+                &mut DynamicSurfaceCache::new(),
+            )
+            .bundles
+            .iter()
+            .flat_map(|bundle| bundle.instances.iter())
+            .any(|instance| instance.node_handle == handle)
+        };
+
+        assert!(!is_rendered(0.0, outside));
+        assert!(!is_rendered(0.0, lod_outside));
+        assert!(is_rendered(0.2, outside));
+        assert!(is_rendered(0.2, lod_outside));
+    }
+
+    #[test]
+    fn test_shadow_map_cull_padding() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+
+        // The cube is 0.1 units outside of the [-1; 1] frustum of the orthographic projection.
+        let outside = MeshBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(1.6, 0.0, 0.0))
+                    .build(),
+            ),
+        )
+        .with_surfaces(vec![SurfaceBuilder::new(surface).build()])
+        .build(&mut graph);
+
+        graph.update_hierarchical_data();
+
+        let is_rendered = |shadow_map_size: usize| {
+            // A texel of 16x16 shadow map is 0.125 units wide, the padding is 0.5 units.
+            let observer_position = ObserverPosition::for_shadow_map(
+                Vector3::default(),
+                -1.0,
+                1.0,
+                Matrix4::identity(),
+                Matrix4::new_orthographic(-1.0, 1.0, -1.0, 1.0, -1.0, 1.0),
+                shadow_map_size,
+            );
+            RenderDataBundleStorage::from_graph(
+                &graph,
+                BitMask::all(),
+                0.0,
+                &observer_position,
+                ImmutableString::new("DirectionalShadow"),
+                RenderDataBundleStorageOptions {
+                    collect_lights: false,
+                    ..Default::default()
+                },
+                &mut DynamicSurfaceCache::new(),
+            )
+            .visible_node_set()
+            .contains(&outside)
+        };
+
+        assert!(is_rendered(16));
+        // The padding of a high-resolution shadow map is too small.
+        assert!(!is_rendered(4096));
+    }
+
+    #[test]
+    fn test_pick_bvh() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+
+        let meshes = (0..8)
+            .map(|i| make_mesh(&mut graph, Vector3::new(i as f32 * 5.0, 0.0, 0.0), &surface))
+            .collect::<Vec<_>>();
+
+        graph.update_hierarchical_data();
+
+        let storage = RenderDataBundleStorage::from_graph(
+            &graph,
+            BitMask::all(),
+            0.0,
+            &make_observer_position(),
+            ImmutableString::new("GBuffer"),
+            RenderDataBundleStorageOptions::default(),
+            &mut DynamicSurfaceCache::new(),
+        );
+
+        let bvh = storage.build_pick_bvh();
+        assert!(!bvh.is_empty());
+
+        let half_size = surface_local_bounds(&surface).half_extents().x;
+
+        let (node, distance) = bvh
+            .raycast(&Ray::from_two_points(
+                Vector3::new(-10.0, 0.0, 0.0),
+                Vector3::new(100.0, 0.0, 0.0),
+            ))
+            .unwrap();
+        assert_eq!(node, meshes[0]);
+        assert!((distance - (10.0 - half_size)).abs() < 1.0e-4);
+
+        let (node, distance) = bvh
+            .raycast(&Ray::from_two_points(
+                Vector3::new(100.0, 0.0, 0.0),
+                Vector3::new(-10.0, 0.0, 0.0),
+            ))
+            .unwrap();
+        assert_eq!(node, meshes[7]);
+        assert!((distance - (65.0 - half_size)).abs() < 1.0e-4);
+
+        // Straight down onto a single mesh.
+        let (node, _) = bvh
+            .raycast(&Ray::from_two_points(
+                Vector3::new(15.0, 10.0, 0.0),
+                Vector3::new(15.0, -10.0, 0.0),
+            ))
+            .unwrap();
+        assert_eq!(node, meshes[3]);
+
+        // Misses and too short rays.
+        assert!(bvh
+            .raycast(&Ray::from_two_points(
+                Vector3::new(-10.0, 5.0, 0.0),
+                Vector3::new(100.0, 5.0, 0.0),
+            ))
+            .is_none());
+        assert!(bvh
+            .raycast(&Ray::from_two_points(
+                Vector3::new(-10.0, 0.0, 0.0),
+                Vector3::new(-5.0, 0.0, 0.0),
+            ))
+            .is_none());
+    }
+
+    #[test]
+    fn test_overlay_stats() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let static_surface = make_surface();
+        let skinned_surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        for _ in 0..3 {
+            storage.push(
+                &static_surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData::default(),
+            );
+        }
+        storage.push(
+            &skinned_surface,
+            &material,
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData {
+                bone_matrices: vec![Matrix4::identity(); 2],
+                ..Default::default()
+            },
+        );
+
+        let triangles = static_surface.data_ref().geometry_buffer.len();
+        let stats = storage.overlay_stats();
+        assert_eq!(
+            stats,
+            OverlayStats {
+                bundles: 2,
+                instances: 4,
+                triangles: triangles * 4,
+                draw_calls: 4,
+                skinning: SkinningStats {
+                    skinned_bundles: 1,
+                    skinned_instances: 1,
+                    bone_matrices: 2,
+                },
+            }
+        );
+
+        let text = storage.overlay_text();
+        assert!(text.contains("Bundles: 2\n"));
+        assert!(text.contains("Instances: 4\n"));
+        assert!(text.contains(&format!("Triangles: {}\n", triangles * 4)));
+
+        // The buffer is cleared before writing.
+        let mut buffer = "garbage".to_owned();
+        storage.write_overlay_text(&mut buffer);
+        assert_eq!(buffer, text);
+    }
+
+    #[test]
+    fn test_storage_diff() {
+        let surface_a = make_surface();
+        let surface_b = make_surface();
+        let surface_c = make_surface();
+        let opaque = make_material(PassHint::Opaque);
+        let transparent = make_material(PassHint::Transparent);
+
+        let push = |storage: &mut RenderDataBundleStorage,
+                    surface: &SurfaceResource,
+                    material: &MaterialResource,
+                    count: usize| {
+            for _ in 0..count {
+                storage.push(
+                    surface,
+                    material,
+                    RenderPath::Deferred,
+                    SortKey::default(),
+                    SurfaceInstanceData::default(),
+                );
+            }
+        };
+
+        let mut before = RenderDataBundleStorage::new_empty(make_observer_position());
+        push(&mut before, &surface_a, &opaque, 2);
+        push(&mut before, &surface_b, &opaque, 1);
+        push(&mut before, &surface_c, &opaque, 1);
+
+        // The same content in a different order.
+        let mut same = RenderDataBundleStorage::new_empty(make_observer_position());
+        push(&mut same, &surface_c, &opaque, 1);
+        push(&mut same, &surface_b, &opaque, 1);
+        push(&mut same, &surface_a, &opaque, 2);
+        let diff = before.diff(&same);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "No Changes\n");
+
+        // Surface A has one more instance, surface B has a different material, surface C is
+        // removed and it is added back with a different render path.
+        let mut after = RenderDataBundleStorage::new_empty(make_observer_position());
+        push(&mut after, &surface_a, &opaque, 3);
+        push(&mut after, &surface_b, &transparent, 1);
+        after.push(
+            &surface_c,
+            &opaque,
+            RenderPath::Forward,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+
+        let key_of = |storage: &RenderDataBundleStorage, surface: &SurfaceResource| {
+            *storage
+                .keyed_bundles()
+                .iter()
+                .find(|(_, bundle)| bundle.data == *surface)
+                .unwrap()
+                .0
+        };
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, vec![(key_of(&after, &surface_c), 1)]);
+        assert_eq!(diff.removed, vec![(key_of(&before, &surface_c), 1)]);
+        assert_eq!(
+            diff.instance_count_changes,
+            vec![InstanceCountChange {
+                key: key_of(&before, &surface_a),
+                before: 2,
+                after: 3,
+            }]
+        );
+        assert_eq!(
+            diff.material_changes,
+            vec![MaterialChange {
+                key: key_of(&before, &surface_b),
+                before: opaque.key(),
+                after: transparent.key(),
+            }]
+        );
+        assert_eq!(diff.to_string().lines().count(), 4);
+        assert_eq!(after.diff(&before).added, diff.removed);
+    }
+
+    #[test]
+    fn test_node_triangle_counts() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+
+        let a = make_mesh(&mut graph, Vector3::new(0.0, 0.0, 0.0), &surface);
+        let b = make_mesh(&mut graph, Vector3::new(0.5, 0.0, 0.0), &surface);
+
+        graph.update_hierarchical_data();
+
+        let storage = RenderDataBundleStorage::from_graph(
+            &graph,
+            BitMask::all(),
+            0.0,
+            &make_observer_position(),
+            ImmutableString::new("GBuffer"),
+            RenderDataBundleStorageOptions {
+                collect_triangle_stats: true,
+                ..Default::default()
+            },
+            &mut DynamicSurfaceCache::new(),
+        );
+
+        let cube_triangles = surface.data_ref().geometry_buffer.len() as u64;
+        let total = storage
+            .bundles
+            .iter()
+            .map(|bundle| {
+                bundle.instances.len() as u64 * bundle.data.data_ref().geometry_buffer.len() as u64
+            })
+            .sum::<u64>();
+
+        let counts = storage.node_triangle_counts.as_ref().unwrap();
+        assert_eq!(counts.get(&a), Some(&cube_triangles));
+        assert_eq!(counts.get(&b), Some(&cube_triangles));
+        assert_eq!(counts.values().sum::<u64>(), total);
+
+        // The statistics must be disabled by default.
+        let storage = RenderDataBundleStorage::from_graph(
+            &graph,
+            BitMask::all(),
+            0.0,
+            &make_observer_position(),
+            ImmutableString::new("GBuffer"),
+            Default::default(),
+            &mut DynamicSurfaceCache::new(),
+        );
+        assert!(storage.node_triangle_counts.is_none());
+    }
+
+    #[test]
+    fn test_visible_node_set() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+
+        let a = make_mesh(&mut graph, Vector3::new(0.0, 0.0, 0.0), &surface);
+        let b = make_mesh(&mut graph, Vector3::new(0.5, 0.0, 0.0), &surface);
+        let hidden = make_mesh(&mut graph, Vector3::new(1.0, 0.0, 0.0), &surface);
+        graph[hidden].set_visibility(false);
+
+        graph.update_hierarchical_data();
+
+        let mut storage = RenderDataBundleStorage::from_graph(
+            &graph,
+            BitMask::all(),
+            0.0,
+            &make_observer_position(),
+            ImmutableString::new("GBuffer"),
+            Default::default(),
+            &mut DynamicSurfaceCache::new(),
+        );
+
+        assert_eq!(storage.visible_node_set(), FxHashSet::from_iter([a, b]));
+
+        // Instances without a node are not included.
+        storage.push(
+            &surface,
+            &make_material(PassHint::Opaque),
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+        assert_eq!(storage.visible_node_set(), FxHashSet::from_iter([a, b]));
+    }
+
+    #[test]
+    fn test_scene_bounds() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+
+        let build = |graph: &Graph| {
+            RenderDataBundleStorage::from_graph(
+                graph,
+                BitMask::all(),
+                0.0,
+                &make_observer_position(),
+                ImmutableString::new("GBuffer"),
+                Default::default(),
+                &mut DynamicSurfaceCache::new(),
+            )
+        };
+
+        assert!(build(&graph).scene_bounds().is_none());
+
+        // Unit cubes centered at the given positions.
+        make_mesh(&mut graph, Vector3::new(-0.25, 0.0, 0.0), &surface);
+        make_mesh(&mut graph, Vector3::new(0.5, 0.25, 0.0), &surface);
+        graph.update_hierarchical_data();
+
+        let bounds = build(&graph).scene_bounds().unwrap();
+        assert_eq!(bounds.min, Vector3::new(-0.75, -0.5, -0.5));
+        assert_eq!(bounds.max, Vector3::new(1.0, 0.75, 0.5));
+    }
+
+    #[test]
+    fn test_push_triangles_geometry_compression() {
+        let mut cache = DynamicSurfaceCache::new();
+        let texture =
+            TextureResource::new_ok(Uuid::new_v4(), ResourceKind::Embedded, Texture::default());
+        let push_sprites = |storage: &mut RenderDataBundleStorage,
+                            cache: &mut DynamicSurfaceCache| {
+            for i in 0..2 {
+                storage.push_sprite(
+                    cache,
+                    Vector3::new(i as f32 * 3.0, 1.0, -2.0),
+                    Vector2::new(1.0, 1.0),
+                    0.0,
+                    Rect::new(0.0, 0.0, 1.0, 1.0),
+                    &texture,
+                    SortKey::default(),
+                    Handle::NONE,
+                );
+            }
+        };
+
+        // Compression is disabled by default.
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        push_sprites(&mut storage, &mut cache);
+        assert!(storage.bundles[0].compressed_geometry.is_none());
+
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        storage.geometry_compression = Some(GeometryCompression::default().with_encoding(
+            VertexAttributeUsage::Position,
+            AttributeEncoding::Quantized16,
+        ));
+        let mut cache = DynamicSurfaceCache::new();
+        push_sprites(&mut storage, &mut cache);
+
+        let bundle = &storage.bundles[0];
+        let compressed = bundle.compressed_geometry.as_ref().unwrap();
+        let data = bundle.data.data_ref();
+        let positions = data
+            .vertex_buffer
+            .attribute_view::<Vector3<f32>>(VertexAttributeUsage::Position)
+            .unwrap();
+        let decoded = compressed
+            .decode_attribute(VertexAttributeUsage::Position)
+            .unwrap();
+        assert_eq!(decoded.len(), 8);
+        for (i, decoded) in decoded.iter().enumerate() {
+            let error = (decoded.xyz() - positions.get(i).unwrap()).amax();
+            assert!(error < 1.0e-3, "{error}");
+        }
+    }
+
+    #[test]
+    fn test_max_vertices_per_batch() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        storage.max_vertices_per_batch = Some(8);
+        let mut cache = DynamicSurfaceCache::new();
+        let texture =
+            TextureResource::new_ok(Uuid::new_v4(), ResourceKind::Embedded, Texture::default());
+
+        // Each sprite is 4 vertices, so the third one must spill into a new bundle.
+        for i in 0..3 {
+            storage.push_sprite(
+                &mut cache,
+                Vector3::new(i as f32, 0.0, 0.0),
+                Vector2::new(1.0, 1.0),
+                0.0,
+                Rect::new(0.0, 0.0, 1.0, 1.0),
+                &texture,
+                SortKey::default(),
+                Handle::NONE,
+            );
+        }
+
+        assert_eq!(storage.bundles.len(), 2);
+        assert_eq!(
+            storage.bundles[0]
+                .data
+                .data_ref()
+                .vertex_buffer
+                .vertex_count(),
+            8
+        );
+        assert_eq!(
+            storage.bundles[1]
+                .data
+                .data_ref()
+                .vertex_buffer
+                .vertex_count(),
+            4
+        );
+        assert_eq!(storage.bundles[0].material, storage.bundles[1].material);
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+        let a = make_mesh(&mut graph, Vector3::new(0.0, 0.0, 0.0), &surface);
+        make_mesh(&mut graph, Vector3::new(0.5, 0.0, 0.0), &surface);
+        graph.update_hierarchical_data();
+
+        let mut cache = DynamicSurfaceCache::new();
+        let mut build = |graph: &Graph| {
+            RenderDataBundleStorage::from_graph(
+                graph,
+                BitMask::all(),
+                0.0,
+                &make_observer_position(),
+                ImmutableString::new("GBuffer"),
+                Default::default(),
+                &mut cache,
+            )
+            .content_hash()
+        };
+
+        let first = build(&graph);
+        assert_eq!(first, build(&graph));
+
+        graph[a]
+            .local_transform_mut()
+            .set_position(Vector3::new(0.0, 1.0, 0.0));
+        graph.update_hierarchical_data();
+
+        assert_ne!(first, build(&graph));
+    }
+
+    #[test]
+    fn test_editor_overlay_excluded_from_stats() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        storage.push(
+            &surface,
+            &material,
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+        let scene_triangles = storage.total_triangles();
+        assert_eq!(scene_triangles, surface.data_ref().geometry_buffer.len());
+
+        // Same material and surface, but overlay bundles must not be merged with the scene ones.
+        storage.push(
+            &surface,
+            &material,
+            RenderPath::EditorOverlay,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+        assert_eq!(storage.bundles.len(), 2);
+        assert_eq!(storage.bundles[1].render_path, RenderPath::EditorOverlay);
+        assert_eq!(storage.total_triangles(), scene_triangles);
+    }
+
+    #[test]
+    fn test_animation_phase() {
+        let a = Vector3::new(1.0, 0.0, 2.0);
+        let b = Vector3::new(3.0, 0.0, -1.0);
+
+        let phase_a = SurfaceInstanceData::animation_phase_from_position(&a);
+        let phase_b = SurfaceInstanceData::animation_phase_from_position(&b);
+        assert_ne!(phase_a, phase_b);
+        assert_eq!(
+            phase_a,
+            SurfaceInstanceData::animation_phase_from_position(&a)
+        );
+        assert!((0.0..1.0).contains(&phase_a));
+        assert!((0.0..1.0).contains(&phase_b));
+
+        // The phase must not affect bundle selection.
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+        for phase in [phase_a, phase_b] {
+            storage.push(
+                &surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    animation_phase: phase,
+                    ..Default::default()
+                },
+            );
+        }
+        assert_eq!(storage.bundles.len(), 1);
+        assert_eq!(storage.bundles[0].instances[0].animation_phase, phase_a);
+        assert_eq!(storage.bundles[0].instances[1].animation_phase, phase_b);
+    }
+
+    #[test]
+    fn test_stale_identifiers() {
+        let material = make_material(PassHint::Opaque);
+        let surface_a = make_surface();
+        let surface_b = make_surface();
+        let node_a = Handle::<Node>::new(1, 1);
+        let node_b = Handle::<Node>::new(2, 1);
+
+        let push = |storage: &mut RenderDataBundleStorage, surface, node_handle| {
+            storage.push(
+                surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    node_handle,
+                    ..Default::default()
+                },
+            );
+        };
+
+        // Frame N has both objects.
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        storage.frame_generation = 1;
+        push(&mut storage, &surface_a, node_a);
+        push(&mut storage, &surface_b, node_b);
+        assert!(storage.stale_identifiers(1).is_empty());
+
+        // Frame N+1 reuses the storage, but the second object is gone.
+        storage.frame_generation = 2;
+        push(&mut storage, &surface_a, node_a);
+
+        assert_eq!(storage.bundles[0].frame_generation, 2);
+        assert_eq!(storage.bundles[1].frame_generation, 1);
+        assert_eq!(
+            storage.stale_identifiers(2),
+            vec![PersistentIdentifier {
+                node_handle: node_b,
+                surface_key: surface_b.key(),
+                index: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_stale_identifiers_shared_bundle() {
+        let material = make_material(PassHint::Opaque);
+        let surface = make_surface();
+        let node_a = Handle::<Node>::new(1, 1);
+        let node_b = Handle::<Node>::new(2, 1);
+
+        let push = |storage: &mut RenderDataBundleStorage, node_handle| {
+            storage.push(
+                &surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    node_handle,
+                    ..Default::default()
+                },
+            );
+        };
+
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        storage.frame_generation = 1;
+        push(&mut storage, node_a);
+        push(&mut storage, node_b);
+        assert_eq!(storage.bundles.len(), 1);
+
+        // The second object is gone, but it shares the bundle with the first one. Staleness is
+        // tracked per bundle, so the bundle is refreshed and its old instances are kept.
+        storage.frame_generation = 2;
+        push(&mut storage, node_a);
+
+        assert!(storage.stale_identifiers(2).is_empty());
+        assert_eq!(storage.bundles[0].instances.len(), 3);
+    }
+
+    #[test]
+    fn test_instance_motion_tracker() {
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+        let persistent = Handle::<Node>::new(1, 1);
+        let appeared = Handle::<Node>::new(2, 1);
+
+        let make_storage = |instances: &[(Handle<Node>, Vector3<f32>)]| {
+            let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+            for (node_handle, position) in instances {
+                storage.push(
+                    &surface,
+                    &material,
+                    RenderPath::Deferred,
+                    SortKey::default(),
+                    SurfaceInstanceData {
+                        world_transform: Matrix4::new_translation(position),
+                        node_handle: *node_handle,
+                        ..Default::default()
+                    },
+                );
+            }
+            storage
+        };
+
+        let prev = make_storage(&[(persistent, Vector3::new(1.0, 0.0, 0.0))]);
+        let curr = make_storage(&[
+            (persistent, Vector3::new(2.0, 0.0, 0.0)),
+            (appeared, Vector3::new(0.0, 3.0, 0.0)),
+        ]);
+
+        let tracker = InstanceMotionTracker::new(&prev, &curr);
+
+        let id = PersistentIdentifier {
+            node_handle: persistent,
+            surface_key: surface.key(),
+            index: 0,
+        };
+        assert_eq!(tracker.persistent.len(), 1);
+        assert_eq!(
+            tracker.persistent.get(&id),
+            Some(&(
+                Matrix4::new_translation(&Vector3::new(1.0, 0.0, 0.0)),
+                Matrix4::new_translation(&Vector3::new(2.0, 0.0, 0.0))
+            ))
+        );
+        assert_eq!(
+            tracker.appeared,
+            vec![PersistentIdentifier {
+                node_handle: appeared,
+                surface_key: surface.key(),
+                index: 0,
+            }]
+        );
+        assert!(tracker.disappeared.is_empty());
+    }
+
+    #[test]
+    fn test_partition_by_tiles() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let material = make_material(PassHint::Opaque);
+        let small = make_surface();
+        let large = make_surface();
+
+        // Spans [0.4; 0.6] on X axis, so it is in the right tile only.
+        storage.push(
+            &small,
+            &material,
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData {
+                world_transform: Matrix4::new_translation(&Vector3::new(0.5, 0.0, 0.0))
+                    * Matrix4::new_scaling(0.2),
+                ..Default::default()
+            },
+        );
+        // Spans [-0.5; 0.5] on X axis, so it is in both tiles.
+        storage.push(
+            &large,
+            &material,
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+
+        let tiles = storage.partition_by_tiles(TileGrid::new(2, 1), Matrix4::identity());
+        assert_eq!(tiles, vec![vec![1], vec![0, 1]]);
+
+        assert!(storage
+            .partition_by_tiles(TileGrid::new(0, 0), Matrix4::identity())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_upload_plan() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let material = make_material(PassHint::Opaque);
+        let small = make_surface();
+        let large = make_surface();
+        let offscreen = make_surface();
+
+        for (surface, scale, offset) in [
+            (&small, 0.2, Vector3::default()),
+            (&large, 1.0, Vector3::default()),
+            (&offscreen, 1.0, Vector3::new(10.0, 0.0, 0.0)),
+        ] {
+            storage.push(
+                surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    world_transform: Matrix4::new_translation(&offset)
+                        * Matrix4::new_scaling(scale),
+                    ..Default::default()
+                },
+            );
+        }
+
+        // Nothing is uploaded yet, so every bundle must be in the plan.
+        let plan = storage.upload_plan(&GeometryCache::default());
+        assert_eq!(
+            plan.uploads
+                .iter()
+                .map(|upload| upload.bundle_index)
+                .collect::<Vec<_>>(),
+            vec![1, 0, 2]
+        );
+        assert_eq!(plan.uploads[0].priority, 0.25);
+        assert_eq!(plan.uploads[2].priority, 0.0);
+        assert!(plan.uploads.iter().all(|upload| upload.size > 0));
+    }
+
+    #[test]
+    fn test_estimate_overdraw() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let transparent = make_material(PassHint::Transparent);
+        let opaque = make_material(PassHint::Opaque);
+        let screen_size = Vector2::new(64, 64);
+
+        assert_eq!(
+            storage.estimate_overdraw(Matrix4::identity(), screen_size),
+            0.0
+        );
+
+        // Two fully overlapping transparent instances, opaque ones must be ignored.
+        for material in [&transparent, &transparent, &opaque] {
+            storage.push(
+                &surface,
+                material,
+                RenderPath::Forward,
+                SortKey::default(),
+                SurfaceInstanceData::default(),
+            );
+        }
+
+        let overdraw = storage.estimate_overdraw(Matrix4::identity(), screen_size);
+        assert!((overdraw - 2.0).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn test_skinning_stats() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        assert_eq!(storage.skinning_stats(), SkinningStats::default());
+
+        let static_surface = make_surface();
+        let skinned_surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        for _ in 0..2 {
+            storage.push(
+                &static_surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData::default(),
+            );
+        }
+        for bone_count in [3, 5] {
+            storage.push(
+                &skinned_surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    bone_matrices: vec![Matrix4::identity(); bone_count],
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert_eq!(
+            storage.skinning_stats(),
+            SkinningStats {
+                skinned_bundles: 1,
+                skinned_instances: 2,
+                bone_matrices: 8,
+            }
+        );
+    }
+
+    #[test]
+    fn test_instance_params() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        let params = [[0.0, 0.0, 0.0, 0.0], [0.25, 1.0, 0.0, 0.5]];
+        for instance_params in params {
+            storage.push(
+                &surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    instance_params,
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert_eq!(storage.bundles.len(), 1);
+        assert_eq!(storage.bundles[0].instances[0].instance_params, params[0]);
+        assert_eq!(storage.bundles[0].instances[1].instance_params, params[1]);
+    }
+
+    #[test]
+    fn test_push_multi_material() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let first = make_material(PassHint::Opaque);
+        let second = make_material(PassHint::Opaque);
+
+        // Cube has 12 triangles, first 6 of them use the first material, the rest - the second.
+        let first_range = ElementRange::Specific {
+            offset: 0,
+            count: 6,
+        };
+        let second_range = ElementRange::Specific {
+            offset: 6,
+            count: 6,
+        };
+
+        storage.push_multi_material(
+            &surface,
+            &[(first_range, first.clone()), (second_range, second.clone())],
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+
+        assert_eq!(storage.bundles.len(), 2);
+        assert_eq!(storage.bundles[0].data, surface);
+        assert_eq!(storage.bundles[0].material, first);
+        assert_eq!(storage.bundles[0].instances[0].element_range, first_range);
+        assert_eq!(storage.bundles[1].data, surface);
+        assert_eq!(storage.bundles[1].material, second);
+        assert_eq!(storage.bundles[1].instances[0].element_range, second_range);
+
+        // Overlapping ranges must be rejected.
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        storage.push_multi_material(
+            &surface,
+            &[(ElementRange::Full, first), (second_range, second.clone())],
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+        assert!(storage.bundles.is_empty());
+
+        // As well as ranges that do not cover the whole surface.
+        storage.push_multi_material(
+            &surface,
+            &[(second_range, second)],
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData::default(),
+        );
+        assert!(storage.bundles.is_empty());
+    }
+
+    #[test]
+    fn test_bundle_builder() {
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        let bundle = RenderDataBundleBuilder::new(surface.clone(), material.clone())
+            .with_sort_index(123)
+            .with_instance(SurfaceInstanceData::default())
+            .build()
+            .unwrap();
+        assert_eq!(bundle.sort_index(), 123);
+        assert_eq!(bundle.render_path, RenderPath::Deferred);
+        assert_eq!(bundle.instances.len(), 1);
+
+        assert_eq!(
+            RenderDataBundleBuilder::new(surface.clone(), material.clone())
+                .build()
+                .unwrap_err(),
+            RenderDataBundleError::NoInstances
+        );
+
+        assert_eq!(
+            RenderDataBundleBuilder::new(surface.clone(), material.clone())
+                .with_skinned(true)
+                .with_instance(SurfaceInstanceData::default())
+                .build()
+                .unwrap_err(),
+            RenderDataBundleError::SkinningMismatch {
+                instance: 0,
+                skinned: true
+            }
+        );
+
+        assert_eq!(
+            RenderDataBundleBuilder::new(surface, material)
+                .with_instance(SurfaceInstanceData::default())
+                .with_instance(SurfaceInstanceData {
+                    bone_matrices: vec![Matrix4::identity()],
+                    ..Default::default()
+                })
+                .build()
+                .unwrap_err(),
+            RenderDataBundleError::SkinningMismatch {
+                instance: 1,
+                skinned: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_origin_rebase() {
+        let eye = Vector3::new(1_000_000.0f64, 10.0, -2_000_000.0);
+        let view = Matrix4::look_at_rh(
+            &Point3::from(eye),
+            &Point3::from(eye - Vector3::z()),
+            &Vector3::y(),
+        );
+        let projection = Matrix4::new_perspective(1.0, 1.0, 0.1, 100.0);
+        // The offset is exactly representable at this magnitude, so the world transform itself
+        // does not introduce any error.
+        let object_position = eye + Vector3::new(1.5, -0.75, -10.0);
+        let local_position = Vector4::new(0.25, 0.5, 0.75, 1.0);
+
+        let reference =
+            projection * view * Matrix4::new_translation(&object_position) * local_position;
+        let reference = reference.xyz() / reference.w;
+
+        let clip_position = |origin_rebase: bool| {
+            let observer_position = ObserverPosition {
+                translation: eye.cast::<f32>(),
+                view_matrix: view.cast::<f32>(),
+                projection_matrix: projection.cast::<f32>(),
+                view_projection_matrix: (projection * view).cast::<f32>(),
+                origin_rebase,
+                ..Default::default()
+            };
+            let mut storage = RenderDataBundleStorage::new_empty(observer_position);
+            storage.push(
+                &make_surface(),
+                &make_material(PassHint::Opaque),
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    world_transform: Matrix4::new_translation(&object_position.cast::<f32>()),
+                    ..Default::default()
+                },
+            );
+
+            let world_transform = storage.bundles[0].instances[0].world_transform;
+            let position = storage.observer_position.instance_view_projection_matrix()
+                * world_transform
+                * local_position.cast::<f32>();
+            (position.xyz() / position.w).cast::<f64>()
+        };
+
+        let plain_error = (clip_position(false) - reference).norm();
+        let rebased_error = (clip_position(true) - reference).norm();
+
+        assert!(rebased_error < 1.0e-4);
+        assert!(rebased_error <= plain_error);
+    }
+
+    #[test]
+    fn test_mirrored_instances() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        for world_transform in [
+            Matrix4::identity(),
+            Matrix4::new_nonuniform_scaling(&Vector3::new(-1.0, 1.0, 1.0)),
+            // Mirroring along two axes is a rotation, so the winding is not flipped.
+            Matrix4::new_nonuniform_scaling(&Vector3::new(-1.0, -1.0, 1.0)),
+        ] {
+            storage.push(
+                &surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    world_transform,
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert_eq!(storage.bundles.len(), 2);
+        assert!(!storage.bundles[0].flip_winding);
+        assert_eq!(storage.bundles[0].instances.len(), 2);
+        assert!(storage.bundles[1].flip_winding);
+        assert_eq!(storage.bundles[1].instances.len(), 1);
+        assert!(storage.bundles[1].instances[0].is_mirrored());
+    }
+
+    #[test]
+    fn test_stencil_state() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        let stencil = |ref_value| {
+            Some(StencilState {
+                func: StencilFunc {
+                    func: CompareFunc::Equal,
+                    ref_value,
+                    mask: 0xFF,
+                },
+                op: Default::default(),
+            })
+        };
+
+        for stencil in [None, stencil(1), stencil(2), stencil(1)] {
+            storage.push(
+                &surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    stencil,
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert_eq!(storage.bundles.len(), 3);
+        assert_eq!(storage.bundles[0].stencil, None);
+        assert_eq!(storage.bundles[1].stencil, stencil(1));
+        assert_eq!(storage.bundles[1].instances.len(), 2);
+        assert_eq!(storage.bundles[2].stencil, stencil(2));
+    }
+
+    #[test]
+    fn test_polygon_offset() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        let offset = |units| {
+            Some(PolygonOffset {
+                factor: -1.0,
+                units,
+            })
+        };
+
+        for polygon_offset in [offset(-1.0), offset(-2.0), offset(-1.0)] {
+            storage.push(
+                &surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    polygon_offset,
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert_eq!(storage.bundles.len(), 2);
+        assert_eq!(storage.bundles[0].polygon_offset, offset(-1.0));
+        assert_eq!(storage.bundles[0].instances.len(), 2);
+        assert_eq!(storage.bundles[1].polygon_offset, offset(-2.0));
+        assert_eq!(storage.bundles[1].instances.len(), 1);
+    }
+
+    #[test]
+    fn test_cull_override() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        for cull_override in [None, Some(CullMode::TwoSided), None] {
+            storage.push(
+                &surface,
+                &material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    cull_override,
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert_eq!(storage.bundles.len(), 2);
+        assert_eq!(storage.bundles[0].cull_override, None);
+        assert_eq!(storage.bundles[0].instances.len(), 2);
+        assert_eq!(storage.bundles[1].cull_override, Some(CullMode::TwoSided));
+        assert_eq!(storage.bundles[1].instances.len(), 1);
+        assert_eq!(CullMode::TwoSided.cull_face(), None);
+        assert_eq!(
+            CullMode::Cull(CullFace::Front).cull_face(),
+            Some(CullFace::Front)
+        );
+    }
+
+    #[test]
+    fn test_depth_state() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let surface = make_surface();
+        let material = make_material(PassHint::Opaque);
+
+        let x_ray = Some(DepthState {
+            test: None,
+            write: false,
+        });
+        for depth_state in [None, x_ray, None, Some(DepthState::default())] {
+            storage.push(
+                &surface,
+                &material,
+                RenderPath::Forward,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    depth_state,
+                    ..Default::default()
+                },
+            );
+        }
+
+        assert_eq!(storage.bundles.len(), 3);
+        assert_eq!(storage.bundles[0].depth_state, None);
+        assert_eq!(storage.bundles[0].instances.len(), 2);
+        assert_eq!(storage.bundles[1].depth_state, x_ray);
+        assert_eq!(storage.bundles[1].instances.len(), 1);
+        // An explicit standard state is still a separate bundle, because it overrides the state of
+        // the material.
+        assert_eq!(storage.bundles[2].depth_state, Some(DepthState::default()));
+    }
+
+    #[test]
+    fn test_push_2d() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let material = make_material(PassHint::Transparent);
+        let front = make_surface();
+        let back = make_surface();
+        let middle = make_surface();
+
+        storage.push_2d(&front, &material, 2.5, SurfaceInstanceData::default());
+        storage.push_2d(&back, &material, -1.0, SurfaceInstanceData::default());
+        storage.push_2d(&middle, &material, 0.0, SurfaceInstanceData::default());
+        storage.sort();
+
+        assert_eq!(storage.bundles.len(), 3);
+        assert_eq!(storage.bundles[0].data, back);
+        assert_eq!(storage.bundles[1].data, middle);
+        assert_eq!(storage.bundles[2].data, front);
+        assert!(storage
+            .bundles
+            .iter()
+            .all(|bundle| bundle.render_path == RenderPath::Forward));
+
+        // Stable bucketing.
+        assert_eq!(
+            SortKey::depth_bucket_from_paint_z(0.1 + 0.2),
+            SortKey::depth_bucket_from_paint_z(0.3)
+        );
+        assert_eq!(
+            SortKey::depth_bucket_from_paint_z(-0.0),
+            SortKey::depth_bucket_from_paint_z(0.0)
+        );
+        assert!(
+            SortKey::depth_bucket_from_paint_z(-0.001) < SortKey::depth_bucket_from_paint_z(0.0)
+        );
+        assert!(
+            SortKey::depth_bucket_from_paint_z(0.0) < SortKey::depth_bucket_from_paint_z(0.001)
+        );
+        assert_eq!(
+            SortKey::depth_bucket_from_paint_z(f32::NAN),
+            SortKey::depth_bucket_from_paint_z(0.0)
+        );
+        assert_eq!(SortKey::depth_bucket_from_paint_z(f32::MAX), u32::MAX);
+        assert_eq!(SortKey::depth_bucket_from_paint_z(-f32::MAX), 0);
+    }
+
+    #[test]
+    fn test_instance_layout() {
+        let instance = SurfaceInstanceData {
+            world_transform: Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0)),
+            blend_shapes_weights: vec![0.5, 0.25],
+            animation_phase: 0.75,
+            instance_params: [1.0, 2.0, 3.0, 4.0],
+            ..Default::default()
+        };
+        let view_projection = Matrix4::new_scaling(2.0);
+
+        // The default layout must match the packing of the built-in instance block.
+        let mut packed_blend_shape_weights =
+            [Vector4::<f32>::default(); ShaderDefinition::MAX_BLEND_SHAPE_WEIGHT_GROUPS];
+        packed_blend_shape_weights[0] = Vector4::new(0.5, 0.25, 0.0, 0.0);
+        let expected = StaticUniformBuffer::<1024>::new()
+            .with(&instance.world_transform)
+            .with(&(view_projection * instance.world_transform))
+            .with(&2i32)
+            .with(&false)
+            .with_slice_with_max_size(
+                &packed_blend_shape_weights,
+                ShaderDefinition::MAX_BLEND_SHAPE_WEIGHT_GROUPS,
+            )
+            .with(&0.75f32)
+            .with(&Vector4::new(1.0, 2.0, 3.0, 4.0))
+            .finish();
+        let mut buffer = StaticUniformBuffer::<1024>::new();
+        InstanceLayout::default().write(&instance, &view_projection, &mut buffer);
+        assert_eq!(buffer.finish().as_slice(), expected.as_slice());
+
+        // A custom subset in a custom order.
+        let layout =
+            InstanceLayout::from_names(&["animationPhase", "instanceParams", "worldMatrix"])
+                .unwrap();
+        assert_eq!(
+            layout.attributes(),
+            &[
+                InstanceAttribute::AnimationPhase,
+                InstanceAttribute::InstanceParams,
+                InstanceAttribute::WorldMatrix
+            ]
+        );
+        let mut buffer = StaticUniformBuffer::<1024>::new();
+        layout.write(&instance, &view_projection, &mut buffer);
+        let bytes = buffer.finish();
+        let floats = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_ne_bytes(chunk.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        // float at 0, padding up to 16 bytes (vec4 alignment), vec4 at 16, mat4 at 32.
+        assert_eq!(bytes.len(), 96);
+        assert_eq!(floats[0], 0.75);
+        assert_eq!(&floats[4..8], &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(&floats[8..24], instance.world_transform.as_slice());
+
+        assert_eq!(
+            InstanceLayout::from_names(&["worldMatrix", "color"]),
+            Err(InstanceLayoutError::UnknownAttribute("color".to_owned()))
+        );
+        assert_eq!(
+            InstanceLayout::new(vec![
+                InstanceAttribute::WorldMatrix,
+                InstanceAttribute::WorldMatrix
+            ]),
+            Err(InstanceLayoutError::DuplicateAttribute(
+                InstanceAttribute::WorldMatrix
+            ))
+        );
+        assert_eq!(InstanceLayout::new(vec![]), Err(InstanceLayoutError::Empty));
+
+        let bundle = RenderDataBundleBuilder::new(make_surface(), make_material(PassHint::Opaque))
+            .with_instance(SurfaceInstanceData::default())
+            .with_instance_layout(layout.clone())
+            .build()
+            .unwrap();
+        assert_eq!(bundle.instance_layout, Some(layout));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut graph = Graph::new();
+        let surface = make_surface();
+        for i in 0..3 {
+            make_mesh(&mut graph, Vector3::new(i as f32, 0.0, 0.0), &surface);
+        }
+        make_mesh(&mut graph, Vector3::default(), &make_surface());
+        graph.update_hierarchical_data();
+
+        let storage = RenderDataBundleStorage::from_graph(
+            &graph,
+            BitMask::all(),
+            0.0,
+            &make_observer_position(),
+            ImmutableString::new("GBuffer"),
+            RenderDataBundleStorageOptions::default(),
+            &mut DynamicSurfaceCache::new(),
+        );
+        assert!(!storage.bundles.is_empty());
+
+        let snapshot = storage.snapshot();
+        let (bundle_count, instance_count, instance_counts) = std::thread::spawn(move || {
+            (
+                snapshot.bundles.len(),
+                snapshot.instance_count(),
+                snapshot
+                    .bundles
+                    .iter()
+                    .map(|bundle| bundle.instances.len())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(bundle_count, storage.bundles.len());
+        assert_eq!(instance_count, 4);
+        assert_eq!(
+            instance_counts,
+            storage
+                .bundles
+                .iter()
+                .map(|bundle| bundle.instances.len())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_export_geometry() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let first_surface = make_surface();
+        let second_surface = make_surface();
+        let first_material = make_material(PassHint::Opaque);
+        let second_material = make_material(PassHint::Opaque);
+
+        for x in [0.0, 10.0] {
+            storage.push(
+                &first_surface,
+                &first_material,
+                RenderPath::Deferred,
+                SortKey::default(),
+                SurfaceInstanceData {
+                    world_transform: Matrix4::new_translation(&Vector3::new(x, 0.0, 0.0)),
+                    ..Default::default()
+                },
+            );
+        }
+        storage.push(
+            &second_surface,
+            &second_material,
+            RenderPath::Deferred,
+            SortKey::default(),
+            SurfaceInstanceData {
+                element_range: ElementRange::Specific {
+                    offset: 0,
+                    count: 2,
+                },
+                ..Default::default()
+            },
+        );
+        assert_eq!(storage.bundles.len(), 2);
+
+        let (vertex_count, triangle_count, range_vertex_count) = {
+            let data = first_surface.data_ref();
+            let mut range_vertices = data.geometry_buffer.triangles_ref()[0..2]
+                .iter()
+                .flat_map(|triangle| triangle.0)
+                .collect::<Vec<_>>();
+            range_vertices.sort_unstable();
+            range_vertices.dedup();
+            (
+                data.vertex_buffer.vertex_count() as usize,
+                data.geometry_buffer.len(),
+                range_vertices.len(),
+            )
+        };
+        let expected_vertices = 2 * vertex_count + range_vertex_count;
+        let expected_triangles = 2 * triangle_count + 2;
+
+        let obj = String::from_utf8(storage.export_geometry(ExportFormat::Obj).unwrap()).unwrap();
+        assert_eq!(
+            obj.lines().filter(|line| line.starts_with("v ")).count(),
+            expected_vertices
+        );
+        assert_eq!(
+            obj.lines().filter(|line| line.starts_with("f ")).count(),
+            expected_triangles
+        );
+        assert_eq!(obj.lines().filter(|line| line.starts_with("g ")).count(), 2);
+        // The second instance of the cube is moved by its world transform.
+        assert!(obj.lines().any(|line| line.starts_with("v 10.5 ")));
+
+        let gltf =
+            gltf::Gltf::from_slice(&storage.export_geometry(ExportFormat::Gltf).unwrap()).unwrap();
+        assert_eq!(gltf.meshes().count(), 2);
+        assert_eq!(gltf.materials().count(), 2);
+        let (vertices, indices) = gltf.meshes().flat_map(|mesh| mesh.primitives()).fold(
+            (0, 0),
+            |(vertices, indices), primitive| {
+                (
+                    vertices + primitive.get(&gltf::Semantic::Positions).unwrap().count(),
+                    indices + primitive.indices().unwrap().count(),
+                )
+            },
+        );
+        assert_eq!(vertices, expected_vertices);
+        assert_eq!(indices, 3 * expected_triangles);
+
+        storage.bundles[1].instances[0].element_range = ElementRange::Specific {
+            offset: triangle_count,
+            count: 1,
+        };
+        assert_eq!(
+            storage.export_geometry(ExportFormat::Obj),
+            Err(ExportError::InvalidElementRange {
+                bundle: 1,
+                instance: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_push_decal() {
+        let mut storage = RenderDataBundleStorage::new_empty(make_observer_position());
+        let texture =
+            TextureResource::new_ok(Uuid::new_v4(), ResourceKind::Embedded, Texture::default());
+        let material = DecalMaterial {
+            diffuse_texture: Some(texture.clone()),
+            normal_texture: None,
+        };
+        let other_material = DecalMaterial {
+            diffuse_texture: None,
+            normal_texture: Some(texture),
+        };
+
+        let transform = Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0));
+        storage.push_decal(&material, transform, Color::WHITE, 1, 5);
+        storage.push_decal(&other_material, transform, Color::WHITE, 0, 0);
+        storage.push_decal(&material, Matrix4::identity(), Color::RED, 0, 7);
+        storage.push_decal(&material.clone(), Matrix4::identity(), Color::RED, 1, 2);
+
+        assert_eq!(storage.decal_batches.len(), 2);
+        assert_eq!(storage.decal_batches[0].instances.len(), 3);
+        assert_eq!(
+            storage.decal_batches[0].instances[0].inv_projector_transform,
+            transform.try_inverse().unwrap()
+        );
+
+        storage.sort();
+
+        // Both batches start at layer 0, the tie is resolved by the sorting index.
+        assert_eq!(storage.decal_batches[0].material, other_material);
+        assert_eq!(
+            storage.decal_batches[1]
+                .instances
+                .iter()
+                .map(|instance| (instance.layer, instance.sort_index))
+                .collect::<Vec<_>>(),
+            vec![(0, 7), (1, 2), (1, 5)]
+        );
+
+        // Sorting must keep the decals coalescing.
+        storage.push_decal(&other_material, transform, Color::WHITE, 2, 0);
+        assert_eq!(storage.decal_batches.len(), 2);
+        assert_eq!(storage.decal_batches[0].instances.len(), 2);
+    }
+
+    #[test]
+    fn test_sort_key_packing() {
+        let key = SortKey::new(0x12, 0x3456, 0x789ABCDE, 0xF0);
+        assert_eq!(key.pack(), 0x12_3456_789ABCDE_F0);
+        assert_eq!(SortKey::unpack(key.pack()), key);
+
+        // Plain sorting indices must survive the round trip.
+        for index in [0, 1, u64::MAX / 2, u64::MAX] {
+            assert_eq!(SortKey::from(index).pack(), index);
+        }
+    }
+
+    #[test]
+    fn test_sort_key_ordering() {
+        let base = SortKey::new(1, 1, 1, 1);
+
+        // Every field must take precedence over all the less significant fields.
+        let keys = [
+            base.with_pass_priority(2).with_material_group(0),
+            base.with_material_group(2).with_depth_bucket(0),
+            base.with_depth_bucket(2).with_tie_break(0),
+            base.with_tie_break(2),
+        ];
+        for key in keys {
+            assert!(key > base);
+            assert!(key.pack() > base.pack());
+        }
+        for pair in keys.windows(2) {
+            assert!(pair[0].pack() > pair[1].pack());
+        }
+
+        assert!(SortKey::depth_bucket_from_view_z(-1.0) < SortKey::depth_bucket_from_view_z(1.0));
+        assert_eq!(SortKey::depth_bucket_from_view_z(f32::MAX), u32::MAX);
+        assert_eq!(SortKey::depth_bucket_from_view_z(-f32::MAX), 0);
+    }
 
     //noinspection ALL
     #[test]
@@ -1250,6 +7973,8 @@ mod test {
             view_matrix: Matrix4::identity(),
             projection_matrix: Matrix4::identity(),
             view_projection_matrix: Matrix4::identity(),
+            origin_rebase: false,
+            cull_padding: 0.0,
         };
 
         let render_context = RenderContext {
@@ -1261,6 +7986,7 @@ mod test {
             graph: &Default::default(),
             render_pass_name: &Default::default(),
             dynamic_surface_cache: &mut Default::default(),
+            instance_filter: None,
         };
 
         let center = u64::MAX / 2;
@@ -102,6 +102,28 @@ impl GeometryCache {
         }
     }
 
+    /// Returns the amount of bytes, that will be uploaded to GPU on next [`Self::get`] call for the
+    /// given surface data. It is zero, if the GPU buffer of the data is up-to-date.
+    pub fn pending_upload_size(&self, data: &SurfaceData) -> usize {
+        let vertices_size = data.vertex_buffer.raw_data().len();
+        let triangles_size = std::mem::size_of_val(data.geometry_buffer.triangles_ref());
+
+        match self.buffer.buffer.get(&data.cache_index) {
+            Some(entry) if entry.layout_hash == data.vertex_buffer.layout_hash() => {
+                let mut size = 0;
+                if data.vertex_buffer.modifications_count() != entry.vertex_modifications_count {
+                    size += vertices_size;
+                }
+                if data.geometry_buffer.modifications_count() != entry.triangles_modifications_count
+                {
+                    size += triangles_size;
+                }
+                size
+            }
+            _ => vertices_size + triangles_size,
+        }
+    }
+
     pub fn update(&mut self, dt: f32) {
         self.buffer.update(dt);
     }
@@ -23,6 +23,8 @@
 use crate::{
     asset::entry::DEFAULT_RESOURCE_LIFETIME,
     core::sparse::{AtomicIndex, SparseBuffer},
+    material::{Material, MaterialResource},
+    resource::texture::TextureResource,
     scene::mesh::{
         buffer::{BytesStorage, TriangleBuffer, VertexAttributeDescriptor, VertexBuffer},
         surface::{SurfaceData, SurfaceResource},
@@ -224,6 +226,8 @@ impl<T> TemporaryCache<T> {
 #[derive(Default)]
 pub struct DynamicSurfaceCache {
     cache: FxHashMap<u64, SurfaceResource>,
+    sprite_materials: FxHashMap<u64, MaterialResource>,
+    debug_line_material: Option<MaterialResource>,
 }
 
 impl DynamicSurfaceCache {
@@ -267,6 +271,36 @@ impl DynamicSurfaceCache {
         }
     }
 
+    /// Tries to get an existing standard 2D material that uses the given texture as the diffuse
+    /// texture or creates a new one and returns it. Such materials are shared across frames, so
+    /// the sprite batches that use them could reuse the same dynamic surfaces.
+    pub fn get_or_create_sprite_material(&mut self, texture: &TextureResource) -> MaterialResource {
+        self.sprite_materials
+            .entry(texture.key())
+            .or_insert_with(|| {
+                let mut material = Material::standard_2d();
+                material.bind("diffuseTexture", texture.clone());
+                MaterialResource::new_ok(Uuid::new_v4(), ResourceKind::Embedded, material)
+            })
+            .clone()
+    }
+
+    /// Tries to get an existing material for debug lines or creates a new one and returns it. The
+    /// material uses the standard sprite shader, which is unlit and multiplies the texture by the
+    /// vertex color. The material is shared across frames, so all the debug lines could be merged
+    /// into a single batch.
+    pub fn get_or_create_debug_line_material(&mut self) -> MaterialResource {
+        self.debug_line_material
+            .get_or_insert_with(|| {
+                MaterialResource::new_ok(
+                    Uuid::new_v4(),
+                    ResourceKind::Embedded,
+                    Material::standard_sprite(),
+                )
+            })
+            .clone()
+    }
+
     /// Clears the surfaces in the cache, does **not** clear the cache itself.
     pub fn clear(&mut self) {
         for surface in self.cache.values_mut() {
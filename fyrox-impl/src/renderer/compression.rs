@@ -0,0 +1,509 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Vertex data compression, that reduces the amount of data uploaded to GPU. See
+//! [`GeometryCompression`] docs for more info.
+
+use crate::{
+    core::{
+        algebra::{Vector2, Vector3, Vector4},
+        byteorder::{ByteOrder, LittleEndian},
+    },
+    scene::mesh::buffer::{
+        VertexAttribute, VertexAttributeDataType, VertexAttributeDescriptor, VertexAttributeUsage,
+        VertexBuffer,
+    },
+};
+use std::fmt::{Display, Formatter};
+
+/// Encoding of a vertex attribute.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AttributeEncoding {
+    /// Every component is quantized to a 16-bit unsigned normalized integer relative to the range
+    /// of the component values (for positions it is the bounding box of the mesh). Supported for
+    /// any attribute with 32-bit floating-point components: positions, texture coordinates, etc.
+    /// The decoded value is `offset + scale * fetched`, where `fetched` is the normalized value in
+    /// `[0; 1]` range.
+    ///
+    /// The decoding restores every component within the range with an error of at most half of the
+    /// quantization step (`(max - min) / 131070`) plus the rounding error of the floating-point
+    /// decoding formula. The lower bound of the range and components with a single unique value are
+    /// restored exactly, the upper bound is restored up to the rounding error of `min + (max - min)`.
+    /// The encoding is lossy, it is not exactly reversible. It halves the size of the attribute.
+    Quantized16,
+    /// A unit vector is encoded using octahedral mapping into two 16-bit unsigned normalized
+    /// integers. Supported only for attributes with three 32-bit floating-point components, that
+    /// are directions: normals and tangents without handedness (tangents of the standard vertex
+    /// formats have four components and cannot be encoded). The vectors are normalized before
+    /// encoding. The decoded value is the octahedral decoding of `offset.xy + scale.xy * fetched`,
+    /// where `fetched` is the normalized value in `[0; 1]` range. The error of the decoded
+    /// direction is below `1e-4`. It reduces the size of the attribute three times.
+    Octahedral16,
+}
+
+/// Compression settings of vertex data. Attributes, that have no encoding in the settings, are
+/// stored as is.
+///
+/// Compressed data cannot be rendered by the standard shaders, a shader must decode every encoded
+/// attribute using the decode descriptors of [`CompressedGeometry`] (see [`AttributeEncoding`]
+/// docs for decoding formulas).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GeometryCompression {
+    /// Encodings of vertex attributes.
+    pub encodings: Vec<(VertexAttributeUsage, AttributeEncoding)>,
+}
+
+/// Decode descriptor of an encoded vertex attribute. It must be passed to shaders, that read
+/// compressed vertex data. See [`AttributeEncoding`] docs for decoding formulas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AttributeDecodeDescriptor {
+    /// Usage of the attribute.
+    pub usage: VertexAttributeUsage,
+    /// Encoding of the attribute.
+    pub encoding: AttributeEncoding,
+    /// Amount of components of the decoded value.
+    pub size: u8,
+    /// Offset of the fetched value.
+    pub offset: Vector4<f32>,
+    /// Scale of the fetched value.
+    pub scale: Vector4<f32>,
+}
+
+/// Compressed vertex data along with its layout and decode descriptors.
+#[derive(Clone, Debug, Default)]
+pub struct CompressedGeometry {
+    /// Layout of the compressed vertices.
+    pub layout: Vec<VertexAttributeDescriptor>,
+    /// Size of a compressed vertex in bytes.
+    pub vertex_size: usize,
+    /// Amount of the vertices.
+    pub vertex_count: usize,
+    /// Compressed vertices.
+    pub data: Vec<u8>,
+    /// Decode descriptors of the encoded attributes.
+    pub decode: Vec<AttributeDecodeDescriptor>,
+}
+
+/// An error that may occur during vertex data compression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeometryCompressionError {
+    /// There is no attribute with the given usage in the vertex buffer.
+    NoSuchAttribute(VertexAttributeUsage),
+    /// The attribute cannot be encoded using the given encoding.
+    UnsupportedEncoding {
+        /// Usage of the attribute.
+        usage: VertexAttributeUsage,
+        /// Requested encoding.
+        encoding: AttributeEncoding,
+    },
+}
+
+impl Display for GeometryCompressionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoSuchAttribute(usage) => {
+                write!(f, "There is no {usage:?} attribute in the vertex buffer.")
+            }
+            Self::UnsupportedEncoding { usage, encoding } => {
+                write!(
+                    f,
+                    "{usage:?} attribute cannot be encoded using {encoding:?}."
+                )
+            }
+        }
+    }
+}
+
+fn octahedral_encode(direction: Vector3<f32>) -> Vector2<f32> {
+    let sign = |value: f32| if value >= 0.0 { 1.0 } else { -1.0 };
+    let direction = direction
+        .try_normalize(f32::EPSILON)
+        .unwrap_or(Vector3::z());
+    let n = direction / (direction.x.abs() + direction.y.abs() + direction.z.abs());
+    if n.z >= 0.0 {
+        n.xy()
+    } else {
+        Vector2::new((1.0 - n.y.abs()) * sign(n.x), (1.0 - n.x.abs()) * sign(n.y))
+    }
+}
+
+fn octahedral_decode(encoded: Vector2<f32>) -> Vector3<f32> {
+    let mut n = Vector3::new(
+        encoded.x,
+        encoded.y,
+        1.0 - encoded.x.abs() - encoded.y.abs(),
+    );
+    let t = (-n.z).max(0.0);
+    n.x += if n.x >= 0.0 { -t } else { t };
+    n.y += if n.y >= 0.0 { -t } else { t };
+    n.normalize()
+}
+
+fn quantize(value: f32) -> u16 {
+    (value.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+}
+
+fn dequantize(value: u16) -> f32 {
+    value as f32 / u16::MAX as f32
+}
+
+fn read_f32_components(
+    data: &[u8],
+    vertex_size: usize,
+    attribute: &VertexAttribute,
+    vertex: usize,
+) -> Vector4<f32> {
+    let mut value = Vector4::default();
+    let begin = vertex * vertex_size + attribute.offset as usize;
+    for (i, component) in value.iter_mut().take(attribute.size as usize).enumerate() {
+        *component = LittleEndian::read_f32(&data[begin + i * 4..]);
+    }
+    value
+}
+
+impl GeometryCompression {
+    /// Sets the encoding of the attribute with the given usage.
+    pub fn with_encoding(
+        mut self,
+        usage: VertexAttributeUsage,
+        encoding: AttributeEncoding,
+    ) -> Self {
+        self.encodings.retain(|(existing, _)| *existing != usage);
+        self.encodings.push((usage, encoding));
+        self
+    }
+
+    /// Returns the encoding of the attribute with the given usage.
+    pub fn encoding(&self, usage: VertexAttributeUsage) -> Option<AttributeEncoding> {
+        self.encodings
+            .iter()
+            .find_map(|(existing, encoding)| (*existing == usage).then_some(*encoding))
+    }
+
+    /// Compresses the given vertex buffer. The order of attributes and their shader locations stay
+    /// the same.
+    pub fn compress(
+        &self,
+        vertex_buffer: &VertexBuffer,
+    ) -> Result<CompressedGeometry, GeometryCompressionError> {
+        for &(usage, encoding) in self.encodings.iter() {
+            let attribute = vertex_buffer
+                .layout()
+                .iter()
+                .find(|attribute| attribute.usage == usage)
+                .ok_or(GeometryCompressionError::NoSuchAttribute(usage))?;
+
+            let supported = attribute.data_type == VertexAttributeDataType::F32
+                && match encoding {
+                    AttributeEncoding::Quantized16 => true,
+                    AttributeEncoding::Octahedral16 => attribute.size == 3,
+                };
+            if !supported {
+                return Err(GeometryCompressionError::UnsupportedEncoding { usage, encoding });
+            }
+        }
+
+        let mut compressed = CompressedGeometry {
+            vertex_count: vertex_buffer.vertex_count() as usize,
+            ..Default::default()
+        };
+
+        let mut offsets = Vec::new();
+        for attribute in vertex_buffer.layout() {
+            let (data_type, size, normalized) = match self.encoding(attribute.usage) {
+                None => (attribute.data_type, attribute.size, attribute.normalized),
+                Some(AttributeEncoding::Quantized16) => {
+                    (VertexAttributeDataType::U16, attribute.size, true)
+                }
+                Some(AttributeEncoding::Octahedral16) => (VertexAttributeDataType::U16, 2, true),
+            };
+            compressed.layout.push(VertexAttributeDescriptor {
+                usage: attribute.usage,
+                data_type,
+                size,
+                divisor: attribute.divisor,
+                shader_location: attribute.shader_location,
+                normalized,
+            });
+            offsets.push(compressed.vertex_size);
+            compressed.vertex_size += data_type.size() as usize * size as usize;
+        }
+
+        let source = vertex_buffer.raw_data();
+        let source_vertex_size = vertex_buffer.vertex_size() as usize;
+        compressed.data = vec![0; compressed.vertex_size * compressed.vertex_count];
+
+        let vertex_size = compressed.vertex_size;
+        for (attribute, offset) in vertex_buffer.layout().iter().zip(offsets) {
+            let destination = |vertex: usize| vertex * vertex_size + offset;
+
+            match self.encoding(attribute.usage) {
+                None => {
+                    let size = attribute.data_type.size() as usize * attribute.size as usize;
+                    for vertex in 0..compressed.vertex_count {
+                        let begin = vertex * source_vertex_size + attribute.offset as usize;
+                        let destination = destination(vertex);
+                        compressed.data[destination..destination + size]
+                            .copy_from_slice(&source[begin..begin + size]);
+                    }
+                }
+                Some(AttributeEncoding::Quantized16) => {
+                    let size = attribute.size as usize;
+                    let mut min = Vector4::repeat(f32::MAX);
+                    let mut max = Vector4::repeat(-f32::MAX);
+                    for vertex in 0..compressed.vertex_count {
+                        let value =
+                            read_f32_components(source, source_vertex_size, attribute, vertex);
+                        min = min.inf(&value);
+                        max = max.sup(&value);
+                    }
+                    if compressed.vertex_count == 0 {
+                        min = Vector4::default();
+                        max = Vector4::default();
+                    }
+                    let range = max - min;
+
+                    for vertex in 0..compressed.vertex_count {
+                        let value =
+                            read_f32_components(source, source_vertex_size, attribute, vertex);
+                        let destination = destination(vertex);
+                        let normalized = (value - min).zip_map(&range, |delta, range| {
+                            if range > 0.0 {
+                                delta / range
+                            } else {
+                                0.0
+                            }
+                        });
+                        for (i, component) in normalized.iter().take(size).enumerate() {
+                            LittleEndian::write_u16(
+                                &mut compressed.data[destination + i * 2..],
+                                quantize(*component),
+                            );
+                        }
+                    }
+
+                    compressed.decode.push(AttributeDecodeDescriptor {
+                        usage: attribute.usage,
+                        encoding: AttributeEncoding::Quantized16,
+                        size: attribute.size,
+                        offset: min,
+                        scale: range,
+                    });
+                }
+                Some(AttributeEncoding::Octahedral16) => {
+                    for vertex in 0..compressed.vertex_count {
+                        let value =
+                            read_f32_components(source, source_vertex_size, attribute, vertex);
+                        let encoded = octahedral_encode(value.xyz());
+                        let destination = destination(vertex);
+                        for (i, component) in encoded.iter().enumerate() {
+                            LittleEndian::write_u16(
+                                &mut compressed.data[destination + i * 2..],
+                                quantize(component * 0.5 + 0.5),
+                            );
+                        }
+                    }
+
+                    compressed.decode.push(AttributeDecodeDescriptor {
+                        usage: attribute.usage,
+                        encoding: AttributeEncoding::Octahedral16,
+                        size: 3,
+                        offset: Vector4::new(-1.0, -1.0, 0.0, 0.0),
+                        scale: Vector4::new(2.0, 2.0, 0.0, 0.0),
+                    });
+                }
+            }
+        }
+
+        Ok(compressed)
+    }
+}
+
+impl CompressedGeometry {
+    /// Decodes the encoded attribute with the given usage on CPU, the same way as shaders do it.
+    /// Returns [`None`] if there is no such encoded attribute. Components, that are not used by
+    /// the attribute, are zero.
+    pub fn decode_attribute(&self, usage: VertexAttributeUsage) -> Option<Vec<Vector4<f32>>> {
+        let descriptor = self
+            .decode
+            .iter()
+            .find(|descriptor| descriptor.usage == usage)?;
+
+        let mut offset = 0;
+        let mut layout_attribute = None;
+        for attribute in self.layout.iter() {
+            if attribute.usage == usage {
+                layout_attribute = Some(attribute);
+                break;
+            }
+            offset += attribute.data_type.size() as usize * attribute.size as usize;
+        }
+        let attribute = layout_attribute?;
+
+        let values = (0..self.vertex_count)
+            .map(|vertex| {
+                let begin = vertex * self.vertex_size + offset;
+                let mut fetched = Vector4::default();
+                for (i, component) in fetched.iter_mut().take(attribute.size as usize).enumerate() {
+                    *component = dequantize(LittleEndian::read_u16(&self.data[begin + i * 2..]));
+                }
+                let value = descriptor.offset + descriptor.scale.component_mul(&fetched);
+                match descriptor.encoding {
+                    AttributeEncoding::Quantized16 => value,
+                    AttributeEncoding::Octahedral16 => octahedral_decode(value.xy()).push(0.0),
+                }
+            })
+            .collect();
+
+        Some(values)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{Matrix4, Vector3, Vector4},
+        renderer::compression::{AttributeEncoding, GeometryCompression, GeometryCompressionError},
+        scene::mesh::{
+            buffer::{VertexAttributeUsage, VertexReadTrait},
+            surface::SurfaceData,
+        },
+    };
+
+    #[test]
+    fn test_geometry_compression_round_trip() {
+        let data = SurfaceData::make_sphere(
+            16,
+            16,
+            3.0,
+            &Matrix4::new_translation(&Vector3::new(10.0, -5.0, 2.0)),
+        );
+        let vertex_buffer = &data.vertex_buffer;
+
+        let compression = GeometryCompression::default()
+            .with_encoding(
+                VertexAttributeUsage::Position,
+                AttributeEncoding::Quantized16,
+            )
+            .with_encoding(
+                VertexAttributeUsage::Normal,
+                AttributeEncoding::Octahedral16,
+            )
+            .with_encoding(
+                VertexAttributeUsage::TexCoord0,
+                AttributeEncoding::Quantized16,
+            );
+        let compressed = compression.compress(vertex_buffer).unwrap();
+
+        assert_eq!(
+            compressed.vertex_count,
+            vertex_buffer.vertex_count() as usize
+        );
+        assert_eq!(compressed.layout.len(), vertex_buffer.layout().len());
+        assert!(compressed.data.len() < vertex_buffer.raw_data().len());
+
+        let positions = compressed
+            .decode_attribute(VertexAttributeUsage::Position)
+            .unwrap();
+        // 6 units range quantized with 16 bits.
+        let position_tolerance = 6.0 / u16::MAX as f32;
+        for (vertex, decoded) in vertex_buffer.iter().zip(positions) {
+            let position = vertex.read_3_f32(VertexAttributeUsage::Position).unwrap();
+            assert!((position - decoded.xyz()).amax() <= position_tolerance);
+            assert_eq!(decoded.w, 0.0);
+        }
+
+        let normals = compressed
+            .decode_attribute(VertexAttributeUsage::Normal)
+            .unwrap();
+        for (vertex, decoded) in vertex_buffer.iter().zip(normals) {
+            let normal = vertex
+                .read_3_f32(VertexAttributeUsage::Normal)
+                .unwrap()
+                .normalize();
+            assert!((normal - decoded.xyz()).norm() < 1.0e-4);
+        }
+
+        let tex_coords = compressed
+            .decode_attribute(VertexAttributeUsage::TexCoord0)
+            .unwrap();
+        for (vertex, decoded) in vertex_buffer.iter().zip(tex_coords) {
+            let tex_coord = vertex.read_2_f32(VertexAttributeUsage::TexCoord0).unwrap();
+            assert!((tex_coord - decoded.xy()).amax() <= 1.0 / u16::MAX as f32);
+        }
+
+        // Attributes without encoding are not decoded.
+        assert!(compressed
+            .decode_attribute(VertexAttributeUsage::Tangent)
+            .is_none());
+    }
+
+    #[test]
+    fn test_geometry_compression_exact_bounds() {
+        let data = SurfaceData::make_cube(Matrix4::new_scaling(2.0));
+        let compressed = GeometryCompression::default()
+            .with_encoding(
+                VertexAttributeUsage::Position,
+                AttributeEncoding::Quantized16,
+            )
+            .compress(&data.vertex_buffer)
+            .unwrap();
+
+        // Cube vertices are the bounds of the range, so they must be restored exactly.
+        let positions = compressed
+            .decode_attribute(VertexAttributeUsage::Position)
+            .unwrap();
+        for (vertex, decoded) in data.vertex_buffer.iter().zip(positions) {
+            let position = vertex.read_3_f32(VertexAttributeUsage::Position).unwrap();
+            assert_eq!(
+                decoded,
+                Vector4::new(position.x, position.y, position.z, 0.0)
+            );
+        }
+    }
+
+    #[test]
+    fn test_geometry_compression_unsupported() {
+        let data = SurfaceData::make_cube(Matrix4::identity());
+
+        // Tangents have four components (with handedness).
+        assert_eq!(
+            GeometryCompression::default()
+                .with_encoding(
+                    VertexAttributeUsage::Tangent,
+                    AttributeEncoding::Octahedral16
+                )
+                .compress(&data.vertex_buffer)
+                .unwrap_err(),
+            GeometryCompressionError::UnsupportedEncoding {
+                usage: VertexAttributeUsage::Tangent,
+                encoding: AttributeEncoding::Octahedral16,
+            }
+        );
+
+        assert_eq!(
+            GeometryCompression::default()
+                .with_encoding(VertexAttributeUsage::Color, AttributeEncoding::Quantized16)
+                .compress(&data.vertex_buffer)
+                .unwrap_err(),
+            GeometryCompressionError::NoSuchAttribute(VertexAttributeUsage::Color)
+        );
+    }
+}
@@ -50,6 +50,7 @@ pub(crate) struct ForwardRenderContext<'a> {
     pub scene_depth: &'a GpuTexture,
     pub ambient_light: Color,
     pub uniform_memory_allocator: &'a mut UniformMemoryAllocator,
+    pub editor_overlay_depth_test: bool,
 }
 
 impl ForwardRenderer {
@@ -78,6 +79,7 @@ impl ForwardRenderer {
             scene_depth,
             ambient_light,
             uniform_memory_allocator,
+            editor_overlay_depth_test,
         } = args;
 
         statistics += bundle_storage.render_to_frame_buffer(
@@ -97,6 +99,30 @@ impl ForwardRenderer {
                 fallback_resources,
                 ambient_light,
                 scene_depth: Some(scene_depth),
+                disable_depth_test: false,
+            },
+        )?;
+
+        // Editor overlay is drawn on top of everything else and it is not a part of the scene, so
+        // its statistics are intentionally ignored.
+        bundle_storage.render_to_frame_buffer(
+            state,
+            geom_cache,
+            shader_cache,
+            |bundle| bundle.render_path == RenderPath::EditorOverlay,
+            |_| true,
+            BundleRenderContext {
+                texture_cache,
+                render_pass_name: &self.render_pass_name,
+                frame_buffer: framebuffer,
+                viewport,
+                uniform_memory_allocator,
+                use_pom: quality_settings.use_parallax_mapping,
+                light_position: &Default::default(),
+                fallback_resources,
+                ambient_light,
+                scene_depth: Some(scene_depth),
+                disable_depth_test: !editor_overlay_depth_test,
             },
         )?;
 
@@ -59,7 +59,6 @@ use crate::{
         FallbackResources, GeometryCache, QualitySettings, RenderPassStatistics, TextureCache,
     },
     scene::{
-        decal::Decal,
         graph::Graph,
         mesh::{surface::SurfaceData, RenderPath},
     },
@@ -253,6 +252,7 @@ impl GBuffer {
                 fallback_resources,
                 ambient_light: Color::WHITE, // TODO
                 scene_depth: None,           // TODO. Add z-pre-pass.
+                disable_depth_test: false,
             },
         )?;
 
@@ -290,12 +290,11 @@ impl GBuffer {
         // for rendering. We'll render in the G-Buffer, but depth will be used from final frame, since
         // decals do not modify depth (only diffuse and normal maps).
         let unit_cube = &self.cube;
-        for decal in graph.linear_iter().filter_map(|n| n.cast::<Decal>()) {
-            let world_view_proj =
-                observer.position.view_projection_matrix * decal.global_transform();
-
-            let diffuse_texture = decal
-                .diffuse_texture()
+        for batch in bundle_storage.decal_batches.iter() {
+            let diffuse_texture = batch
+                .material
+                .diffuse_texture
+                .as_ref()
                 .and_then(|t| {
                     texture_cache
                         .get(server, t)
@@ -304,11 +303,12 @@ impl GBuffer {
                 .unwrap_or((
                     fallback_resources.white_dummy.clone(),
                     fallback_resources.linear_clamp_sampler.clone(),
-                ))
-                .clone();
+                ));
 
-            let normal_texture = decal
-                .normal_texture()
+            let normal_texture = batch
+                .material
+                .normal_texture
+                .as_ref()
                 .and_then(|t| {
                     texture_cache
                         .get(server, t)
@@ -317,45 +317,47 @@ impl GBuffer {
                 .unwrap_or((
                     fallback_resources.normal_dummy.clone(),
                     fallback_resources.linear_clamp_sampler.clone(),
-                ))
-                .clone();
+                ));
 
-            let inv_world_decal = decal.global_transform().try_inverse().unwrap_or_default();
-            let color = decal.color().srgb_to_linear_f32();
-            let layer_index = decal.layer() as u32;
-            let properties = PropertyGroup::from([
-                property("worldViewProjection", &world_view_proj),
-                property("invViewProj", &inv_view_proj),
-                property("invWorldDecal", &inv_world_decal),
-                property("resolution", &resolution),
-                property("color", &color),
-                property("layerIndex", &layer_index),
-            ]);
-            let material = RenderMaterial::from([
-                binding(
-                    "sceneDepth",
-                    (depth, &fallback_resources.nearest_clamp_sampler),
-                ),
-                binding("diffuseTexture", (&diffuse_texture.0, &diffuse_texture.1)),
-                binding("normalTexture", (&normal_texture.0, &normal_texture.1)),
-                binding(
-                    "decalMask",
-                    (decal_mask, &fallback_resources.nearest_clamp_sampler),
-                ),
-                binding("properties", &properties),
-            ]);
+            for instance in batch.instances.iter() {
+                let world_view_proj =
+                    observer.position.view_projection_matrix * instance.projector_transform;
+                let color = instance.color.srgb_to_linear_f32();
+                let layer_index = instance.layer as u32;
+                let properties = PropertyGroup::from([
+                    property("worldViewProjection", &world_view_proj),
+                    property("invViewProj", &inv_view_proj),
+                    property("invWorldDecal", &instance.inv_projector_transform),
+                    property("resolution", &resolution),
+                    property("color", &color),
+                    property("layerIndex", &layer_index),
+                ]);
+                let material = RenderMaterial::from([
+                    binding(
+                        "sceneDepth",
+                        (depth, &fallback_resources.nearest_clamp_sampler),
+                    ),
+                    binding("diffuseTexture", (&diffuse_texture.0, &diffuse_texture.1)),
+                    binding("normalTexture", (&normal_texture.0, &normal_texture.1)),
+                    binding(
+                        "decalMask",
+                        (decal_mask, &fallback_resources.nearest_clamp_sampler),
+                    ),
+                    binding("properties", &properties),
+                ]);
 
-            statistics += self.decal_shader.run_pass(
-                1,
-                &ImmutableString::new("Primary"),
-                &self.decal_framebuffer,
-                unit_cube,
-                viewport,
-                &material,
-                uniform_buffer_cache,
-                Default::default(),
-                None,
-            )?;
+                statistics += self.decal_shader.run_pass(
+                    1,
+                    &ImmutableString::new("Primary"),
+                    &self.decal_framebuffer,
+                    unit_cube,
+                    viewport,
+                    &material,
+                    uniform_buffer_cache,
+                    Default::default(),
+                    None,
+                )?;
+            }
         }
 
         Ok(statistics)
@@ -594,6 +594,8 @@ impl DeferredLightRenderer {
                     depth_test: Some(CompareFunc::Less),
                     blend: None,
                     scissor_box: None,
+                    polygon_offset: None,
+                    alpha_to_coverage: false,
                 };
                 let properties =
                     PropertyGroup::from([property("worldViewProjection", &shape_wvp_matrix)]);
@@ -28,6 +28,7 @@ pub mod framework;
 
 pub mod bundle;
 pub mod cache;
+pub mod compression;
 pub mod debug_renderer;
 pub mod observer;
 pub mod stats;
@@ -478,6 +479,7 @@ pub struct Renderer {
     /// A set of associated data for each scene that was rendered.
     pub scene_data_map: FxHashMap<Handle<Scene>, SceneRenderData>,
     backbuffer_clear_color: Color,
+    editor_overlay_depth_test: bool,
     /// Texture cache with GPU textures.
     pub texture_cache: TextureCache,
     /// Uniform buffer cache.
@@ -853,6 +855,7 @@ impl Renderer {
             screen_space_debug_renderer: DebugRenderer::new(&*server)?,
             scene_data_map: Default::default(),
             backbuffer_clear_color: Color::BLACK,
+            editor_overlay_depth_test: true,
             texture_cache: Default::default(),
             geometry_cache: Default::default(),
             forward_renderer: ForwardRenderer::new(),
@@ -912,6 +915,18 @@ impl Renderer {
         self.backbuffer_clear_color = color;
     }
 
+    /// Enables or disables depth test for objects with [`crate::scene::mesh::RenderPath::EditorOverlay`]
+    /// render path. When disabled, such objects will be drawn on top of the scene. Enabled by default.
+    pub fn set_editor_overlay_depth_test(&mut self, enabled: bool) {
+        self.editor_overlay_depth_test = enabled;
+    }
+
+    /// Returns `true` if depth test is enabled for objects with
+    /// [`crate::scene::mesh::RenderPath::EditorOverlay`] render path.
+    pub fn is_editor_overlay_depth_test_enabled(&self) -> bool {
+        self.editor_overlay_depth_test
+    }
+
     /// Returns a reference to current graphics server.
     pub fn graphics_server(&self) -> &dyn GraphicsServer {
         &*self.server
@@ -1241,6 +1256,13 @@ impl Renderer {
                 GBUFFER_PASS_NAME.clone(),
                 RenderDataBundleStorageOptions {
                     collect_lights: true,
+                    collect_triangle_stats: false,
+                    max_vertices_per_batch: self.quality_settings.max_vertices_per_batch,
+                    geometry_compression: None,
+                    instance_filter: None,
+                    instance_count_cache: None,
+                    frame_generation: 0,
+                    lod_bias: 0.0,
                 },
                 &mut self.dynamic_surface_cache,
             );
@@ -1322,6 +1344,7 @@ impl Renderer {
                 scene_depth: depth,
                 ambient_light: scene.rendering_options.ambient_lighting_color,
                 uniform_memory_allocator: &mut self.uniform_memory_allocator,
+                editor_overlay_depth_test: self.editor_overlay_depth_test,
             })?;
 
             for render_pass in self.scene_render_passes.iter() {
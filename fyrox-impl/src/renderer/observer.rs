@@ -20,7 +20,7 @@
 
 #![allow(missing_docs)] // TODO
 
-use crate::renderer::utils::CubeMapFaceDescriptor;
+use crate::renderer::{bundle::SurfaceInstanceData, utils::CubeMapFaceDescriptor};
 use crate::{
     core::{
         algebra::{Matrix4, Point3, Vector2, Vector3},
@@ -53,9 +53,68 @@ pub struct ObserverPosition {
     /// Projection matrix of the observer.
     pub projection_matrix: Matrix4<f32>,
     pub view_projection_matrix: Matrix4<f32>,
+    /// Enables camera-relative rendering. When set, the translation of the observer is subtracted
+    /// from world transforms (and bone matrices) of every surface instance added to a render data
+    /// bundle storage, and view-projection matrices passed to shaders are adjusted accordingly.
+    /// This improves precision of rendering of objects far from the world origin. Keep in mind,
+    /// that in this mode `fyrox_instanceData.worldMatrix` gives positions relative to the
+    /// observer; world-space positions could be reconstructed by adding
+    /// `fyrox_cameraData.position` to them.
+    ///
+    /// The mode is internal for now and the renderer never enables it: the camera position and
+    /// positions of light sources are still uploaded to shaders in world space, so the standard
+    /// shaders would calculate wrong view vectors and lighting for rebased instances.
+    pub(crate) origin_rebase: bool,
+    /// A margin (in world units) by which the frustum planes are moved outwards before culling.
+    /// The same margin (normalized by the depth range of the observer) widens the distance ranges
+    /// of LOD levels. Positive padding makes the observer render slightly more than strictly
+    /// visible, which is useful for shadow observers, to prevent shadows from popping at the
+    /// borders of the frustum and at LOD transitions. Default is 0, which means exact culling.
+    pub cull_padding: f32,
 }
 
+/// Amount of shadow map texels by which the frustum of a shadow map observer is extended, see
+/// [`ObserverPosition::for_shadow_map`]. It covers the filtering kernel of soft shadows.
+pub const SHADOW_CULL_PADDING_TEXELS: f32 = 4.0;
+
 impl ObserverPosition {
+    /// Creates a "virtual camera" of a light source, that is used to render a shadow map of the
+    /// given size (in texels). Shadow maps are rendered with cull padding (see
+    /// [`Self::cull_padding`]), which is equal to [`SHADOW_CULL_PADDING_TEXELS`] world-space sizes
+    /// of a shadow map texel at the far plane. It makes the objects that are slightly outside of
+    /// the light frustum to cast shadows, which prevents shadows from popping at the edges of the
+    /// shadow map.
+    pub fn for_shadow_map(
+        translation: Vector3<f32>,
+        z_near: f32,
+        z_far: f32,
+        view_matrix: Matrix4<f32>,
+        projection_matrix: Matrix4<f32>,
+        shadow_map_size: usize,
+    ) -> Self {
+        // W component of clip-space position at the far plane, it is equal to the far plane
+        // distance for perspective projection and 1.0 for orthographic.
+        let far_w = projection_matrix[(3, 3)] - projection_matrix[(3, 2)] * z_far;
+        let horizontal_scale = projection_matrix[(0, 0)].abs();
+        let cull_padding = if horizontal_scale > f32::EPSILON && shadow_map_size > 0 {
+            let texel_size = 2.0 * far_w.abs() / horizontal_scale / shadow_map_size as f32;
+            SHADOW_CULL_PADDING_TEXELS * texel_size
+        } else {
+            0.0
+        };
+
+        Self {
+            translation,
+            z_near,
+            z_far,
+            view_matrix,
+            projection_matrix,
+            view_projection_matrix: projection_matrix * view_matrix,
+            origin_rebase: false,
+            cull_padding,
+        }
+    }
+
     pub fn from_camera(camera: &Camera) -> Self {
         Self {
             translation: camera.global_position(),
@@ -64,7 +123,62 @@ impl ObserverPosition {
             view_matrix: camera.view_matrix(),
             projection_matrix: camera.projection_matrix(),
             view_projection_matrix: camera.view_projection_matrix(),
+            origin_rebase: false,
+            cull_padding: 0.0,
+        }
+    }
+
+    /// Returns a view-projection matrix, that should be used together with world transforms of
+    /// surface instances. It is the same as `projection_matrix * view_matrix` if
+    /// origin rebasing is off, otherwise it is adjusted to take relative world transforms.
+    pub fn instance_view_projection_matrix(&self) -> Matrix4<f32> {
+        if self.origin_rebase {
+            // Large translations cancel each other here, do it with double precision.
+            let view = self.view_matrix.cast::<f64>()
+                * Matrix4::new_translation(&self.translation.cast::<f64>());
+            self.projection_matrix * view.cast::<f32>()
+        } else {
+            self.projection_matrix * self.view_matrix
+        }
+    }
+
+    /// Rebases world transform (or bone matrices, if any) of the given instance relative to the
+    /// observer if origin rebasing is on, otherwise returns the instance as is.
+    pub fn rebase_instance(&self, mut instance: SurfaceInstanceData) -> SurfaceInstanceData {
+        if self.origin_rebase {
+            let rebase = |matrix: &mut Matrix4<f32>| {
+                let w = matrix[(3, 3)];
+                for i in 0..3 {
+                    matrix[(i, 3)] -= self.translation[i] * w;
+                }
+            };
+
+            // World transform of skinned instances is not used, bone matrices contain world
+            // transforms of bones instead.
+            if instance.bone_matrices.is_empty() {
+                rebase(&mut instance.world_transform);
+            } else {
+                instance.bone_matrices.iter_mut().for_each(rebase);
+            }
+        }
+        instance
+    }
+
+    /// Calculates a scaling factor for an object at the given world-space position, that makes the
+    /// object to have constant size on screen regardless of its distance to the observer. The size
+    /// is defined as a fraction of the viewport height, an object with the unit height (in local
+    /// coordinates) will occupy exactly this fraction after scaling. It is useful for editor
+    /// handles and gizmos (see [`crate::scene::mesh::RenderPath::EditorOverlay`]). Works with both
+    /// perspective and orthographic projections.
+    pub fn constant_screen_size_scale(&self, position: &Vector3<f32>, screen_fraction: f32) -> f32 {
+        // W component of clip-space position is the distance along the view direction for
+        // perspective projection and 1.0 for orthographic.
+        let w = (self.view_projection_matrix * position.push(1.0)).w;
+        let vertical_scale = self.projection_matrix[(1, 1)];
+        if vertical_scale.abs() <= f32::EPSILON {
+            return 1.0;
         }
+        screen_fraction * 2.0 * w.abs() / vertical_scale.abs()
     }
 }
 
@@ -117,6 +231,8 @@ impl ObserversCollection {
                                 view_matrix,
                                 projection_matrix,
                                 view_projection_matrix,
+                                origin_rebase: false,
+                                cull_padding: 0.0,
                             },
                             environment_map: None,
                             render_mask: *probe.render_mask,
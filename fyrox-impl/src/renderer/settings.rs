@@ -90,6 +90,13 @@ pub struct QualitySettings {
     /// feature that may have bugs and unstable behavior. Disabled by default.
     #[serde(default)]
     pub use_light_occlusion_culling: bool,
+
+    /// Maximum amount of vertices in a dynamically batched bundle (sprites, particles, etc.) of
+    /// the scene pass, see
+    /// [`crate::renderer::bundle::RenderDataBundleStorage::max_vertices_per_batch`]. Shadow
+    /// passes are not limited. [`None`] (default) means no limit.
+    #[serde(default)]
+    pub max_vertices_per_batch: Option<u32>,
 }
 
 impl Default for QualitySettings {
@@ -132,6 +139,8 @@ impl QualitySettings {
 
             use_occlusion_culling: false,
             use_light_occlusion_culling: false,
+
+            max_vertices_per_batch: None,
         }
     }
 
@@ -173,6 +182,8 @@ impl QualitySettings {
 
             use_occlusion_culling: false,
             use_light_occlusion_culling: false,
+
+            max_vertices_per_batch: None,
         }
     }
 
@@ -214,6 +225,8 @@ impl QualitySettings {
 
             use_occlusion_culling: false,
             use_light_occlusion_culling: false,
+
+            max_vertices_per_batch: None,
         }
     }
 
@@ -255,6 +268,8 @@ impl QualitySettings {
 
             use_occlusion_culling: false,
             use_light_occlusion_culling: false,
+
+            max_vertices_per_batch: None,
         }
     }
 }
@@ -47,6 +47,7 @@ use crate::{
     scene::{
         graph::Graph,
         light::directional::{FrustumSplitOptions, CSM_NUM_CASCADES},
+        mesh::RenderPath,
     },
 };
 use approx::relative_eq;
@@ -260,17 +261,24 @@ impl CsmRenderer {
                 graph,
                 observer.render_mask,
                 elapsed_time,
-                &ObserverPosition {
-                    translation: observer_position,
+                &ObserverPosition::for_shadow_map(
+                    observer_position,
                     z_near,
                     z_far,
-                    view_matrix: light_view_matrix,
-                    projection_matrix: cascade_projection_matrix,
-                    view_projection_matrix: cascade_projection_matrix * light_view_matrix,
-                },
+                    light_view_matrix,
+                    cascade_projection_matrix,
+                    self.size,
+                ),
                 DIRECTIONAL_SHADOW_PASS_NAME.clone(),
                 RenderDataBundleStorageOptions {
                     collect_lights: false,
+                    collect_triangle_stats: false,
+                    max_vertices_per_batch: None,
+                    geometry_compression: None,
+                    instance_filter: None,
+                    instance_count_cache: None,
+                    frame_generation: 0,
+                    lod_bias: 0.0,
                 },
                 dynamic_surface_cache,
             );
@@ -279,7 +287,7 @@ impl CsmRenderer {
                 state,
                 geom_cache,
                 shader_cache,
-                |_| true,
+                |bundle| bundle.render_path != RenderPath::EditorOverlay,
                 |_| true,
                 BundleRenderContext {
                     texture_cache,
@@ -292,6 +300,7 @@ impl CsmRenderer {
                     fallback_resources,
                     ambient_light: Color::WHITE, // TODO
                     scene_depth: None,
+                    disable_depth_test: false,
                 },
             )?;
         }
@@ -42,7 +42,7 @@ use crate::{
         FallbackResources, GeometryCache, RenderPassStatistics, ShadowMapPrecision,
         POINT_SHADOW_PASS_NAME,
     },
-    scene::{collider::BitMask, graph::Graph},
+    scene::{collider::BitMask, graph::Graph, mesh::RenderPath},
 };
 
 pub struct PointShadowMapRenderer {
@@ -177,17 +177,24 @@ impl PointShadowMapRenderer {
                 graph,
                 render_mask,
                 elapsed_time,
-                &ObserverPosition {
-                    translation: light_pos,
+                &ObserverPosition::for_shadow_map(
+                    light_pos,
                     z_near,
                     z_far,
-                    view_matrix: light_view_matrix,
-                    projection_matrix: light_projection_matrix,
-                    view_projection_matrix: light_projection_matrix * light_view_matrix,
-                },
+                    light_view_matrix,
+                    light_projection_matrix,
+                    cascade_size,
+                ),
                 POINT_SHADOW_PASS_NAME.clone(),
                 RenderDataBundleStorageOptions {
                     collect_lights: false,
+                    collect_triangle_stats: false,
+                    max_vertices_per_batch: None,
+                    geometry_compression: None,
+                    instance_filter: None,
+                    instance_count_cache: None,
+                    frame_generation: 0,
+                    lod_bias: 0.0,
                 },
                 dynamic_surface_cache,
             );
@@ -196,7 +203,7 @@ impl PointShadowMapRenderer {
                 state,
                 geom_cache,
                 shader_cache,
-                |_| true,
+                |bundle| bundle.render_path != RenderPath::EditorOverlay,
                 |_| true,
                 BundleRenderContext {
                     texture_cache,
@@ -209,6 +216,7 @@ impl PointShadowMapRenderer {
                     fallback_resources,
                     ambient_light: Color::WHITE, // TODO
                     scene_depth: None,
+                    disable_depth_test: false,
                 },
             )?;
         }
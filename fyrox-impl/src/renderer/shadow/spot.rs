@@ -40,7 +40,7 @@ use crate::{
         FallbackResources, GeometryCache, RenderPassStatistics, ShadowMapPrecision,
         SPOT_SHADOW_PASS_NAME,
     },
-    scene::graph::Graph,
+    scene::{graph::Graph, mesh::RenderPath},
 };
 use fyrox_graphics::framebuffer::GpuFrameBuffer;
 use fyrox_graphics::gpu_texture::GpuTexture;
@@ -144,17 +144,24 @@ impl SpotShadowMapRenderer {
             graph,
             render_mask,
             elapsed_time,
-            &ObserverPosition {
-                translation: light_position,
+            &ObserverPosition::for_shadow_map(
+                light_position,
                 z_near,
                 z_far,
-                view_matrix: light_view_matrix,
-                projection_matrix: light_projection_matrix,
-                view_projection_matrix: light_projection_matrix * light_view_matrix,
-            },
+                light_view_matrix,
+                light_projection_matrix,
+                cascade_size,
+            ),
             SPOT_SHADOW_PASS_NAME.clone(),
             RenderDataBundleStorageOptions {
                 collect_lights: false,
+                collect_triangle_stats: false,
+                max_vertices_per_batch: None,
+                geometry_compression: None,
+                instance_filter: None,
+                instance_count_cache: None,
+                frame_generation: 0,
+                lod_bias: 0.0,
             },
             dynamic_surface_cache,
         );
@@ -163,7 +170,7 @@ impl SpotShadowMapRenderer {
             server,
             geom_cache,
             shader_cache,
-            |_| true,
+            |bundle| bundle.render_path != RenderPath::EditorOverlay,
             |_| true,
             BundleRenderContext {
                 texture_cache,
@@ -176,6 +183,7 @@ impl SpotShadowMapRenderer {
                 fallback_resources,
                 ambient_light: Color::WHITE, // TODO
                 scene_depth: None,
+                disable_depth_test: false,
             },
         )?;
 
@@ -375,6 +375,8 @@ impl UiRenderer {
                 }),
                 stencil_op: Default::default(),
                 scissor_box,
+                polygon_offset: None,
+                alpha_to_coverage: false,
             };
 
             let element_range = ElementRange::Specific {
@@ -24,7 +24,7 @@
 
 use crate::{
     core::{
-        algebra::{Matrix4, Vector3},
+        algebra::{Matrix4, Vector2, Vector3},
         log::Log,
         math::{aabb::AxisAlignedBoundingBox, Matrix4Ext},
         pool::{ErasedHandle, Handle},
@@ -36,8 +36,9 @@ use crate::{
     },
     engine::SerializationContext,
     graph::BaseSceneGraph,
+    material::MaterialResource,
     resource::model::ModelResource,
-    scene::{node::Node, transform::Transform},
+    scene::{mesh::RenderPath, node::Node, transform::Transform},
     script::{Script, ScriptTrait},
 };
 use fyrox_core::algebra::UnitQuaternion;
@@ -121,6 +122,79 @@ impl LevelOfDetail {
     }
 }
 
+/// Impostor level of a LOD group, see [`LodGroup::impostor`]. When the LOD group is in the distance
+/// range of the impostor, the objects of every level of the group are hidden and a single
+/// camera-facing quad is rendered instead, at the position of the node with the LOD group. The
+/// quad should be rendered with a material, that shows a pre-rendered picture of the objects.
+///
+/// The distance range is expressed in normalized distance (see [`LevelOfDetail`]) and it should be
+/// the farthest range of the group, that does not overlap with the ranges of the levels.
+#[derive(Debug, Clone, Visit, Reflect, PartialEq, TypeUuidProvider)]
+#[type_uuid(id = "3f1d7c26-94b8-4e0a-b6d5-8c2e9a71f403")]
+pub struct ImpostorLevel {
+    #[reflect(
+        description = "Beginning of the range in which the impostor will be visible. \
+    It is expressed in normalized coordinates: where 0.0 - closest to camera, 1.0 - \
+    farthest from camera."
+    )]
+    begin: f32,
+    #[reflect(
+        description = "End of the range in which the impostor will be visible. \
+    It is expressed in normalized coordinates: where 0.0 - closest to camera, 1.0 - \
+    farthest from camera."
+    )]
+    end: f32,
+    /// World-space size (width and height) of the quad.
+    pub size: Vector2<f32>,
+    /// A material of the quad.
+    pub material: MaterialResource,
+    /// A render path of the quad.
+    pub render_path: RenderPath,
+}
+
+impl Default for ImpostorLevel {
+    fn default() -> Self {
+        Self {
+            begin: 1.0,
+            end: 1.0,
+            size: Vector2::repeat(1.0),
+            material: Default::default(),
+            render_path: Default::default(),
+        }
+    }
+}
+
+impl ImpostorLevel {
+    /// Creates new impostor level. The range is clamped to (0; 1) range, just like with
+    /// [`LevelOfDetail::new`].
+    pub fn new(begin: f32, end: f32, size: Vector2<f32>, material: MaterialResource) -> Self {
+        let begin = begin.min(end);
+        let end = end.max(begin);
+        Self {
+            begin: begin.clamp(0.0, 1.0),
+            end: end.clamp(0.0, 1.0),
+            size,
+            material,
+            render_path: Default::default(),
+        }
+    }
+
+    /// Returns starting point of the range.
+    pub fn begin(&self) -> f32 {
+        self.begin
+    }
+
+    /// Returns end point of the range.
+    pub fn end(&self) -> f32 {
+        self.end
+    }
+
+    /// Returns `true` if the given normalized distance is in the range of the impostor.
+    pub fn is_in_range(&self, normalized_distance: f32) -> bool {
+        normalized_distance >= self.begin && normalized_distance <= self.end
+    }
+}
+
 /// LOD (Level-Of-Detail) group is a set of cascades (levels), where each cascade takes specific
 /// distance range. Each cascade contains list of objects that should or shouldn't be rendered
 /// if distance satisfy cascade range. LOD may significantly improve performance if your scene
@@ -136,6 +210,10 @@ impl LevelOfDetail {
 pub struct LodGroup {
     /// Set of cascades.
     pub levels: Vec<LevelOfDetail>,
+    /// Optional impostor level, that replaces every level with a single quad at far distances,
+    /// see [`ImpostorLevel`] for more info.
+    #[visit(optional)]
+    pub impostor: Option<ImpostorLevel>,
 }
 
 /// Mobility defines a group for scene node which has direct impact on performance
@@ -498,6 +576,9 @@ pub struct Base {
     #[reflect(setter = "set_frustum_culling")]
     frustum_culling: InheritableVariable<bool>,
 
+    #[reflect(setter = "set_always_render")]
+    always_render: InheritableVariable<bool>,
+
     // When `true` it means that this node is instance of `resource`.
     // More precisely - this node is root of whole descendant nodes
     // hierarchy which was instantiated from resource.
@@ -863,6 +944,23 @@ impl Base {
             .set_value_and_mark_modified(frustum_culling)
     }
 
+    /// Returns `true` if the node is never culled by observers, see [`Self::set_always_render`]
+    /// for more info.
+    #[inline]
+    pub fn always_render(&self) -> bool {
+        *self.always_render
+    }
+
+    /// Sets whether the node should be rendered regardless of its position relative to an observer.
+    /// Such node bypasses both frustum culling (even if [`Self::frustum_culling`] is `true`) and
+    /// LOD filtering. It is useful for skyboxes, screen-space effects and always-on overlays. The
+    /// node is still skipped if it is invisible, disabled or filtered out by the render mask.
+    #[inline]
+    pub fn set_always_render(&mut self, always_render: bool) -> bool {
+        self.always_render
+            .set_value_and_mark_modified(always_render)
+    }
+
     /// Returns true if the node should cast shadows, false - otherwise.
     #[inline]
     pub fn cast_shadows(&self) -> bool {
@@ -1214,6 +1312,7 @@ impl Visit for Base {
         self.tag.visit("Tag", &mut region)?;
         let _ = self.properties.visit("Properties", &mut region);
         let _ = self.frustum_culling.visit("FrustumCulling", &mut region);
+        let _ = self.always_render.visit("AlwaysRender", &mut region);
         let _ = self.cast_shadows.visit("CastShadows", &mut region);
         let _ = self.instance_id.visit("InstanceId", &mut region);
         let _ = self.enabled.visit("Enabled", &mut region);
@@ -1255,6 +1354,7 @@ pub struct BaseBuilder {
     inv_bind_pose_transform: Matrix4<f32>,
     tag: String,
     frustum_culling: bool,
+    always_render: bool,
     cast_shadows: bool,
     scripts: Vec<ScriptRecord>,
     instance_id: SceneNodeId,
@@ -1282,6 +1382,7 @@ impl BaseBuilder {
             inv_bind_pose_transform: Matrix4::identity(),
             tag: Default::default(),
             frustum_culling: true,
+            always_render: false,
             cast_shadows: true,
             scripts: vec![],
             instance_id: SceneNodeId(Uuid::new_v4()),
@@ -1372,6 +1473,14 @@ impl BaseBuilder {
         self
     }
 
+    /// Sets whether the node should be rendered regardless of its position relative to an observer,
+    /// see [`Base::set_always_render`] for more info.
+    #[inline]
+    pub fn with_always_render(mut self, always_render: bool) -> Self {
+        self.always_render = always_render;
+        self
+    }
+
     /// Sets whether mesh should cast shadows or not.
     #[inline]
     pub fn with_cast_shadows(mut self, cast_shadows: bool) -> Self {
@@ -1430,6 +1539,7 @@ impl BaseBuilder {
             tag: self.tag.into(),
             properties: Default::default(),
             frustum_culling: self.frustum_culling.into(),
+            always_render: self.always_render.into(),
             cast_shadows: self.cast_shadows.into(),
             scripts: self.scripts,
             instance_id: SceneNodeId(Uuid::new_v4()),
@@ -33,12 +33,16 @@ use crate::{
         variable::InheritableVariable,
         visitor::prelude::*,
     },
+    renderer::{
+        self,
+        bundle::{DecalMaterial, RenderContext},
+    },
     resource::texture::TextureResource,
     scene::node::constructor::NodeConstructor,
     scene::{
         base::{Base, BaseBuilder},
         graph::Graph,
-        node::{Node, NodeTrait},
+        node::{Node, NodeTrait, RdcControlFlow},
     },
 };
 
@@ -240,6 +244,29 @@ impl NodeTrait for Decal {
     fn id(&self) -> Uuid {
         Self::type_uuid()
     }
+
+    fn collect_render_data(&self, ctx: &mut RenderContext) -> RdcControlFlow {
+        // Bounding box of a decal does not match its projection volume, so frustum culling is
+        // not used here.
+        if !self.should_be_rendered(None, ctx.render_mask)
+            || renderer::is_shadow_pass(ctx.render_pass_name)
+        {
+            return RdcControlFlow::Continue;
+        }
+
+        ctx.storage.push_decal(
+            &DecalMaterial {
+                diffuse_texture: self.diffuse_texture.deref().clone(),
+                normal_texture: self.normal_texture.deref().clone(),
+            },
+            self.global_transform(),
+            *self.color,
+            *self.layer,
+            0,
+        );
+
+        RdcControlFlow::Continue
+    }
 }
 
 /// Allows you to create a Decal in a declarative manner.
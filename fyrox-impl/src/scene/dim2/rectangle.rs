@@ -101,6 +101,41 @@ impl VertexTrait for RectangleVertex {
     }
 }
 
+impl RectangleVertex {
+    /// Creates the vertices of a textured quad. `corner` maps local coordinates of a corner of a
+    /// unit quad (in `-0.5..0.5` range) to the position of the respective vertex. X axis of 2D
+    /// scenes points to the left, so the corners with negative X are mapped to the right side of
+    /// `uv_rect` and the image is not mirrored.
+    pub(crate) fn quad(
+        corner: impl Fn(f32, f32) -> Vector3<f32>,
+        uv_rect: &Rect<f32>,
+        color: Color,
+    ) -> [Self; 4] {
+        [
+            Self {
+                position: corner(-0.5, 0.5),
+                tex_coord: uv_rect.right_top_corner(),
+                color,
+            },
+            Self {
+                position: corner(0.5, 0.5),
+                tex_coord: uv_rect.left_top_corner(),
+                color,
+            },
+            Self {
+                position: corner(0.5, -0.5),
+                tex_coord: uv_rect.left_bottom_corner(),
+                color,
+            },
+            Self {
+                position: corner(-0.5, -0.5),
+                tex_coord: uv_rect.right_bottom_corner(),
+                color,
+            },
+        ]
+    }
+}
+
 impl PartialEq for RectangleVertex {
     fn eq(&self, other: &Self) -> bool {
         self.position == other.position
@@ -318,36 +353,15 @@ impl NodeTrait for Rectangle {
 
         type Vertex = RectangleVertex;
 
-        let vertices = [
-            Vertex {
-                position: global_transform
-                    .transform_point(&Point3::new(-0.5, 0.5, 0.0))
-                    .coords,
-                tex_coord: self.uv_rect.right_top_corner(),
-                color: *self.color,
-            },
-            Vertex {
-                position: global_transform
-                    .transform_point(&Point3::new(0.5, 0.5, 0.0))
-                    .coords,
-                tex_coord: self.uv_rect.left_top_corner(),
-                color: *self.color,
+        let vertices = Vertex::quad(
+            |x, y| {
+                global_transform
+                    .transform_point(&Point3::new(x, y, 0.0))
+                    .coords
             },
-            Vertex {
-                position: global_transform
-                    .transform_point(&Point3::new(0.5, -0.5, 0.0))
-                    .coords,
-                tex_coord: self.uv_rect.left_bottom_corner(),
-                color: *self.color,
-            },
-            Vertex {
-                position: global_transform
-                    .transform_point(&Point3::new(-0.5, -0.5, 0.0))
-                    .coords,
-                tex_coord: self.uv_rect.right_bottom_corner(),
-                color: *self.color,
-            },
-        ];
+            &self.uv_rect,
+            *self.color,
+        );
 
         let triangles = [TriangleDefinition([0, 1, 2]), TriangleDefinition([2, 3, 0])];
 
@@ -358,7 +372,7 @@ impl NodeTrait for Rectangle {
             Vertex::layout(),
             &self.material,
             RenderPath::Forward,
-            sort_index,
+            sort_index.into(),
             self.handle(),
             &mut move |mut vertex_buffer, mut triangle_buffer| {
                 let start_vertex_index = vertex_buffer.vertex_count();
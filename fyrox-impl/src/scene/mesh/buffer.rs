@@ -146,7 +146,7 @@ impl Default for VertexAttributeUsage {
 }
 
 /// Input vertex attribute descriptor used to construct layouts and feed vertex buffer.
-#[derive(Debug, Hash)]
+#[derive(Copy, Clone, Debug, Hash)]
 pub struct VertexAttributeDescriptor {
     /// Claimed usage of the attribute. It could be Position, Normal, etc.
     pub usage: VertexAttributeUsage,
@@ -44,7 +44,7 @@ use crate::{
     material::MaterialResource,
     renderer::{
         self,
-        bundle::{RenderContext, RenderDataBundleStorageTrait, SurfaceInstanceData},
+        bundle::{RenderContext, RenderDataBundleStorageTrait, SortKey, SurfaceInstanceData},
         framework::ElementRange,
     },
     scene::{
@@ -105,6 +105,34 @@ pub enum RenderPath {
     /// Forward rendering path supports translucency and custom blending. However current support
     /// of forward rendering is very little. It is ideal for transparent objects like glass.
     Forward = 1,
+
+    /// The render path will be selected automatically using [`crate::material::PassHint`] of the
+    /// material that is used for rendering. Transparent materials will be rendered using
+    /// [`RenderPath::Forward`], the rest - using [`RenderPath::Deferred`].
+    Auto = 2,
+
+    /// Editor overlay render path is used for editor gizmos and other helper geometry. Such objects
+    /// are rendered after the rest of the scene, they do not cast shadows and their draw calls and
+    /// triangles are not counted in scene statistics. Depth testing for this path could be disabled
+    /// using [`crate::renderer::Renderer::set_editor_overlay_depth_test`].
+    EditorOverlay = 3,
+}
+
+impl RenderPath {
+    /// Resolves [`RenderPath::Auto`] into an actual render path using the pass hint of the given
+    /// material. Explicit render paths are returned as is.
+    pub fn resolve(self, material: &MaterialResource) -> RenderPath {
+        match self {
+            RenderPath::Auto => {
+                if material.pass_hint().is_transparent() {
+                    RenderPath::Forward
+                } else {
+                    RenderPath::Deferred
+                }
+            }
+            _ => self,
+        }
+    }
 }
 
 fn transform_vertex(mut vertex: VertexViewMut, world: &Matrix4<f32>) {
@@ -186,6 +214,7 @@ impl BatchContainer {
                 graph: ctx.graph,
                 render_pass_name: ctx.render_pass_name,
                 dynamic_surface_cache: ctx.dynamic_surface_cache,
+                instance_filter: None,
             });
         }
     }
@@ -207,7 +236,7 @@ impl RenderDataBundleStorageTrait for BatchContainer {
         layout: &[VertexAttributeDescriptor],
         material: &MaterialResource,
         _render_path: RenderPath,
-        _sort_index: u64,
+        _sort_key: SortKey,
         _node_handle: Handle<Node>,
         func: &mut dyn FnMut(VertexBufferRefMut, TriangleBufferRefMut),
     ) {
@@ -235,7 +264,7 @@ impl RenderDataBundleStorageTrait for BatchContainer {
         data: &SurfaceResource,
         material: &MaterialResource,
         _render_path: RenderPath,
-        _sort_index: u64,
+        _sort_key: SortKey,
         instance_data: SurfaceInstanceData,
     ) {
         let src_data = data.data_ref();
@@ -687,13 +716,23 @@ impl NodeTrait for Mesh {
                     &batch.data,
                     &batch.material,
                     self.render_path(),
-                    sorting_index,
+                    sorting_index.into(),
                     SurfaceInstanceData {
                         world_transform: Matrix4::identity(),
                         bone_matrices: Default::default(),
                         blend_shapes_weights: Default::default(),
                         element_range: ElementRange::Full,
                         node_handle: self.handle(),
+                        lod_level: None,
+                        clip_bounds: None,
+                        animation_phase: SurfaceInstanceData::animation_phase_from_position(
+                            &self.global_position(),
+                        ),
+                        instance_params: Default::default(),
+                        stencil: None,
+                        polygon_offset: None,
+                        cull_override: None,
+                        depth_state: None,
                     },
                 );
             }
@@ -748,7 +787,7 @@ impl NodeTrait for Mesh {
                             surface_data,
                             substitute_material.as_ref().unwrap_or(surface.material()),
                             self.render_path(),
-                            sorting_index,
+                            sorting_index.into(),
                             SurfaceInstanceData {
                                 world_transform: world,
                                 bone_matrices: surface
@@ -770,6 +809,16 @@ impl NodeTrait for Mesh {
                                     .collect(),
                                 element_range: ElementRange::Full,
                                 node_handle: self.handle(),
+                                lod_level: None,
+                                clip_bounds: None,
+                                animation_phase: SurfaceInstanceData::animation_phase_from_position(
+                                    &self.global_position(),
+                                ),
+                                instance_params: Default::default(),
+                                stencil: None,
+                                polygon_offset: None,
+                                cull_override: None,
+                                depth_state: None,
                             },
                         );
                     }
@@ -784,7 +833,7 @@ impl NodeTrait for Mesh {
                                 .collect::<Vec<_>>(),
                             surface.material(),
                             *self.render_path,
-                            sorting_index,
+                            sorting_index.into(),
                             self.handle(),
                             &mut move |mut vertex_buffer, mut triangle_buffer| {
                                 let start_vertex_index = vertex_buffer.vertex_count();
@@ -198,7 +198,8 @@ pub trait NodeTrait: BaseNodeTrait + Reflect + Visit + ComponentProvider {
     }
 
     /// Checks if the node should be rendered or not. A node should be rendered if it is enabled,
-    /// visible and (optionally) is inside some viewing frustum.
+    /// visible and (optionally) is inside some viewing frustum. Nodes with
+    /// [`Base::always_render`] flag are never culled by the frustum.
     #[inline]
     fn should_be_rendered(&self, frustum: Option<&Frustum>, render_mask: BitMask) -> bool {
         if *self.render_mask & render_mask == BitMask::none() {
@@ -213,7 +214,7 @@ pub trait NodeTrait: BaseNodeTrait + Reflect + Visit + ComponentProvider {
             return false;
         }
 
-        if self.frustum_culling() {
+        if self.frustum_culling() && !self.always_render() {
             if let Some(frustum) = frustum {
                 if !frustum.is_intersects_aabb(&self.world_bounding_box()) {
                     return false;
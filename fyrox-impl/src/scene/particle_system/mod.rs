@@ -643,7 +643,7 @@ impl NodeTrait for ParticleSystem {
             Vertex::layout(),
             &self.material,
             RenderPath::Forward,
-            sort_index,
+            sort_index.into(),
             self.handle(),
             &mut move |mut vertex_buffer, mut triangle_buffer| {
                 let vertices = sorted_particles.iter().flat_map(move |particle_index| {
@@ -368,7 +368,7 @@ impl NodeTrait for Sprite {
             Vertex::layout(),
             &self.material,
             RenderPath::Forward,
-            sort_index,
+            sort_index.into(),
             self.handle(),
             &mut move |mut vertex_buffer, mut triangle_buffer| {
                 let start_vertex_index = vertex_buffer.vertex_count();
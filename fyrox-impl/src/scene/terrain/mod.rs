@@ -39,7 +39,7 @@ use crate::{
     material::{Material, MaterialProperty, MaterialResource},
     renderer::{
         self,
-        bundle::{RenderContext, SurfaceInstanceData},
+        bundle::{RenderContext, SortKey, SurfaceInstanceData},
         framework::ElementRange,
     },
     resource::texture::{
@@ -2626,6 +2626,7 @@ impl NodeTrait for Terrain {
         if !self.global_visibility()
             || !self.is_globally_enabled()
             || (self.frustum_culling()
+                && !self.always_render()
                 && !ctx
                     .frustum
                     .is_none_or(|f| f.is_intersects_aabb(&self.world_bounding_box())))
@@ -2668,7 +2669,7 @@ impl NodeTrait for Terrain {
                     &chunk_transform,
                     self.height_map_size(),
                     self.chunk_size(),
-                    ctx.frustum,
+                    ctx.frustum.filter(|_| !self.always_render()),
                     ctx.observer_position.translation,
                     &levels,
                     &mut selection,
@@ -2722,13 +2723,23 @@ impl NodeTrait for Terrain {
                             &self.geometry.data,
                             &material,
                             RenderPath::Deferred,
-                            layer_index as u64,
+                            SortKey::from(layer_index as u64),
                             SurfaceInstanceData {
                                 world_transform: node_transform,
                                 bone_matrices: Default::default(),
                                 blend_shapes_weights: Default::default(),
                                 element_range: ElementRange::Full,
                                 node_handle: self.handle(),
+                                lod_level: None,
+                                clip_bounds: None,
+                                animation_phase: SurfaceInstanceData::animation_phase_from_position(
+                                    &self.global_position(),
+                                ),
+                                instance_params: Default::default(),
+                                stencil: None,
+                                polygon_offset: None,
+                                cull_override: None,
+                                depth_state: None,
                             },
                         );
                     } else {
@@ -2738,13 +2749,24 @@ impl NodeTrait for Terrain {
                                     &self.geometry.data,
                                     &material,
                                     RenderPath::Deferred,
-                                    layer_index as u64,
+                                    SortKey::from(layer_index as u64),
                                     SurfaceInstanceData {
                                         world_transform: node_transform,
                                         bone_matrices: Default::default(),
                                         blend_shapes_weights: Default::default(),
                                         element_range: self.geometry.quadrants[i],
                                         node_handle: self.handle(),
+                                        lod_level: None,
+                                        clip_bounds: None,
+                                        animation_phase:
+                                            SurfaceInstanceData::animation_phase_from_position(
+                                                &self.global_position(),
+                                            ),
+                                        instance_params: Default::default(),
+                                        stencil: None,
+                                        polygon_offset: None,
+                                        cull_override: None,
+                                        depth_state: None,
                                     },
                                 );
                             }
@@ -221,7 +221,7 @@ fn push_highlight(
         StaticVertex::layout(),
         material,
         RenderPath::Forward,
-        sort_index,
+        sort_index.into(),
         ctx.tile_map_handle(),
         &mut move |mut vertex_buffer, mut triangle_buffer| {
             let start_vertex_index = vertex_buffer.vertex_count();
@@ -254,7 +254,7 @@ fn push_cursor(
         StaticVertex::layout(),
         material,
         RenderPath::Forward,
-        sort_index,
+        sort_index.into(),
         ctx.tile_map_handle(),
         &mut move |mut vertex_buffer, mut triangle_buffer| {
             let start_vertex_index = vertex_buffer.vertex_count();
@@ -207,7 +207,7 @@ impl TileMapRenderContext<'_, '_> {
             RectangleVertex::layout(),
             &STANDARD_2D.resource,
             RenderPath::Forward,
-            sort_index,
+            sort_index.into(),
             self.tile_map_handle,
             &mut move |mut vertex_buffer, mut triangle_buffer| {
                 let start_vertex_index = vertex_buffer.vertex_count();
@@ -252,7 +252,7 @@ impl TileMapRenderContext<'_, '_> {
             TileVertex::layout(),
             material,
             RenderPath::Forward,
-            sort_index,
+            sort_index.into(),
             self.tile_map_handle,
             &mut move |mut vertex_buffer, mut triangle_buffer| {
                 let start_vertex_index = vertex_buffer.vertex_count();
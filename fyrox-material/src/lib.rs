@@ -607,6 +607,86 @@ impl Default for MaterialProperty {
 pub struct Material {
     shader: ShaderResource,
     resource_bindings: FxHashMap<ImmutableString, MaterialResourceBinding>,
+    pass_hint: PassHint,
+    premultiplied_alpha: bool,
+    coverage_mode: CoverageMode,
+}
+
+/// A hint for the renderer that tells which kind of render pass the material is meant for. It is
+/// used only when an object is rendered with automatic render path selection, explicit render paths
+/// always take precedence over the hint.
+#[derive(
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Visit,
+    Reflect,
+    AsRefStr,
+    EnumString,
+    VariantNames,
+    TypeUuidProvider,
+)]
+#[type_uuid(id = "6f0e3f7a-5b1c-4b8e-9d2a-3c7e41a0d5b2")]
+pub enum PassHint {
+    /// The material is fully opaque and could be rendered in the deferred pass.
+    #[default]
+    Opaque,
+    /// The material is opaque, but discards some fragments (for example, by alpha testing). It
+    /// could be rendered in the deferred pass as well.
+    Masked,
+    /// The material is translucent and must be rendered in the forward pass with blending.
+    Transparent,
+}
+
+impl PassHint {
+    /// Returns `true` if the hint requires blending.
+    pub fn is_transparent(self) -> bool {
+        self == PassHint::Transparent
+    }
+}
+
+/// Coverage mode of a material, tells the renderer how fragments of the material should affect the
+/// coverage of multisampled pixels. The mode is a part of the pipeline state, so objects with
+/// different modes are never drawn in the same draw call.
+///
+/// # Multisampling
+///
+/// [`CoverageMode::AlphaToCoverage`] has effect only when the render target is multisampled (MSAA),
+/// the alpha of the fragment is converted to a coverage mask with as many bits as there are samples
+/// per pixel. With single-sampled targets the coverage mask has just one bit and the mode degrades
+/// to a plain alpha test at 0.5. Alpha-to-coverage is meant for opaque or masked materials (foliage,
+/// fences, etc.), it should not be combined with blending.
+#[derive(
+    Default,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    Debug,
+    Visit,
+    Reflect,
+    AsRefStr,
+    EnumString,
+    VariantNames,
+    TypeUuidProvider,
+)]
+#[type_uuid(id = "b3d1c6a2-7e4f-4a59-8c0d-2f6e9a1b5c73")]
+pub enum CoverageMode {
+    /// Standard coverage, a sample is covered if it is inside a triangle.
+    #[default]
+    None,
+    /// The alpha of the fragment defines the coverage of the samples of the pixel, see the
+    /// [multisampling notes](CoverageMode#multisampling).
+    AlphaToCoverage,
+    /// Conservative rasterization, a pixel is covered if a triangle touches any part of it. This is
+    /// a hint, graphics servers that do not support conservative rasterization draw such objects
+    /// with standard coverage.
+    Conservative,
 }
 
 #[derive(Debug, Visit, Clone, Reflect)]
@@ -710,6 +790,13 @@ impl Visit for Material {
                 .visit("ResourceBindings", &mut region)?;
         }
 
+        // Backward compatibility.
+        let _ = self.pass_hint.visit("PassHint", &mut region);
+        let _ = self
+            .premultiplied_alpha
+            .visit("PremultipliedAlpha", &mut region);
+        let _ = self.coverage_mode.visit("CoverageMode", &mut region);
+
         Ok(())
     }
 }
@@ -863,6 +950,9 @@ impl Material {
         Self {
             shader,
             resource_bindings: Default::default(),
+            pass_hint: Default::default(),
+            premultiplied_alpha: false,
+            coverage_mode: Default::default(),
         }
     }
 
@@ -879,6 +969,9 @@ impl Material {
         let mut material = Material {
             shader: Default::default(),
             resource_bindings: Default::default(),
+            pass_hint: Default::default(),
+            premultiplied_alpha: false,
+            coverage_mode: Default::default(),
         };
         let mut visitor = Visitor::load_from_memory(&content)?;
         visitor.blackboard.register(Arc::new(resource_manager));
@@ -1086,6 +1179,39 @@ impl Material {
         &self.shader
     }
 
+    /// Sets a new pass hint of the material. See [`PassHint`] docs for more info.
+    pub fn set_pass_hint(&mut self, pass_hint: PassHint) -> PassHint {
+        std::mem::replace(&mut self.pass_hint, pass_hint)
+    }
+
+    /// Returns current pass hint of the material.
+    pub fn pass_hint(&self) -> PassHint {
+        self.pass_hint
+    }
+
+    /// Defines whether the color produced by the shader of the material has its alpha already
+    /// multiplied into RGB components. The renderer uses this flag to select the blending function
+    /// for transparent objects.
+    pub fn set_premultiplied_alpha(&mut self, premultiplied_alpha: bool) -> bool {
+        std::mem::replace(&mut self.premultiplied_alpha, premultiplied_alpha)
+    }
+
+    /// Returns `true` if the material produces premultiplied-alpha colors, `false` - otherwise.
+    pub fn is_premultiplied_alpha(&self) -> bool {
+        self.premultiplied_alpha
+    }
+
+    /// Sets a new coverage mode of the material and returns the previous one. See [`CoverageMode`]
+    /// for more info.
+    pub fn set_coverage_mode(&mut self, coverage_mode: CoverageMode) -> CoverageMode {
+        std::mem::replace(&mut self.coverage_mode, coverage_mode)
+    }
+
+    /// Returns current coverage mode of the material.
+    pub fn coverage_mode(&self) -> CoverageMode {
+        self.coverage_mode
+    }
+
     /// Returns immutable reference to internal property storage.
     pub fn bindings(&self) -> &FxHashMap<ImmutableString, MaterialResourceBinding> {
         &self.resource_bindings
@@ -1125,6 +1251,18 @@ pub trait MaterialResourceExtension {
     /// Creates a deep copy of the material resource.
     fn deep_copy(&self) -> MaterialResource;
 
+    /// Returns the pass hint of the material. If the material is not loaded, then the default hint
+    /// ([`PassHint::Opaque`]) is returned.
+    fn pass_hint(&self) -> PassHint;
+
+    /// Returns `true` if the material produces premultiplied-alpha colors. If the material is not
+    /// loaded, then `false` is returned.
+    fn is_premultiplied_alpha(&self) -> bool;
+
+    /// Returns the coverage mode of the material. If the material is not loaded, then the default
+    /// mode ([`CoverageMode::None`]) is returned.
+    fn coverage_mode(&self) -> CoverageMode;
+
     /// Creates a deep copy of the material resource and marks it as procedural.
     fn deep_copy_as_embedded(&self) -> MaterialResource {
         let material = self.deep_copy();
@@ -1141,6 +1279,29 @@ impl MaterialResourceExtension for MaterialResource {
         Self::new_ok(Uuid::new_v4(), ResourceKind::Embedded, material)
     }
 
+    fn pass_hint(&self) -> PassHint {
+        let state = self.state();
+        state
+            .data_ref()
+            .map(|material| material.pass_hint())
+            .unwrap_or_default()
+    }
+
+    fn is_premultiplied_alpha(&self) -> bool {
+        let state = self.state();
+        state
+            .data_ref()
+            .is_some_and(|material| material.is_premultiplied_alpha())
+    }
+
+    fn coverage_mode(&self) -> CoverageMode {
+        let state = self.state();
+        state
+            .data_ref()
+            .map(|material| material.coverage_mode())
+            .unwrap_or_default()
+    }
+
     fn deep_copy(&self) -> MaterialResource {
         let material_state = self.header();
         let kind = material_state.kind;
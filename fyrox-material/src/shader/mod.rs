@@ -263,6 +263,8 @@
 //! | blendShapesCount     | `int`      | Total amount of blend shapes.               |
 //! | useSkeletalAnimation | `bool`     | Whether skinned meshes is rendering or not. |
 //! | blendShapesWeights   | `vec4[32]` | Blend shape weights.                        |
+//! | animationPhase       | `float`    | Animation phase of the instance, [0; 1).    |
+//! | instanceParams       | `vec4`     | Arbitrary parameters of the instance.       |
 //!
 //! ### `fyrox_boneMatrices`
 //!
@@ -732,6 +734,8 @@ impl ShaderDefinition {
                             "blendShapesWeights",
                             Self::MAX_BLEND_SHAPE_WEIGHT_GROUPS,
                         ),
+                        ShaderProperty::new_float("animationPhase"),
+                        ShaderProperty::new_vector4("instanceParams"),
                     ]);
                 }
                 "fyrox_boneMatrices" => {
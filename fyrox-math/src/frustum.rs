@@ -67,6 +67,11 @@ impl Frustum {
             Plane::from_abcd(m[3] + m[2], m[7] + m[6], m[11] + m[10], m[15] + m[14])?,
         ];
 
+        Some(Self::from_planes(planes))
+    }
+
+    #[inline]
+    fn from_planes(planes: [Plane; 6]) -> Self {
         let corners = [
             planes[Self::LEFT].intersection_point(&planes[Self::TOP], &planes[Self::FAR]),
             planes[Self::LEFT].intersection_point(&planes[Self::BOTTOM], &planes[Self::FAR]),
@@ -78,7 +83,18 @@ impl Frustum {
             planes[Self::RIGHT].intersection_point(&planes[Self::TOP], &planes[Self::NEAR]),
         ];
 
-        Some(Self { planes, corners })
+        Self { planes, corners }
+    }
+
+    /// Returns a copy of the frustum with every plane moved outwards by the given margin (in world
+    /// units). The corners are recalculated for the new planes. Negative margin shrinks the frustum.
+    #[inline]
+    pub fn inflated(&self, margin: f32) -> Self {
+        // Normals of the planes point inside the frustum.
+        Self::from_planes(self.planes.map(|plane| Plane {
+            normal: plane.normal,
+            d: plane.d + margin,
+        }))
     }
 
     #[inline]
@@ -0,0 +1,206 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Accessibility metadata of widgets. The metadata describes widgets to screen readers and other
+//! assistive technologies: a role defines what a widget is (a button, a check box, etc.) and a label
+//! is a text, that is announced for the widget. The metadata is stored in
+//! [`crate::widget::Widget::accessibility`], see [`Accessibility`] docs for more info.
+
+use crate::{
+    button::Button,
+    check_box::CheckBox,
+    core::{reflect::prelude::*, type_traits::prelude::*, visitor::prelude::*},
+    dropdown_list::DropdownList,
+    image::Image,
+    list_view::{ListView, ListViewItem},
+    menu::{Menu, MenuItem},
+    progress_bar::ProgressBar,
+    scroll_bar::ScrollBar,
+    text::Text,
+    text_box::TextBox,
+    tree::{Tree, TreeRoot},
+    window::Window,
+    UiNode,
+};
+use strum_macros::{AsRefStr, EnumString, VariantNames};
+
+/// A role of a widget for assistive technologies.
+#[derive(
+    Visit,
+    Reflect,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    TypeUuidProvider,
+    AsRefStr,
+    EnumString,
+    VariantNames,
+)]
+#[type_uuid(id = "5d1e8b3a-c7f2-4e69-a0b4-92f3d6c1e7a8")]
+pub enum AccessibilityRole {
+    /// A widget without any specific role (a container, a decorator, etc.).
+    #[default]
+    Generic,
+    /// A button.
+    Button,
+    /// A check box.
+    CheckBox,
+    /// An editable text field.
+    TextInput,
+    /// A static text.
+    Text,
+    /// An image.
+    Image,
+    /// A widget, that allows to select a value from a range (a scroll bar, a slider, etc.).
+    Slider,
+    /// A progress indicator.
+    ProgressBar,
+    /// A list of items.
+    List,
+    /// An item of a list.
+    ListItem,
+    /// A dropdown list of items.
+    ComboBox,
+    /// A menu.
+    Menu,
+    /// An item of a menu.
+    MenuItem,
+    /// A tree of items.
+    Tree,
+    /// An item of a tree.
+    TreeItem,
+    /// A window.
+    Window,
+}
+
+impl AccessibilityRole {
+    /// Returns a role, that is implied by the type of the given widget. It is used, when
+    /// accessibility metadata of the widget does not specify a role explicitly.
+    pub fn of_widget(widget: &UiNode) -> Self {
+        if widget.cast::<Button>().is_some() {
+            Self::Button
+        } else if widget.cast::<CheckBox>().is_some() {
+            Self::CheckBox
+        } else if widget.cast::<TextBox>().is_some() {
+            Self::TextInput
+        } else if widget.cast::<Text>().is_some() {
+            Self::Text
+        } else if widget.cast::<Image>().is_some() {
+            Self::Image
+        } else if widget.cast::<ScrollBar>().is_some() {
+            Self::Slider
+        } else if widget.cast::<ProgressBar>().is_some() {
+            Self::ProgressBar
+        } else if widget.cast::<ListView>().is_some() {
+            Self::List
+        } else if widget.cast::<ListViewItem>().is_some() {
+            Self::ListItem
+        } else if widget.cast::<DropdownList>().is_some() {
+            Self::ComboBox
+        } else if widget.cast::<Menu>().is_some() {
+            Self::Menu
+        } else if widget.cast::<MenuItem>().is_some() {
+            Self::MenuItem
+        } else if widget.cast::<TreeRoot>().is_some() {
+            Self::Tree
+        } else if widget.cast::<Tree>().is_some() {
+            Self::TreeItem
+        } else if widget.cast::<Window>().is_some() {
+            Self::Window
+        } else {
+            Self::Generic
+        }
+    }
+
+    /// Returns the effective role of the given widget: the role of its accessibility metadata, or
+    /// the role implied by the type of the widget, if the metadata does not specify a role.
+    pub fn effective(widget: &UiNode) -> Self {
+        widget
+            .accessibility
+            .as_ref()
+            .and_then(|accessibility| accessibility.role)
+            .unwrap_or_else(|| Self::of_widget(widget))
+    }
+}
+
+/// Accessibility metadata of a widget.
+#[derive(Visit, Reflect, Clone, Debug, Default, PartialEq, TypeUuidProvider)]
+#[type_uuid(id = "b4f0a6d2-3e81-4c5b-8d7a-e1c9f2b05e36")]
+pub struct Accessibility {
+    /// A role of the widget. [`None`] means that the role is implied by the type of the widget,
+    /// see [`AccessibilityRole::of_widget`].
+    pub role: Option<AccessibilityRole>,
+    /// A text, that is announced for the widget. Empty label means that assistive technologies
+    /// should use the content of the widget (if any).
+    pub label: String,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        accessibility::{Accessibility, AccessibilityRole},
+        button::ButtonBuilder,
+        check_box::CheckBoxBuilder,
+        widget::WidgetBuilder,
+        UserInterface,
+    };
+    use fyrox_core::algebra::Vector2;
+    use fyrox_graph::BaseSceneGraph;
+
+    #[test]
+    fn test_effective_role() {
+        let mut ui = UserInterface::new(Vector2::new(100.0, 100.0));
+        let ctx = &mut ui.build_ctx();
+        let button = ButtonBuilder::new(WidgetBuilder::new()).build(ctx);
+        let check_box =
+            CheckBoxBuilder::new(WidgetBuilder::new().with_accessibility(Accessibility {
+                role: None,
+                label: "Enabled".to_string(),
+            }))
+            .build(ctx);
+        let menu_like_button =
+            ButtonBuilder::new(WidgetBuilder::new().with_accessibility(Accessibility {
+                role: Some(AccessibilityRole::MenuItem),
+                label: Default::default(),
+            }))
+            .build(ctx);
+
+        assert_eq!(
+            AccessibilityRole::effective(ui.node(button)),
+            AccessibilityRole::Button
+        );
+        assert_eq!(
+            AccessibilityRole::effective(ui.node(check_box)),
+            AccessibilityRole::CheckBox
+        );
+        assert_eq!(
+            AccessibilityRole::effective(ui.node(menu_like_button)),
+            AccessibilityRole::MenuItem
+        );
+        assert_eq!(
+            AccessibilityRole::effective(ui.node(ui.root())),
+            AccessibilityRole::Generic
+        );
+    }
+}
@@ -28,8 +28,9 @@ use crate::{
     border::BorderBuilder,
     brush::Brush,
     core::{
-        algebra::Vector2, color::Color, pool::Handle, reflect::prelude::*, type_traits::prelude::*,
-        variable::InheritableVariable, visitor::prelude::*,
+        algebra::Vector2, color::Color, pool::Handle, reflect::prelude::*,
+        sstorage::ImmutableString, type_traits::prelude::*, variable::InheritableVariable,
+        visitor::prelude::*,
     },
     define_constructor,
     grid::{Column, GridBuilder, Row},
@@ -163,6 +164,12 @@ pub struct CheckBox {
     pub uncheck_mark: InheritableVariable<Handle<UiNode>>,
     /// Check mark that is used when the state is `None`.
     pub undefined_mark: InheritableVariable<Handle<UiNode>>,
+    /// Name of a radio group of the check box. Only one check box of a group could be checked at
+    /// a time: when a check box is checked, every other check box of the same group in the user
+    /// interface is unchecked. A checked check box of a group can't be unchecked by a click. An
+    /// empty name means that the check box does not belong to any group.
+    #[visit(optional)]
+    pub group: InheritableVariable<ImmutableString>,
 }
 
 impl CheckBox {
@@ -196,6 +203,29 @@ impl ConstructorProvider<UiNode, UserInterface> for CheckBox {
 
 crate::define_widget_deref!(CheckBox);
 
+impl CheckBox {
+    fn is_radio_locked(&self) -> bool {
+        !self.group.is_empty() && *self.checked == Some(true)
+    }
+
+    fn uncheck_group_members(&self, ui: &UserInterface) {
+        for (handle, node) in ui.nodes().pair_iter() {
+            if let Some(check_box) = node.cast::<CheckBox>() {
+                if handle != self.handle
+                    && *check_box.checked == Some(true)
+                    && *check_box.group == *self.group
+                {
+                    ui.send_message(CheckBoxMessage::checked(
+                        handle,
+                        MessageDirection::ToWidget,
+                        Some(false),
+                    ));
+                }
+            }
+        }
+    }
+}
+
 impl Control for CheckBox {
     fn handle_routed_message(&mut self, ui: &mut UserInterface, message: &mut UiMessage) {
         self.widget.handle_routed_message(ui, message);
@@ -217,7 +247,10 @@ impl Control for CheckBox {
                     {
                         ui.release_mouse_capture();
 
-                        if let Some(value) = *self.checked {
+                        if self.is_radio_locked() {
+                            // A checked member of a radio group could be unchecked only by
+                            // checking another member of the group.
+                        } else if let Some(value) = *self.checked {
                             // Invert state if it is defined.
                             ui.send_message(CheckBoxMessage::checked(
                                 self.handle(),
@@ -236,11 +269,13 @@ impl Control for CheckBox {
                 }
                 WidgetMessage::KeyDown(key_code) => {
                     if !message.handled() && *key_code == KeyCode::Space {
-                        ui.send_message(CheckBoxMessage::checked(
-                            self.handle,
-                            MessageDirection::ToWidget,
-                            self.checked.map(|checked| !checked),
-                        ));
+                        if !self.is_radio_locked() {
+                            ui.send_message(CheckBoxMessage::checked(
+                                self.handle,
+                                MessageDirection::ToWidget,
+                                self.checked.map(|checked| !checked),
+                            ));
+                        }
                         message.set_handled(true);
                     }
                 }
@@ -255,6 +290,10 @@ impl Control for CheckBox {
 
                 ui.send_message(message.reverse());
 
+                if value == Some(true) && !self.group.is_empty() {
+                    self.uncheck_group_members(ui);
+                }
+
                 if self.check_mark.is_some() {
                     match value {
                         None => {
@@ -307,6 +346,7 @@ pub struct CheckBoxBuilder {
     undefined_mark: Option<Handle<UiNode>>,
     background: Option<Handle<UiNode>>,
     content: Handle<UiNode>,
+    group: ImmutableString,
 }
 
 impl CheckBoxBuilder {
@@ -320,6 +360,7 @@ impl CheckBoxBuilder {
             undefined_mark: None,
             content: Handle::NONE,
             background: None,
+            group: Default::default(),
         }
     }
 
@@ -360,6 +401,12 @@ impl CheckBoxBuilder {
         self
     }
 
+    /// Sets the name of a radio group of the check box. See [`CheckBox::group`] for more info.
+    pub fn with_group(mut self, group: impl Into<ImmutableString>) -> Self {
+        self.group = group.into();
+        self
+    }
+
     /// Finishes check box building and adds it to the user interface.
     pub fn build(self, ctx: &mut BuildContext) -> Handle<UiNode> {
         let check_mark = self.check_mark.unwrap_or_else(|| {
@@ -482,6 +529,7 @@ impl CheckBoxBuilder {
             check_mark: check_mark.into(),
             uncheck_mark: uncheck_mark.into(),
             undefined_mark: undefined_mark.into(),
+            group: self.group.into(),
         };
         ctx.add_node(UiNode::new(cb))
     }
@@ -490,12 +538,13 @@ impl CheckBoxBuilder {
 #[cfg(test)]
 mod test {
     use crate::{
-        check_box::{CheckBoxBuilder, CheckBoxMessage},
+        check_box::{CheckBox, CheckBoxBuilder, CheckBoxMessage},
         message::MessageDirection,
         widget::WidgetBuilder,
         UserInterface,
     };
     use fyrox_core::algebra::Vector2;
+    use fyrox_graph::BaseSceneGraph;
 
     #[test]
     fn check_box() {
@@ -519,6 +568,35 @@ mod test {
         assert_eq!(ui.poll_message(), Some(input_message.reverse()));
     }
 
+    #[test]
+    fn test_radio_group() {
+        let mut ui = UserInterface::new(Vector2::new(100.0, 100.0));
+
+        let ctx = &mut ui.build_ctx();
+        let a = CheckBoxBuilder::new(WidgetBuilder::new())
+            .with_group("Group")
+            .checked(Some(true))
+            .build(ctx);
+        let b = CheckBoxBuilder::new(WidgetBuilder::new())
+            .with_group("Group")
+            .build(ctx);
+        let other = CheckBoxBuilder::new(WidgetBuilder::new())
+            .checked(Some(true))
+            .build(ctx);
+
+        ui.send_message(CheckBoxMessage::checked(
+            b,
+            MessageDirection::ToWidget,
+            Some(true),
+        ));
+        while ui.poll_message().is_some() {}
+
+        let checked = |handle| *ui.node(handle).cast::<CheckBox>().unwrap().checked;
+        assert_eq!(checked(a), Some(false));
+        assert_eq!(checked(b), Some(true));
+        assert_eq!(checked(other), Some(true));
+    }
+
     use crate::test::test_widget_deletion;
 
     #[test]
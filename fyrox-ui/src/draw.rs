@@ -866,8 +866,12 @@ impl DrawingContext {
         &self.command_buffer
     }
 
+    /// Pushes new opacity on the stack. The opacity is multiplied by the current one, so nested
+    /// opacities are combined (for example, opacity of a widget is multiplied by the opacity of its
+    /// ancestors).
     pub fn push_opacity(&mut self, opacity: f32) {
-        self.opacity_stack.push(opacity);
+        let current = self.opacity_stack.last().copied().unwrap_or(1.0);
+        self.opacity_stack.push(current * opacity);
     }
 
     pub fn pop_opacity(&mut self) {
@@ -0,0 +1,145 @@
+// Copyright (c) 2019-present Dmitry Stepanov and Fyrox Engine contributors.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Event handler bindings of widgets. A binding maps an event of a widget to a name of a handler,
+//! that could be resolved by scripts (or any other user code) when the event is emitted. The
+//! bindings are stored in [`crate::widget::Widget::event_handlers`], see [`WidgetEvent`] for the
+//! list of supported events.
+
+use crate::{
+    button::Button,
+    check_box::CheckBox,
+    core::{reflect::prelude::*, type_traits::prelude::*, visitor::prelude::*},
+    dropdown_list::DropdownList,
+    list_view::ListView,
+    menu::MenuItem,
+    text_box::TextBox,
+    UiNode,
+};
+use strum_macros::{AsRefStr, EnumString, VariantNames};
+
+/// An event of a widget, that could be bound to a handler.
+#[derive(
+    Visit,
+    Reflect,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    TypeUuidProvider,
+    AsRefStr,
+    EnumString,
+    VariantNames,
+)]
+#[type_uuid(id = "a7c0e1f4-2d36-4b8e-9f51-6c2b8d4e0a13")]
+pub enum WidgetEvent {
+    /// A click on a button or a menu item.
+    #[default]
+    Click,
+    /// A change of the value of a widget with `value` property (scroll bar, numeric up-down, etc.).
+    ValueChanged,
+    /// A change of the checked state of a check box.
+    Checked,
+    /// A change of the text of a text box.
+    TextChanged,
+    /// A change of the selection of a list view or a dropdown list.
+    SelectionChanged,
+}
+
+impl WidgetEvent {
+    /// Checks whether the given widget emits the event. Value changes are detected using
+    /// reflection: any widget with `value` property is considered to emit them.
+    pub fn is_emitted_by(&self, widget: &UiNode) -> bool {
+        match self {
+            WidgetEvent::Click => {
+                widget.cast::<Button>().is_some() || widget.cast::<MenuItem>().is_some()
+            }
+            WidgetEvent::ValueChanged => {
+                let mut has_value = false;
+                widget.field("value", &mut |field| has_value = field.is_some());
+                has_value
+            }
+            WidgetEvent::Checked => widget.cast::<CheckBox>().is_some(),
+            WidgetEvent::TextChanged => widget.cast::<TextBox>().is_some(),
+            WidgetEvent::SelectionChanged => {
+                widget.cast::<ListView>().is_some() || widget.cast::<DropdownList>().is_some()
+            }
+        }
+    }
+
+    /// Returns a list of events, that are emitted by the given widget.
+    pub fn emitted_by(widget: &UiNode) -> Vec<WidgetEvent> {
+        [
+            WidgetEvent::Click,
+            WidgetEvent::ValueChanged,
+            WidgetEvent::Checked,
+            WidgetEvent::TextChanged,
+            WidgetEvent::SelectionChanged,
+        ]
+        .into_iter()
+        .filter(|event| event.is_emitted_by(widget))
+        .collect()
+    }
+}
+
+/// A binding of an event of a widget to a named handler.
+#[derive(Visit, Reflect, Clone, Debug, Default, PartialEq, TypeUuidProvider)]
+#[type_uuid(id = "3e9b5f27-81c4-4d0a-b6e2-f4a9c1d7e852")]
+pub struct EventHandlerBinding {
+    /// An event of the widget.
+    pub event: WidgetEvent,
+    /// A name of the handler of the event.
+    pub handler: String,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        button::ButtonBuilder, check_box::CheckBoxBuilder, event_handler::WidgetEvent,
+        scroll_bar::ScrollBarBuilder, widget::WidgetBuilder, UserInterface,
+    };
+    use fyrox_core::algebra::Vector2;
+    use fyrox_graph::BaseSceneGraph;
+
+    #[test]
+    fn test_emitted_events() {
+        let mut ui = UserInterface::new(Vector2::new(100.0, 100.0));
+        let ctx = &mut ui.build_ctx();
+        let button = ButtonBuilder::new(WidgetBuilder::new()).build(ctx);
+        let check_box = CheckBoxBuilder::new(WidgetBuilder::new()).build(ctx);
+        let scroll_bar = ScrollBarBuilder::new(WidgetBuilder::new()).build(ctx);
+
+        assert_eq!(
+            WidgetEvent::emitted_by(ui.node(button)),
+            vec![WidgetEvent::Click]
+        );
+        assert_eq!(
+            WidgetEvent::emitted_by(ui.node(check_box)),
+            vec![WidgetEvent::Checked]
+        );
+        assert_eq!(
+            WidgetEvent::emitted_by(ui.node(scroll_bar)),
+            vec![WidgetEvent::ValueChanged]
+        );
+    }
+}
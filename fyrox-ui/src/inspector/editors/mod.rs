@@ -24,6 +24,7 @@
 use crate::inspector::editors::texture_slice::TextureSlicePropertyEditorDefinition;
 use crate::{
     absm::{EventAction, EventKind},
+    accessibility::{Accessibility, AccessibilityRole},
     bit::BitField,
     border::Border,
     brush::{Brush, GradientPoint},
@@ -43,6 +44,7 @@ use crate::{
     },
     decorator::Decorator,
     dropdown_list::DropdownList,
+    event_handler::{EventHandlerBinding, WidgetEvent},
     expander::Expander,
     formatted_text::{FormattedText, WrapMode},
     grid::{Grid, GridDimension, SizeMode},
@@ -82,6 +84,7 @@ use crate::{
     menu::{Menu, MenuItem},
     message::{CursorIcon, UiMessage},
     messagebox::MessageBox,
+    navigation::FocusNavigation,
     nine_patch::{NinePatch, StretchMode},
     numeric::NumericUpDown,
     path::PathEditor,
@@ -100,7 +103,7 @@ use crate::{
     uuid::UuidEditor,
     vec::VecEditor,
     vector_image::{Primitive, VectorImage},
-    widget::Widget,
+    widget::{DragAxis, DragBehavior, Widget},
     window::Window,
     wrap_panel::WrapPanel,
     BuildContext, HorizontalAlignment, Orientation, RcUiNodeHandle, RcUiNodeHandleInner, Thickness,
@@ -569,6 +572,24 @@ impl PropertyEditorDefinitionContainer {
         container.insert(InspectablePropertyEditorDefinition::<EventAction>::new());
         container.register_inheritable_vec_collection::<EventAction>();
 
+        container.register_inheritable_enum::<WidgetEvent, _>();
+        container.insert(InspectablePropertyEditorDefinition::<EventHandlerBinding>::new());
+        container.register_inheritable_vec_collection::<EventHandlerBinding>();
+
+        container.register_inheritable_enum::<AccessibilityRole, _>();
+        container.insert(EnumPropertyEditorDefinition::<AccessibilityRole>::new_optional());
+        container.insert(InspectablePropertyEditorDefinition::<Accessibility>::new());
+        container.insert(EnumPropertyEditorDefinition::<Accessibility>::new_optional());
+        container.insert(InheritablePropertyEditorDefinition::<Option<Accessibility>>::new());
+
+        container.insert(InspectablePropertyEditorDefinition::<FocusNavigation>::new());
+        container.insert(InheritablePropertyEditorDefinition::<FocusNavigation>::new());
+
+        container.register_inheritable_enum::<DragAxis, _>();
+        container.insert(InspectablePropertyEditorDefinition::<DragBehavior>::new());
+        container.insert(EnumPropertyEditorDefinition::<DragBehavior>::new_optional());
+        container.insert(InheritablePropertyEditorDefinition::<Option<DragBehavior>>::new());
+
         container.insert(EnumPropertyEditorDefinition::<Parameter>::new());
 
         container.insert(EnumPropertyEditorDefinition::<TextCommitMode>::new());
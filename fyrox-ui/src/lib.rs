@@ -206,6 +206,7 @@ pub use fyrox_core as core;
 use message::TouchPhase;
 
 pub mod absm;
+pub mod accessibility;
 mod alignment;
 pub mod animation;
 pub mod bit;
@@ -223,6 +224,7 @@ pub mod dock;
 pub mod draw;
 pub mod dropdown_list;
 pub mod dropdown_menu;
+pub mod event_handler;
 pub mod expander;
 pub mod file_browser;
 pub mod font;
@@ -293,7 +295,7 @@ use crate::{
         UiMessage,
     },
     popup::{Placement, PopupMessage},
-    widget::{Widget, WidgetBuilder, WidgetMessage},
+    widget::{DragAxis, Widget, WidgetBuilder, WidgetMessage},
 };
 use copypasta::ClipboardContext;
 use fxhash::{FxHashMap, FxHashSet};
@@ -304,6 +306,7 @@ use fyrox_resource::{
 use serde::{Deserialize, Serialize};
 use std::{
     any::TypeId,
+    borrow::Cow,
     cell::{Ref, RefCell, RefMut},
     collections::{btree_set::BTreeSet, hash_map::Entry, VecDeque},
     error::Error,
@@ -513,6 +516,13 @@ pub struct DragContext {
     pub drag_node: Handle<UiNode>,
     pub click_pos: Vector2<f32>,
     pub drag_preview: Handle<UiNode>,
+    /// A widget with [`Widget::drag_behavior`], that is being moved by the mouse.
+    #[visit(optional)]
+    pub move_node: Handle<UiNode>,
+    /// Position of the mouse cursor relative to the moved widget, in local coordinates of the
+    /// parent of the widget.
+    #[visit(optional)]
+    pub move_offset: Vector2<f32>,
 }
 
 impl Default for DragContext {
@@ -522,6 +532,8 @@ impl Default for DragContext {
             drag_node: Default::default(),
             click_pos: Vector2::new(0.0, 0.0),
             drag_preview: Default::default(),
+            move_node: Default::default(),
+            move_offset: Default::default(),
         }
     }
 }
@@ -599,6 +611,16 @@ struct DoubleClickEntry {
     click_count: u32,
 }
 
+/// An animation of the opacity of a widget, see [`UserInterface::fade_opacity`].
+#[derive(Clone, Debug)]
+struct OpacityFade {
+    widget: Handle<UiNode>,
+    from: f32,
+    to: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
 struct Clipboard(Option<RefCell<ClipboardContext>>);
 
 impl Debug for Clipboard {
@@ -710,6 +732,8 @@ pub struct UserInterface {
     pub default_font: FontResource,
     #[reflect(hidden)]
     double_click_entries: FxHashMap<MouseButton, DoubleClickEntry>,
+    #[reflect(hidden)]
+    opacity_fades: Vec<OpacityFade>,
     pub double_click_time_slice: f32,
     pub tooltip_appear_delay: f32,
     pub standard_material: WidgetMaterial,
@@ -805,6 +829,7 @@ impl Clone for UserInterface {
             z_index_update_set: self.z_index_update_set.clone(),
             default_font: self.default_font.clone(),
             double_click_entries: self.double_click_entries.clone(),
+            opacity_fades: self.opacity_fades.clone(),
             double_click_time_slice: self.double_click_time_slice,
             tooltip_appear_delay: self.tooltip_appear_delay,
             standard_material: Default::default(),
@@ -968,6 +993,28 @@ fn is_on_screen(node: &UiNode, nodes: &Pool<UiNode, WidgetContainer>) -> bool {
     true
 }
 
+/// Returns children of the node sorted by their render order (see [`Widget::render_order`]). The
+/// sorting is stable, so siblings with the same render order keep the order of children.
+fn children_in_render_order<'a>(
+    nodes: &Pool<UiNode, WidgetContainer>,
+    node: &'a UiNode,
+) -> Cow<'a, [Handle<UiNode>]> {
+    let render_order = |handle: &Handle<UiNode>| {
+        nodes
+            .try_borrow(*handle)
+            .map(|child| *child.render_order)
+            .unwrap_or_default()
+    };
+
+    if node.children().iter().all(|child| render_order(child) == 0) {
+        Cow::Borrowed(node.children())
+    } else {
+        let mut children = node.children().to_vec();
+        children.sort_by_key(render_order);
+        Cow::Owned(children)
+    }
+}
+
 fn draw_node(
     nodes: &Pool<UiNode, WidgetContainer>,
     node_handle: Handle<UiNode>,
@@ -982,14 +1029,17 @@ fn draw_node(
         return;
     }
 
-    let pushed = if !is_node_enabled(nodes, node_handle) {
-        drawing_context.push_opacity(0.4);
-        true
-    } else if let Some(opacity) = node.opacity() {
-        drawing_context.push_opacity(opacity);
-        true
-    } else {
-        false
+    // Opacities are multiplied down the hierarchy, so a disabled branch is dimmed only once, by
+    // its topmost disabled widget.
+    let dimmed =
+        !node.enabled() && (node.parent().is_none() || is_node_enabled(nodes, node.parent()));
+    let pushed = match (node.opacity(), dimmed) {
+        (None, false) => false,
+        (opacity, dimmed) => {
+            let dimming = if dimmed { 0.4 } else { 1.0 };
+            drawing_context.push_opacity(opacity.unwrap_or(1.0) * dimming);
+            true
+        }
     };
 
     drawing_context.transform_stack.push(node.visual_transform);
@@ -1005,7 +1055,7 @@ fn draw_node(
     }
 
     // Continue on children
-    for &child_node in node.children().iter() {
+    for &child_node in children_in_render_order(nodes, node).iter() {
         // Do not continue render of top-most nodes - they'll be rendered in separate pass.
         if !nodes[child_node].is_draw_on_top() {
             draw_node(nodes, child_node, drawing_context);
@@ -1107,6 +1157,7 @@ impl UserInterface {
             z_index_update_set: Default::default(),
             default_font: BUILT_IN_FONT.resource(),
             double_click_entries: Default::default(),
+            opacity_fades: Default::default(),
             double_click_time_slice: 0.5, // 500 ms is standard in most operating systems.
             tooltip_appear_delay: 0.55,
             standard_material: Default::default(),
@@ -1333,6 +1384,7 @@ impl UserInterface {
         }
 
         self.update_tooltips(dt);
+        self.update_opacity_fades(dt);
 
         if !self.drag_context.is_dragging {
             // Try to fetch new cursor icon starting from current picked node. Traverse
@@ -1498,6 +1550,9 @@ impl UserInterface {
                 size.y = node.height();
             }
 
+            size =
+                constrain_to_aspect_ratio(size, node.width(), node.height(), node.aspect_ratio());
+
             size = transform_size(size, &node.layout_transform);
 
             if !node.ignore_layout_rounding {
@@ -1595,6 +1650,13 @@ impl UserInterface {
                 desired_size.y = node.height();
             }
 
+            desired_size = constrain_to_aspect_ratio(
+                desired_size,
+                node.width(),
+                node.height(),
+                node.aspect_ratio(),
+            );
+
             desired_size.x = desired_size.x.clamp(node.min_size().x, node.max_size().x);
             desired_size.y = desired_size.y.clamp(node.min_size().y, node.max_size().y);
 
@@ -1691,7 +1753,7 @@ impl UserInterface {
             (Handle::NONE, 0)
         };
 
-        for child_handle in widget.children() {
+        for child_handle in children_in_render_order(&self.nodes, widget).iter() {
             *level += 1;
             let picked_child = self.pick_node(*child_handle, pt, level);
             if picked_child.is_some() && *level > topmost_picked_level {
@@ -2207,6 +2269,64 @@ impl UserInterface {
         }
     }
 
+    /// Smoothly changes the opacity of the given widget from its current value (or 1.0, if the widget
+    /// has no opacity) to the target value over the given duration (in seconds). The opacity is
+    /// changed by [`Self::update`], the widget has the exact target opacity once the fade is done.
+    /// A new fade replaces the active fade of the widget (if any). Zero or negative duration sets
+    /// the target opacity immediately.
+    pub fn fade_opacity(&mut self, widget: Handle<UiNode>, target: f32, duration: f32) {
+        self.cancel_opacity_fade(widget);
+
+        let Some(node) = self.nodes.try_borrow_mut(widget) else {
+            return;
+        };
+
+        if duration > 0.0 {
+            self.opacity_fades.push(OpacityFade {
+                widget,
+                from: node.opacity().unwrap_or(1.0),
+                to: target,
+                duration,
+                elapsed: 0.0,
+            });
+        } else {
+            node.set_opacity(Some(target));
+        }
+    }
+
+    /// Stops the active opacity fade of the given widget (if any), the widget keeps its current
+    /// opacity. See [`Self::fade_opacity`].
+    pub fn cancel_opacity_fade(&mut self, widget: Handle<UiNode>) {
+        self.opacity_fades.retain(|fade| fade.widget != widget);
+    }
+
+    /// Returns `true` if the given widget has an active opacity fade, `false` - otherwise.
+    pub fn is_opacity_fading(&self, widget: Handle<UiNode>) -> bool {
+        self.opacity_fades.iter().any(|fade| fade.widget == widget)
+    }
+
+    /// Returns the opacity, that the given widget will have once its active opacity fade is done,
+    /// or [`None`] if the widget is not fading. See [`Self::fade_opacity`].
+    pub fn opacity_fade_target(&self, widget: Handle<UiNode>) -> Option<f32> {
+        self.opacity_fades
+            .iter()
+            .find(|fade| fade.widget == widget)
+            .map(|fade| fade.to)
+    }
+
+    fn update_opacity_fades(&mut self, dt: f32) {
+        let nodes = &mut self.nodes;
+        self.opacity_fades.retain_mut(|fade| {
+            let Some(node) = nodes.try_borrow_mut(fade.widget) else {
+                return false;
+            };
+            fade.elapsed += dt;
+            let t = (fade.elapsed / fade.duration).min(1.0);
+            node.set_opacity(Some(fade.from + (fade.to - fade.from) * t));
+            t < 1.0
+        });
+    }
+
     /// Find any tooltips that are being hovered and activate them.
     /// As well, update their time.
     fn update_tooltips(&mut self, dt: f32) {
@@ -2319,6 +2439,65 @@ impl UserInterface {
         }
     }
 
+    /// Looks for a widget with drag behavior (see [`Widget::drag_behavior`]) in the hierarchy starting
+    /// from the picked node and remembers where it was grabbed.
+    fn try_start_move(&mut self) {
+        self.drag_context.move_node = Handle::NONE;
+
+        let mut handle = self.picked_node;
+        while let Some(node) = self.nodes.try_borrow(handle) {
+            if node.drag_behavior().is_some() {
+                let cursor = self.parent_local_cursor_position(node);
+                self.drag_context.move_node = handle;
+                self.drag_context.move_offset = cursor - node.actual_local_position();
+                break;
+            }
+            handle = node.parent();
+        }
+    }
+
+    /// Moves the widget grabbed by [`Self::try_start_move`] to the cursor, respecting its drag
+    /// behavior.
+    fn move_dragged_node(&mut self) {
+        let Some(node) = self.nodes.try_borrow(self.drag_context.move_node) else {
+            return;
+        };
+        let Some(drag_behavior) = node.drag_behavior() else {
+            return;
+        };
+
+        let current = node.actual_local_position();
+        let mut position = self.parent_local_cursor_position(node) - self.drag_context.move_offset;
+        match drag_behavior.axis {
+            DragAxis::Both => (),
+            DragAxis::Horizontal => position.y = current.y,
+            DragAxis::Vertical => position.x = current.x,
+        }
+        if drag_behavior.keep_in_parent {
+            if let Some(parent) = self.nodes.try_borrow(node.parent()) {
+                let max = (parent.actual_local_size() - node.actual_local_size())
+                    .sup(&Vector2::default());
+                position = position.sup(&Vector2::default()).inf(&max);
+            }
+        }
+
+        if position != current {
+            self.send_message(WidgetMessage::desired_position(
+                self.drag_context.move_node,
+                MessageDirection::ToWidget,
+                position,
+            ));
+        }
+    }
+
+    fn parent_local_cursor_position(&self, node: &UiNode) -> Vector2<f32> {
+        self.nodes
+            .try_borrow(node.parent())
+            .map_or(self.cursor_position, |parent| {
+                parent.screen_to_local(self.cursor_position)
+            })
+    }
+
     /// Translates raw window event into some specific UI message. This is one of the
     /// most important methods of UI. You must call it each time you received a message
     /// from a window.
@@ -2384,6 +2563,10 @@ impl UserInterface {
                             self.drag_context.click_pos = self.cursor_position;
                         }
 
+                        if button == MouseButton::Left {
+                            self.try_start_move();
+                        }
+
                         self.request_focus(self.picked_node);
 
                         if self.picked_node.is_some() {
@@ -2406,6 +2589,10 @@ impl UserInterface {
                         }
                     }
                     ButtonState::Released => {
+                        if button == MouseButton::Left {
+                            self.drag_context.move_node = Handle::NONE;
+                        }
+
                         if self.picked_node.is_some() {
                             self.send_message(WidgetMessage::mouse_up(
                                 self.picked_node,
@@ -2480,6 +2667,8 @@ impl UserInterface {
                     self.cursor_icon = CursorIcon::Crosshair;
                 }
 
+                self.move_dragged_node();
+
                 if self.drag_context.is_dragging
                     && self.nodes.is_valid_handle(self.drag_context.drag_preview)
                 {
@@ -3380,6 +3569,37 @@ fn less_than_or_close(value1: f32, value2: f32) -> bool {
     (value1 < value2) || are_close(value1, value2)
 }
 
+/// Adjusts the size so it has the given aspect ratio (width divided by height). If only one of the
+/// explicit `width` and `height` is set, the other dimension is derived from it. Otherwise the
+/// largest rectangle of the given proportions that fits into the size is returned.
+fn constrain_to_aspect_ratio(
+    size: Vector2<f32>,
+    width: f32,
+    height: f32,
+    aspect_ratio: Option<f32>,
+) -> Vector2<f32> {
+    let Some(aspect_ratio) = aspect_ratio.filter(|ratio| ratio.is_finite() && *ratio > 0.0) else {
+        return size;
+    };
+
+    match (width > 0.0, height > 0.0) {
+        (true, false) => Vector2::new(size.x, size.x / aspect_ratio),
+        (false, true) => Vector2::new(size.y * aspect_ratio, size.y),
+        _ => {
+            if size.x.is_infinite() && size.y.is_infinite() {
+                size
+            } else if size.x.is_infinite() {
+                Vector2::new(size.y * aspect_ratio, size.y)
+            } else if size.y.is_infinite() {
+                Vector2::new(size.x, size.x / aspect_ratio)
+            } else {
+                let width = size.x.min(size.y * aspect_ratio);
+                Vector2::new(width, width / aspect_ratio)
+            }
+        }
+    }
+}
+
 /// Calculates a new size for the rect after transforming it with the given matrix. Basically it
 /// finds a new rectangle that can contain the rotated rectangle.
 ///
@@ -3588,13 +3808,17 @@ pub mod test {
 mod test_inner {
     use crate::{
         border::BorderBuilder,
-        core::algebra::{Rotation2, UnitComplex, Vector2},
+        canvas::CanvasBuilder,
+        core::{
+            algebra::{Rotation2, UnitComplex, Vector2},
+            math::Rect,
+        },
         message::MessageDirection,
-        message::{ButtonState, KeyCode},
+        message::{ButtonState, KeyCode, MouseButton},
         text_box::TextBoxBuilder,
         transform_size,
-        widget::{WidgetBuilder, WidgetMessage},
-        OsEvent, UserInterface,
+        widget::{DragAxis, DragBehavior, WidgetBuilder, WidgetMessage},
+        HorizontalAlignment, OsEvent, UserInterface, VerticalAlignment,
     };
     use fyrox_graph::BaseSceneGraph;
 
@@ -3607,6 +3831,208 @@ mod test_inner {
         dbg!(input, transformed);
     }
 
+    #[test]
+    fn test_aspect_ratio() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let widget = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(200.0)
+                .with_aspect_ratio(2.0)
+                .with_horizontal_alignment(HorizontalAlignment::Left)
+                .with_vertical_alignment(VerticalAlignment::Top),
+        )
+        .build(&mut ui.build_ctx());
+        ui.update(screen_size, 0.0, &Default::default());
+        assert_eq!(
+            ui.node(widget).actual_local_size(),
+            Vector2::new(200.0, 100.0)
+        );
+
+        ui.send_message(WidgetMessage::width(
+            widget,
+            MessageDirection::ToWidget,
+            400.0,
+        ));
+        while ui.poll_message().is_some() {}
+        ui.update(screen_size, 0.0, &Default::default());
+        assert_eq!(
+            ui.node(widget).actual_local_size(),
+            Vector2::new(400.0, 200.0)
+        );
+    }
+
+    #[test]
+    fn test_clip_to_bounds() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let child = BorderBuilder::new(WidgetBuilder::new().with_width(300.0).with_height(300.0))
+            .build(&mut ui.build_ctx());
+        let parent = CanvasBuilder::new(
+            WidgetBuilder::new()
+                .with_width(100.0)
+                .with_height(100.0)
+                .with_clip_to_bounds(false)
+                .with_child(child),
+        )
+        .build(&mut ui.build_ctx());
+        ui.update(screen_size, 0.0, &Default::default());
+        assert_eq!(
+            ui.node(child).clip_bounds(),
+            Rect::new(0.0, 0.0, 300.0, 300.0)
+        );
+
+        // Overflowing child must be clipped right after the flag is changed.
+        ui.node_mut(parent).set_clip_to_bounds(true);
+        ui.update(screen_size, 0.0, &Default::default());
+        assert_eq!(
+            ui.node(child).clip_bounds(),
+            Rect::new(0.0, 0.0, 100.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn test_drag_behavior() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let widget = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(20.0)
+                .with_height(20.0)
+                .with_desired_position(Vector2::new(10.0, 10.0))
+                .with_drag_behavior(DragBehavior {
+                    axis: DragAxis::Horizontal,
+                    keep_in_parent: true,
+                }),
+        )
+        .build(&mut ui.build_ctx());
+        CanvasBuilder::new(
+            WidgetBuilder::new()
+                .with_width(100.0)
+                .with_height(100.0)
+                .with_child(widget),
+        )
+        .build(&mut ui.build_ctx());
+        ui.update(screen_size, 0.0, &Default::default());
+        ui.draw();
+
+        let drag = |ui: &mut UserInterface, from: Vector2<f32>, to: Vector2<f32>| {
+            ui.process_os_event(&OsEvent::CursorMoved { position: from });
+            ui.process_os_event(&OsEvent::MouseInput {
+                button: MouseButton::Left,
+                state: ButtonState::Pressed,
+            });
+            ui.process_os_event(&OsEvent::CursorMoved { position: to });
+            ui.process_os_event(&OsEvent::MouseInput {
+                button: MouseButton::Left,
+                state: ButtonState::Released,
+            });
+            while ui.poll_message().is_some() {}
+            ui.update(screen_size, 0.0, &Default::default());
+            ui.draw();
+        };
+
+        // The widget moves only horizontally and stays within the parent.
+        drag(&mut ui, Vector2::new(15.0, 15.0), Vector2::new(200.0, 50.0));
+        assert_eq!(
+            ui.node(widget).actual_local_position(),
+            Vector2::new(80.0, 10.0)
+        );
+
+        // Non-draggable widget stays in place.
+        ui.node_mut(widget).set_drag_behavior(None);
+        drag(&mut ui, Vector2::new(85.0, 15.0), Vector2::new(20.0, 15.0));
+        assert_eq!(
+            ui.node(widget).actual_local_position(),
+            Vector2::new(80.0, 10.0)
+        );
+    }
+
+    #[test]
+    fn test_opacity() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let child = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(50.0)
+                .with_height(50.0)
+                .with_opacity(Some(0.5)),
+        )
+        .build(&mut ui.build_ctx());
+        let parent = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(100.0)
+                .with_height(100.0)
+                .with_opacity(Some(0.5))
+                .with_child(child),
+        )
+        .build(&mut ui.build_ctx());
+        ui.update(screen_size, 0.0, &Default::default());
+
+        // Opacity of the parent is multiplied into the opacity of the child.
+        ui.draw();
+        let opacities = ui
+            .drawing_context
+            .get_commands()
+            .iter()
+            .map(|command| command.opacity)
+            .collect::<Vec<_>>();
+        assert!(opacities.contains(&0.5));
+        assert!(opacities.contains(&0.25));
+
+        ui.fade_opacity(parent, 1.0, 1.0);
+        ui.update(screen_size, 0.5, &Default::default());
+        assert!(ui.is_opacity_fading(parent));
+        assert_eq!(ui.node(parent).opacity(), Some(0.75));
+        assert_eq!(ui.opacity_fade_target(parent), Some(1.0));
+        ui.update(screen_size, 0.6, &Default::default());
+        assert!(!ui.is_opacity_fading(parent));
+        assert_eq!(ui.opacity_fade_target(parent), None);
+        assert_eq!(ui.node(parent).opacity(), Some(1.0));
+    }
+
+    #[test]
+    fn test_hit_test_visibility() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let below = BorderBuilder::new(WidgetBuilder::new().with_width(100.0).with_height(100.0))
+            .build(&mut ui.build_ctx());
+        let child = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(50.0)
+                .with_height(50.0)
+                .with_horizontal_alignment(HorizontalAlignment::Left)
+                .with_vertical_alignment(VerticalAlignment::Top),
+        )
+        .build(&mut ui.build_ctx());
+        // Only widgets that draw something could be picked, so the container must be a border.
+        let container = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(100.0)
+                .with_height(100.0)
+                .with_child(child),
+        )
+        .build(&mut ui.build_ctx());
+        let over_child = Vector2::new(25.0, 25.0);
+        let over_container = Vector2::new(75.0, 75.0);
+
+        ui.update(screen_size, 0.0, &Default::default());
+        ui.draw();
+        assert_eq!(ui.hit_test(over_child), child);
+        assert_eq!(ui.hit_test(over_container), container);
+
+        // The container and all its descendants (even hit-test visible ones) are click-through.
+        ui.node_mut(container).set_hit_test_visibility(false);
+        assert!(ui.node(child).is_hit_test_visible());
+        assert_eq!(ui.hit_test(over_child), below);
+        assert_eq!(ui.hit_test(over_container), below);
+
+        // The child alone is click-through, the container is picked instead.
+        ui.node_mut(container).set_hit_test_visibility(true);
+        ui.node_mut(child).set_hit_test_visibility(false);
+        assert_eq!(ui.hit_test(over_child), container);
+    }
+
     #[test]
     fn center() {
         let screen_size = Vector2::new(1000.0, 1000.0);
@@ -3627,6 +4053,34 @@ mod test_inner {
         assert_eq!(actual_position, expected_position);
     }
 
+    #[test]
+    fn test_render_order() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let mut make_border = |render_order| {
+            BorderBuilder::new(
+                WidgetBuilder::new()
+                    .with_width(100.0)
+                    .with_height(100.0)
+                    .with_render_order(render_order),
+            )
+            .build(&mut ui.build_ctx())
+        };
+        let first = make_border(1);
+        let second = make_border(0);
+        let point = Vector2::new(50.0, 50.0);
+
+        ui.update(screen_size, 0.0, &Default::default());
+        ui.draw();
+        // The first widget is on top, even though it goes before the second one.
+        assert_eq!(ui.hit_test(point), first);
+
+        ui.node_mut(first).set_render_order(0);
+        ui.draw();
+        // Same render order, the order of children is used.
+        assert_eq!(ui.hit_test(point), second);
+    }
+
     #[test]
     fn test_keyboard_focus() {
         let screen_size = Vector2::new(1000.0, 1000.0);
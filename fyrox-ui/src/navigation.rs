@@ -19,14 +19,15 @@
 // SOFTWARE.
 
 //! A widget, that handles keyboard navigation on its descendant widgets using Tab key. See [`NavigationLayer`]
-//! docs for more info and usage examples.
+//! docs for more info and usage examples. The module also contains directional focus navigation
+//! metadata of widgets (see [`FocusNavigation`]), that is used for gamepad-friendly interfaces.
 
 #![warn(missing_docs)]
 
 use crate::{
     core::{
-        pool::Handle, reflect::prelude::*, type_traits::prelude::*, variable::InheritableVariable,
-        visitor::prelude::*,
+        algebra::Vector2, pool::Handle, reflect::prelude::*, type_traits::prelude::*,
+        variable::InheritableVariable, visitor::prelude::*,
     },
     message::{KeyCode, MessageDirection, UiMessage},
     scroll_viewer::{ScrollViewer, ScrollViewerMessage},
@@ -34,8 +35,160 @@ use crate::{
     BuildContext, Control, UiNode, UserInterface,
 };
 
-use fyrox_graph::SceneGraph;
+use fyrox_graph::{BaseSceneGraph, SceneGraph};
 use std::ops::{Deref, DerefMut};
+use strum_macros::{AsRefStr, EnumString, VariantNames};
+
+/// A direction of focus navigation, see [`FocusNavigation`].
+#[derive(
+    Visit,
+    Reflect,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    TypeUuidProvider,
+    AsRefStr,
+    EnumString,
+    VariantNames,
+)]
+#[type_uuid(id = "0c6f4e2b-8a9d-4b71-95e3-d2a7f1c84b60")]
+pub enum FocusDirection {
+    /// Upwards (arrow up key).
+    #[default]
+    Up,
+    /// Downwards (arrow down key).
+    Down,
+    /// To the left (arrow left key).
+    Left,
+    /// To the right (arrow right key).
+    Right,
+}
+
+impl FocusDirection {
+    /// All the directions.
+    pub const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
+
+    /// Returns a direction that corresponds to the given arrow key.
+    pub fn from_key(key: KeyCode) -> Option<Self> {
+        match key {
+            KeyCode::ArrowUp => Some(Self::Up),
+            KeyCode::ArrowDown => Some(Self::Down),
+            KeyCode::ArrowLeft => Some(Self::Left),
+            KeyCode::ArrowRight => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    /// Returns a screen-space unit vector of the direction (Y axis points down).
+    pub fn vector(self) -> Vector2<f32> {
+        match self {
+            Self::Up => Vector2::new(0.0, -1.0),
+            Self::Down => Vector2::new(0.0, 1.0),
+            Self::Left => Vector2::new(-1.0, 0.0),
+            Self::Right => Vector2::new(1.0, 0.0),
+        }
+    }
+}
+
+/// Directional focus navigation links of a widget. When the widget has keyboard focus and an arrow
+/// key is pressed, a [`NavigationLayer`] ancestor moves the focus to the widget linked in the
+/// corresponding direction. [`Handle::NONE`] means that there is no link in the direction. It is
+/// stored in [`Widget::focus_navigation`].
+#[derive(Visit, Reflect, Copy, Clone, Debug, Default, PartialEq, Eq, TypeUuidProvider)]
+#[type_uuid(id = "e5a3b8d1-7f24-4c06-a9e2-3b61d0f7c58e")]
+pub struct FocusNavigation {
+    /// A widget, that is focused when arrow up key is pressed.
+    pub up: Handle<UiNode>,
+    /// A widget, that is focused when arrow down key is pressed.
+    pub down: Handle<UiNode>,
+    /// A widget, that is focused when arrow left key is pressed.
+    pub left: Handle<UiNode>,
+    /// A widget, that is focused when arrow right key is pressed.
+    pub right: Handle<UiNode>,
+}
+
+impl FocusNavigation {
+    /// Returns a widget linked in the given direction.
+    pub fn target(&self, direction: FocusDirection) -> Handle<UiNode> {
+        match direction {
+            FocusDirection::Up => self.up,
+            FocusDirection::Down => self.down,
+            FocusDirection::Left => self.left,
+            FocusDirection::Right => self.right,
+        }
+    }
+
+    /// Sets a widget linked in the given direction.
+    pub fn set_target(&mut self, direction: FocusDirection, target: Handle<UiNode>) {
+        match direction {
+            FocusDirection::Up => self.up = target,
+            FocusDirection::Down => self.down = target,
+            FocusDirection::Left => self.left = target,
+            FocusDirection::Right => self.right = target,
+        }
+    }
+
+    /// Returns `true` if there are no links.
+    pub fn is_empty(&self) -> bool {
+        FocusDirection::ALL
+            .iter()
+            .all(|direction| self.target(*direction).is_none())
+    }
+
+    /// Computes navigation links of the widget from the screen positions of the candidate widgets:
+    /// for each direction the closest candidate in that direction is linked. Candidates that are
+    /// far away from the axis of the direction are penalized, so a widget right below is preferred
+    /// over a closer widget diagonally below. The layout of the widgets must be up-to-date.
+    pub fn compute(
+        ui: &UserInterface,
+        widget: Handle<UiNode>,
+        candidates: &[Handle<UiNode>],
+    ) -> Self {
+        let mut navigation = Self::default();
+        let Some(widget_ref) = ui.try_get(widget) else {
+            return navigation;
+        };
+        let center = widget_ref.screen_bounds().center();
+
+        for direction in FocusDirection::ALL {
+            let axis = direction.vector();
+            let mut closest = None;
+            for &candidate in candidates {
+                if candidate == widget {
+                    continue;
+                }
+                let Some(candidate_ref) = ui.try_get(candidate) else {
+                    continue;
+                };
+                let delta = candidate_ref.screen_bounds().center() - center;
+                let along = delta.dot(&axis);
+                if along <= 0.0 {
+                    continue;
+                }
+                let across = (delta - axis.scale(along)).norm();
+                let score = along + 2.0 * across;
+                if closest.is_none_or(|(_, closest_score)| score < closest_score) {
+                    closest = Some((candidate, score));
+                }
+            }
+            if let Some((candidate, _)) = closest {
+                navigation.set_target(direction, candidate);
+            }
+        }
+
+        navigation
+    }
+}
+
+/// Returns `true` if the widget could receive keyboard focus via navigation: it either accepts
+/// input or it has a tab index.
+pub fn is_focusable(widget: &Widget) -> bool {
+    widget.accepts_input || widget.tab_index.is_some()
+}
 
 /// A widget, that handles keyboard navigation on its descendant widgets using Tab key. It should
 /// be used as a root widget for an hierarchy, that should support Tab key navigation:
@@ -102,6 +255,24 @@ impl Control for NavigationLayer {
     fn handle_routed_message(&mut self, ui: &mut UserInterface, message: &mut UiMessage) {
         self.widget.handle_routed_message(ui, message);
 
+        if let Some(&WidgetMessage::KeyDown(key)) = message.data() {
+            if !message.handled() {
+                if let Some(direction) = FocusDirection::from_key(key) {
+                    let target = ui
+                        .try_get(ui.keyboard_focus_node)
+                        .map(|focused| focused.focus_navigation.target(direction))
+                        .unwrap_or_default();
+                    if ui
+                        .try_get(target)
+                        .is_some_and(|target| target.is_globally_visible())
+                    {
+                        ui.send_message(WidgetMessage::focus(target, MessageDirection::ToWidget));
+                        message.set_handled(true);
+                    }
+                }
+            }
+        }
+
         if let Some(WidgetMessage::KeyDown(KeyCode::Tab)) = message.data() {
             // Collect all descendant widgets, that supports Tab navigation.
             let mut tab_list = Vec::new();
@@ -189,11 +360,57 @@ impl NavigationLayerBuilder {
 
 #[cfg(test)]
 mod test {
-    use crate::navigation::NavigationLayerBuilder;
-    use crate::{test::test_widget_deletion, widget::WidgetBuilder};
+    use crate::navigation::{FocusDirection, FocusNavigation, NavigationLayerBuilder};
+    use crate::{
+        border::BorderBuilder, canvas::CanvasBuilder, core::algebra::Vector2,
+        test::test_widget_deletion, widget::WidgetBuilder, UserInterface,
+    };
+    use fyrox_core::pool::Handle;
 
     #[test]
     fn test_deletion() {
         test_widget_deletion(|ctx| NavigationLayerBuilder::new(WidgetBuilder::new()).build(ctx));
     }
+
+    #[test]
+    fn test_compute_focus_navigation() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let ctx = &mut ui.build_ctx();
+        // 2x2 grid of widgets and one more widget far below the top left one.
+        let widgets = [
+            (0.0, 0.0),
+            (100.0, 0.0),
+            (0.0, 100.0),
+            (100.0, 100.0),
+            (30.0, 500.0),
+        ]
+        .map(|(x, y)| {
+            BorderBuilder::new(
+                WidgetBuilder::new()
+                    .with_desired_position(Vector2::new(x, y))
+                    .with_width(50.0)
+                    .with_height(50.0),
+            )
+            .build(ctx)
+        });
+        CanvasBuilder::new(WidgetBuilder::new().with_children(widgets)).build(ctx);
+        ui.update(screen_size, 0.0, &Default::default());
+
+        let [top_left, top_right, bottom_left, bottom_right, far] = widgets;
+        assert_eq!(
+            FocusNavigation::compute(&ui, top_left, &widgets),
+            FocusNavigation {
+                up: Handle::NONE,
+                down: bottom_left,
+                left: Handle::NONE,
+                right: top_right,
+            }
+        );
+        let navigation = FocusNavigation::compute(&ui, bottom_right, &widgets);
+        assert_eq!(navigation.target(FocusDirection::Up), top_right);
+        assert_eq!(navigation.target(FocusDirection::Left), bottom_left);
+        assert_eq!(navigation.target(FocusDirection::Down), far);
+        assert!(FocusNavigation::default().is_empty());
+    }
 }
@@ -54,7 +54,7 @@ use std::{
 };
 
 /// A set of potential values for styled properties.
-#[derive(Visit, Reflect, Debug, Clone)]
+#[derive(Visit, Reflect, Debug, Clone, PartialEq)]
 pub enum StyleProperty {
     /// A numeric property.
     Number(f32),
@@ -24,19 +24,23 @@
 #![warn(missing_docs)]
 
 use crate::{
+    accessibility::Accessibility,
     brush::Brush,
     core::{
         algebra::{Matrix3, Point2, Vector2},
         math::Rect,
         pool::Handle,
         reflect::prelude::*,
+        type_traits::prelude::*,
         uuid::Uuid,
         visitor::prelude::*,
         ImmutableString,
     },
     core::{parking_lot::Mutex, variable::InheritableVariable},
     define_constructor,
+    event_handler::{EventHandlerBinding, WidgetEvent},
     message::{CursorIcon, Force, KeyCode, MessageDirection, UiMessage},
+    navigation::FocusNavigation,
     style::{
         resource::{StyleResource, StyleResourceExt},
         Style, StyledProperty,
@@ -55,6 +59,7 @@ use std::{
     fmt::{Debug, Formatter},
     sync::{mpsc::Sender, Arc},
 };
+use strum_macros::{AsRefStr, EnumString, VariantNames};
 
 /// Sorting predicate that is used to sort widgets by some criteria.
 #[derive(Clone)]
@@ -265,6 +270,12 @@ pub enum WidgetMessage {
     /// Direction: **From/To UI**
     MaxSize(Vector2<f32>),
 
+    /// A request to set aspect ratio (width divided by height) constraint of widget. [`None`] removes the
+    /// constraint. See [`Widget::aspect_ratio`] for more info.
+    ///
+    /// Direction: **From/To UI**
+    AspectRatio(Option<f32>),
+
     /// A request to set minimum size of widget. Minimum size restricts size of a widget during layout pass. For example
     /// you can set minimum size to a button which was placed into a grid's cell, if minimum size wouldn't be set, button
     /// would be compressed to fill entire cell.
@@ -582,6 +593,11 @@ impl WidgetMessage {
         WidgetMessage:MaxSize => fn max_size(Vector2<f32>), layout: false
     );
 
+    define_constructor!(
+        /// Creates [`WidgetMessage::AspectRatio`] message.
+        WidgetMessage:AspectRatio => fn aspect_ratio(Option<f32>), layout: false
+    );
+
     define_constructor!(
         /// Creates [`WidgetMessage::HorizontalAlignment`] message.
         WidgetMessage:HorizontalAlignment => fn horizontal_alignment(HorizontalAlignment), layout: false
@@ -777,6 +793,46 @@ impl DerefMut for WidgetMaterial {
     }
 }
 
+/// An axis along which a widget could be moved by dragging, see [`DragBehavior`].
+#[derive(
+    Visit,
+    Reflect,
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    TypeUuidProvider,
+    AsRefStr,
+    EnumString,
+    VariantNames,
+)]
+#[type_uuid(id = "8c2f5e1a-4b7d-4d93-b6a0-1e9f3c7d2a54")]
+pub enum DragAxis {
+    /// The widget could be moved in any direction.
+    #[default]
+    Both,
+    /// The widget could be moved only horizontally.
+    Horizontal,
+    /// The widget could be moved only vertically.
+    Vertical,
+}
+
+/// Defines how a widget is moved when it is dragged with the left mouse button, see
+/// [`Widget::drag_behavior`]. The user interface changes the desired position of the widget, which
+/// means that the widget moves freely only if its parent respects desired positions of its children
+/// (for example, [`crate::canvas::Canvas`]).
+#[derive(Visit, Reflect, Copy, Clone, Debug, Default, PartialEq, Eq, TypeUuidProvider)]
+#[type_uuid(id = "3f7a9d2c-6e15-4b8a-9c40-d2b5e8f1a673")]
+pub struct DragBehavior {
+    /// An axis along which the widget could be moved.
+    pub axis: DragAxis,
+    /// A flag, that defines whether the widget must stay within the bounds of its parent.
+    pub keep_in_parent: bool,
+}
+
 /// Widget is a base UI element, that is always used to build derived, more complex, widgets. In general, it is a container
 /// for layout information, basic visual appearance, visibility options, parent-child information. It does almost nothing
 /// on its own, instead, the user interface modifies its state accordingly.
@@ -806,6 +862,10 @@ pub struct Widget {
     /// Maximum width and height. Default is [`f32::INFINITY`] for both axes.
     #[reflect(setter = "set_max_size_notify")]
     pub max_size: InheritableVariable<Vector2<f32>>,
+    /// Optional aspect ratio (width divided by height) constraint. The layout system keeps the proportions
+    /// of the widget when it is resized. Default is [`None`] (no constraint).
+    #[reflect(setter = "set_aspect_ratio_notify")]
+    pub aspect_ratio: InheritableVariable<Option<f32>>,
     /// Background brush of the widget.
     pub background: InheritableVariable<StyledProperty<Brush>>,
     /// Foreground brush of the widget.
@@ -846,14 +906,34 @@ pub struct Widget {
     #[reflect(hidden)]
     pub is_mouse_directly_over: bool,
     /// A flag, that defines whether the widget is "visible" for hit testing (picking). Could be useful to prevent some widgets
-    /// from any interactions with mouse.
+    /// from any interactions with mouse. Keep in mind, that the flag affects the entire hierarchy of the widget, its descendants
+    /// won't be picked even if they are hit-test visible.
     pub hit_test_visibility: InheritableVariable<bool>,
     /// Index of the widget in parent's children list that defines its order in drawing and picking.
     pub z_index: InheritableVariable<usize>,
+    /// Defines the order in which the widget is drawn and picked among its siblings, widgets with
+    /// larger values are drawn on top of widgets with smaller values. Unlike [`Self::z_index`], it
+    /// does not change the order of children of the parent widget and does not affect layout.
+    /// Siblings with the same render order are drawn in the order of children. Default is 0.
+    pub render_order: InheritableVariable<i32>,
+    /// Bindings of events of the widget to named handlers, see [`crate::event_handler`] module docs
+    /// for more info.
+    pub event_handlers: InheritableVariable<Vec<EventHandlerBinding>>,
+    /// Accessibility metadata of the widget, see [`crate::accessibility`] module docs for more info.
+    /// [`None`] means that the widget has no metadata.
+    pub accessibility: InheritableVariable<Option<Accessibility>>,
+    /// Directional focus navigation links of the widget, see [`FocusNavigation`] docs for more info.
+    pub focus_navigation: InheritableVariable<FocusNavigation>,
     /// A flag, that defines whether the drag from drag'n'drop functionality can be started by the widget or not.
     pub allow_drag: InheritableVariable<bool>,
     /// A flag, that defines whether the drop from drag'n'drop functionality can be accepted by the widget or not.
     pub allow_drop: InheritableVariable<bool>,
+    /// Defines how the widget is moved when it is dragged with the mouse, see [`DragBehavior`] docs for more
+    /// info. [`None`] means that the widget cannot be moved by dragging.
+    pub drag_behavior: InheritableVariable<Option<DragBehavior>>,
+    /// A flag, that marks the widget as having its own styling. Such widgets are left untouched, when a
+    /// theme is applied to a whole UI document (for example, by the editor).
+    pub theme_override: InheritableVariable<bool>,
     /// Optional, user-defined data.
     #[reflect(hidden)]
     #[visit(skip)]
@@ -875,6 +955,7 @@ pub struct Widget {
     /// An optional ref counted handle to a context menu used by the widget.
     pub context_menu: Option<RcUiNodeHandle>,
     /// A flag, that defines whether the widget should be clipped by the parent bounds or not.
+    #[reflect(setter = "set_clip_to_bounds_notify")]
     pub clip_to_bounds: InheritableVariable<bool>,
     /// Current render transform of the node. It modifies layout information of the widget, as well as it affects visual transform
     /// of the widget.
@@ -1071,6 +1152,36 @@ impl Widget {
         *self.allow_drop
     }
 
+    /// Sets new drag behavior of the widget. [`None`] makes the widget non-draggable.
+    #[inline]
+    pub fn set_drag_behavior(&mut self, drag_behavior: Option<DragBehavior>) -> &mut Self {
+        self.drag_behavior
+            .set_value_and_mark_modified(drag_behavior);
+        self
+    }
+
+    /// Returns current drag behavior of the widget.
+    #[inline]
+    pub fn drag_behavior(&self) -> Option<DragBehavior> {
+        *self.drag_behavior
+    }
+
+    /// Sets whether the widget has its own styling, that must be preserved when a theme is applied to a
+    /// whole UI document.
+    #[inline]
+    pub fn set_theme_override(&mut self, theme_override: bool) -> &mut Self {
+        self.theme_override
+            .set_value_and_mark_modified(theme_override);
+        self
+    }
+
+    /// Returns `true` if the widget has its own styling, that must be preserved when a theme is applied
+    /// to a whole UI document.
+    #[inline]
+    pub fn has_theme_override(&self) -> bool {
+        *self.theme_override
+    }
+
     /// Maps the given point from screen to local widget's coordinates. Could be used to transform
     /// mouse cursor position (which is in screen space) to local widget coordinates.
     #[inline]
@@ -1129,6 +1240,17 @@ impl Widget {
         *self.hit_test_visibility
     }
 
+    /// Sets whether the widget is able to participate in hit testing or not. Hit-test invisible widget is "transparent"
+    /// for the mouse: it does not intercept mouse events and they're received by the widgets beneath. Picking skips the
+    /// entire hierarchy of such widget, which means that its descendants won't receive mouse events as well, even if
+    /// they are hit-test visible.
+    #[inline]
+    pub fn set_hit_test_visibility(&mut self, visibility: bool) -> &mut Self {
+        self.hit_test_visibility
+            .set_value_and_mark_modified(visibility);
+        self
+    }
+
     /// Sets the new maximum size of the widget.
     #[inline]
     pub fn set_max_size(&mut self, value: Vector2<f32>) -> &mut Self {
@@ -1159,6 +1281,25 @@ impl Widget {
         self.max_size.y
     }
 
+    /// Sets new aspect ratio (width divided by height) constraint of the widget. [`None`] removes the
+    /// constraint. Non-positive and non-finite ratios are ignored by the layout system.
+    #[inline]
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: Option<f32>) -> &mut Self {
+        self.aspect_ratio.set_value_and_mark_modified(aspect_ratio);
+        self
+    }
+
+    fn set_aspect_ratio_notify(&mut self, aspect_ratio: Option<f32>) -> Option<f32> {
+        self.invalidate_layout();
+        std::mem::replace(&mut self.aspect_ratio, aspect_ratio)
+    }
+
+    /// Returns current aspect ratio constraint of the widget.
+    #[inline]
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        *self.aspect_ratio
+    }
+
     /// Sets new Z index for the widget. Z index defines the sorting (stable) index which will be used to "arrange" widgets
     /// in the correct order.
     #[inline]
@@ -1174,6 +1315,28 @@ impl Widget {
         *self.z_index
     }
 
+    /// Sets new render order of the widget. See [`Self::render_order`] docs for more info.
+    #[inline]
+    pub fn set_render_order(&mut self, render_order: i32) -> &mut Self {
+        self.render_order.set_value_and_mark_modified(render_order);
+        self
+    }
+
+    /// Returns current render order of the widget.
+    #[inline]
+    pub fn render_order(&self) -> i32 {
+        *self.render_order
+    }
+
+    /// Returns a name of the handler bound to the given event of the widget, if any.
+    #[inline]
+    pub fn event_handler(&self, event: WidgetEvent) -> Option<&str> {
+        self.event_handlers
+            .iter()
+            .find(|binding| binding.event == event)
+            .map(|binding| binding.handler.as_str())
+    }
+
     /// Sets the new background of the widget.
     #[inline]
     pub fn set_background(&mut self, brush: Brush) -> &mut Self {
@@ -1219,6 +1382,27 @@ impl Widget {
         *self.width
     }
 
+    /// Sets whether the widget (and its descendants) should be clipped by its bounds or not. Clipping bounds
+    /// are recalculated on the next layout update.
+    #[inline]
+    pub fn set_clip_to_bounds(&mut self, clip_to_bounds: bool) -> &mut Self {
+        self.set_clip_to_bounds_notify(clip_to_bounds);
+        self
+    }
+
+    fn set_clip_to_bounds_notify(&mut self, clip_to_bounds: bool) -> bool {
+        // Clipping bounds are calculated after the arrangement, so it must be invalidated.
+        self.invalidate_arrange();
+        self.clip_to_bounds
+            .set_value_and_mark_modified(clip_to_bounds)
+    }
+
+    /// Returns `true` if the widget is clipped by its bounds, `false` - otherwise.
+    #[inline]
+    pub fn clip_to_bounds(&self) -> bool {
+        *self.clip_to_bounds
+    }
+
     /// Return `true` if the widget is set to be drawn on top of every other, normally drawn, widgets, `false` - otherwise.
     pub fn is_draw_on_top(&self) -> bool {
         *self.draw_on_top
@@ -1484,6 +1668,11 @@ impl Widget {
                             self.set_min_size_notify(*min_size);
                         }
                     }
+                    &WidgetMessage::AspectRatio(aspect_ratio) => {
+                        if *self.aspect_ratio != aspect_ratio {
+                            self.set_aspect_ratio_notify(aspect_ratio);
+                        }
+                    }
                     &WidgetMessage::Row(row) => {
                         if *self.row != row {
                             self.set_row_notify(row);
@@ -1867,6 +2056,8 @@ pub struct WidgetBuilder {
     pub max_size: Option<Vector2<f32>>,
     /// Min size of the widget.
     pub min_size: Option<Vector2<f32>>,
+    /// Aspect ratio constraint of the widget.
+    pub aspect_ratio: Option<f32>,
     /// Background brush of the widget.
     pub background: Option<StyledProperty<Brush>>,
     /// Foreground brush of the widget.
@@ -1885,10 +2076,22 @@ pub struct WidgetBuilder {
     pub visibility: bool,
     /// Z index of the widget.
     pub z_index: usize,
+    /// Render order of the widget.
+    pub render_order: i32,
+    /// Event handler bindings of the widget.
+    pub event_handlers: Vec<EventHandlerBinding>,
+    /// Accessibility metadata of the widget.
+    pub accessibility: Option<Accessibility>,
+    /// Directional focus navigation links of the widget.
+    pub focus_navigation: FocusNavigation,
     /// Whether the dragging of the widget is allowed or not.
     pub allow_drag: bool,
     /// Whether the drop of the widget is allowed or not.
     pub allow_drop: bool,
+    /// Drag behavior of the widget.
+    pub drag_behavior: Option<DragBehavior>,
+    /// Whether the widget has its own styling, that must be preserved when a theme is applied.
+    pub theme_override: bool,
     /// User-defined data.
     pub user_data: Option<Arc<Mutex<dyn Any + Send>>>,
     /// Whether to draw the widget on top of any other or not.
@@ -1947,6 +2150,7 @@ impl WidgetBuilder {
             horizontal_alignment: HorizontalAlignment::default(),
             max_size: None,
             min_size: None,
+            aspect_ratio: None,
             background: None,
             foreground: None,
             row: 0,
@@ -1957,8 +2161,14 @@ impl WidgetBuilder {
             is_hit_test_visible: true,
             visibility: true,
             z_index: 0,
+            render_order: 0,
+            event_handlers: Default::default(),
+            accessibility: None,
+            focus_navigation: Default::default(),
             allow_drag: false,
             allow_drop: false,
+            drag_behavior: None,
+            theme_override: false,
             user_data: None,
             draw_on_top: false,
             enabled: true,
@@ -2050,6 +2260,12 @@ impl WidgetBuilder {
         self
     }
 
+    /// Sets the aspect ratio (width divided by height) constraint of the widget.
+    pub fn with_aspect_ratio(mut self, aspect_ratio: f32) -> Self {
+        self.aspect_ratio = Some(aspect_ratio);
+        self
+    }
+
     /// Sets the desired background brush of the widget.
     pub fn with_background(mut self, brush: StyledProperty<Brush>) -> Self {
         self.background = Some(brush);
@@ -2110,6 +2326,33 @@ impl WidgetBuilder {
         self
     }
 
+    /// Sets the desired render order of the widget.
+    pub fn with_render_order(mut self, render_order: i32) -> Self {
+        self.render_order = render_order;
+        self
+    }
+
+    /// Binds the given event of the widget to a handler with the given name.
+    pub fn with_event_handler(mut self, event: WidgetEvent, handler: impl Into<String>) -> Self {
+        self.event_handlers.push(EventHandlerBinding {
+            event,
+            handler: handler.into(),
+        });
+        self
+    }
+
+    /// Sets the desired directional focus navigation links of the widget.
+    pub fn with_focus_navigation(mut self, focus_navigation: FocusNavigation) -> Self {
+        self.focus_navigation = focus_navigation;
+        self
+    }
+
+    /// Sets the desired accessibility metadata of the widget.
+    pub fn with_accessibility(mut self, accessibility: Accessibility) -> Self {
+        self.accessibility = Some(accessibility);
+        self
+    }
+
     /// Adds a child handle to the widget. [`Handle::NONE`] values are ignored.
     pub fn with_child(mut self, handle: Handle<UiNode>) -> Self {
         if handle.is_some() {
@@ -2158,6 +2401,19 @@ impl WidgetBuilder {
         self
     }
 
+    /// Sets the desired drag behavior of the widget.
+    pub fn with_drag_behavior(mut self, drag_behavior: DragBehavior) -> Self {
+        self.drag_behavior = Some(drag_behavior);
+        self
+    }
+
+    /// Marks the widget as having its own styling, that must be preserved when a theme is applied to a
+    /// whole UI document.
+    pub fn with_theme_override(mut self, theme_override: bool) -> Self {
+        self.theme_override = theme_override;
+        self
+    }
+
     /// Enables or disables dragging of the widget.
     pub fn with_allow_drag(mut self, allow_drag: bool) -> Self {
         self.allow_drag = allow_drag;
@@ -2262,6 +2518,7 @@ impl WidgetBuilder {
                 .max_size
                 .unwrap_or_else(|| Vector2::new(f32::INFINITY, f32::INFINITY))
                 .into(),
+            aspect_ratio: self.aspect_ratio.into(),
             background: self
                 .background
                 .unwrap_or_else(|| ctx.style.property(Style::BRUSH_PRIMARY))
@@ -2288,8 +2545,14 @@ impl WidgetBuilder {
             prev_measure: Default::default(),
             prev_arrange: Default::default(),
             z_index: self.z_index.into(),
+            render_order: self.render_order.into(),
+            event_handlers: self.event_handlers.clone().into(),
+            accessibility: self.accessibility.clone().into(),
+            focus_navigation: self.focus_navigation.into(),
             allow_drag: self.allow_drag.into(),
             allow_drop: self.allow_drop.into(),
+            drag_behavior: self.drag_behavior.into(),
+            theme_override: self.theme_override.into(),
             user_data: self.user_data.clone(),
             draw_on_top: self.draw_on_top.into(),
             enabled: self.enabled.into(),